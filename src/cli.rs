@@ -1,15 +1,34 @@
 use crate::Result;
+use crate::codegen::Shell;
 use crate::commands::{
-    add::handle_add_command, init::handle_init_command, list::handle_list_command,
-    remove::handle_remove_command, history::handle_history_command,
+    add::{AddOptions, handle_add_command, handle_session_add_command, then_command},
+    announce::handle_announce_command,
+    complete::alias_candidates,
+    history::{handle_history_command, handle_last_command},
+    import::ImportSource,
+    init::handle_init_command,
+    list::{handle_here_command, handle_list_command},
+    remove::handle_remove_command,
+    resolve::handle_resolve_command,
 };
-use crate::store::Store;
-use clap::{Parser, Subcommand};
+use crate::hooks::HookEvent;
+use crate::store::{BackendKind, KubeGuard, ScopeSpec, Store};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::{ArgValueCandidates, CompleteEnv};
+use std::io::Write;
 
 #[derive(Parser)]
 #[command(name = "aka")]
 #[command(about = "Instant terminal alias manager")]
 #[command(version)]
+#[command(long_version = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("AKA_GIT_SHA"),
+    " ",
+    env!("AKA_BUILD_DATE"),
+    ")"
+))]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
@@ -21,8 +40,134 @@ pub struct Cli {
     /// Command value for implicit add
     #[arg(required = false)]
     pub implicit_value: Option<String>,
+
+    /// Output format; `json` reports errors as a JSON object on stderr
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    /// Refuse commands that change the store (also implied by an aka.lock file next to it)
+    #[arg(long, global = true)]
+    pub readonly: bool,
+
+    /// Append debug logs at this level (error, warn, info, debug, trace) to the log file
+    #[arg(long, global = true, value_name = "LEVEL")]
+    pub log_level: Option<String>,
+
+    /// Color the output: auto (unless NO_COLOR is set or the output is not a terminal),
+    /// always or never
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+}
+
+impl Cli {
+    /// Whether the command given would write to the store.
+    fn changes_store(&self) -> bool {
+        match &self.command {
+            Some(Commands::Add { session, .. }) => !session,
+            Some(Commands::Lint { fix }) => *fix,
+            Some(Commands::Doctor { repair, quarantine }) => *repair || *quarantine,
+            Some(Commands::Generate { command }) => matches!(
+                command,
+                GenerateCommands::Compose | GenerateCommands::Ssh { .. }
+            ),
+            Some(Commands::Var { command }) => !matches!(command, VarCommands::List),
+            Some(
+                Commands::Last { .. }
+                | Commands::Remove { .. }
+                | Commands::Edit { .. }
+                | Commands::Import { .. }
+                | Commands::Project { .. }
+                | Commands::Scope { .. }
+                | Commands::Migrate { .. }
+                | Commands::Review { .. },
+            ) => true,
+            Some(_) => false,
+            // `aka <alias> <command>` adds and `aka <alias>` removes
+            None => self.implicit_alias.is_some(),
+        }
+    }
+
+    /// The hook events the command given is followed by, when it may change the store.
+    fn hook_events(&self) -> &'static [HookEvent] {
+        match &self.command {
+            Some(Commands::Add { session, .. }) if !session => &[HookEvent::PostAdd],
+            Some(Commands::Last { .. }) => &[HookEvent::PostAdd],
+            Some(Commands::Remove { .. } | Commands::Review { .. }) => &[HookEvent::PostRemove],
+            // Editing can both add and remove aliases; each hook is told about its own part
+            Some(Commands::Edit { .. }) => &[HookEvent::PostAdd, HookEvent::PostRemove],
+            Some(
+                Commands::Project { .. }
+                | Commands::Import { .. }
+                | Commands::Scope { .. }
+                | Commands::Migrate { .. },
+            ) => &[HookEvent::PostSync],
+            Some(Commands::Lint { fix: true }) => &[HookEvent::PostSync],
+            Some(Commands::Doctor { repair, quarantine }) if *repair || *quarantine => {
+                &[HookEvent::PostSync]
+            }
+            Some(Commands::Generate {
+                command: GenerateCommands::Compose | GenerateCommands::Ssh { .. },
+            }) => &[HookEvent::PostSync],
+            Some(_) => &[],
+            None => match (&self.implicit_alias, &self.implicit_value) {
+                (Some(_), Some(_)) => &[HookEvent::PostAdd],
+                (Some(_), None) => &[HookEvent::PostRemove],
+                (None, _) => &[],
+            },
+        }
+    }
+
+    /// Refuse a command that would change the store when this run is read-only, before any
+    /// of it runs.
+    fn ensure_writable(&self) -> Result<()> {
+        if !self.changes_store() {
+            return Ok(());
+        }
+        let reason = if self.readonly {
+            Some("--readonly".to_string())
+        } else {
+            let dir = crate::store::data_dir()?;
+            crate::store::write_lock(&dir)
+                .map(|path| format!("{} exists", path.display()))
+                .or_else(|| crate::store::unwritable(&dir))
+        };
+        match reason {
+            Some(reason) => Err(crate::error::AkaError::ReadOnly(reason).into()),
+            None => Ok(()),
+        }
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Decide once for all colored output whether to color it: `--color` first, then `NO_COLOR`,
+/// then whether the stream written to is a terminal.
+fn apply_color_choice(choice: ColorChoice) {
+    match choice {
+        ColorChoice::Always => owo_colors::set_override(true),
+        ColorChoice::Never => owo_colors::set_override(false),
+        // Also ahead of FORCE_COLOR, which the detection would put first
+        ColorChoice::Auto if std::env::var_os("NO_COLOR").is_some() => {
+            owo_colors::set_override(false)
+        }
+        // Left to the detection of each stream
+        ColorChoice::Auto => {}
+    }
+}
+
+// Parsed once per run, so the size of `Add` does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum Commands {
     /// Add a new alias
@@ -32,10 +177,106 @@ pub enum Commands {
         /// Command to alias (optional for history picker)
         command: Option<String>,
 
-        /// Directory scope (defaults to current directory if not global)
+        /// Directory scope (current directory when given without a value; `.`, `..`, `workspace`
+        /// or `@repo` for the repository root; also global, tmux:NAME, exact:PATH or recursive:PATH)
         #[arg(long, short = 's', num_args(0..=1), default_missing_value = ".")]
         scope: Option<String>,
 
+        /// Make the alias global, the same as omitting --scope
+        #[arg(long, short = 'g', conflicts_with_all = ["scope", "recursive"])]
+        global: bool,
+
+        /// Make the alias recursive for subdirectories
+        #[arg(long, short)]
+        recursive: bool,
+
+        /// Initial query for the history picker
+        #[arg(long, conflicts_with = "command")]
+        query: Option<String>,
+
+        /// Only run while `kubectl config current-context` matches this glob
+        #[arg(long, value_name = "GLOB", requires = "command")]
+        kube_context: Option<String>,
+
+        /// Refuse to run while `kubectl config current-context` matches this glob (e.g. 'prod-*')
+        #[arg(
+            long,
+            value_name = "GLOB",
+            requires = "command",
+            conflicts_with = "kube_context"
+        )]
+        deny_kube_context: Option<String>,
+
+        /// Save the command as a script file in the data directory and alias to it
+        #[arg(long, requires = "command")]
+        as_script: bool,
+
+        /// Store the current values of these environment variables and export them when the alias runs
+        #[arg(long, value_name = "VARS", value_delimiter = ',', requires = "alias")]
+        capture_env: Vec<String>,
+
+        /// Write a multi-line script in $EDITOR instead of giving a command
+        #[arg(long, requires = "alias", conflicts_with_all = ["command", "as_script"])]
+        script: bool,
+
+        /// Add the command as run, with its arguments, to the shell history afterwards
+        #[arg(long, requires = "alias")]
+        write_history: bool,
+
+        /// Print how long the command ran once it finishes
+        #[arg(long, requires = "alias")]
+        time: bool,
+
+        /// Send a desktop notification with the exit status once the command finishes
+        #[arg(long, requires = "alias")]
+        notify: bool,
+
+        /// Also append the command's stdout and stderr to this file, rotated past 1 MiB
+        #[arg(long, value_name = "PATH", requires = "alias")]
+        log_output: Option<std::path::PathBuf>,
+
+        /// Only define the alias where this program is on PATH when the shell loads aliases
+        #[arg(long, value_name = "PROGRAM", requires = "alias")]
+        if_installed: Option<String>,
+
+        /// Command to run instead of COMMAND, repeated for fallbacks: the first whose program
+        /// is installed is used (e.g. --try 'bat -p' --try cat)
+        #[arg(
+            long = "try",
+            value_name = "COMMAND",
+            requires = "alias",
+            conflicts_with_all = ["command", "as_script", "script", "then"]
+        )]
+        tries: Vec<String>,
+
+        /// Run this existing alias afterwards, if the command succeeded (repeatable); it is
+        /// stored as a reference, so later changes to it carry over
+        #[arg(
+            long,
+            value_name = "ALIAS",
+            requires = "alias",
+            conflicts_with_all = ["as_script", "script"],
+            add = ArgValueCandidates::new(|| alias_candidates(false))
+        )]
+        then: Vec<String>,
+
+        /// Print the function for this shell only instead of saving it: eval "$(aka add --session ...)"
+        #[arg(long, requires = "command", conflicts_with = "as_script")]
+        session: bool,
+    },
+    /// Alias the most recent command from shell history
+    Last {
+        /// Alias name (prompted if omitted)
+        alias: Option<String>,
+
+        /// Directory scope (current directory when given without a value; same forms as for add)
+        #[arg(long, short = 's', num_args(0..=1), default_missing_value = ".")]
+        scope: Option<String>,
+
+        /// Make the alias global, the same as omitting --scope
+        #[arg(long, short = 'g', conflicts_with_all = ["scope", "recursive"])]
+        global: bool,
+
         /// Make the alias recursive for subdirectories
         #[arg(long, short)]
         recursive: bool,
@@ -44,14 +285,17 @@ pub enum Commands {
     #[command(visible_alias = "rm")]
     Remove {
         /// Alias name (optional with --all)
-        #[arg(required_unless_present = "all")]
+        #[arg(
+            required_unless_present = "all",
+            add = ArgValueCandidates::new(|| alias_candidates(false))
+        )]
         alias: Option<String>,
 
         /// Remove all aliases
         #[arg(long, conflicts_with = "alias")]
         all: bool,
 
-        /// Scope to remove (global or directory path)
+        /// Scope to remove: global, tmux:NAME, exact:PATH, recursive:PATH or a directory
         #[arg(long, short = 's')]
         scope: Option<String>,
 
@@ -59,37 +303,522 @@ pub enum Commands {
         #[arg(long, short = 'f')]
         force: bool,
     },
+    /// Edit the alias that applies here in $EDITOR (script-backed aliases open their script)
+    Edit {
+        #[arg(
+            required_unless_present = "all",
+            add = ArgValueCandidates::new(|| alias_candidates(false))
+        )]
+        alias: Option<String>,
+
+        /// Edit every alias at once as a TOML file, applying all changes in one transaction
+        #[arg(long, conflicts_with = "alias")]
+        all: bool,
+    },
     /// List all aliases
     #[command(visible_alias = "ls")]
     List {
         /// Show all aliases regardless of current scope
         #[arg(long, short)]
         all: bool,
+
+        /// Also show which aliases call each other
+        #[arg(long, short)]
+        long: bool,
+
+        /// Line format using {alias}, {command} and {scope}, e.g. '{alias}\t{command}'
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Only show definitions in this scope: global, tmux:NAME, exact:PATH, recursive:PATH or a directory
+        #[arg(long, short = 's', conflicts_with = "all")]
+        scope: Option<String>,
+    },
+    /// Show aliases active in the current directory
+    Here,
+    /// Print the command an alias runs in the current directory
+    Resolve {
+        /// Alias name
+        #[arg(add = ArgValueCandidates::new(|| alias_candidates(true)))]
+        alias: String,
+
+        /// Print the shell statements running the definition, for `init --dump --dispatch`
+        #[arg(long, hide = true)]
+        body: bool,
+
+        /// Shell to write the statements for (with --body)
+        #[arg(long, value_enum, requires = "body", default_value_t = Shell::Zsh)]
+        shell: Shell,
+
+        /// Treat ALIAS as a command line and expand its first word, for the zsh expand widget
+        #[arg(long, hide = true, conflicts_with = "body")]
+        line: bool,
+    },
+    /// Print the aliases active in the current directory as plain alias lines for another shell
+    Effective {
+        /// Shell syntax to write
+        #[arg(long, value_enum, default_value_t = Shell::Zsh)]
+        shell: Shell,
+    },
+    /// Open an SSH session whose bash has the aliases active here defined
+    Ssh {
+        /// Host to connect to
+        host: String,
+        /// Options passed to ssh, after `--` (e.g. -- -p 2222)
+        #[arg(last = true)]
+        ssh_args: Vec<String>,
+    },
+    /// Show how the alias picks its command in the current directory, step by step
+    Explain {
+        /// Alias name
+        #[arg(add = ArgValueCandidates::new(|| alias_candidates(true)))]
+        alias: String,
+    },
+    /// Generate shortcut aliases from project files (re-run to refresh them)
+    Generate {
+        #[command(subcommand)]
+        command: GenerateCommands,
+    },
+    /// Create recursive aliases for the justfile recipes or Makefile targets in the current directory
+    #[command(group = ArgGroup::new("source").required(true))]
+    Import {
+        /// Import recipes from the justfile
+        #[arg(long, group = "source")]
+        from_just: bool,
+        /// Import targets from the Makefile
+        #[arg(long, group = "source")]
+        from_make: bool,
+    },
+    /// Keep aliases in sync with the current project's manifests
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommands,
+    },
+    /// Manage variables shared across aliases, used in commands as @{var:NAME}
+    Var {
+        #[command(subcommand)]
+        command: VarCommands,
+    },
+    /// Manage the directories aliases are scoped to
+    Scope {
+        #[command(subcommand)]
+        command: ScopeCommands,
+    },
+    /// Show the paths, store, shell and hook status aka is using (useful for bug reports)
+    Env {
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the version, commit, build date, supported shells and store schema
+    Version {
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
     },
     /// Initialize shell integration
     Init {
         #[arg(long, hide = true)]
         dump: bool,
+
+        /// Report how long generating and evaluating the dump took (with --dump)
+        #[arg(long, requires = "dump")]
+        timing: bool,
+
+        /// Shell to write for (detected from the calling shell by default)
+        #[arg(long, value_enum)]
+        shell: Option<Shell>,
+
+        /// Define only the definitions that apply in the current directory, and redefine them
+        /// on each directory change (zsh, bash and fish)
+        #[arg(long)]
+        activate_on_cd: bool,
+
+        /// Load aliases from a dump file kept in the data directory, regenerated only when
+        /// the store changes (zsh and bash)
+        #[arg(long, conflicts_with = "activate_on_cd")]
+        cached: bool,
+
+        /// Write the dump for POSIX sh (dash, ash, busybox sh), e.g. for minimal containers
+        #[arg(
+            long,
+            requires = "dump",
+            conflicts_with_all = ["shell", "activate_on_cd", "cached"]
+        )]
+        posix: bool,
+
+        /// Precede each alias in the dump with comments listing its scopes in the order they
+        /// are tried, to find out why a scoped alias does not apply
+        #[arg(long, requires = "dump", conflicts_with_all = ["activate_on_cd", "cached"])]
+        annotate: bool,
+
+        /// Define one-line stubs that look up the definition to run on each call, so
+        /// sourcing stays fast with hundreds of aliases (zsh and bash)
+        #[arg(
+            long,
+            conflicts_with_all = ["timing", "activate_on_cd", "cached", "posix", "annotate"]
+        )]
+        dispatch: bool,
+
+        /// Strip comments, indentation and blank lines from the dump, so it is quicker to
+        /// evaluate over slow links (zsh, bash and sh)
+        #[arg(
+            long,
+            requires = "dump",
+            conflicts_with_all = ["activate_on_cd", "cached", "annotate", "dispatch"]
+        )]
+        minify: bool,
+
+        /// Write each alias function to its own file in the data directory and mark it
+        /// `autoload`, so it is read only when first called (zsh)
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "timing", "activate_on_cd", "cached", "posix", "annotate", "dispatch", "minify"
+            ]
+        )]
+        autoload: bool,
+
+        /// Write the dump to a file in the data directory, kept up to date by every aka
+        /// command that changes the store, and print the line that sources it
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "dump", "timing", "activate_on_cd", "cached", "posix", "annotate", "dispatch",
+                "minify", "autoload"
+            ]
+        )]
+        print_path: bool,
+
+        /// Rewrite the dumps kept in the data directory (--cached, --print-path) even when
+        /// they look current, e.g. after installing a program an alias checks for
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "dump", "timing", "activate_on_cd", "cached", "posix", "annotate", "dispatch",
+                "minify", "autoload", "print_path"
+            ]
+        )]
+        refresh: bool,
     },
     /// Install completion to shell
-    Install,
+    Install {
+        /// Shell whose rc file to add aka to (detected from the calling shell by default)
+        #[arg(long, value_enum)]
+        shell: Option<Shell>,
+
+        /// Add aka to this file instead, e.g. ~/.zshrc.local or a file kept in dotfiles
+        #[arg(long, value_name = "FILE")]
+        path: Option<std::path::PathBuf>,
+    },
+    /// Remove what `aka install` added to rc files
+    Uninstall {
+        /// Shell whose usual rc file to check as well (detected from the calling shell by
+        /// default)
+        #[arg(long, value_enum)]
+        shell: Option<Shell>,
+    },
+    /// Convert the store to another backend (the old file is kept as .bak)
+    Migrate {
+        /// Target backend: redb, json, or sqlite (when built with the `sqlite` feature)
+        #[arg(long)]
+        to: BackendKind,
+    },
+    /// Check stored aliases for missing programs, unquoted arguments, and deprecated commands
+    Lint {
+        /// Rewrite the definitions that can be fixed automatically
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Check the store for records that fail to decode
+    Doctor {
+        /// Keep the definitions that still decode and quarantine records with none
+        #[arg(long)]
+        repair: bool,
+        /// Move corrupt records out of the alias table without repairing them
+        #[arg(long, conflicts_with = "repair")]
+        quarantine: bool,
+    },
+    /// Go through aliases not added, reviewed, or used for a while and keep, edit, or delete them
+    Review {
+        /// Months without review or use before an alias is listed
+        #[arg(long, default_value_t = 6)]
+        months: u64,
+    },
+    /// Show how often aliases are used (recorded while AKA_USAGE_LOG is set)
+    Stats {
+        /// Estimate the characters and typing time saved
+        #[arg(long)]
+        time_saved: bool,
+    },
+    /// Print a prompt segment such as `[aka:3]` counting the scoped aliases active here
+    Prompt {
+        /// Output format; `{count}` and `{names}` are replaced with the active aliases
+        #[arg(long, default_value = crate::commands::prompt::DEFAULT_PROMPT_FORMAT)]
+        format: String,
+        /// Print a starship.toml module that shows this segment
+        #[arg(long, conflicts_with = "format")]
+        starship: bool,
+    },
+    /// Exit 0 if an alias is defined and 1 otherwise, printing nothing (for scripts)
+    Has {
+        /// Alias name
+        alias: String,
+        /// Only succeed if a definition applies in the current directory
+        #[arg(long)]
+        here: bool,
+    },
+    /// Print the number of aliases
+    Count {
+        /// `here` counts only the aliases with a definition for the current directory
+        #[arg(long, value_enum, default_value_t = crate::commands::query::CountScope::All)]
+        scope: crate::commands::query::CountScope,
+    },
+    /// Print a reminder of a rarely used alias (changes daily; good for a prompt or MOTD)
+    Tip,
+    /// Print the scoped aliases active in the current directory (used by the cd hook)
+    #[command(hide = true)]
+    Announce,
+}
+
+#[derive(Subcommand)]
+pub enum GenerateCommands {
+    /// up/down, logs-<service> and sh-<service> for the nearest docker-compose.yml
+    Compose,
+    /// ssh-<host> for each Host in ~/.ssh/config
+    Ssh {
+        /// Prefix for the generated alias names
+        #[arg(long, default_value = "ssh-")]
+        prefix: String,
+        /// SSH config to read instead of ~/.ssh/config
+        #[arg(long)]
+        config: Option<std::path::PathBuf>,
+        /// Remove every generated SSH alias instead
+        #[arg(long, conflicts_with_all = ["prefix", "config"])]
+        prune: bool,
+    },
+    /// Raycast script commands that run each alias, written to a directory
+    Raycast {
+        /// Output directory (defaults to `raycast` in the data directory)
+        #[arg(long)]
+        dir: Option<std::path::PathBuf>,
+    },
+    /// Export the aliases to a file and print Dockerfile lines that bake it into an image
+    DockerfileSnippet {
+        /// File to export to, relative to the Docker build context
+        #[arg(long, default_value = "aka-aliases.sh")]
+        file: std::path::PathBuf,
+    },
+    /// Devcontainer feature that installs aka with the current aliases and shell hook
+    Devcontainer {
+        /// Feature directory to write
+        #[arg(long, default_value = ".devcontainer/aka")]
+        dir: std::path::PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProjectCommands {
+    /// Create or refresh recursive aliases from .aka.toml [aliases] and package.json scripts
+    Sync,
+}
+
+#[derive(Subcommand)]
+pub enum VarCommands {
+    /// Set a variable (e.g. `aka var set REGISTRY ghcr.io/acme`)
+    Set { name: String, value: String },
+    /// Remove a variable
+    Unset { name: String },
+    /// List variables
+    #[command(visible_alias = "ls")]
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum ScopeCommands {
+    /// Rewrite every scope under OLD to the same place under NEW (after moving a project)
+    Move {
+        /// Previous project directory (it may no longer exist)
+        old: String,
+        /// New project directory
+        new: String,
+    },
 }
 
 pub async fn run_cli() -> Result<()> {
+    // Answers the shell's completion requests (see `aka init`) and exits
+    CompleteEnv::with_factory(Cli::command)
+        .var("AKA_COMPLETE")
+        .complete();
+
     let cli = Cli::parse();
+    apply_color_choice(cli.color);
+    // A broken log setup must not get in the way of the command itself
+    if let Err(e) = init_logging(cli.log_level.as_deref()) {
+        eprintln!("aka: logging disabled: {}", e);
+    }
+    tracing::info!(args = ?std::env::args().skip(1).collect::<Vec<_>>(), "invoked");
+
+    let output = cli.output;
+    let changes_store = cli.changes_store();
+    let events = cli.hook_events();
+    // A migration rewrites the store in another format without changing any alias
+    let rewrites_store = matches!(cli.command, Some(Commands::Migrate { .. }));
+    let hooks: Vec<(HookEvent, std::path::PathBuf)> = crate::hooks::hooks_dir()
+        .map(|dir| {
+            events
+                .iter()
+                .filter_map(|&event| Some((event, crate::hooks::find(&dir, event)?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    // The aliases before the command, to tell a hook what changed; read only when there is one
+    let before = match hooks.is_empty() {
+        true => None,
+        false => match Store::new().and_then(|store| store.list()) {
+            Ok(before) => Some(before),
+            Err(e) => {
+                eprintln!("aka: hooks skipped: {}", e);
+                None
+            }
+        },
+    };
+    let result = match cli.ensure_writable() {
+        Ok(()) => run(cli).await,
+        Err(e) => Err(e),
+    };
+    if let Some(before) = before
+        && result.is_ok()
+    {
+        for (event, path) in &hooks {
+            if let Err(e) = run_hook(*event, path, &before, events.len() > 1, rewrites_store) {
+                eprintln!("aka: {}", e);
+            }
+        }
+    }
+    // The change is made already, so a dump left stale only warrants a warning
+    if changes_store
+        && result.is_ok()
+        && let Err(e) = crate::commands::init::refresh_sourced_dumps(false)
+    {
+        eprintln!("aka: could not update the sourced dump: {}", e);
+    }
+    if let Err(e) = &result {
+        tracing::error!(error = %e, "command failed");
+    }
+    match result {
+        Err(e) if output == OutputFormat::Json => {
+            eprintln!("{}", crate::error::error_json(&e));
+            std::process::exit(1);
+        }
+        result => result,
+    }
+}
 
+/// Run the user's hook at `path` for `event`, when the aliases differ from `before` or `always`
+/// is set. `split` tells it only about its own part of a command that runs several hooks.
+fn run_hook(
+    event: HookEvent,
+    path: &std::path::Path,
+    before: &std::collections::HashMap<String, Vec<crate::store::AliasDefinition>>,
+    split: bool,
+    always: bool,
+) -> std::result::Result<(), crate::error::AkaError> {
+    let after = Store::new()?.list()?;
+    let payload = match split {
+        true => crate::hooks::split_payload(event, before, &after),
+        false => crate::hooks::payload(event, before, &after),
+    };
+    let payload = match always {
+        true => payload.or_else(|| Some(crate::hooks::unchanged_payload(event))),
+        false => payload,
+    };
+    match payload {
+        Some(payload) => crate::hooks::run(path, event, &payload),
+        None => Ok(()),
+    }
+}
+
+fn init_logging(flag: Option<&str>) -> std::result::Result<(), crate::error::AkaError> {
+    let config = crate::config::load()?.log;
+    let level = crate::logging::log_level(flag, &config)?;
+    crate::logging::init(level, &crate::logging::log_path(&config)?)
+}
+
+async fn run(cli: Cli) -> Result<()> {
     let result = match cli.command {
         Some(Commands::Add {
             alias,
             command,
             scope,
+            global,
             recursive,
+            query,
+            kube_context,
+            deny_kube_context,
+            as_script,
+            script,
+            capture_env,
+            session,
+            write_history,
+            time,
+            notify,
+            log_output,
+            if_installed,
+            tries,
+            then,
         }) => {
             let mut store = Store::new()?;
+            let scope = scope.or(global.then(|| "global".to_string()));
+            let command = match &alias {
+                Some(a) if !then.is_empty() => Some(then_command(&store, a, command, &then)?),
+                _ => command,
+            };
+            let mut tries = tries.into_iter();
+            let command = command.or(tries.next());
+            let fallbacks: Vec<String> = tries.collect();
+            let kube = kube_context
+                .map(KubeGuard::Only)
+                .or(deny_kube_context.map(KubeGuard::Deny));
             match (alias, command) {
-                (Some(a), Some(c)) => handle_add_command(&mut store, a, c, scope, recursive)?,
+                (Some(a), None) if script => {
+                    let body = crate::commands::script::compose_script(
+                        &crate::commands::script::scripts_dir()?,
+                        crate::commands::script::open_in_editor,
+                    )?;
+                    let options = AddOptions {
+                        kube,
+                        as_script: true,
+                        capture_env,
+                        write_history,
+                        time,
+                        notify,
+                        log_output,
+                        if_installed,
+                        fallbacks,
+                    };
+                    handle_add_command(&mut store, a, body, scope, recursive, options)?
+                }
+                (Some(a), Some(c)) => {
+                    let options = AddOptions {
+                        kube,
+                        as_script,
+                        capture_env,
+                        write_history,
+                        time,
+                        notify,
+                        log_output,
+                        if_installed,
+                        fallbacks,
+                    };
+                    if session {
+                        handle_session_add_command(&store, a, c, scope, recursive, options)?
+                    } else {
+                        handle_add_command(&mut store, a, c, scope, recursive, options)?
+                    }
+                }
                 (None, None) => {
-                    handle_history_command(&mut store, None, scope, recursive, 200)?
+                    handle_history_command(&mut store, None, scope, recursive, 200, query)?
                 }
                 _ => {
                     return Err(crate::error::AkaError::ConfigError(
@@ -100,6 +829,16 @@ pub async fn run_cli() -> Result<()> {
                 }
             }
         }
+        Some(Commands::Last {
+            alias,
+            scope,
+            global,
+            recursive,
+        }) => {
+            let mut store = Store::new()?;
+            let scope = scope.or(global.then(|| "global".to_string()));
+            handle_last_command(&mut store, alias, scope, recursive)?
+        }
         Some(Commands::Remove {
             alias,
             all,
@@ -107,35 +846,419 @@ pub async fn run_cli() -> Result<()> {
             force,
         }) => {
             let mut store = Store::new()?;
-            handle_remove_command(&mut store, alias, all, scope, force)?
+            let confirm = crate::config::load()?.confirm.remove_all;
+            let output = handle_remove_command(&mut store, alias, all, scope, force, confirm)?;
+            crate::commands::script::prune_scripts(
+                &store,
+                &crate::commands::script::scripts_dir()?,
+            )?;
+            output
+        }
+        Some(Commands::Edit { alias, all }) => {
+            let mut store = Store::new()?;
+            match alias {
+                Some(alias) if !all => {
+                    let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+                    crate::commands::edit::handle_edit_command(
+                        &mut store,
+                        &alias,
+                        &current_dir,
+                        &crate::commands::script::scripts_dir()?,
+                        crate::commands::script::open_in_editor,
+                    )?
+                }
+                _ => crate::commands::edit::handle_edit_all_command(
+                    &mut store,
+                    crate::commands::script::open_in_editor,
+                    &mut std::io::stdin().lock(),
+                    &mut std::io::stdout(),
+                )?,
+            }
+        }
+        Some(Commands::List {
+            all,
+            long,
+            template,
+            scope,
+        }) => {
+            let store = Store::new()?;
+            let template = template.or(crate::config::load()?.list.template);
+            let scope = scope.as_deref().map(ScopeSpec::parse).transpose()?;
+            handle_list_command(&store, all, long, template.as_deref(), scope.as_ref())?
+        }
+        Some(Commands::Here) => {
+            let store = Store::new()?;
+            handle_here_command(&store)?
+        }
+        Some(Commands::Resolve {
+            alias,
+            body,
+            shell,
+            line,
+        }) => {
+            let store = Store::new()?;
+            let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+            if line {
+                crate::commands::resolve::handle_resolve_line_command(&store, &alias, &current_dir)?
+            } else if body {
+                crate::commands::resolve::handle_resolve_body_command(
+                    &store,
+                    &alias,
+                    &current_dir,
+                    shell,
+                )?
+            } else {
+                handle_resolve_command(&store, &alias, &current_dir)?
+            }
+        }
+        Some(Commands::Effective { shell }) => {
+            let store = Store::new()?;
+            let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+            crate::commands::effective::handle_effective_command(&store, shell, &current_dir)?
+        }
+        Some(Commands::Ssh { host, ssh_args }) => {
+            let store = Store::new()?;
+            let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+            crate::commands::ssh::handle_ssh_command(&store, &host, &ssh_args, &current_dir)?
+        }
+        Some(Commands::Explain { alias }) => {
+            let store = Store::new()?;
+            let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+            crate::commands::explain::handle_explain_command(&store, &alias, &current_dir)?
+        }
+        Some(Commands::Generate {
+            command: GenerateCommands::Compose,
+        }) => {
+            let mut store = Store::new()?;
+            let cwd = std::env::current_dir()?;
+            let cwd = std::fs::canonicalize(&cwd).unwrap_or(cwd);
+            crate::commands::generate::handle_generate_compose_command(&mut store, &cwd)?
         }
-        Some(Commands::List { all }) => {
+        Some(Commands::Generate {
+            command:
+                GenerateCommands::Ssh {
+                    prefix,
+                    config,
+                    prune,
+                },
+        }) => {
+            let mut store = Store::new()?;
+            let config = match config {
+                Some(path) => path,
+                None => crate::commands::generate::ssh_config_path()?,
+            };
+            crate::commands::generate::handle_generate_ssh_command(
+                &mut store, &config, &prefix, prune,
+            )?
+        }
+        Some(Commands::Generate {
+            command: GenerateCommands::Raycast { dir },
+        }) => {
+            let store = Store::new()?;
+            let dir = match dir {
+                Some(dir) => dir,
+                None => crate::commands::generate::raycast_dir()?,
+            };
+            crate::commands::generate::handle_generate_raycast_command(&store, &dir)?
+        }
+        Some(Commands::Generate {
+            command: GenerateCommands::DockerfileSnippet { file },
+        }) => {
             let store = Store::new()?;
-            handle_list_command(&store, all)?
+            crate::commands::generate::handle_generate_dockerfile_command(&store, &file)?
         }
-        Some(Commands::Init { dump }) => {
-            if dump {
+        Some(Commands::Generate {
+            command: GenerateCommands::Devcontainer { dir },
+        }) => {
+            let store = Store::new()?;
+            crate::commands::generate::handle_generate_devcontainer_command(&store, &dir)?
+        }
+        Some(Commands::Import { from_just, .. }) => {
+            let mut store = Store::new()?;
+            let cwd = std::env::current_dir()?;
+            let cwd = std::fs::canonicalize(&cwd).unwrap_or(cwd);
+            let source = if from_just {
+                ImportSource::Just
+            } else {
+                ImportSource::Make
+            };
+            crate::commands::import::handle_import_command(&mut store, &cwd, source)?
+        }
+        Some(Commands::Project {
+            command: ProjectCommands::Sync,
+        }) => {
+            let mut store = Store::new()?;
+            let cwd = std::env::current_dir()?;
+            let cwd = std::fs::canonicalize(&cwd).unwrap_or(cwd);
+            crate::commands::project::handle_project_sync_command(&mut store, &cwd)?
+        }
+        Some(Commands::Var { command }) => {
+            let mut store = Store::new()?;
+            match command {
+                VarCommands::Set { name, value } => {
+                    crate::commands::var::handle_var_set_command(&mut store, &name, &value)?
+                }
+                VarCommands::Unset { name } => {
+                    crate::commands::var::handle_var_unset_command(&mut store, &name)?
+                }
+                VarCommands::List => crate::commands::var::handle_var_list_command(&store)?,
+            }
+        }
+        Some(Commands::Scope {
+            command: ScopeCommands::Move { old, new },
+        }) => {
+            let mut store = Store::new()?;
+            crate::commands::scope::handle_scope_move_command(&mut store, &old, &new)?
+        }
+        Some(Commands::Init {
+            dump,
+            timing,
+            shell,
+            activate_on_cd,
+            cached,
+            posix,
+            annotate,
+            dispatch,
+            minify,
+            autoload,
+            print_path,
+            refresh,
+        }) => {
+            let mut out = std::io::stdout().lock();
+            let shell = shell.unwrap_or_else(crate::commands::init::detect_shell);
+            let current_dir = match activate_on_cd {
+                true => Some(std::env::current_dir()?.to_string_lossy().to_string()),
+                false => None,
+            };
+            let activate_in = current_dir.as_deref();
+            if refresh {
+                let written = crate::commands::init::refresh_sourced_dumps(true)?;
+                writeln!(out, "Refreshed {} dump(s)", written)?;
+            } else if minify {
+                let store = Store::new()?;
+                let codegen = match posix {
+                    true => &crate::codegen::PosixCodegen as &dyn crate::codegen::ShellCodegen,
+                    false => {
+                        crate::commands::init::check_minify(shell)?;
+                        shell.codegen()
+                    }
+                };
+                crate::commands::init::write_minified_dump(&store, codegen, timing, &mut out)?;
+            } else if posix {
                 let store = Store::new()?;
-                handle_init_command(Some(&store), dump)?
+                crate::commands::init::write_shell_dump(
+                    Some(&store),
+                    &crate::codegen::PosixCodegen,
+                    timing,
+                    None,
+                    annotate,
+                    &mut out,
+                )?;
+            } else if print_path {
+                crate::commands::init::write_source_line(&Store::new()?, shell, &mut out)?;
+            } else if autoload {
+                match dump {
+                    true => crate::commands::init::write_autoload_dump(
+                        &Store::new()?,
+                        shell,
+                        &crate::commands::init::autoload_dir()?,
+                        &mut out,
+                    )?,
+                    false => crate::commands::init::write_autoload_hook(shell, &mut out)?,
+                }
+            } else if dispatch {
+                match dump {
+                    true => {
+                        crate::commands::init::write_dispatch_dump(&Store::new()?, shell, &mut out)?
+                    }
+                    false => crate::commands::init::write_dispatch_hook(shell, &mut out)?,
+                }
+            } else if annotate {
+                crate::commands::init::write_shell_dump(
+                    Some(&Store::new()?),
+                    shell.codegen(),
+                    timing,
+                    None,
+                    true,
+                    &mut out,
+                )?;
+            } else if dump && cached {
+                let store = Store::new()?;
+                let path = crate::commands::init::cached_dump_path(shell)?;
+                crate::commands::init::write_cached_dump(&store, shell, &path, false)?;
+            } else if dump {
+                let store = Store::new()?;
+                handle_init_command(
+                    Some(&store),
+                    shell,
+                    dump,
+                    timing,
+                    activate_in,
+                    cached,
+                    &mut out,
+                )?;
             } else {
-                handle_init_command(None, dump)?
+                handle_init_command(None, shell, dump, timing, activate_in, cached, &mut out)?;
+            }
+            String::new()
+        }
+        Some(Commands::Env { json }) => crate::commands::env::handle_env_command(json)?,
+        Some(Commands::Version { json }) => crate::commands::version::handle_version_command(json)?,
+        Some(Commands::Install { shell, path }) => {
+            crate::commands::install::handle_install_command(
+                shell.unwrap_or_else(crate::commands::init::detect_shell),
+                path,
+                &crate::commands::install::installs_path()?,
+            )?
+        }
+        Some(Commands::Uninstall { shell }) => crate::commands::install::handle_uninstall_command(
+            shell.unwrap_or_else(crate::commands::init::detect_shell),
+            &crate::commands::install::installs_path()?,
+        )?,
+        Some(Commands::Migrate { to }) => {
+            crate::commands::migrate::handle_migrate_command(&crate::store::data_dir()?, to)?
+        }
+        Some(Commands::Lint { fix }) => {
+            let mut store = Store::new()?;
+            let config = crate::config::load()?;
+            crate::commands::lint::handle_lint_command(
+                &mut store,
+                &config.lint,
+                fix,
+                crate::commands::lint::on_path,
+            )?
+        }
+        Some(Commands::Doctor { repair, quarantine }) => {
+            let mut store = Store::new()?;
+            let action = match (repair, quarantine) {
+                (true, _) => crate::commands::doctor::DoctorAction::Repair,
+                (_, true) => crate::commands::doctor::DoctorAction::Quarantine,
+                _ => crate::commands::doctor::DoctorAction::Report,
+            };
+            crate::commands::doctor::handle_doctor_command(&mut store, action)?
+        }
+        Some(Commands::Review { months }) => {
+            let mut store = Store::new()?;
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            crate::commands::review::record_usage(
+                &mut store,
+                &crate::usage::usage_log_path()?,
+                now,
+            )?;
+            crate::commands::review::handle_review_command(
+                &mut store,
+                months,
+                now,
+                &crate::commands::script::scripts_dir()?,
+                crate::commands::script::open_in_editor,
+                &mut std::io::stdin().lock(),
+                &mut std::io::stdout(),
+            )?
+        }
+        Some(Commands::Stats { time_saved }) => {
+            let store = Store::new()?;
+            let counts = crate::usage::load_usage_counts(&crate::usage::usage_log_path()?)?;
+            let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+            crate::commands::stats::handle_stats_command(&store, &counts, &current_dir, time_saved)?
+        }
+        Some(Commands::Tip) => {
+            let store = Store::new()?;
+            let counts = crate::usage::load_usage_counts(&crate::usage::usage_log_path()?)?;
+            let day = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() / 86_400)
+                .unwrap_or(0);
+            crate::commands::tip::handle_tip_command(&store, &counts, day)?
+        }
+        Some(Commands::Prompt { starship: true, .. }) => crate::commands::prompt::starship_module(),
+        Some(Commands::Prompt { format, .. }) => {
+            let store = Store::new()?;
+            let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+            crate::commands::prompt::handle_prompt_command(&store, &current_dir, &format)?
+        }
+        Some(Commands::Has { alias, here }) => {
+            let found = {
+                let store = Store::new()?;
+                let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+                crate::commands::query::handle_has_command(
+                    &store,
+                    &alias,
+                    here.then_some(current_dir.as_str()),
+                )?
+            };
+            if !found {
+                std::process::exit(1);
             }
+            String::new()
+        }
+        Some(Commands::Count { scope }) => {
+            let store = Store::new()?;
+            let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+            crate::commands::query::handle_count_command(&store, scope, &current_dir)?.to_string()
+        }
+        Some(Commands::Announce) => {
+            let store = Store::new()?;
+            let current_dir = std::env::current_dir()?.to_string_lossy().to_string();
+            handle_announce_command(&store, &current_dir)?
         }
-        Some(Commands::Install) => crate::commands::install::handle_install_command()?,
         None => {
+            if !crate::config::implicit_enabled(&crate::config::load()?.cli) {
+                let mut command = Cli::command();
+                let error = match &cli.implicit_alias {
+                    Some(alias) => command.error(
+                        clap::error::ErrorKind::InvalidSubcommand,
+                        format!("unrecognized subcommand '{}' (implicit mode is off)", alias),
+                    ),
+                    None => command.error(
+                        clap::error::ErrorKind::MissingSubcommand,
+                        "a subcommand is required (implicit mode is off)",
+                    ),
+                };
+                error.exit();
+            }
+
             // Handle implicit commands
             match (cli.implicit_alias, cli.implicit_value) {
                 (Some(alias), Some(command)) => {
                     let mut store = Store::new()?;
-                    handle_add_command(&mut store, alias, command, None, false)?
+                    handle_add_command(
+                        &mut store,
+                        alias,
+                        command,
+                        None,
+                        false,
+                        AddOptions::default(),
+                    )?
                 }
                 (Some(alias), None) => {
                     let mut store = Store::new()?;
-                    handle_remove_command(&mut store, Some(alias), false, None, false)?
+                    let mut aliases = store.list()?;
+                    crate::system::merge_system_aliases(&mut aliases);
+                    // `aka lst` is more likely a mistyped command than an alias to remove
+                    if !aliases.contains_key(&alias) {
+                        let cli = Cli::command();
+                        let names = cli.get_subcommands().map(|c| c.get_name());
+                        let suggestions: Vec<String> =
+                            crate::commands::complete::closest_names(&alias, names)
+                                .into_iter()
+                                .map(str::to_string)
+                                .collect();
+                        if !suggestions.is_empty() {
+                            return Err(
+                                crate::error::AkaError::UnknownCommand(alias, suggestions).into()
+                            );
+                        }
+                    }
+                    let confirm = crate::config::load()?.confirm.remove_all;
+                    handle_remove_command(&mut store, Some(alias), false, None, false, confirm)?
                 }
                 (None, None) => {
                     let store = Store::new()?;
-                    handle_list_command(&store, false)?
+                    let template = crate::config::load()?.list.template;
+                    handle_list_command(&store, false, false, template.as_deref(), None)?
                 }
                 _ => {
                     unreachable!("Invalid argument combination");
@@ -144,7 +1267,9 @@ pub async fn run_cli() -> Result<()> {
         }
     };
 
-    println!("{}", result);
+    if !result.is_empty() {
+        println!("{}", result);
+    }
 
     Ok(())
 }
@@ -0,0 +1,582 @@
+use crate::store::{AliasDefinition, AliasScope, KubeGuard, sort_by_precedence};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+pub mod bash;
+pub mod elvish;
+pub mod fish;
+pub mod nu;
+pub mod posix;
+pub mod powershell;
+pub mod zsh;
+pub mod zsh_bash;
+
+pub use bash::BashCodegen;
+pub use elvish::ElvishCodegen;
+pub use fish::FishCodegen;
+pub use nu::NuCodegen;
+pub use posix::PosixCodegen;
+pub use powershell::PowerShellCodegen;
+pub use zsh::ZshCodegen;
+pub use zsh_bash::ZshBashCodegen;
+
+/// Shell that generated code is written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Shell {
+    Zsh,
+    Bash,
+    Fish,
+    Nu,
+    #[cfg_attr(feature = "cli", value(name = "powershell", alias = "pwsh"))]
+    PowerShell,
+    Elvish,
+}
+
+impl Shell {
+    /// Every shell `aka init` can write for.
+    pub const ALL: [Shell; 6] = [
+        Self::Zsh,
+        Self::Bash,
+        Self::Fish,
+        Self::Nu,
+        Self::PowerShell,
+        Self::Elvish,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Zsh => "zsh",
+            Self::Bash => "bash",
+            Self::Fish => "fish",
+            Self::Nu => "nu",
+            Self::PowerShell => "powershell",
+            Self::Elvish => "elvish",
+        }
+    }
+
+    /// The shell called `name`, which may be a path such as `/usr/bin/fish`, a login
+    /// shell's `-zsh`, or `pwsh.exe`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        let name = name.rsplit(['/', '\\']).next().unwrap_or(name);
+        let name = name.trim_start_matches('-');
+        let name = name.strip_suffix(".exe").unwrap_or(name);
+        if name == "pwsh" {
+            return Some(Self::PowerShell);
+        }
+        Self::ALL.into_iter().find(|shell| shell.name() == name)
+    }
+
+    /// Code generator for this shell's alias functions.
+    pub fn codegen(self) -> &'static dyn ShellCodegen {
+        match self {
+            Self::Zsh => &ZshCodegen,
+            Self::Bash => &BashCodegen,
+            Self::Fish => &FishCodegen,
+            Self::Nu => &NuCodegen,
+            Self::PowerShell => &PowerShellCodegen,
+            Self::Elvish => &ElvishCodegen,
+        }
+    }
+}
+
+/// Directory test guarding one branch of an alias function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirTest<'a> {
+    /// The current directory is exactly this path.
+    Exact(&'a str),
+    /// The current directory is this path or below it.
+    Recursive(&'a str),
+    /// The shell runs inside the tmux session with this name.
+    TmuxSession(&'a str),
+}
+
+impl DirTest<'_> {
+    /// Prefix shared by every directory strictly below a recursive scope, so shells can
+    /// match `path` itself or `prefix*` with the same component boundaries as
+    /// [`AliasScope::matches`]: `/tmp` gives `/tmp/`, which `/tmpfoo` does not start with.
+    pub fn subdir_prefix(path: &str) -> String {
+        if path.ends_with('/') {
+            path.to_string()
+        } else {
+            format!("{}/", path)
+        }
+    }
+}
+
+/// Size in bytes past which a `--log-output` file is moved to `<path>.1` before the next run.
+pub const LOG_OUTPUT_MAX_BYTES: u64 = 1024 * 1024;
+
+/// Program used to send desktop notifications, picked when the dump is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notifier {
+    /// macOS `osascript -e 'display notification ...'`.
+    Osascript,
+    /// `notify-send` from libnotify.
+    NotifySend,
+}
+
+impl Notifier {
+    /// The first notifier found on `PATH`, if any.
+    pub fn detect() -> Option<Self> {
+        Self::detect_with(on_path)
+    }
+
+    fn detect_with(on_path: impl Fn(&str) -> bool) -> Option<Self> {
+        if on_path("osascript") {
+            Some(Self::Osascript)
+        } else if on_path("notify-send") {
+            Some(Self::NotifySend)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether `program` is an executable file in one of the `PATH` directories.
+pub fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+/// Per-shell building blocks for `init --dump`.
+///
+/// The driver in this module decides what to emit; implementations only decide how it is
+/// spelled in their shell.
+pub trait ShellCodegen {
+    /// Statements emitted before any alias: save shell state and remove the aliases
+    /// managed by the previous dump.
+    fn cleanup(&self, out: &mut dyn Write) -> io::Result<()>;
+
+    /// A line the shell ignores, used to label groups of aliases.
+    fn comment(&self, out: &mut dyn Write, text: &str) -> io::Result<()>;
+
+    /// A line the shell ignores directly above what follows, unlike the group labels of
+    /// [`ShellCodegen::comment`].
+    fn note(&self, out: &mut dyn Write, text: &str) -> io::Result<()>;
+
+    /// Define `name` as a function whose body is `body`.
+    fn function(&self, out: &mut dyn Write, name: &str, body: &str) -> io::Result<()>;
+
+    /// Body that runs the first matching branch, or `otherwise` when none match.
+    fn conditional(&self, branches: &[(DirTest<'_>, String)], otherwise: &str) -> String;
+
+    /// Statement running a stored alias command with the function's arguments.
+    fn command(&self, command: &str) -> String;
+
+    /// Statement that stops `name` unless the kubectl context satisfies `guard`.
+    fn kube_guard(&self, name: &str, guard: &KubeGuard) -> String;
+
+    /// Statement exporting `env` for the rest of the function only.
+    fn local_env(&self, env: &BTreeMap<String, String>) -> String;
+
+    /// Statements running `body` with its stdout and stderr, merged, also appended to the
+    /// file at `path`, which is rotated once it grows past [`LOG_OUTPUT_MAX_BYTES`]. The
+    /// command's exit status is kept.
+    fn log_output(&self, body: &str, path: &str) -> String;
+
+    /// Statements run after the command, ending the function with the command's exit status.
+    fn preserve_status(&self, after: &[String]) -> String;
+
+    /// Statement noting when the command started, for [`ShellCodegen::report_time`].
+    fn start_timer(&self) -> String;
+
+    /// Statement printing to stderr how long `name` ran.
+    fn report_time(&self, name: &str) -> String;
+
+    /// Statement that adds `command`, with the function's arguments filled in, to the shell
+    /// history.
+    fn record_history(&self, command: &str) -> String;
+
+    /// Statement sending a desktop notification through `notifier` that `name` finished,
+    /// with its exit status.
+    fn notify(&self, name: &str, notifier: Notifier) -> String;
+
+    /// Statement that prints `message` to stderr while `AKA_DEBUG` is set.
+    fn trace(&self, message: &str) -> String;
+
+    /// Statement running the real `name` command when no definition applies.
+    fn passthrough(&self, name: &str) -> String;
+
+    /// Statement defining `name` as a native alias for `command`, which takes the arguments
+    /// at the end, or `None` when the shell's aliases cannot stand in for a function.
+    fn alias(&self, name: &str, command: &str) -> Option<String>;
+
+    /// Statement giving the function `name` the completion of the program `program`, or
+    /// `None` when the shell cannot borrow another command's completion.
+    fn inherit_completion(&self, name: &str, program: &str) -> Option<String>;
+
+    /// The `_aka_dispatch` function, which runs the definition that
+    /// `aka resolve --body --shell <shell>` prints for an alias at call time, or `None` when
+    /// the shell has no dispatcher.
+    fn dispatcher(&self, shell: &str) -> Option<String>;
+
+    /// One-line function `name` handing its arguments over to `_aka_dispatch`.
+    fn dispatch_stub(&self, name: &str) -> Option<String>;
+
+    /// Statements emitted after every alias: record the managed names and restore shell state.
+    fn finish(&self, out: &mut dyn Write, managed: &[String]) -> io::Result<()>;
+
+    /// Statement recording when the shell started evaluating the dump.
+    fn timing_start(&self, out: &mut dyn Write) -> io::Result<()>;
+
+    /// Statements printing to stderr how long evaluating the dump took, alongside the
+    /// `generated_us` microseconds aka spent writing it.
+    fn timing_report(
+        &self,
+        out: &mut dyn Write,
+        aliases: usize,
+        generated_us: u64,
+    ) -> io::Result<()>;
+}
+
+/// How a scope is described in trace messages and group headers.
+fn describe_scope(scope: &AliasScope) -> String {
+    match scope {
+        AliasScope::Exact(path) => format!("Exact {}", path),
+        AliasScope::Recursive(path) => format!("Recursive {}", path),
+        AliasScope::Tmux(session) => format!("Tmux {}", session),
+        AliasScope::Global => "Global".to_string(),
+    }
+}
+
+/// Header for the group an alias is dumped in: its directory scopes in precedence order,
+/// or `None` when it only has a global definition.
+fn group_label(defs: &[AliasDefinition]) -> Option<String> {
+    let mut defs = defs.to_vec();
+    sort_by_precedence(&mut defs);
+    let scopes: Vec<String> = defs
+        .iter()
+        .filter(|def| def.scope != AliasScope::Global)
+        .map(|def| describe_scope(&def.scope))
+        .collect();
+    (!scopes.is_empty()).then(|| scopes.join(", "))
+}
+
+/// Write a complete dump of `aliases` using `codegen`.
+///
+/// Aliases are grouped by scope under comment headers, global ones first, and sorted by name
+/// within each group so the output is the same from run to run.
+pub fn write_dump<I>(codegen: &dyn ShellCodegen, out: &mut dyn Write, aliases: I) -> io::Result<()>
+where
+    I: IntoIterator<Item = (String, Vec<AliasDefinition>)>,
+{
+    write_dump_with_options(codegen, out, aliases, DumpOptions::default())
+}
+
+/// Choices for [`write_dump_with_options`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DumpOptions {
+    /// Aliases that need nothing a function adds become native aliases, so completion and
+    /// `which` treat them as the shell's own. Native aliases skip `AKA_DISABLE` and the usage
+    /// log.
+    pub plain_aliases: bool,
+    /// Comments above each alias list its definitions in the order they are tried, for
+    /// finding out why a scoped definition does not apply.
+    pub annotate: bool,
+}
+
+/// [`write_dump`] with `options`.
+pub fn write_dump_with_options<I>(
+    codegen: &dyn ShellCodegen,
+    out: &mut dyn Write,
+    aliases: I,
+    options: DumpOptions,
+) -> io::Result<()>
+where
+    I: IntoIterator<Item = (String, Vec<AliasDefinition>)>,
+{
+    let mut groups: BTreeMap<Option<String>, Vec<(String, Vec<AliasDefinition>)>> = BTreeMap::new();
+    for (alias, defs) in aliases {
+        groups
+            .entry(group_label(&defs))
+            .or_default()
+            .push((alias, defs));
+    }
+
+    codegen.cleanup(out)?;
+    let mut managed = Vec::new();
+    for (label, mut aliases) in groups {
+        aliases.sort_by(|a, b| a.0.cmp(&b.0));
+        match label {
+            Some(scopes) => codegen.comment(out, &format!("Scoped: {}", scopes))?,
+            None => codegen.comment(out, "Global")?,
+        }
+        for (alias, defs) in aliases {
+            if options.annotate {
+                write_annotation(codegen, out, &alias, &defs)?;
+            }
+            let native = match plain_command(&defs) {
+                Some(command) if options.plain_aliases => codegen.alias(&alias, command),
+                _ => None,
+            };
+            match native {
+                Some(line) => writeln!(out, "{}", line)?,
+                None => write_alias(codegen, out, &alias, defs)?,
+            }
+            managed.push(alias);
+        }
+    }
+    codegen.finish(out, &managed)?;
+    out.flush()
+}
+
+/// Where a definition in `scope` applies, in words.
+fn scope_reach(scope: &AliasScope) -> String {
+    match scope {
+        AliasScope::Exact(path) => format!("in {} only", path),
+        AliasScope::Recursive(path) => format!("in {} and below", path),
+        AliasScope::Tmux(session) => format!("in tmux session {}", session),
+        AliasScope::Global => "anywhere".to_string(),
+    }
+}
+
+/// Comments describing `alias`: its definitions in the order the function tries them, and
+/// what runs when none applies.
+fn write_annotation(
+    codegen: &dyn ShellCodegen,
+    out: &mut dyn Write,
+    alias: &str,
+    defs: &[AliasDefinition],
+) -> io::Result<()> {
+    let mut defs = defs.to_vec();
+    sort_by_precedence(&mut defs);
+    let header = match defs.len() {
+        1 => format!("{}: 1 definition", alias),
+        n => format!("{}: {} definitions, the first that applies runs", alias, n),
+    };
+    codegen.note(out, &header)?;
+    for (i, def) in defs.iter().enumerate() {
+        codegen.note(
+            out,
+            &format!("  {}. {}: {}", i + 1, scope_reach(&def.scope), def.command),
+        )?;
+    }
+    if defs.iter().all(|def| def.scope != AliasScope::Global) {
+        codegen.note(out, &format!("  otherwise: the real {} command", alias))?;
+    }
+    Ok(())
+}
+
+/// The command of an alias with a single global definition that takes its arguments at the
+/// end and asks for nothing around it, which a native alias can stand in for.
+fn plain_command(defs: &[AliasDefinition]) -> Option<&str> {
+    let [def] = defs else {
+        return None;
+    };
+    let plain = def.scope == AliasScope::Global
+        && def.kube.is_none()
+        && def.env.is_empty()
+        && !def.write_history
+        && !def.time
+        && !def.notify
+        && def.log_output.is_none()
+        && ZshBashCodegen.command(&def.command) == format!("{} \"$@\"", def.command);
+    plain.then_some(def.command.as_str())
+}
+
+/// Write a dump of `aliases` already resolved for one directory, for the hook that redefines
+/// them on every directory change.
+///
+/// Each alias has the single definition that applies there, so its function runs it without
+/// testing the directory.
+pub fn write_resolved_dump<I>(
+    codegen: &dyn ShellCodegen,
+    out: &mut dyn Write,
+    current_dir: &str,
+    aliases: I,
+) -> io::Result<()>
+where
+    I: IntoIterator<Item = (String, AliasDefinition)>,
+{
+    let mut aliases: Vec<(String, AliasDefinition)> = aliases.into_iter().collect();
+    aliases.sort_by(|a, b| a.0.cmp(&b.0));
+
+    codegen.cleanup(out)?;
+    codegen.comment(out, &format!("Resolved for {}", current_dir))?;
+    let mut managed = Vec::new();
+    for (alias, def) in aliases {
+        let notifier = def.notify.then(Notifier::detect).flatten();
+        let body = definition_body(codegen, &alias, &def, notifier);
+        codegen.function(out, &alias, &codegen.conditional(&[], &body))?;
+        write_completion(codegen, out, &alias, std::slice::from_ref(&def))?;
+        managed.push(alias);
+    }
+    codegen.finish(out, &managed)?;
+    out.flush()
+}
+
+/// Write a dump where every alias is a one-line stub handing over to a single dispatcher,
+/// which asks `aka resolve --body --shell <shell>` for the definition to run at call time.
+///
+/// Sourcing it stays quick however many aliases there are, at the cost of running aka on each
+/// call. Shells without a dispatcher get [`write_dump`] instead.
+pub fn write_dispatch_dump<I>(
+    codegen: &dyn ShellCodegen,
+    out: &mut dyn Write,
+    shell: &str,
+    aliases: I,
+) -> io::Result<()>
+where
+    I: IntoIterator<Item = (String, Vec<AliasDefinition>)>,
+{
+    let Some(dispatcher) = codegen.dispatcher(shell) else {
+        return write_dump(codegen, out, aliases);
+    };
+    let mut aliases: Vec<(String, Vec<AliasDefinition>)> = aliases.into_iter().collect();
+    aliases.sort_by(|a, b| a.0.cmp(&b.0));
+
+    codegen.cleanup(out)?;
+    out.write_all(dispatcher.as_bytes())?;
+    codegen.comment(out, "Dispatched")?;
+    let mut managed = Vec::new();
+    for (alias, defs) in aliases {
+        if let Some(stub) = codegen.dispatch_stub(&alias) {
+            writeln!(out, "{}", stub)?;
+        }
+        write_completion(codegen, out, &alias, &defs)?;
+        managed.push(alias);
+    }
+    codegen.finish(out, &managed)?;
+    out.flush()
+}
+
+/// Statements running `def`, the definition of `alias` that applies, as the dispatcher of
+/// [`write_dispatch_dump`] evaluates them.
+pub fn dispatched_body(codegen: &dyn ShellCodegen, alias: &str, def: &AliasDefinition) -> String {
+    let notifier = def.notify.then(Notifier::detect).flatten();
+    definition_body(codegen, alias, def, notifier)
+}
+
+/// Emit the function for one alias, dispatching on its directory-scoped definitions.
+pub fn write_alias(
+    codegen: &dyn ShellCodegen,
+    out: &mut dyn Write,
+    alias: &str,
+    mut defs: Vec<AliasDefinition>,
+) -> io::Result<()> {
+    sort_by_precedence(&mut defs);
+    write_function(codegen, out, alias, &defs)?;
+    write_completion(codegen, out, alias, &defs)
+}
+
+/// Emit the function for `alias` alone, from `defs` sorted by precedence.
+pub(crate) fn write_function(
+    codegen: &dyn ShellCodegen,
+    out: &mut dyn Write,
+    alias: &str,
+    defs: &[AliasDefinition],
+) -> io::Result<()> {
+    let notifier = defs
+        .iter()
+        .any(|def| def.notify)
+        .then(Notifier::detect)
+        .flatten();
+
+    let mut branches = Vec::new();
+    let mut otherwise = None;
+    for def in defs {
+        let body = definition_body(codegen, alias, def, notifier);
+        match &def.scope {
+            AliasScope::Exact(path) => branches.push((DirTest::Exact(path), body)),
+            AliasScope::Recursive(path) => branches.push((DirTest::Recursive(path), body)),
+            AliasScope::Tmux(session) => branches.push((DirTest::TmuxSession(session), body)),
+            AliasScope::Global => otherwise = Some(body),
+        }
+    }
+    let otherwise = otherwise.unwrap_or_else(|| {
+        let message = format!(
+            "{}: no definition applies, running the command itself",
+            alias
+        );
+        format!(
+            "{}; {}",
+            codegen.trace(&message),
+            codegen.passthrough(alias)
+        )
+    });
+
+    let body = codegen.conditional(&branches, &otherwise);
+    codegen.function(out, alias, &body)
+}
+
+/// Words that start a compound command rather than name a program.
+const SHELL_KEYWORDS: &[&str] = &[
+    "case", "coproc", "do", "done", "elif", "else", "esac", "fi", "for", "function", "if", "in",
+    "select", "then", "time", "until", "while",
+];
+
+/// Give the function for `alias` the completion of the program its definitions run, when
+/// they all start with the same plain word other than the alias itself.
+pub(crate) fn write_completion(
+    codegen: &dyn ShellCodegen,
+    out: &mut dyn Write,
+    alias: &str,
+    defs: &[AliasDefinition],
+) -> io::Result<()> {
+    let mut programs = defs
+        .iter()
+        .map(|def| def.command.split_whitespace().next().unwrap_or_default());
+    let Some(program) = programs.next() else {
+        return Ok(());
+    };
+    let plain = !program.is_empty()
+        && program
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '+'));
+    if !plain
+        || program == alias
+        || SHELL_KEYWORDS.contains(&program)
+        || programs.any(|other| other != program)
+    {
+        return Ok(());
+    }
+    match codegen.inherit_completion(alias, program) {
+        Some(line) => writeln!(out, "{}", line),
+        None => Ok(()),
+    }
+}
+
+/// Statements running one definition of `alias` with everything it asks for around the
+/// command.
+fn definition_body(
+    codegen: &dyn ShellCodegen,
+    alias: &str,
+    def: &AliasDefinition,
+    notifier: Option<Notifier>,
+) -> String {
+    let mut body = codegen.command(&def.command);
+    if let Some(path) = &def.log_output {
+        body = codegen.log_output(&body, path);
+    }
+    let mut after = Vec::new();
+    if def.time {
+        body = format!("{}; {}", codegen.start_timer(), body);
+        after.push(codegen.report_time(alias));
+    }
+    if def.write_history {
+        after.push(codegen.record_history(&def.command));
+    }
+    if def.notify
+        && let Some(notifier) = notifier
+    {
+        after.push(codegen.notify(alias, notifier));
+    }
+    if !after.is_empty() {
+        body = format!("{}; {}", body, codegen.preserve_status(&after));
+    }
+    if !def.env.is_empty() {
+        body = format!("{}; {}", codegen.local_env(&def.env), body);
+    }
+    if let Some(guard) = &def.kube {
+        body = format!("{}; {}", codegen.kube_guard(alias, guard), body);
+    }
+    let message = format!(
+        "{}: {} -> {}",
+        alias,
+        describe_scope(&def.scope),
+        def.command
+    );
+    format!("{}; {}", codegen.trace(&message), body)
+}
@@ -0,0 +1,215 @@
+use super::zsh_bash::{
+    BASH_RESTORE_ALIASES, BASH_SAVE_ALIASES, CLEANUP_MANAGED, bash_inherit_completion,
+    history_template,
+};
+use super::{DirTest, Notifier, ShellCodegen, ZshBashCodegen};
+use crate::store::KubeGuard;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Functions for bash alone, written by `aka init --shell bash`.
+///
+/// The functions themselves are the ones [`ZshBashCodegen`] writes; only the setup and the
+/// history line skip the runtime check for zsh.
+pub struct BashCodegen;
+
+impl ShellCodegen for BashCodegen {
+    fn cleanup(&self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(BASH_SAVE_ALIASES.as_bytes())?;
+        out.write_all(CLEANUP_MANAGED.as_bytes())
+    }
+
+    fn comment(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        ZshBashCodegen.comment(out, text)
+    }
+
+    fn note(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        ZshBashCodegen.note(out, text)
+    }
+
+    fn function(&self, out: &mut dyn Write, name: &str, body: &str) -> io::Result<()> {
+        ZshBashCodegen.function(out, name, body)
+    }
+
+    fn conditional(&self, branches: &[(DirTest<'_>, String)], otherwise: &str) -> String {
+        ZshBashCodegen.conditional(branches, otherwise)
+    }
+
+    fn command(&self, command: &str) -> String {
+        ZshBashCodegen.command(command)
+    }
+
+    fn kube_guard(&self, name: &str, guard: &KubeGuard) -> String {
+        ZshBashCodegen.kube_guard(name, guard)
+    }
+
+    fn local_env(&self, env: &BTreeMap<String, String>) -> String {
+        ZshBashCodegen.local_env(env)
+    }
+
+    fn log_output(&self, body: &str, path: &str) -> String {
+        ZshBashCodegen.log_output(body, path)
+    }
+
+    fn preserve_status(&self, after: &[String]) -> String {
+        ZshBashCodegen.preserve_status(after)
+    }
+
+    fn start_timer(&self) -> String {
+        ZshBashCodegen.start_timer()
+    }
+
+    fn report_time(&self, name: &str) -> String {
+        ZshBashCodegen.report_time(name)
+    }
+
+    fn record_history(&self, command: &str) -> String {
+        format!(
+            "local _aka_line=\"{}\"; history -s -- \"$_aka_line\"",
+            history_template(command)
+        )
+    }
+
+    fn notify(&self, name: &str, notifier: Notifier) -> String {
+        ZshBashCodegen.notify(name, notifier)
+    }
+
+    fn trace(&self, message: &str) -> String {
+        ZshBashCodegen.trace(message)
+    }
+
+    fn passthrough(&self, name: &str) -> String {
+        ZshBashCodegen.passthrough(name)
+    }
+
+    fn alias(&self, name: &str, command: &str) -> Option<String> {
+        ZshBashCodegen.alias(name, command)
+    }
+
+    fn inherit_completion(&self, name: &str, program: &str) -> Option<String> {
+        Some(bash_inherit_completion(name, program))
+    }
+
+    fn dispatcher(&self, shell: &str) -> Option<String> {
+        ZshBashCodegen.dispatcher(shell)
+    }
+
+    fn dispatch_stub(&self, name: &str) -> Option<String> {
+        ZshBashCodegen.dispatch_stub(name)
+    }
+
+    fn finish(&self, out: &mut dyn Write, managed: &[String]) -> io::Result<()> {
+        writeln!(out, "export AKA_MANAGED_ALIASES=\"{}\"", managed.join(" "))?;
+        out.write_all(BASH_RESTORE_ALIASES.as_bytes())?;
+        out.write_all(b"unset _aka_aliases_was_on\n")
+    }
+
+    fn timing_start(&self, out: &mut dyn Write) -> io::Result<()> {
+        // EPOCHREALTIME needs bash 5; without it only generation time is shown
+        writeln!(out, "_aka_timing_start=\"$EPOCHREALTIME\"")
+    }
+
+    fn timing_report(
+        &self,
+        out: &mut dyn Write,
+        aliases: usize,
+        generated_us: u64,
+    ) -> io::Result<()> {
+        ZshBashCodegen.timing_report(out, aliases, generated_us)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::write_dump;
+    use crate::store::{AliasDefinition, AliasScope};
+    use std::process::Command;
+
+    const GOLDEN: &str = include_str!("../../tests/golden/bash.bash");
+
+    #[test]
+    fn test_dump_matches_golden_file() {
+        let aliases = vec![
+            (
+                "ll".to_string(),
+                vec![AliasDefinition::new(
+                    "ls -la".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+            (
+                "greet".to_string(),
+                vec![
+                    AliasDefinition::new("echo hi".to_string(), AliasScope::Global),
+                    AliasDefinition::new(
+                        "echo hello @1".to_string(),
+                        AliasScope::Recursive("/work".to_string()),
+                    ),
+                ],
+            ),
+        ];
+
+        let mut out = Vec::new();
+        write_dump(&BashCodegen, &mut out, aliases).unwrap();
+        let script = String::from_utf8(out).unwrap();
+        assert_eq!(script, GOLDEN);
+        assert!(!script.contains("ZSH_VERSION"));
+    }
+
+    #[test]
+    fn test_dump_runs_in_bash() {
+        let mut def = AliasDefinition::new("echo hello @1".to_string(), AliasScope::Global);
+        def.write_history = true;
+        let mut out = Vec::new();
+        write_dump(
+            &BashCodegen,
+            &mut out,
+            vec![("greet".to_string(), vec![def])],
+        )
+        .unwrap();
+        let script = format!("{}greet world", String::from_utf8(out).unwrap());
+
+        let Ok(output) = Command::new("bash").arg("-c").arg(&script).output() else {
+            return;
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello world\n");
+    }
+
+    #[test]
+    fn test_functions_inherit_completion() {
+        let aliases = vec![
+            (
+                "g".to_string(),
+                vec![AliasDefinition::new(
+                    "git status".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+            (
+                "mixed".to_string(),
+                vec![
+                    AliasDefinition::new("git log".to_string(), AliasScope::Global),
+                    AliasDefinition::new(
+                        "tig".to_string(),
+                        AliasScope::Recursive("/work".to_string()),
+                    ),
+                ],
+            ),
+        ];
+        let mut out = Vec::new();
+        write_dump(&BashCodegen, &mut out, aliases).unwrap();
+        let script = format!(
+            "_git_test() {{ :; }}\ncomplete -o default -F _git_test git\n{}complete -p g; complete -p mixed",
+            String::from_utf8(out).unwrap()
+        );
+
+        let Ok(output) = Command::new("bash").arg("-c").arg(&script).output() else {
+            return;
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "complete -o default -F _git_test g\n"
+        );
+    }
+}
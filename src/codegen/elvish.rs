@@ -0,0 +1,232 @@
+use super::{DirTest, Notifier, ShellCodegen, ZshBashCodegen};
+use crate::store::KubeGuard;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Functions for elvish, loaded with `eval (aka init --dump --shell elvish | slurp)`.
+///
+/// elvish cannot run POSIX shell, so it only picks the definition from `$pwd` and each
+/// branch runs in bash, as for nushell; everything inside a branch is delegated to
+/// [`ZshBashCodegen`]. Functions are added to the interactive namespace with `edit:add-var`,
+/// since `eval` defines them in a namespace of its own.
+pub struct ElvishCodegen;
+
+impl ShellCodegen for ElvishCodegen {
+    fn cleanup(&self, out: &mut dyn Write) -> io::Result<()> {
+        // Functions removed from aka are replaced on reload but stay defined until the next
+        // shell, as elvish cannot delete a variable from the interactive namespace
+        writeln!(out, "use str")
+    }
+
+    fn comment(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        writeln!(out, "\n# {}", text.replace('\n', " "))
+    }
+
+    fn note(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        writeln!(out, "# {}", text.replace('\n', " "))
+    }
+
+    fn function(&self, out: &mut dyn Write, name: &str, body: &str) -> io::Result<()> {
+        writeln!(out, "fn {} {{|@args|", name)?;
+        writeln!(
+            out,
+            "    if (not-eq $E:AKA_DISABLE '') {{ e:{} $@args; return }}",
+            name
+        )?;
+        writeln!(out, "    var current_dir = $pwd")?;
+        writeln!(
+            out,
+            "    if (not-eq $E:AKA_USAGE_LOG '') {{ echo {} >> $E:AKA_USAGE_LOG }}",
+            quote(name)
+        )?;
+        out.write_all(body.as_bytes())?;
+        writeln!(out, "}}")?;
+        writeln!(out, "edit:add-var {}~ ${}~", name, name)
+    }
+
+    fn conditional(&self, branches: &[(DirTest<'_>, String)], otherwise: &str) -> String {
+        let mut body = String::new();
+        for (i, (test, command)) in branches.iter().enumerate() {
+            let op = if i == 0 { "    if" } else { " elif" };
+            let test = match test {
+                DirTest::Exact(path) => format!("(eq $current_dir {})", quote(path)),
+                DirTest::Recursive(path) => format!(
+                    "(or (eq $current_dir {}) (str:has-prefix $current_dir {}))",
+                    quote(path),
+                    quote(&DirTest::subdir_prefix(path))
+                ),
+                DirTest::TmuxSession(session) => format!(
+                    "(and (not-eq $E:TMUX '') (eq (str:trim-space (e:tmux display-message -p '#S' | slurp)) {}))",
+                    quote(session)
+                ),
+            };
+            body.push_str(&format!("{} {} {{\n", op, test));
+            body.push_str(&format!("        {}\n    }}", run_in_bash(command)));
+        }
+        if branches.is_empty() {
+            body.push_str(&format!("    {}\n", run_in_bash(otherwise)));
+        } else {
+            body.push_str(" else {\n");
+            body.push_str(&format!("        {}\n    }}\n", run_in_bash(otherwise)));
+        }
+        body
+    }
+
+    fn command(&self, command: &str) -> String {
+        ZshBashCodegen.command(command)
+    }
+
+    fn kube_guard(&self, name: &str, guard: &KubeGuard) -> String {
+        ZshBashCodegen.kube_guard(name, guard)
+    }
+
+    fn local_env(&self, env: &BTreeMap<String, String>) -> String {
+        ZshBashCodegen.local_env(env)
+    }
+
+    fn log_output(&self, body: &str, path: &str) -> String {
+        ZshBashCodegen.log_output(body, path)
+    }
+
+    fn preserve_status(&self, after: &[String]) -> String {
+        ZshBashCodegen.preserve_status(after)
+    }
+
+    fn start_timer(&self) -> String {
+        ZshBashCodegen.start_timer()
+    }
+
+    fn report_time(&self, name: &str) -> String {
+        ZshBashCodegen.report_time(name)
+    }
+
+    fn record_history(&self, _command: &str) -> String {
+        // The bash running the command is not the interactive shell, so there is no history
+        // to add to
+        ":".to_string()
+    }
+
+    fn notify(&self, name: &str, notifier: Notifier) -> String {
+        ZshBashCodegen.notify(name, notifier)
+    }
+
+    fn trace(&self, message: &str) -> String {
+        ZshBashCodegen.trace(message)
+    }
+
+    fn passthrough(&self, name: &str) -> String {
+        ZshBashCodegen.passthrough(name)
+    }
+
+    fn alias(&self, _name: &str, _command: &str) -> Option<String> {
+        // elvish has no aliases
+        None
+    }
+
+    fn inherit_completion(&self, _name: &str, _program: &str) -> Option<String> {
+        // elvish completers are keyed by command and set up by the user
+        None
+    }
+
+    fn dispatcher(&self, _shell: &str) -> Option<String> {
+        // Not written for elvish; the full dump is used
+        None
+    }
+
+    fn dispatch_stub(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    fn finish(&self, out: &mut dyn Write, managed: &[String]) -> io::Result<()> {
+        writeln!(
+            out,
+            "set E:AKA_MANAGED_ALIASES = {}",
+            quote(&managed.join(" "))
+        )
+    }
+
+    fn timing_start(&self, out: &mut dyn Write) -> io::Result<()> {
+        // `date +%N` is GNU only, as for fish
+        writeln!(out, "var _aka_timing_start = (e:date +%s%N)")
+    }
+
+    fn timing_report(
+        &self,
+        out: &mut dyn Write,
+        aliases: usize,
+        generated_us: u64,
+    ) -> io::Result<()> {
+        writeln!(
+            out,
+            "printf \"aka: %s aliases, generated in %s ms, eval took %.1f ms\\n\" {} {:.1} (/ (- (e:date +%s%N) $_aka_timing_start) 1000000.0) >&2",
+            aliases,
+            generated_us as f64 / 1000.0
+        )
+    }
+}
+
+/// `body`, a bash function body, run by bash with the function's arguments.
+fn run_in_bash(body: &str) -> String {
+    format!(
+        "e:bash -c {} aka $@args",
+        quote(&format!("_aka() {{ {}; }}; _aka \"$@\"", body))
+    )
+}
+
+/// `value` in elvish single quotes, where a quote is written twice.
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::write_dump;
+    use crate::store::{AliasDefinition, AliasScope};
+
+    const GOLDEN: &str = include_str!("../../tests/golden/elvish.elv");
+
+    #[test]
+    fn test_dump_matches_golden_file() {
+        let aliases = vec![
+            (
+                "ll".to_string(),
+                vec![AliasDefinition::new(
+                    "ls -la".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+            (
+                "build".to_string(),
+                vec![
+                    AliasDefinition::new("make".to_string(), AliasScope::Global),
+                    AliasDefinition::new(
+                        "cargo build".to_string(),
+                        AliasScope::Recursive("/work/rust".to_string()),
+                    ),
+                    AliasDefinition::new(
+                        "cargo build -p app".to_string(),
+                        AliasScope::Exact("/work/rust/app".to_string()),
+                    ),
+                ],
+            ),
+            (
+                "gcm".to_string(),
+                vec![AliasDefinition::new(
+                    "git commit -m \"@1\" && awk '{print @2}'".to_string(),
+                    AliasScope::Tmux("work".to_string()),
+                )],
+            ),
+        ];
+
+        let mut out = Vec::new();
+        write_dump(&ElvishCodegen, &mut out, aliases).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), GOLDEN);
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("/work/rust"), "'/work/rust'");
+        assert_eq!(quote("it's"), "'it''s'");
+    }
+}
@@ -0,0 +1,465 @@
+use super::{DirTest, LOG_OUTPUT_MAX_BYTES, Notifier, ShellCodegen};
+use crate::store::KubeGuard;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Functions for fish, loaded with `aka init --dump --shell fish | source`.
+pub struct FishCodegen;
+
+impl ShellCodegen for FishCodegen {
+    fn cleanup(&self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(
+            concat!(
+                "for al in (string split -n ' ' -- \"$AKA_MANAGED_ALIASES\")\n",
+                "    functions -e $al\n",
+                "end\n",
+                // fish can only set $status by returning from a function
+                "function _aka_set_status\n",
+                "    return $argv[1]\n",
+                "end\n",
+            )
+            .as_bytes(),
+        )
+    }
+
+    fn comment(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        writeln!(out, "\n# {}", text.replace('\n', " "))
+    }
+
+    fn note(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        writeln!(out, "# {}", text.replace('\n', " "))
+    }
+
+    fn function(&self, out: &mut dyn Write, name: &str, body: &str) -> io::Result<()> {
+        writeln!(out, "functions -e {}", name)?;
+        writeln!(out, "function {}", name)?;
+        writeln!(
+            out,
+            "    if test -n \"$AKA_DISABLE\"; {}; return; end",
+            self.passthrough(name)
+        )?;
+        writeln!(out, "    set -l current_dir $PWD")?;
+        writeln!(
+            out,
+            "    if test -n \"$AKA_USAGE_LOG\"; printf '%s\\n' {} >> $AKA_USAGE_LOG; end",
+            name
+        )?;
+        out.write_all(body.as_bytes())?;
+        writeln!(out, "end")
+    }
+
+    fn conditional(&self, branches: &[(DirTest<'_>, String)], otherwise: &str) -> String {
+        let mut body = String::new();
+        for (i, (test, command)) in branches.iter().enumerate() {
+            let op = if i == 0 { "if" } else { "else if" };
+            // `contains` rather than `test` so an empty substitution is a mismatch, not an error
+            let test = match test {
+                DirTest::Exact(path) => format!("test \"$current_dir\" = {}", quote(path)),
+                DirTest::Recursive(path) => {
+                    let prefix = DirTest::subdir_prefix(path);
+                    format!(
+                        "test \"$current_dir\" = {}; or contains -- {} (string sub -l {} -- $current_dir)",
+                        quote(path),
+                        quote(&prefix),
+                        prefix.chars().count()
+                    )
+                }
+                DirTest::TmuxSession(session) => format!(
+                    "test -n \"$TMUX\"; and contains -- {} (tmux display-message -p '#S' 2>/dev/null)",
+                    quote(session)
+                ),
+            };
+            body.push_str(&format!("    {} {}\n", op, test));
+            body.push_str(&format!("        {}\n", command));
+        }
+        if !branches.is_empty() {
+            body.push_str("    else\n");
+        }
+        body.push_str(&format!("        {}\n", otherwise));
+        if !branches.is_empty() {
+            body.push_str("    end\n");
+        }
+        body
+    }
+
+    fn command(&self, command: &str) -> String {
+        let (command, positional) = replace_placeholders(command);
+        if positional || command.contains("$argv") {
+            command
+        } else {
+            format!("{} $argv", command)
+        }
+    }
+
+    fn kube_guard(&self, name: &str, guard: &KubeGuard) -> String {
+        let context = "set -l _aka_context (kubectl config current-context 2>/dev/null); switch \"$_aka_context\"";
+        match guard {
+            KubeGuard::Only(pattern) => format!(
+                "{}; case {}; case '*'; echo \"aka: '{}' only runs in kube context {}\" >&2; return 1; end",
+                context,
+                quote(pattern),
+                name,
+                pattern
+            ),
+            KubeGuard::Deny(pattern) => format!(
+                "{}; case {}; echo \"aka: '{}' refuses to run in kube context {}\" >&2; return 1; end",
+                context,
+                quote(pattern),
+                name,
+                pattern
+            ),
+        }
+    }
+
+    fn local_env(&self, env: &BTreeMap<String, String>) -> String {
+        let assignments: Vec<String> = env
+            .iter()
+            .map(|(name, value)| format!("set -lx {} {}", name, quote(value)))
+            .collect();
+        assignments.join("; ")
+    }
+
+    fn log_output(&self, body: &str, path: &str) -> String {
+        format!(
+            concat!(
+                "set -l _aka_log {}; ",
+                "if test -f $_aka_log; and test (wc -c < $_aka_log | string trim) -gt {}; ",
+                "mv -f -- $_aka_log $_aka_log.1; end; ",
+                "begin; {}; end 2>&1 | tee -a -- $_aka_log; ",
+                "_aka_set_status $pipestatus[1]"
+            ),
+            quote(path),
+            LOG_OUTPUT_MAX_BYTES,
+            body
+        )
+    }
+
+    fn preserve_status(&self, after: &[String]) -> String {
+        format!(
+            "set -l _aka_status $status; {}; return $_aka_status",
+            after.join("; ")
+        )
+    }
+
+    fn start_timer(&self) -> String {
+        "set -l _aka_started (date +%s)".to_string()
+    }
+
+    fn report_time(&self, name: &str) -> String {
+        format!(
+            "printf 'aka: %s finished in %ss\\n' {} (math (date +%s) - $_aka_started) >&2",
+            name
+        )
+    }
+
+    fn record_history(&self, command: &str) -> String {
+        // `history append` is missing from older fish, which then just skips the entry
+        format!(
+            "set -l _aka_line {}; builtin history append -- \"$_aka_line\" 2>/dev/null",
+            history_template(command)
+        )
+    }
+
+    fn notify(&self, name: &str, notifier: Notifier) -> String {
+        let message = "finished with exit status $_aka_status";
+        match notifier {
+            Notifier::Osascript => format!(
+                r#"osascript -e "display notification \"{}\" with title \"aka: {}\"" >/dev/null 2>&1"#,
+                message, name
+            ),
+            Notifier::NotifySend => format!(
+                r#"notify-send "aka: {}" "{}" >/dev/null 2>&1"#,
+                name, message
+            ),
+        }
+    }
+
+    fn trace(&self, message: &str) -> String {
+        format!(
+            "if test -n \"$AKA_DEBUG\"; printf 'aka: %s\\n' {} >&2; end",
+            quote(message)
+        )
+    }
+
+    fn passthrough(&self, name: &str) -> String {
+        format!("command {} $argv", name)
+    }
+
+    fn alias(&self, name: &str, command: &str) -> Option<String> {
+        Some(format!("alias {} {}", name, quote(command)))
+    }
+
+    fn inherit_completion(&self, name: &str, program: &str) -> Option<String> {
+        Some(format!("complete -c {} -w {}", name, program))
+    }
+
+    fn dispatcher(&self, _shell: &str) -> Option<String> {
+        // fish keeps the full dump; `init --dispatch` is refused for it
+        None
+    }
+
+    fn dispatch_stub(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    fn finish(&self, out: &mut dyn Write, managed: &[String]) -> io::Result<()> {
+        writeln!(
+            out,
+            "set -gx AKA_MANAGED_ALIASES {}",
+            quote(&managed.join(" "))
+        )
+    }
+
+    fn timing_start(&self, out: &mut dyn Write) -> io::Result<()> {
+        // `date +%N` is GNU only; elsewhere only generation time is shown
+        writeln!(out, "set -g _aka_timing_start (date +%s%N 2>/dev/null)")
+    }
+
+    fn timing_report(
+        &self,
+        out: &mut dyn Write,
+        aliases: usize,
+        generated_us: u64,
+    ) -> io::Result<()> {
+        writeln!(
+            out,
+            concat!(
+                "set -g _aka_timing_eval unknown\n",
+                "if string match -qr '^[0-9]+$' -- \"$_aka_timing_start\"\n",
+                "    set -l _aka_now (date +%s%N)\n",
+                "    set _aka_timing_eval (math --scale 1 \"($_aka_now - $_aka_timing_start) / 1000000\")' ms'\n",
+                "end\n",
+                "printf 'aka: %s aliases, generated in %s ms, eval took %s\\n' {} '{:.1}' \"$_aka_timing_eval\" >&2\n",
+                "set -e _aka_timing_start _aka_timing_eval",
+            ),
+            aliases,
+            generated_us as f64 / 1000.0
+        )
+    }
+}
+
+/// `value` in fish single quotes, where only `\` and `'` need escaping.
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+/// Turn `@N` placeholders into `$argv[N]`, returning whether any were found.
+///
+/// fish does not split variables into words, so unquoted placeholders need no quotes.
+/// Inside single quotes the text is kept as `$N` for tools such as awk, and `\@` escapes a
+/// literal `@`, as in the zsh and bash output.
+fn replace_placeholders(command: &str) -> (String, bool) {
+    let mut output = String::with_capacity(command.len());
+    let mut chars = command.chars().peekable();
+    let mut in_single_quote = false;
+    let mut in_double_quote = false;
+    let mut escaped = false;
+    let mut positional = false;
+
+    while let Some(c) = chars.next() {
+        if escaped {
+            escaped = false;
+            output.push(c);
+            continue;
+        }
+
+        match c {
+            '\\' if !in_single_quote => escaped = true,
+            '\'' if !in_double_quote => in_single_quote = !in_single_quote,
+            '"' if !in_single_quote => in_double_quote = !in_double_quote,
+            '@' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                let mut index = String::new();
+                while let Some(&d) = chars.peek()
+                    && d.is_ascii_digit()
+                {
+                    index.push(d);
+                    chars.next();
+                }
+                if in_single_quote {
+                    output.push_str(&format!("${}", index));
+                } else {
+                    output.push_str(&format!("$argv[{}]", index));
+                    positional = true;
+                }
+                continue;
+            }
+            _ => {}
+        }
+        output.push(c);
+    }
+    (output, positional)
+}
+
+/// fish words that concatenate to `command` as it ran, for the history.
+///
+/// Literal text is single-quoted; unquoted placeholders are filled with the argument
+/// re-quoted by `string escape`, and quoted ones with the argument as-is. Commands without
+/// placeholders get the forwarded arguments as further words.
+fn history_template(command: &str) -> String {
+    let mut words = String::new();
+    let mut literal = String::new();
+    let mut chars = command.chars().peekable();
+    let mut in_single_quote = false;
+    let mut in_double_quote = false;
+    let mut escaped = false;
+    let mut positional = false;
+
+    while let Some(c) = chars.next() {
+        if escaped {
+            escaped = false;
+        } else {
+            match c {
+                '\\' if !in_single_quote => escaped = true,
+                '\'' if !in_double_quote => in_single_quote = !in_single_quote,
+                '"' if !in_single_quote => in_double_quote = !in_double_quote,
+                '@' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                    let mut index = String::new();
+                    while let Some(&d) = chars.peek()
+                        && d.is_ascii_digit()
+                    {
+                        index.push(d);
+                        chars.next();
+                    }
+                    if in_single_quote {
+                        literal.push_str(&format!("${}", index));
+                        continue;
+                    }
+                    words.push_str(&quote(&std::mem::take(&mut literal)));
+                    if in_double_quote {
+                        words.push_str(&format!("\"$argv[{}]\"", index));
+                    } else {
+                        words.push_str(&format!("(string escape -- \"$argv[{}]\")", index));
+                    }
+                    positional = true;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        literal.push(c);
+    }
+    if !literal.is_empty() || words.is_empty() {
+        words.push_str(&quote(&literal));
+    }
+
+    if !positional && !command.contains("$argv") {
+        words.push_str(" (string escape -- $argv)");
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::write_dump;
+    use crate::store::{AliasDefinition, AliasScope};
+    use std::process::{Command, Output};
+
+    const GOLDEN: &str = include_str!("../../tests/golden/fish.fish");
+
+    fn run_fish(script: &str, args: &[&str]) -> Option<Output> {
+        Command::new("fish")
+            .arg("--no-config")
+            .arg("-c")
+            .arg(script)
+            .args(args)
+            .output()
+            .ok()
+    }
+
+    #[test]
+    fn test_dump_matches_golden_file() {
+        let aliases = vec![
+            (
+                "ll".to_string(),
+                vec![AliasDefinition::new(
+                    "ls -la".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+            (
+                "build".to_string(),
+                vec![
+                    AliasDefinition::new("make".to_string(), AliasScope::Global),
+                    AliasDefinition::new(
+                        "cargo build".to_string(),
+                        AliasScope::Recursive("/work/rust".to_string()),
+                    ),
+                    AliasDefinition::new(
+                        "cargo build -p app".to_string(),
+                        AliasScope::Exact("/work/rust/app".to_string()),
+                    ),
+                ],
+            ),
+            (
+                "gcm".to_string(),
+                vec![AliasDefinition::new(
+                    "git commit -m \"@1\" && awk '{print @2}'".to_string(),
+                    AliasScope::Tmux("work".to_string()),
+                )],
+            ),
+        ];
+
+        let mut out = Vec::new();
+        write_dump(&FishCodegen, &mut out, aliases).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), GOLDEN);
+    }
+
+    #[test]
+    fn test_replace_placeholders() {
+        assert_eq!(
+            replace_placeholders("echo @1 \"x @12\" '{print @2}' \\@3"),
+            (
+                "echo $argv[1] \"x $argv[12]\" '{print $2}' \\@3".to_string(),
+                true
+            )
+        );
+        assert_eq!(FishCodegen.command("ls -la"), "ls -la $argv");
+        assert_eq!(quote(r"it's \n"), r"'it\'s \\n'");
+    }
+
+    #[test]
+    fn test_history_template() {
+        assert_eq!(
+            history_template("git commit -m @1"),
+            "'git commit -m '(string escape -- \"$argv[1]\")"
+        );
+        assert_eq!(
+            history_template("echo \"hi @1\""),
+            "'echo \"hi '\"$argv[1]\"'\"'"
+        );
+        assert_eq!(history_template("ls"), "'ls' (string escape -- $argv)");
+    }
+
+    #[test]
+    fn test_scoped_function_runs_in_fish() {
+        let mut out = Vec::new();
+        write_dump(
+            &FishCodegen,
+            &mut out,
+            vec![(
+                "f".to_string(),
+                vec![
+                    AliasDefinition::new("echo global".to_string(), AliasScope::Global),
+                    AliasDefinition::new(
+                        "echo tmp @1".to_string(),
+                        AliasScope::Recursive("/tmp".to_string()),
+                    ),
+                ],
+            )],
+        )
+        .unwrap();
+        let script = format!(
+            "{}\ncd /tmp; f a; cd /; f b; echo $AKA_MANAGED_ALIASES",
+            String::from_utf8(out).unwrap()
+        );
+
+        let Some(output) = run_fish(&script, &[]) else {
+            return;
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "tmp a\nglobal b\nf\n"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use super::{DirTest, Notifier, ShellCodegen, ZshBashCodegen};
+use crate::store::KubeGuard;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Commands for nushell, loaded by the hook from `aka init --shell nu`.
+///
+/// Stored commands are POSIX shell, so nushell only picks the definition from `$env.PWD`
+/// and each branch runs in bash as the zsh/bash code would; everything inside a branch is
+/// delegated to [`ZshBashCodegen`].
+pub struct NuCodegen;
+
+impl ShellCodegen for NuCodegen {
+    fn cleanup(&self, out: &mut dyn Write) -> io::Result<()> {
+        // nushell cannot remove a command from the scope, so aliases removed from aka stay
+        // defined until the next shell
+        writeln!(out, "# Generated by aka init --dump --shell nu")
+    }
+
+    fn comment(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        writeln!(out, "\n# {}", text.replace('\n', " "))
+    }
+
+    fn note(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        writeln!(out, "# {}", text.replace('\n', " "))
+    }
+
+    fn function(&self, out: &mut dyn Write, name: &str, body: &str) -> io::Result<()> {
+        writeln!(out, "def --wrapped {} [...rest] {{", name)?;
+        writeln!(
+            out,
+            "    if ($env.AKA_DISABLE? | is-not-empty) {{ ^{} ...$rest; return }}",
+            name
+        )?;
+        writeln!(out, "    let current_dir = $env.PWD")?;
+        writeln!(
+            out,
+            "    if ($env.AKA_USAGE_LOG? | is-not-empty) {{ {} + \"\\n\" | save --append $env.AKA_USAGE_LOG }}",
+            quote(name)
+        )?;
+        out.write_all(body.as_bytes())?;
+        writeln!(out, "}}")
+    }
+
+    fn conditional(&self, branches: &[(DirTest<'_>, String)], otherwise: &str) -> String {
+        let mut body = String::new();
+        for (i, (test, command)) in branches.iter().enumerate() {
+            let op = if i == 0 { "    if" } else { " else if" };
+            let test = match test {
+                DirTest::Exact(path) => format!("$current_dir == {}", quote(path)),
+                DirTest::Recursive(path) => format!(
+                    "$current_dir == {} or ($current_dir | str starts-with {})",
+                    quote(path),
+                    quote(&DirTest::subdir_prefix(path))
+                ),
+                DirTest::TmuxSession(session) => format!(
+                    "($env.TMUX? | is-not-empty) and ((^tmux display-message -p '#S' | complete | get stdout | str trim) == {})",
+                    quote(session)
+                ),
+            };
+            body.push_str(&format!("{} {} {{\n", op, test));
+            body.push_str(&format!("        {}\n    }}", run_in_bash(command)));
+        }
+        if branches.is_empty() {
+            body.push_str(&format!("    {}\n", run_in_bash(otherwise)));
+        } else {
+            body.push_str(" else {\n");
+            body.push_str(&format!("        {}\n    }}\n", run_in_bash(otherwise)));
+        }
+        body
+    }
+
+    fn command(&self, command: &str) -> String {
+        ZshBashCodegen.command(command)
+    }
+
+    fn kube_guard(&self, name: &str, guard: &KubeGuard) -> String {
+        ZshBashCodegen.kube_guard(name, guard)
+    }
+
+    fn local_env(&self, env: &BTreeMap<String, String>) -> String {
+        ZshBashCodegen.local_env(env)
+    }
+
+    fn log_output(&self, body: &str, path: &str) -> String {
+        ZshBashCodegen.log_output(body, path)
+    }
+
+    fn preserve_status(&self, after: &[String]) -> String {
+        ZshBashCodegen.preserve_status(after)
+    }
+
+    fn start_timer(&self) -> String {
+        ZshBashCodegen.start_timer()
+    }
+
+    fn report_time(&self, name: &str) -> String {
+        ZshBashCodegen.report_time(name)
+    }
+
+    fn record_history(&self, _command: &str) -> String {
+        // The bash running the command is not the interactive shell, so there is no history
+        // to add to
+        ":".to_string()
+    }
+
+    fn notify(&self, name: &str, notifier: Notifier) -> String {
+        ZshBashCodegen.notify(name, notifier)
+    }
+
+    fn trace(&self, message: &str) -> String {
+        ZshBashCodegen.trace(message)
+    }
+
+    fn passthrough(&self, name: &str) -> String {
+        ZshBashCodegen.passthrough(name)
+    }
+
+    fn alias(&self, _name: &str, _command: &str) -> Option<String> {
+        // nushell aliases run nu commands, while stored commands are run by bash
+        None
+    }
+
+    fn inherit_completion(&self, _name: &str, _program: &str) -> Option<String> {
+        // nushell completes externals through its own completer
+        None
+    }
+
+    fn dispatcher(&self, _shell: &str) -> Option<String> {
+        // nu parses the dump once at startup and cannot eval a fetched body
+        None
+    }
+
+    fn dispatch_stub(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    fn finish(&self, out: &mut dyn Write, managed: &[String]) -> io::Result<()> {
+        writeln!(
+            out,
+            "$env.AKA_MANAGED_ALIASES = {}",
+            quote(&managed.join(" "))
+        )
+    }
+
+    fn timing_start(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "let _aka_timing_start = (date now)")
+    }
+
+    fn timing_report(
+        &self,
+        out: &mut dyn Write,
+        aliases: usize,
+        generated_us: u64,
+    ) -> io::Result<()> {
+        writeln!(
+            out,
+            "print -e $\"aka: {} aliases, generated in {:.1} ms, eval took ((date now) - $_aka_timing_start)\"",
+            aliases,
+            generated_us as f64 / 1000.0
+        )
+    }
+}
+
+/// `body`, a bash function body, run by bash with the command's arguments.
+fn run_in_bash(body: &str) -> String {
+    format!(
+        "^bash -c {} aka ...$rest",
+        quote(&format!("_aka() {{ {}; }}; _aka \"$@\"", body))
+    )
+}
+
+/// `value` as a nushell string literal: single quoted, or a raw string when it contains
+/// a single quote, since neither has escapes.
+pub fn quote(value: &str) -> String {
+    if !value.contains('\'') {
+        return format!("'{}'", value);
+    }
+    let mut hashes = "#".to_string();
+    while value.contains(&format!("'{}", hashes)) {
+        hashes.push('#');
+    }
+    format!("r{}'{}'{}", hashes, value, hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::write_dump;
+    use crate::store::{AliasDefinition, AliasScope};
+    use std::process::Command;
+
+    const GOLDEN: &str = include_str!("../../tests/golden/nu.nu");
+
+    #[test]
+    fn test_dump_matches_golden_file() {
+        let aliases = vec![
+            (
+                "ll".to_string(),
+                vec![AliasDefinition::new(
+                    "ls -la".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+            (
+                "build".to_string(),
+                vec![
+                    AliasDefinition::new("make".to_string(), AliasScope::Global),
+                    AliasDefinition::new(
+                        "cargo build".to_string(),
+                        AliasScope::Recursive("/work/rust".to_string()),
+                    ),
+                    AliasDefinition::new(
+                        "cargo build -p app".to_string(),
+                        AliasScope::Exact("/work/rust/app".to_string()),
+                    ),
+                ],
+            ),
+            (
+                "gcm".to_string(),
+                vec![AliasDefinition::new(
+                    "git commit -m \"@1\" && awk '{print @2}'".to_string(),
+                    AliasScope::Tmux("work".to_string()),
+                )],
+            ),
+        ];
+
+        let mut out = Vec::new();
+        write_dump(&NuCodegen, &mut out, aliases).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), GOLDEN);
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("/work/rust"), "'/work/rust'");
+        assert_eq!(quote("it's"), "r#'it's'#");
+        assert_eq!(quote("a'#b"), "r##'a'#b'##");
+    }
+
+    #[test]
+    fn test_branch_runs_in_bash() {
+        let body = format!(
+            "{}; {}",
+            NuCodegen.trace("say: Global -> echo @1"),
+            NuCodegen.command("echo \"it's\" @1")
+        );
+        let script = format!("_aka() {{ {}; }}; _aka \"$@\"", body);
+        let Ok(output) = Command::new("bash")
+            .arg("-c")
+            .arg(&script)
+            .args(["aka", "two words"])
+            .output()
+        else {
+            return;
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "it's two words\n");
+        assert!(run_in_bash(&body).starts_with("^bash -c r#'_aka() { "));
+    }
+}
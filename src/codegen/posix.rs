@@ -0,0 +1,275 @@
+use super::zsh_bash::CLEANUP_MANAGED;
+use super::{DirTest, LOG_OUTPUT_MAX_BYTES, Notifier, ShellCodegen, ZshBashCodegen};
+use crate::store::KubeGuard;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Functions for POSIX sh, written by `aka init --dump --posix` for dash, ash or busybox sh
+/// in minimal containers.
+///
+/// The commands are the ones [`ZshBashCodegen`] writes; the function frame avoids `[[ ]]`
+/// and `local`, so the state it keeps lives in `_aka_` globals. sh has no function-local
+/// exports either, so environment captured with `add --capture-env` stays exported once the
+/// alias has run.
+pub struct PosixCodegen;
+
+/// Whether sh accepts `name` as a function name: letters, digits and underscores, not
+/// starting with a digit.
+fn is_function_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl ShellCodegen for PosixCodegen {
+    fn cleanup(&self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(CLEANUP_MANAGED.as_bytes())
+    }
+
+    fn comment(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        ZshBashCodegen.comment(out, text)
+    }
+
+    fn note(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        ZshBashCodegen.note(out, text)
+    }
+
+    fn function(&self, out: &mut dyn Write, name: &str, body: &str) -> io::Result<()> {
+        if !is_function_name(name) {
+            return writeln!(out, "# {}: not a valid function name in sh, skipped", name);
+        }
+        writeln!(
+            out,
+            "unalias {} 2>/dev/null; unset -f {} 2>/dev/null",
+            name, name
+        )?;
+        writeln!(out, "{}() {{", name)?;
+        writeln!(
+            out,
+            "    if [ -n \"$AKA_DISABLE\" ]; then {}; return; fi",
+            self.passthrough(name)
+        )?;
+        writeln!(
+            out,
+            "    if [ -n \"$AKA_USAGE_LOG\" ]; then printf '%s\\n' {} >> \"$AKA_USAGE_LOG\"; fi",
+            name
+        )?;
+        out.write_all(body.as_bytes())?;
+        writeln!(out, "}}")
+    }
+
+    fn conditional(&self, branches: &[(DirTest<'_>, String)], otherwise: &str) -> String {
+        let mut body = String::new();
+        for (i, (test, command)) in branches.iter().enumerate() {
+            let op = if i == 0 { "if" } else { "elif" };
+            let test = match test {
+                DirTest::Exact(path) => format!("[ \"$PWD\" = \"{}\" ]", path),
+                DirTest::Recursive(path) => format!(
+                    "[ \"$PWD\" = \"{}\" ] || case \"$PWD\" in \"{}\"*) true;; *) false;; esac",
+                    path,
+                    DirTest::subdir_prefix(path)
+                ),
+                DirTest::TmuxSession(session) => format!(
+                    "[ -n \"$TMUX\" ] && [ \"$(tmux display-message -p '#S' 2>/dev/null)\" = \"{}\" ]",
+                    session
+                ),
+            };
+            body.push_str(&format!("    {} {}; then\n", op, test));
+            body.push_str(&format!("        {}\n", command));
+        }
+        if !branches.is_empty() {
+            body.push_str("    else\n");
+        }
+        body.push_str(&format!("        {}\n", otherwise));
+        if !branches.is_empty() {
+            body.push_str("    fi\n");
+        }
+        body
+    }
+
+    fn command(&self, command: &str) -> String {
+        ZshBashCodegen.command(command)
+    }
+
+    fn kube_guard(&self, name: &str, guard: &KubeGuard) -> String {
+        ZshBashCodegen.kube_guard(name, guard)
+    }
+
+    fn local_env(&self, env: &BTreeMap<String, String>) -> String {
+        let assignments: Vec<String> = env
+            .iter()
+            .map(|(name, value)| format!("{}='{}'", name, value.replace('\'', r"'\''")))
+            .collect();
+        format!("export {}", assignments.join(" "))
+    }
+
+    fn log_output(&self, body: &str, path: &str) -> String {
+        // Without `PIPESTATUS`, the command's status comes back on fd 4 while tee writes the
+        // output to the real stdout on fd 3
+        format!(
+            concat!(
+                "_aka_log='{}'; ",
+                "if [ -f \"$_aka_log\" ] && [ \"$(wc -c < \"$_aka_log\")\" -gt {} ]; ",
+                "then mv -f -- \"$_aka_log\" \"$_aka_log.1\"; fi; ",
+                "{{ _aka_status=$({{ {{ {{ {}; }} 2>&1; echo \"$?\" >&4; }} ",
+                "| tee -a -- \"$_aka_log\" >&3; }} 4>&1); }} 3>&1; ",
+                "(exit \"$_aka_status\")"
+            ),
+            path.replace('\'', r"'\''"),
+            LOG_OUTPUT_MAX_BYTES,
+            body
+        )
+    }
+
+    fn preserve_status(&self, after: &[String]) -> String {
+        format!("_aka_status=$?; {}; return $_aka_status", after.join("; "))
+    }
+
+    fn start_timer(&self) -> String {
+        "_aka_started=$(date +%s)".to_string()
+    }
+
+    fn report_time(&self, name: &str) -> String {
+        format!(
+            "printf 'aka: %s finished in %ss\\n' {} \"$(($(date +%s) - _aka_started))\" >&2",
+            name
+        )
+    }
+
+    fn record_history(&self, _command: &str) -> String {
+        // sh keeps no history that a function could add to
+        ":".to_string()
+    }
+
+    fn notify(&self, name: &str, notifier: Notifier) -> String {
+        ZshBashCodegen.notify(name, notifier)
+    }
+
+    fn trace(&self, message: &str) -> String {
+        ZshBashCodegen.trace(message)
+    }
+
+    fn passthrough(&self, name: &str) -> String {
+        ZshBashCodegen.passthrough(name)
+    }
+
+    fn alias(&self, name: &str, command: &str) -> Option<String> {
+        is_function_name(name).then(|| ZshBashCodegen.alias(name, command))?
+    }
+
+    fn inherit_completion(&self, _name: &str, _program: &str) -> Option<String> {
+        // sh has no programmable completion
+        None
+    }
+
+    fn dispatcher(&self, _shell: &str) -> Option<String> {
+        // `aka resolve --body` writes for zsh and bash, whose bodies sh may not run
+        None
+    }
+
+    fn dispatch_stub(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    fn finish(&self, out: &mut dyn Write, managed: &[String]) -> io::Result<()> {
+        writeln!(out, "export AKA_MANAGED_ALIASES=\"{}\"", managed.join(" "))
+    }
+
+    fn timing_start(&self, _out: &mut dyn Write) -> io::Result<()> {
+        // sh has no clock finer than seconds, so only the generation time is reported
+        Ok(())
+    }
+
+    fn timing_report(
+        &self,
+        out: &mut dyn Write,
+        aliases: usize,
+        generated_us: u64,
+    ) -> io::Result<()> {
+        writeln!(
+            out,
+            "printf 'aka: %s aliases, generated in %s ms\\n' {} '{:.1}' >&2",
+            aliases,
+            generated_us as f64 / 1000.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::write_dump;
+    use crate::store::{AliasDefinition, AliasScope};
+    use std::process::Command;
+
+    const GOLDEN: &str = include_str!("../../tests/golden/posix.sh");
+
+    #[test]
+    fn test_dump_matches_golden_file() {
+        let aliases = vec![
+            (
+                "ll".to_string(),
+                vec![AliasDefinition::new(
+                    "ls -la".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+            (
+                "greet".to_string(),
+                vec![
+                    AliasDefinition::new("echo hi".to_string(), AliasScope::Global),
+                    AliasDefinition::new(
+                        "echo hello @1".to_string(),
+                        AliasScope::Recursive("/work".to_string()),
+                    ),
+                ],
+            ),
+            (
+                "cat-pretty".to_string(),
+                vec![AliasDefinition::new(
+                    "bat -p".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+        ];
+
+        let mut out = Vec::new();
+        write_dump(&PosixCodegen, &mut out, aliases).unwrap();
+        let script = String::from_utf8(out).unwrap();
+        assert_eq!(script, GOLDEN);
+        assert!(!script.contains("[[") && !script.contains("local "));
+    }
+
+    #[test]
+    fn test_dump_runs_in_dash() {
+        let mut def = AliasDefinition::new("echo hello @1".to_string(), AliasScope::Global);
+        def.time = true;
+        def.env = BTreeMap::from([("GREETING".to_string(), "it's".to_string())]);
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("out.log");
+        def.log_output = Some(log.to_str().unwrap().to_string());
+        let scoped = AliasDefinition::new(
+            "echo in tmp @1; false".to_string(),
+            AliasScope::Recursive("/tmp".to_string()),
+        );
+        let mut out = Vec::new();
+        write_dump(
+            &PosixCodegen,
+            &mut out,
+            vec![("greet".to_string(), vec![def, scoped])],
+        )
+        .unwrap();
+        let script = format!(
+            "{}cd /\ngreet world; echo \"$? $GREETING\"\ncd /tmp\ngreet there; echo \"$?\"",
+            String::from_utf8(out).unwrap()
+        );
+
+        let Ok(output) = Command::new("dash").arg("-c").arg(&script).output() else {
+            return;
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "hello world\n0 it's\nin tmp there\n1\n"
+        );
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "hello world\n");
+    }
+}
@@ -0,0 +1,416 @@
+use super::{DirTest, LOG_OUTPUT_MAX_BYTES, Notifier, ShellCodegen};
+use crate::store::KubeGuard;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Functions for PowerShell, loaded with
+/// `Invoke-Expression (& aka init --dump --shell powershell | Out-String)`.
+///
+/// Functions are defined in the global scope because the hook reloads them from inside the
+/// `prompt` function.
+pub struct PowerShellCodegen;
+
+impl ShellCodegen for PowerShellCodegen {
+    fn cleanup(&self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(
+            concat!(
+                "if ($env:AKA_MANAGED_ALIASES) {\n",
+                "    foreach ($al in $env:AKA_MANAGED_ALIASES -split ' ') { Remove-Item -LiteralPath \"Function:$al\" -ErrorAction SilentlyContinue }\n",
+                "}\n",
+            )
+            .as_bytes(),
+        )
+    }
+
+    fn comment(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        writeln!(out, "\n# {}", text.replace('\n', " "))
+    }
+
+    fn note(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        writeln!(out, "# {}", text.replace('\n', " "))
+    }
+
+    fn function(&self, out: &mut dyn Write, name: &str, body: &str) -> io::Result<()> {
+        // Aliases such as `ls` take precedence over functions
+        writeln!(
+            out,
+            "Remove-Item -LiteralPath 'Alias:{}' -Force -ErrorAction SilentlyContinue",
+            name
+        )?;
+        writeln!(out, "function global:{} {{", name)?;
+        writeln!(
+            out,
+            "    if ($env:AKA_DISABLE) {{ {}; return }}",
+            self.passthrough(name)
+        )?;
+        writeln!(out, "    $current_dir = $PWD.Path")?;
+        writeln!(
+            out,
+            "    if ($env:AKA_USAGE_LOG) {{ Add-Content -LiteralPath $env:AKA_USAGE_LOG -Value {} }}",
+            quote(name)
+        )?;
+        if body.contains("$_aka_saved_env") {
+            // `$env:` is process wide, so captured variables are put back when the function ends
+            writeln!(out, "    try {{")?;
+            for line in body.lines() {
+                writeln!(out, "    {}", line)?;
+            }
+            writeln!(out, "    }} finally {{")?;
+            writeln!(
+                out,
+                "        if ($_aka_saved_env) {{ foreach ($_aka_entry in $_aka_saved_env.GetEnumerator()) {{ [Environment]::SetEnvironmentVariable($_aka_entry.Key, $_aka_entry.Value) }} }}"
+            )?;
+            writeln!(out, "    }}")?;
+        } else {
+            out.write_all(body.as_bytes())?;
+        }
+        writeln!(out, "}}")
+    }
+
+    fn conditional(&self, branches: &[(DirTest<'_>, String)], otherwise: &str) -> String {
+        let mut body = String::new();
+        for (i, (test, command)) in branches.iter().enumerate() {
+            let op = if i == 0 { "    if" } else { " elseif" };
+            let test = match test {
+                DirTest::Exact(path) => format!("$current_dir -ceq {}", quote(path)),
+                // `$PWD` uses `\` on Windows
+                DirTest::Recursive(path) => format!(
+                    "$current_dir -ceq {} -or $current_dir.StartsWith({} + [IO.Path]::DirectorySeparatorChar, [StringComparison]::Ordinal)",
+                    quote(path),
+                    quote(path.trim_end_matches(['/', '\\']))
+                ),
+                DirTest::TmuxSession(session) => format!(
+                    "$env:TMUX -and ((tmux display-message -p '#S' 2>$null) -ceq {})",
+                    quote(session)
+                ),
+            };
+            body.push_str(&format!("{} ({}) {{\n", op, test));
+            body.push_str(&format!("        {}\n    }}", command));
+        }
+        if branches.is_empty() {
+            body.push_str(&format!("    {}\n", otherwise));
+        } else {
+            body.push_str(" else {\n");
+            body.push_str(&format!("        {}\n    }}\n", otherwise));
+        }
+        body
+    }
+
+    fn command(&self, command: &str) -> String {
+        let (command, positional) = replace_placeholders(command);
+        if positional || command.contains("$args") {
+            command
+        } else {
+            format!("{} @args", command)
+        }
+    }
+
+    fn kube_guard(&self, name: &str, guard: &KubeGuard) -> String {
+        let context = "$_aka_context = kubectl config current-context 2>$null";
+        let (test, message) = match guard {
+            KubeGuard::Only(pattern) => (
+                format!("-not ($_aka_context -clike {})", quote(pattern)),
+                format!("aka: '{}' only runs in kube context {}", name, pattern),
+            ),
+            KubeGuard::Deny(pattern) => (
+                format!("$_aka_context -clike {}", quote(pattern)),
+                format!("aka: '{}' refuses to run in kube context {}", name, pattern),
+            ),
+        };
+        format!(
+            "{}; if ({}) {{ [Console]::Error.WriteLine({}); $global:LASTEXITCODE = 1; return }}",
+            context,
+            test,
+            quote(&message)
+        )
+    }
+
+    fn local_env(&self, env: &BTreeMap<String, String>) -> String {
+        let saved: Vec<String> = env
+            .keys()
+            .map(|name| format!("{} = $env:{}", quote(name), name))
+            .collect();
+        let assignments: Vec<String> = env
+            .iter()
+            .map(|(name, value)| format!("$env:{} = {}", name, quote(value)))
+            .collect();
+        format!(
+            "$_aka_saved_env = @{{ {} }}; {}",
+            saved.join("; "),
+            assignments.join("; ")
+        )
+    }
+
+    fn log_output(&self, body: &str, path: &str) -> String {
+        format!(
+            concat!(
+                "$_aka_log = {}; ",
+                "if ((Test-Path -LiteralPath $_aka_log) -and (Get-Item -LiteralPath $_aka_log).Length -gt {}) ",
+                "{{ Move-Item -Force -LiteralPath $_aka_log -Destination \"$_aka_log.1\" }}; ",
+                "& {{ {} }} @args 2>&1 | Tee-Object -FilePath $_aka_log -Append"
+            ),
+            quote(path),
+            LOG_OUTPUT_MAX_BYTES,
+            body
+        )
+    }
+
+    fn preserve_status(&self, after: &[String]) -> String {
+        format!(
+            "$_aka_status = if ($?) {{ 0 }} elseif ($LASTEXITCODE) {{ $LASTEXITCODE }} else {{ 1 }}; {}; $global:LASTEXITCODE = $_aka_status",
+            after.join("; ")
+        )
+    }
+
+    fn start_timer(&self) -> String {
+        "$_aka_started = Get-Date".to_string()
+    }
+
+    fn report_time(&self, name: &str) -> String {
+        format!(
+            "[Console]::Error.WriteLine({} + [int]((Get-Date) - $_aka_started).TotalSeconds + 's')",
+            quote(&format!("aka: {} finished in ", name))
+        )
+    }
+
+    fn record_history(&self, command: &str) -> String {
+        // Ctrl-R searches PSReadLine's history, not `Get-History`
+        let line = if replace_placeholders(command).1 {
+            format!(
+                "$_aka_line = {}; for ($_aka_i = $args.Count; $_aka_i -ge 1; $_aka_i--) {{ $_aka_line = $_aka_line.Replace(\"@$_aka_i\", $args[$_aka_i - 1]) }}",
+                quote(command)
+            )
+        } else {
+            format!("$_aka_line = (@({}) + $args) -join ' '", quote(command))
+        };
+        format!(
+            "{}; if ('Microsoft.PowerShell.PSConsoleReadLine' -as [type]) {{ [Microsoft.PowerShell.PSConsoleReadLine]::AddToHistory($_aka_line) }}",
+            line
+        )
+    }
+
+    fn notify(&self, name: &str, notifier: Notifier) -> String {
+        match notifier {
+            Notifier::Osascript => format!(
+                "osascript -e ('display notification \"finished with exit status ' + $_aka_status + '\" with title \"aka: {}\"') *> $null",
+                name.replace('\'', "''")
+            ),
+            Notifier::NotifySend => format!(
+                "notify-send {} \"finished with exit status $_aka_status\" *> $null",
+                quote(&format!("aka: {}", name))
+            ),
+        }
+    }
+
+    fn trace(&self, message: &str) -> String {
+        format!(
+            "if ($env:AKA_DEBUG) {{ [Console]::Error.WriteLine({}) }}",
+            quote(&format!("aka: {}", message))
+        )
+    }
+
+    fn passthrough(&self, name: &str) -> String {
+        format!(
+            "& (Get-Command -Name {} -CommandType Application -TotalCount 1 -ErrorAction Stop) @args",
+            quote(name)
+        )
+    }
+
+    fn alias(&self, _name: &str, _command: &str) -> Option<String> {
+        // PowerShell aliases name a command only, without arguments
+        None
+    }
+
+    fn inherit_completion(&self, _name: &str, _program: &str) -> Option<String> {
+        // native completers are registered per program and cannot be copied
+        None
+    }
+
+    fn dispatcher(&self, _shell: &str) -> Option<String> {
+        // Not written for PowerShell; the full dump is used
+        None
+    }
+
+    fn dispatch_stub(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    fn finish(&self, out: &mut dyn Write, managed: &[String]) -> io::Result<()> {
+        writeln!(
+            out,
+            "$env:AKA_MANAGED_ALIASES = {}",
+            quote(&managed.join(" "))
+        )
+    }
+
+    fn timing_start(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "$_aka_timing_start = Get-Date")
+    }
+
+    fn timing_report(
+        &self,
+        out: &mut dyn Write,
+        aliases: usize,
+        generated_us: u64,
+    ) -> io::Result<()> {
+        writeln!(
+            out,
+            concat!(
+                "$_aka_timing_eval = [math]::Round(((Get-Date) - $_aka_timing_start).TotalMilliseconds, 1)\n",
+                "[Console]::Error.WriteLine('aka: {} aliases, generated in {:.1} ms, eval took ' + $_aka_timing_eval + ' ms')\n",
+                "Remove-Variable _aka_timing_start, _aka_timing_eval",
+            ),
+            aliases,
+            generated_us as f64 / 1000.0
+        )
+    }
+}
+
+/// `value` in PowerShell single quotes, where a quote is written twice.
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Turn `@N` placeholders into `$args[N-1]`, returning whether any were found.
+///
+/// Inside double quotes the argument is written as a subexpression so it expands in the
+/// string. Inside single quotes the text is kept as `$N` for tools such as awk, and `\@`
+/// escapes a literal `@`, as in the zsh and bash output. Other backslashes are kept, since
+/// PowerShell paths use them.
+fn replace_placeholders(command: &str) -> (String, bool) {
+    let mut output = String::with_capacity(command.len());
+    let mut chars = command.chars().peekable();
+    let mut in_single_quote = false;
+    let mut in_double_quote = false;
+    let mut positional = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if !in_single_quote && chars.peek() == Some(&'@') => {
+                output.push('@');
+                chars.next();
+                continue;
+            }
+            '\'' if !in_double_quote => in_single_quote = !in_single_quote,
+            '"' if !in_single_quote => in_double_quote = !in_double_quote,
+            '@' if chars.peek().is_some_and(|d| matches!(d, '1'..='9')) => {
+                let mut index = String::new();
+                while let Some(&d) = chars.peek()
+                    && d.is_ascii_digit()
+                {
+                    index.push(d);
+                    chars.next();
+                }
+                if in_single_quote {
+                    output.push_str(&format!("${}", index));
+                    continue;
+                }
+                let index = index.parse::<usize>().unwrap_or(1) - 1;
+                if in_double_quote {
+                    output.push_str(&format!("$($args[{}])", index));
+                } else {
+                    output.push_str(&format!("$args[{}]", index));
+                }
+                positional = true;
+                continue;
+            }
+            _ => {}
+        }
+        output.push(c);
+    }
+    (output, positional)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::write_dump;
+    use crate::store::{AliasDefinition, AliasScope};
+    use std::process::Command;
+
+    const GOLDEN: &str = include_str!("../../tests/golden/powershell.ps1");
+
+    #[test]
+    fn test_dump_matches_golden_file() {
+        let aliases = vec![
+            (
+                "ll".to_string(),
+                vec![AliasDefinition::new(
+                    "ls -la".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+            (
+                "build".to_string(),
+                vec![
+                    AliasDefinition::new("make".to_string(), AliasScope::Global),
+                    AliasDefinition::new(
+                        "cargo build".to_string(),
+                        AliasScope::Recursive("/work/rust".to_string()),
+                    ),
+                    AliasDefinition::new(
+                        "cargo build -p app".to_string(),
+                        AliasScope::Exact("/work/rust/app".to_string()),
+                    ),
+                ],
+            ),
+            (
+                "gcm".to_string(),
+                vec![AliasDefinition::new(
+                    "git commit -m \"@1\" && awk '{print @2}'".to_string(),
+                    AliasScope::Tmux("work".to_string()),
+                )],
+            ),
+        ];
+
+        let mut out = Vec::new();
+        write_dump(&PowerShellCodegen, &mut out, aliases).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), GOLDEN);
+    }
+
+    #[test]
+    fn test_replace_placeholders() {
+        assert_eq!(
+            replace_placeholders("echo @1 \"x @12\" '{print @2}' \\@3 C:\\tools"),
+            (
+                "echo $args[0] \"x $($args[11])\" '{print $2}' @3 C:\\tools".to_string(),
+                true
+            )
+        );
+        assert_eq!(PowerShellCodegen.command("ls -la"), "ls -la @args");
+        assert_eq!(quote("it's"), "'it''s'");
+    }
+
+    #[test]
+    fn test_captured_env_is_restored_in_pwsh() {
+        let mut out = Vec::new();
+        let mut def = AliasDefinition::new(
+            "Write-Output \"$env:AKA_TEST_VALUE @1\"".to_string(),
+            AliasScope::Global,
+        );
+        def.env
+            .insert("AKA_TEST_VALUE".to_string(), "inside".to_string());
+        write_dump(
+            &PowerShellCodegen,
+            &mut out,
+            vec![("show".to_string(), vec![def])],
+        )
+        .unwrap();
+        let script = format!(
+            "{}\nshow arg; Write-Output \"after $env:AKA_TEST_VALUE\"",
+            String::from_utf8(out).unwrap()
+        );
+
+        let Ok(output) = Command::new("pwsh")
+            .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            .env("AKA_TEST_VALUE", "outside")
+            .output()
+        else {
+            return;
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "inside arg\nafter outside\n"
+        );
+    }
+}
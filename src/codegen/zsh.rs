@@ -0,0 +1,274 @@
+use super::zsh_bash::{
+    CLEANUP_MANAGED, ZSH_RESTORE_ALIASES, ZSH_SAVE_ALIASES, history_template,
+    zsh_inherit_completion,
+};
+use super::{DirTest, Notifier, ShellCodegen, ZshBashCodegen, write_completion, write_function};
+use crate::store::{AliasDefinition, KubeGuard, sort_by_precedence};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Functions for zsh alone, written by `aka init --shell zsh`.
+///
+/// The functions themselves are the ones [`ZshBashCodegen`] writes; only the setup and the
+/// history line skip the runtime check for bash.
+pub struct ZshCodegen;
+
+impl ShellCodegen for ZshCodegen {
+    fn cleanup(&self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(ZSH_SAVE_ALIASES.as_bytes())?;
+        out.write_all(CLEANUP_MANAGED.as_bytes())
+    }
+
+    fn comment(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        ZshBashCodegen.comment(out, text)
+    }
+
+    fn note(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        ZshBashCodegen.note(out, text)
+    }
+
+    fn function(&self, out: &mut dyn Write, name: &str, body: &str) -> io::Result<()> {
+        ZshBashCodegen.function(out, name, body)
+    }
+
+    fn conditional(&self, branches: &[(DirTest<'_>, String)], otherwise: &str) -> String {
+        ZshBashCodegen.conditional(branches, otherwise)
+    }
+
+    fn command(&self, command: &str) -> String {
+        ZshBashCodegen.command(command)
+    }
+
+    fn kube_guard(&self, name: &str, guard: &KubeGuard) -> String {
+        ZshBashCodegen.kube_guard(name, guard)
+    }
+
+    fn local_env(&self, env: &BTreeMap<String, String>) -> String {
+        ZshBashCodegen.local_env(env)
+    }
+
+    fn log_output(&self, body: &str, path: &str) -> String {
+        ZshBashCodegen.log_output(body, path)
+    }
+
+    fn preserve_status(&self, after: &[String]) -> String {
+        ZshBashCodegen.preserve_status(after)
+    }
+
+    fn start_timer(&self) -> String {
+        ZshBashCodegen.start_timer()
+    }
+
+    fn report_time(&self, name: &str) -> String {
+        ZshBashCodegen.report_time(name)
+    }
+
+    fn record_history(&self, command: &str) -> String {
+        format!(
+            "local _aka_line=\"{}\"; print -s -r -- \"$_aka_line\"",
+            history_template(command)
+        )
+    }
+
+    fn notify(&self, name: &str, notifier: Notifier) -> String {
+        ZshBashCodegen.notify(name, notifier)
+    }
+
+    fn trace(&self, message: &str) -> String {
+        ZshBashCodegen.trace(message)
+    }
+
+    fn passthrough(&self, name: &str) -> String {
+        ZshBashCodegen.passthrough(name)
+    }
+
+    fn alias(&self, name: &str, command: &str) -> Option<String> {
+        ZshBashCodegen.alias(name, command)
+    }
+
+    fn inherit_completion(&self, name: &str, program: &str) -> Option<String> {
+        Some(zsh_inherit_completion(name, program))
+    }
+
+    fn dispatcher(&self, shell: &str) -> Option<String> {
+        ZshBashCodegen.dispatcher(shell)
+    }
+
+    fn dispatch_stub(&self, name: &str) -> Option<String> {
+        ZshBashCodegen.dispatch_stub(name)
+    }
+
+    fn finish(&self, out: &mut dyn Write, managed: &[String]) -> io::Result<()> {
+        writeln!(out, "export AKA_MANAGED_ALIASES=\"{}\"", managed.join(" "))?;
+        out.write_all(ZSH_RESTORE_ALIASES.as_bytes())?;
+        out.write_all(b"unset _aka_aliases_was_on\n")
+    }
+
+    fn timing_start(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "zmodload zsh/datetime 2>/dev/null\n_aka_timing_start=\"$EPOCHREALTIME\""
+        )
+    }
+
+    fn timing_report(
+        &self,
+        out: &mut dyn Write,
+        aliases: usize,
+        generated_us: u64,
+    ) -> io::Result<()> {
+        ZshBashCodegen.timing_report(out, aliases, generated_us)
+    }
+}
+
+/// Write a dump that marks each alias `autoload`, with its function in a file of its own in
+/// `dir`, so zsh reads a function only when it is first called and startup stays quick however
+/// many aliases there are.
+///
+/// Each file defines the function and then calls it, the way zsh runs a file that defines its
+/// own function. Files are rewritten only when they change, and those of aliases that are
+/// gone are removed; aliases whose names cannot be file names are defined in the dump.
+pub fn write_autoload_dump<I>(out: &mut dyn Write, dir: &Path, aliases: I) -> io::Result<()>
+where
+    I: IntoIterator<Item = (String, Vec<AliasDefinition>)>,
+{
+    let mut aliases: Vec<(String, Vec<AliasDefinition>)> = aliases.into_iter().collect();
+    aliases.sort_by(|a, b| a.0.cmp(&b.0));
+    std::fs::create_dir_all(dir)?;
+    let names: BTreeSet<&str> = aliases.iter().map(|(alias, _)| alias.as_str()).collect();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let stale = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| !names.contains(name));
+        if stale && path.is_file() {
+            std::fs::remove_file(&path)?;
+        }
+    }
+
+    let quoted_dir = dir.to_string_lossy().replace('\'', r"'\''");
+    ZshCodegen.cleanup(out)?;
+    writeln!(out, "fpath=('{d}' ${{fpath:#'{d}'}})", d = quoted_dir)?;
+    ZshCodegen.comment(out, "Autoloaded")?;
+    let mut managed = Vec::new();
+    for (alias, mut defs) in aliases {
+        sort_by_precedence(&mut defs);
+        if alias.contains('/') || alias == "." || alias == ".." {
+            // Not a file name, so defined here instead
+            write_function(&ZshCodegen, out, &alias, &defs)?;
+            write_completion(&ZshCodegen, out, &alias, &defs)?;
+            managed.push(alias);
+            continue;
+        }
+        let mut function = Vec::new();
+        write_function(&ZshCodegen, &mut function, &alias, &defs)?;
+        // The first line clears an earlier definition, which autoload has done already
+        let function = String::from_utf8_lossy(&function);
+        let function = function.split_once('\n').map_or("", |(_, rest)| rest);
+        let contents = format!("{}{} \"$@\"\n", function, alias);
+        let path = dir.join(&alias);
+        if std::fs::read_to_string(&path).ok().as_deref() != Some(contents.as_str()) {
+            std::fs::write(&path, contents)?;
+        }
+        writeln!(
+            out,
+            "unalias {a} 2>/dev/null; unset -f {a} 2>/dev/null; autoload -Uz {a}",
+            a = alias
+        )?;
+        write_completion(&ZshCodegen, out, &alias, &defs)?;
+        managed.push(alias);
+    }
+    ZshCodegen.finish(out, &managed)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::write_dump;
+    use crate::store::{AliasDefinition, AliasScope};
+    use std::process::Command;
+
+    const GOLDEN: &str = include_str!("../../tests/golden/zsh.zsh");
+
+    #[test]
+    fn test_dump_matches_golden_file() {
+        let aliases = vec![
+            (
+                "ll".to_string(),
+                vec![AliasDefinition::new(
+                    "ls -la".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+            (
+                "greet".to_string(),
+                vec![
+                    AliasDefinition::new("echo hi".to_string(), AliasScope::Global),
+                    AliasDefinition::new(
+                        "echo hello @1".to_string(),
+                        AliasScope::Recursive("/work".to_string()),
+                    ),
+                ],
+            ),
+        ];
+
+        let mut out = Vec::new();
+        write_dump(&ZshCodegen, &mut out, aliases).unwrap();
+        let script = String::from_utf8(out).unwrap();
+        assert_eq!(script, GOLDEN);
+        assert!(!script.contains("BASH_VERSION"));
+    }
+
+    #[test]
+    fn test_autoload_dump_writes_a_file_per_alias() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("gone"), "gone() { :; }\n").unwrap();
+        let aliases = vec![(
+            "greet".to_string(),
+            vec![AliasDefinition::new(
+                "echo hello @1".to_string(),
+                AliasScope::Global,
+            )],
+        )];
+        let mut out = Vec::new();
+        write_autoload_dump(&mut out, dir.path(), aliases).unwrap();
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("autoload -Uz greet\n"));
+        assert!(!script.contains("greet() {"));
+        assert!(!dir.path().join("gone").exists());
+        let function = std::fs::read_to_string(dir.path().join("greet")).unwrap();
+        assert!(function.starts_with("greet() {\n"));
+        assert!(function.ends_with("}\ngreet \"$@\"\n"));
+
+        let script = format!("{}greet world; greet again", script);
+        let Ok(output) = Command::new("zsh").arg("-fc").arg(&script).output() else {
+            return;
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "hello world\nhello again\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_runs_in_zsh() {
+        let mut def = AliasDefinition::new("echo hello @1".to_string(), AliasScope::Global);
+        def.write_history = true;
+        let mut out = Vec::new();
+        write_dump(
+            &ZshCodegen,
+            &mut out,
+            vec![("greet".to_string(), vec![def])],
+        )
+        .unwrap();
+        let script = format!("{}greet world", String::from_utf8(out).unwrap());
+
+        let Ok(output) = Command::new("zsh").arg("-c").arg(&script).output() else {
+            return;
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello world\n");
+    }
+}
@@ -0,0 +1,1300 @@
+use super::{DirTest, LOG_OUTPUT_MAX_BYTES, Notifier, ShellCodegen};
+use crate::store::KubeGuard;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Functions for zsh and bash, which share one script that branches on the running shell.
+///
+/// `aka init` writes for one of them with [`ZshCodegen`](super::ZshCodegen) or
+/// [`BashCodegen`](super::BashCodegen), which reuse everything but the shell-specific parts.
+pub struct ZshBashCodegen;
+
+/// Turn off zsh's alias expansion while the functions are defined, remembering its state.
+pub(super) const ZSH_SAVE_ALIASES: &str = concat!(
+    "if [[ -o aliases ]]; then\n",
+    "    _aka_aliases_was_on=1\n",
+    "else\n",
+    "    _aka_aliases_was_on=0\n",
+    "fi\n",
+    "unsetopt aliases\n",
+);
+
+/// Turn off bash's alias expansion while the functions are defined, remembering its state.
+pub(super) const BASH_SAVE_ALIASES: &str = concat!(
+    "_aka_aliases_was_on=$(shopt -q expand_aliases && echo 1 || echo 0)\n",
+    "shopt -u expand_aliases\n",
+);
+
+/// Remove the aliases and functions defined by the previous dump.
+pub(super) const CLEANUP_MANAGED: &str = concat!(
+    "if [ -n \"$AKA_MANAGED_ALIASES\" ]; then\n",
+    "    for al in $AKA_MANAGED_ALIASES; do unalias $al 2>/dev/null; unset -f $al 2>/dev/null; done\n",
+    "fi\n",
+);
+
+pub(super) const ZSH_RESTORE_ALIASES: &str = concat!(
+    "if [ \"${_aka_aliases_was_on:-0}\" = \"1\" ]; then\n",
+    "    setopt aliases\n",
+    "fi\n",
+);
+
+pub(super) const BASH_RESTORE_ALIASES: &str = concat!(
+    "if [ \"${_aka_aliases_was_on:-0}\" = \"1\" ]; then\n",
+    "    shopt -s expand_aliases\n",
+    "fi\n",
+);
+
+/// Runs the alias named by its first argument with the rest: the body of the definition that
+/// applies comes from `aka resolve --body` and is evaluated here, so `local` and `return` in it
+/// act on this function. When none applies, or aka fails, the real command runs.
+const DISPATCHER: &str = concat!(
+    "_aka_dispatch() {\n",
+    "    local _aka_name=\"$1\" _aka_body\n",
+    "    shift\n",
+    "    if [ -n \"$AKA_DISABLE\" ]; then command \"$_aka_name\" \"$@\"; return; fi\n",
+    "    if [ -n \"$AKA_USAGE_LOG\" ]; then printf '%s\\n' \"$_aka_name\" >> \"$AKA_USAGE_LOG\"; fi\n",
+    "    if _aka_body=\"$(command aka resolve --body --shell @SHELL@ \"$_aka_name\" 2>/dev/null)\"; then\n",
+    "        eval \"$_aka_body\"\n",
+    "    else\n",
+    "        command \"$_aka_name\" \"$@\"\n",
+    "    fi\n",
+    "}\n",
+);
+
+/// Give `name` the completion of `program` in zsh, once `compinit` has run.
+pub(super) fn zsh_inherit_completion(name: &str, program: &str) -> String {
+    format!("(( $+functions[compdef] )) && compdef {}={}", name, program)
+}
+
+/// Give `name` the completion of `program` in bash, loading it first through
+/// bash-completion when it is loaded lazily.
+pub(super) fn bash_inherit_completion(name: &str, program: &str) -> String {
+    format!(
+        "if complete -p {p} &>/dev/null || {{ _completion_loader {p}; complete -p {p}; }} &>/dev/null; then eval \"$(complete -p {p}) {n}\"; fi",
+        p = program,
+        n = name
+    )
+}
+
+/// Run `zsh` or `bash`, whichever shell evaluates the script.
+fn branch_on_shell(zsh: &str, bash: &str) -> String {
+    let indent =
+        |code: &str| -> String { code.lines().map(|line| format!("    {}\n", line)).collect() };
+    format!(
+        "if [ -n \"$ZSH_VERSION\" ]; then\n{}elif [ -n \"$BASH_VERSION\" ]; then\n{}fi\n",
+        indent(zsh),
+        indent(bash)
+    )
+}
+
+impl ShellCodegen for ZshBashCodegen {
+    fn cleanup(&self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(branch_on_shell(ZSH_SAVE_ALIASES, BASH_SAVE_ALIASES).as_bytes())?;
+        out.write_all(CLEANUP_MANAGED.as_bytes())
+    }
+
+    fn comment(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        writeln!(out, "\n# {}", text.replace('\n', " "))
+    }
+
+    fn note(&self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        writeln!(out, "# {}", text.replace('\n', " "))
+    }
+
+    fn function(&self, out: &mut dyn Write, name: &str, body: &str) -> io::Result<()> {
+        writeln!(
+            out,
+            "unalias {} 2>/dev/null; unset -f {} 2>/dev/null",
+            name, name
+        )?;
+        writeln!(out, "{}() {{", name)?;
+        writeln!(
+            out,
+            "    if [ -n \"$AKA_DISABLE\" ]; then {}; return; fi",
+            self.passthrough(name)
+        )?;
+        writeln!(out, "    local current_dir=\"$PWD\"")?;
+        writeln!(
+            out,
+            "    if [ -n \"$AKA_USAGE_LOG\" ]; then printf '%s\\n' {} >> \"$AKA_USAGE_LOG\"; fi",
+            name
+        )?;
+        out.write_all(body.as_bytes())?;
+        writeln!(out, "}}")
+    }
+
+    fn conditional(&self, branches: &[(DirTest<'_>, String)], otherwise: &str) -> String {
+        let mut body = String::new();
+        for (i, (test, command)) in branches.iter().enumerate() {
+            let op = if i == 0 { "if" } else { "elif" };
+            let test = match test {
+                DirTest::Exact(path) => format!("[[ \"$current_dir\" == \"{}\" ]]", path),
+                DirTest::Recursive(path) => format!(
+                    "[[ \"$current_dir\" == \"{}\" || \"$current_dir\" == \"{}\"* ]]",
+                    path,
+                    DirTest::subdir_prefix(path)
+                ),
+                DirTest::TmuxSession(session) => format!(
+                    "[[ -n \"$TMUX\" && \"$(tmux display-message -p '#S' 2>/dev/null)\" == \"{}\" ]]",
+                    session
+                ),
+            };
+            body.push_str(&format!("    {} {}; then\n", op, test));
+            body.push_str(&format!("        {}\n", command));
+        }
+        if !branches.is_empty() {
+            body.push_str("    else\n");
+        }
+        body.push_str(&format!("        {}\n", otherwise));
+        if !branches.is_empty() {
+            body.push_str("    fi\n");
+        }
+        body
+    }
+
+    fn command(&self, command: &str) -> String {
+        prepare_command_body(command)
+    }
+
+    fn kube_guard(&self, name: &str, guard: &KubeGuard) -> String {
+        let context = "case \"$(kubectl config current-context 2>/dev/null)\" in";
+        match guard {
+            KubeGuard::Only(pattern) => format!(
+                "{} {}) ;; *) echo \"aka: '{}' only runs in kube context {}\" >&2; return 1;; esac",
+                context, pattern, name, pattern
+            ),
+            KubeGuard::Deny(pattern) => format!(
+                "{} {}) echo \"aka: '{}' refuses to run in kube context {}\" >&2; return 1;; esac",
+                context, pattern, name, pattern
+            ),
+        }
+    }
+
+    fn local_env(&self, env: &BTreeMap<String, String>) -> String {
+        let assignments: Vec<String> = env
+            .iter()
+            .map(|(name, value)| format!("{}='{}'", name, value.replace('\'', r"'\''")))
+            .collect();
+        format!("local -x {}", assignments.join(" "))
+    }
+
+    fn log_output(&self, body: &str, path: &str) -> String {
+        // The pipeline's status is tee's; restore the command's from bash's `PIPESTATUS` or
+        // zsh's `pipestatus`, only one of which is set.
+        format!(
+            concat!(
+                "local _aka_log='{}'; ",
+                "if [ -f \"$_aka_log\" ] && [ \"$(wc -c < \"$_aka_log\")\" -gt {} ]; ",
+                "then mv -f -- \"$_aka_log\" \"$_aka_log.1\"; fi; ",
+                "{{ {}; }} 2>&1 | tee -a -- \"$_aka_log\"; ",
+                "(exit \"${{PIPESTATUS[0]}}${{pipestatus[1]}}\")"
+            ),
+            path.replace('\'', r"'\''"),
+            LOG_OUTPUT_MAX_BYTES,
+            body
+        )
+    }
+
+    fn preserve_status(&self, after: &[String]) -> String {
+        format!(
+            "local _aka_status=$?; {}; return $_aka_status",
+            after.join("; ")
+        )
+    }
+
+    fn start_timer(&self) -> String {
+        "local _aka_started=$SECONDS".to_string()
+    }
+
+    fn report_time(&self, name: &str) -> String {
+        format!(
+            "printf 'aka: %s finished in %ss\\n' {} \"$((SECONDS - _aka_started))\" >&2",
+            name
+        )
+    }
+
+    fn record_history(&self, command: &str) -> String {
+        format!(
+            concat!(
+                "local _aka_line=\"{}\"; ",
+                "if [ -n \"$ZSH_VERSION\" ]; then print -s -r -- \"$_aka_line\"; ",
+                "else history -s -- \"$_aka_line\"; fi"
+            ),
+            history_template(command)
+        )
+    }
+
+    fn notify(&self, name: &str, notifier: Notifier) -> String {
+        let message = "finished with exit status $_aka_status";
+        match notifier {
+            Notifier::Osascript => format!(
+                r#"osascript -e "display notification \"{}\" with title \"aka: {}\"" >/dev/null 2>&1"#,
+                message, name
+            ),
+            Notifier::NotifySend => {
+                format!(
+                    r#"notify-send "aka: {}" "{}" >/dev/null 2>&1"#,
+                    name, message
+                )
+            }
+        }
+    }
+
+    fn trace(&self, message: &str) -> String {
+        format!(
+            "if [ -n \"$AKA_DEBUG\" ]; then printf 'aka: %s\\n' '{}' >&2; fi",
+            message.replace('\'', r"'\''")
+        )
+    }
+
+    fn passthrough(&self, name: &str) -> String {
+        format!("command {} \"$@\"", name)
+    }
+
+    fn alias(&self, name: &str, command: &str) -> Option<String> {
+        Some(format!(
+            "alias {}='{}'",
+            name,
+            command.replace('\'', r"'\''")
+        ))
+    }
+
+    fn inherit_completion(&self, name: &str, program: &str) -> Option<String> {
+        Some(format!(
+            "if [ -n \"$ZSH_VERSION\" ]; then {}; elif [ -n \"$BASH_VERSION\" ]; then {}; fi",
+            zsh_inherit_completion(name, program),
+            bash_inherit_completion(name, program)
+        ))
+    }
+
+    fn dispatcher(&self, shell: &str) -> Option<String> {
+        Some(DISPATCHER.replace("@SHELL@", shell))
+    }
+
+    fn dispatch_stub(&self, name: &str) -> Option<String> {
+        Some(format!(
+            "unalias {} 2>/dev/null; {}() {{ _aka_dispatch {} \"$@\"; }}",
+            name, name, name
+        ))
+    }
+
+    fn finish(&self, out: &mut dyn Write, managed: &[String]) -> io::Result<()> {
+        writeln!(out, "export AKA_MANAGED_ALIASES=\"{}\"", managed.join(" "))?;
+        out.write_all(branch_on_shell(ZSH_RESTORE_ALIASES, BASH_RESTORE_ALIASES).as_bytes())?;
+        out.write_all(b"unset _aka_aliases_was_on\n")
+    }
+
+    fn timing_start(&self, out: &mut dyn Write) -> io::Result<()> {
+        // EPOCHREALTIME needs bash 5 or zsh/datetime; without it only generation time is shown
+        writeln!(
+            out,
+            "[ -n \"$ZSH_VERSION\" ] && zmodload zsh/datetime 2>/dev/null\n_aka_timing_start=\"$EPOCHREALTIME\""
+        )
+    }
+
+    fn timing_report(
+        &self,
+        out: &mut dyn Write,
+        aliases: usize,
+        generated_us: u64,
+    ) -> io::Result<()> {
+        writeln!(
+            out,
+            concat!(
+                "_aka_timing_eval=\"unknown\"\n",
+                "if [ -n \"$_aka_timing_start\" ] && [ -n \"$EPOCHREALTIME\" ]; then\n",
+                "    _aka_timing_eval=\"$(awk \"BEGIN {{ printf \\\"%.1f ms\\\", ($EPOCHREALTIME - $_aka_timing_start) * 1000 }}\")\"\n",
+                "fi\n",
+                "printf 'aka: %s aliases, generated in %s ms, eval took %s\\n' {} '{:.1}' \"$_aka_timing_eval\" >&2\n",
+                "unset _aka_timing_start _aka_timing_eval",
+            ),
+            aliases,
+            generated_us as f64 / 1000.0
+        )
+    }
+}
+
+fn prepare_command_body(command: &str) -> String {
+    let command = replace_placeholders(command);
+    if has_positional_args(&command) {
+        command
+    } else if let Some(group) = command.strip_suffix("; }") {
+        // A trailing `{ ...; }` group passes the arguments to its last command
+        format!("{} \"$@\"; }}", group)
+    } else {
+        // Append "$@" if no args usage
+        format!("{} \"$@\"", command)
+    }
+}
+
+/// Turn `@N` placeholders into positional parameters.
+///
+/// Unquoted placeholders become `"${N}"` so arguments containing spaces survive word
+/// splitting and `@10` is not read as `$1` followed by `0`. Inside single quotes the text is
+/// kept as `$N` for tools such as awk, and `\@` escapes a literal `@`.
+fn replace_placeholders(command: &str) -> String {
+    let mut output = String::with_capacity(command.len());
+    let mut chars = command.chars().peekable();
+    let mut in_single_quote = false;
+    let mut in_double_quote = false;
+    let mut escaped = false;
+
+    while let Some(c) = chars.next() {
+        if escaped {
+            escaped = false;
+            output.push(c);
+            continue;
+        }
+
+        match c {
+            '\\' if !in_single_quote => escaped = true,
+            '\'' if !in_double_quote => in_single_quote = !in_single_quote,
+            '"' if !in_single_quote => in_double_quote = !in_double_quote,
+            '@' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                let mut index = String::new();
+                while let Some(&d) = chars.peek()
+                    && d.is_ascii_digit()
+                {
+                    index.push(d);
+                    chars.next();
+                }
+                if in_single_quote {
+                    output.push_str(&format!("${}", index));
+                } else if in_double_quote {
+                    output.push_str(&format!("${{{}}}", index));
+                } else {
+                    output.push_str(&format!("\"${{{}}}\"", index));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        output.push(c);
+    }
+    output
+}
+
+/// Double-quoted shell text that expands to `command` as it ran, for the history.
+///
+/// Placeholders are filled in the way [`replace_placeholders`] passes them: unquoted ones with
+/// the argument re-quoted by `printf %q`, quoted ones as-is, and single-quoted ones stay `$N`.
+/// Commands without placeholders get the forwarded arguments appended.
+pub(super) fn history_template(command: &str) -> String {
+    let mut output = String::with_capacity(command.len());
+    let mut chars = command.chars().peekable();
+    let mut in_single_quote = false;
+    let mut in_double_quote = false;
+    let mut escaped = false;
+
+    while let Some(c) = chars.next() {
+        if escaped {
+            escaped = false;
+        } else {
+            match c {
+                '\\' if !in_single_quote => escaped = true,
+                '\'' if !in_double_quote => in_single_quote = !in_single_quote,
+                '"' if !in_single_quote => in_double_quote = !in_double_quote,
+                '@' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                    let mut index = String::new();
+                    while let Some(&d) = chars.peek()
+                        && d.is_ascii_digit()
+                    {
+                        index.push(d);
+                        chars.next();
+                    }
+                    if in_single_quote {
+                        output.push_str(&format!("\\${}", index));
+                    } else if in_double_quote {
+                        output.push_str(&format!("${{{}}}", index));
+                    } else {
+                        output.push_str(&format!("$(printf %q \"${{{}}}\")", index));
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if matches!(c, '\\' | '"' | '$' | '`') {
+            output.push('\\');
+        }
+        output.push(c);
+    }
+
+    if !has_positional_args(&replace_placeholders(command)) {
+        output.push_str("${1+$(printf ' %q' \"$@\")}");
+    }
+    output
+}
+
+/// Whether the function for `command` places its arguments itself, through `@N` placeholders
+/// or positional parameters, instead of appending them.
+pub fn places_arguments(command: &str) -> bool {
+    has_positional_args(&replace_placeholders(command))
+}
+
+fn has_positional_args(command: &str) -> bool {
+    let mut chars = command.chars().peekable();
+    let mut in_single_quote = false;
+    let mut in_double_quote = false;
+    let mut escaped = false;
+
+    while let Some(c) = chars.next() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        if c == '\\' {
+            escaped = true;
+            continue;
+        }
+
+        if in_single_quote {
+            if c == '\'' {
+                in_single_quote = false;
+            }
+            continue;
+        }
+
+        if in_double_quote {
+            if c == '"' {
+                in_double_quote = false;
+                continue; // Consumed quote
+            }
+            // Fallthrough to check for $ inside double quotes
+        } else {
+            // Not in any quote
+            if c == '\'' {
+                in_single_quote = true;
+                continue;
+            }
+            if c == '"' {
+                in_double_quote = true;
+                continue;
+            }
+        }
+
+        // Check for $ (valid in unquoted or double-quoted)
+        if c == '$'
+            && let Some(&next) = chars.peek()
+        {
+            // Check for $1, $2, ... $9, $0
+            if next.is_ascii_digit() {
+                return true;
+            }
+            // Check for $@, $*, $#
+            if matches!(next, '@' | '*' | '#') {
+                return true;
+            }
+            // Check for ${...}
+            if next == '{' {
+                let mut lookahead = chars.clone();
+                lookahead.next();
+
+                let mut content_type = None;
+
+                for inner in lookahead {
+                    if inner == '}' {
+                        if content_type == Some(true) {
+                            return true;
+                        }
+                        break;
+                    }
+                    if inner.is_ascii_digit() || matches!(inner, '@' | '*' | '#') {
+                        if content_type == Some(false) {
+                            // Mixed digits and letters? e.g. ${1foo}. Not positional.
+                            break;
+                        }
+                        content_type = Some(true);
+                    } else {
+                        // Any other char implies named variable
+                        content_type = Some(false);
+                    }
+                }
+            }
+        }
+    }
+    false
+}
+
+/// `script`, a dump for zsh, bash or sh, made as small as it gets without changing what it does.
+///
+/// Comments, indentation and blank lines go, except inside quotes, here-documents and continued
+/// lines, so commands spanning lines keep their text. The setup before the first alias and the
+/// teardown from `export AKA_MANAGED_ALIASES` on are aka's own and are each joined onto a line.
+pub fn minify(script: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut setup_end = None;
+    let mut teardown_start = None;
+    let mut scan = ShellScan::default();
+    let mut heredocs = Vec::new();
+
+    for line in script.lines() {
+        if let Some((delimiter, strip_tabs)) = heredocs.last() {
+            let end = match strip_tabs {
+                true => line.trim_start_matches('\t'),
+                false => line,
+            };
+            if end == delimiter {
+                heredocs.pop();
+            }
+            lines.push(line);
+            continue;
+        }
+        let top_level = scan.at_top_level();
+        let text = match top_level {
+            true => line.trim_start(),
+            false => line,
+        };
+        if top_level && text.starts_with('#') {
+            setup_end.get_or_insert(lines.len());
+            continue;
+        }
+        if top_level && text.is_empty() {
+            continue;
+        }
+        if top_level && teardown_start.is_none() && text.starts_with("export AKA_MANAGED_ALIASES=")
+        {
+            teardown_start = Some(lines.len());
+        }
+        heredocs = scan.line(text);
+        heredocs.reverse();
+        lines.push(text);
+    }
+
+    let teardown_start = teardown_start.unwrap_or(lines.len());
+    let setup_end = setup_end.unwrap_or(teardown_start).min(teardown_start);
+    let mut minified = String::new();
+    for (block, join) in [
+        (&lines[..setup_end], true),
+        (&lines[setup_end..teardown_start], false),
+        (&lines[teardown_start..], true),
+    ] {
+        if block.is_empty() {
+            continue;
+        }
+        match join {
+            true => minified.push_str(&join_lines(block)),
+            false => minified.push_str(&block.join("\n")),
+        }
+        minified.push('\n');
+    }
+    minified
+}
+
+/// `lines` of aka's own code on one line: each ends a command, or opens a block with `then`,
+/// `do`, `else` or `{`.
+fn join_lines(lines: &[&str]) -> String {
+    let mut joined = String::new();
+    for line in lines {
+        if !joined.is_empty() {
+            let opens_block = ["then", "do", "else", "{"].iter().any(|word| {
+                joined
+                    .strip_suffix(word)
+                    .is_some_and(|rest| rest.is_empty() || rest.ends_with([' ', ';']))
+            });
+            joined.push_str(match opens_block || joined.ends_with(';') {
+                true => " ",
+                false => "; ",
+            });
+        }
+        joined.push_str(line);
+    }
+    joined
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Quote {
+    Single,
+    Double,
+    /// `$'...'`, which takes backslash escapes
+    AnsiC,
+}
+
+/// Where a shell script is left at the end of each line: inside quotes, or on a line ending in
+/// a backslash.
+#[derive(Default)]
+struct ShellScan {
+    quote: Option<Quote>,
+    continued: bool,
+}
+
+impl ShellScan {
+    fn at_top_level(&self) -> bool {
+        self.quote.is_none() && !self.continued
+    }
+
+    /// Follow `text` to its end, returning the here-documents it starts, whose bodies come
+    /// next: their delimiters and whether leading tabs are stripped.
+    fn line(&mut self, text: &str) -> Vec<(String, bool)> {
+        let mut heredocs = Vec::new();
+        let mut chars = text.char_indices().peekable();
+        let mut word_start = true;
+        self.continued = false;
+        while let Some((i, c)) = chars.next() {
+            match self.quote {
+                Some(Quote::Single) => {
+                    if c == '\'' {
+                        self.quote = None;
+                    }
+                }
+                Some(quote) => {
+                    let close = if quote == Quote::Double { '"' } else { '\'' };
+                    if c == '\\' {
+                        chars.next();
+                    } else if c == close {
+                        self.quote = None;
+                    }
+                }
+                None => match c {
+                    '\\' => self.continued = chars.next().is_none(),
+                    '\'' => self.quote = Some(Quote::Single),
+                    '"' => self.quote = Some(Quote::Double),
+                    '$' if chars.next_if(|&(_, next)| next == '\'').is_some() => {
+                        self.quote = Some(Quote::AnsiC)
+                    }
+                    '#' if word_start => break,
+                    '<' if text[i..].starts_with("<<<") => {
+                        chars.nth(1);
+                    }
+                    '<' if text[i..].starts_with("<<") => {
+                        chars.next();
+                        let strip_tabs = chars.next_if(|&(_, c)| c == '-').is_some();
+                        while chars.next_if(|&(_, c)| c == ' ' || c == '\t').is_some() {}
+                        let mut delimiter = String::new();
+                        while let Some((_, c)) = chars.next_if(|&(_, c)| {
+                            !c.is_whitespace() && !matches!(c, ';' | '&' | '|' | '<' | '>' | ')')
+                        }) {
+                            if !matches!(c, '\'' | '"' | '\\') {
+                                delimiter.push(c);
+                            }
+                        }
+                        heredocs.push((delimiter, strip_tabs));
+                    }
+                    _ => {}
+                },
+            }
+            word_start = self.quote.is_none()
+                && (c.is_whitespace() || matches!(c, ';' | '&' | '|' | '(' | ')'));
+        }
+        heredocs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::write_dump;
+    use crate::store::{AliasDefinition, AliasScope};
+    use proptest::prelude::*;
+    use std::path::Path;
+    use std::process::{Command, Output};
+
+    const GOLDEN: &str = include_str!("../../tests/golden/zsh_bash.sh");
+
+    #[test]
+    fn test_dump_matches_golden_file() {
+        let aliases = vec![
+            (
+                "ll".to_string(),
+                vec![AliasDefinition::new(
+                    "ls -la".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+            (
+                "build".to_string(),
+                vec![
+                    AliasDefinition::new("make".to_string(), AliasScope::Global),
+                    AliasDefinition::new(
+                        "cargo build".to_string(),
+                        AliasScope::Recursive("/work/rust".to_string()),
+                    ),
+                    AliasDefinition::new(
+                        "cargo build --release".to_string(),
+                        AliasScope::Exact("/work/rust/app".to_string()),
+                    ),
+                ],
+            ),
+            (
+                "greet".to_string(),
+                vec![AliasDefinition::new(
+                    "echo hello @1".to_string(),
+                    AliasScope::Exact("/tmp".to_string()),
+                )],
+            ),
+        ];
+
+        let mut out = Vec::new();
+        write_dump(&ZshBashCodegen, &mut out, aliases.clone()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), GOLDEN);
+
+        // Input order does not leak into the output
+        let mut out = Vec::new();
+        write_dump(&ZshBashCodegen, &mut out, aliases.into_iter().rev()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), GOLDEN);
+    }
+
+    #[test]
+    fn test_plain_aliases_for_simple_commands() {
+        let mut timed = AliasDefinition::new("sleep 0".to_string(), AliasScope::Global);
+        timed.time = true;
+        let aliases = vec![
+            (
+                "ll".to_string(),
+                vec![AliasDefinition::new(
+                    "echo \"it's\" listed".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+            (
+                "say".to_string(),
+                vec![AliasDefinition::new(
+                    "echo @1".to_string(),
+                    AliasScope::Global,
+                )],
+            ),
+            ("nap".to_string(), vec![timed]),
+        ];
+        let mut out = Vec::new();
+        let options = crate::codegen::DumpOptions {
+            plain_aliases: true,
+            ..Default::default()
+        };
+        crate::codegen::write_dump_with_options(&ZshBashCodegen, &mut out, aliases, options)
+            .unwrap();
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("\nalias ll='echo \"it'\\''s\" listed'\n"));
+        assert!(script.contains("\nsay() {\n"));
+        assert!(script.contains("\nnap() {\n"));
+        assert!(script.contains("export AKA_MANAGED_ALIASES=\"ll nap say\"\n"));
+
+        let script = format!(
+            "{}shopt -s expand_aliases\ntype -t ll\neval 'll now'",
+            script
+        );
+        let Some(output) = run_shell("bash", &[], &script, &[]) else {
+            return;
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "alias\nit's listed now\n"
+        );
+    }
+
+    #[test]
+    fn test_annotated_dump_lists_definitions_in_order() {
+        let aliases = vec![(
+            "build".to_string(),
+            vec![
+                AliasDefinition::new(
+                    "cargo build".to_string(),
+                    AliasScope::Recursive("/work".to_string()),
+                ),
+                AliasDefinition::new(
+                    "cargo build -p app".to_string(),
+                    AliasScope::Exact("/work/app".to_string()),
+                ),
+            ],
+        )];
+        let options = crate::codegen::DumpOptions {
+            annotate: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        crate::codegen::write_dump_with_options(&ZshBashCodegen, &mut out, aliases, options)
+            .unwrap();
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains(concat!(
+            "# build: 2 definitions, the first that applies runs\n",
+            "#   1. in /work/app only: cargo build -p app\n",
+            "#   2. in /work and below: cargo build\n",
+            "#   otherwise: the real build command\n",
+            "unalias build"
+        )));
+    }
+
+    #[test]
+    fn test_minify_keeps_multiline_commands_intact() {
+        let command = concat!(
+            "cat <<-'EOF'\n",
+            "\t  # kept\n",
+            "\n",
+            "\t  indented $x\n",
+            "\tEOF\n",
+            "    # dropped\n",
+            "    printf '%s\\n' '  a\n",
+            "  # b' $'  c\\'\n",
+            "  d'\n",
+            "    echo one \\\n",
+            "  two # dropped too"
+        );
+        let aliases = vec![(
+            "f".to_string(),
+            vec![AliasDefinition::new(
+                command.to_string(),
+                AliasScope::Global,
+            )],
+        )];
+        let mut out = Vec::new();
+        crate::codegen::write_dump_with_options(
+            &ZshBashCodegen,
+            &mut out,
+            aliases,
+            Default::default(),
+        )
+        .unwrap();
+        let script = String::from_utf8(out).unwrap();
+        let minified = minify(&script);
+        assert!(minified.contains(concat!(
+            "\tEOF\n",
+            "printf '%s\\n' '  a\n",
+            "  # b' $'  c\\'\n",
+            "  d'\n",
+            "echo one \\\n",
+            "  two # dropped too \"$@\"\n"
+        )));
+        assert!(minified.starts_with("if [ -n \"$ZSH_VERSION\" ]; then if [[ -o aliases ]]"));
+        assert!(minified.ends_with("; unset _aka_aliases_was_on\n"));
+
+        let Some(expected) = run_shell("bash", &[], &format!("{}f", script), &[]) else {
+            return;
+        };
+        let output = run_shell("bash", &[], &format!("{}f", minified), &[]).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&expected.stdout)
+        );
+        assert!(String::from_utf8_lossy(&output.stdout).contains("  # kept\n\n  indented $x\n"));
+        assert!(
+            output.stderr.is_empty(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    /// Define `f` from `command` the way `init --dump` would.
+    fn alias_function(command: &str) -> String {
+        let defs = vec![
+            AliasDefinition::new(command.to_string(), AliasScope::Global),
+            AliasDefinition::new(command.to_string(), AliasScope::Exact("/tmp".to_string())),
+        ];
+        let mut out = Vec::new();
+        crate::codegen::write_alias(&ZshBashCodegen, &mut out, "f", defs).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// Run `script` with `args` as positional parameters; `None` if the shell is not installed.
+    fn run_shell(shell: &str, flags: &[&str], script: &str, args: &[String]) -> Option<Output> {
+        Command::new(shell)
+            .args(flags)
+            .arg("-c")
+            .arg(script)
+            .arg("aka-test")
+            .args(args)
+            .output()
+            .ok()
+    }
+
+    fn word() -> impl Strategy<Value = String> {
+        "[a-zA-Z0-9_./=-]{1,8}"
+    }
+
+    fn token() -> impl Strategy<Value = String> {
+        let placeholder = (0u8..12).prop_map(|n| format!("@{}", n));
+        let double_part = prop_oneof![
+            "[a-zA-Z0-9 _.'@-]{0,6}",
+            (0u8..12).prop_map(|n| format!("@{}", n)),
+            Just("$HOME".to_string()),
+        ];
+        prop_oneof![
+            word(),
+            placeholder,
+            "[^'\\n\\x00]{0,8}".prop_map(|s| format!("'{}'", s)),
+            prop::collection::vec(double_part, 0..3).prop_map(|p| format!("\"{}\"", p.concat())),
+            Just("\"$@\"".to_string()),
+            Just("${HOME}".to_string()),
+            Just("\\@1".to_string()),
+        ]
+    }
+
+    fn simple_command() -> impl Strategy<Value = String> {
+        (word(), prop::collection::vec(token(), 0..4)).prop_map(|(head, rest)| {
+            std::iter::once(head)
+                .chain(rest)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    }
+
+    fn command() -> impl Strategy<Value = String> {
+        let operator = prop_oneof![Just(" && "), Just(" | "), Just("; ")];
+        (
+            simple_command(),
+            prop::collection::vec((operator, simple_command()), 0..2),
+        )
+            .prop_map(|(first, rest)| {
+                rest.into_iter()
+                    .fold(first, |acc, (op, cmd)| format!("{}{}{}", acc, op, cmd))
+            })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn prop_generated_function_parses(command in command()) {
+            let script = alias_function(&command);
+            for shell in ["bash", "zsh"] {
+                if let Some(output) = run_shell(shell, &["-n"], &script, &[]) {
+                    prop_assert!(
+                        output.status.success(),
+                        "{} rejected:\n{}\n{}",
+                        shell,
+                        script,
+                        String::from_utf8_lossy(&output.stderr)
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn prop_placeholder_receives_argument_verbatim(
+            args in prop::collection::vec("\\PC{0,12}", 1..12),
+            pick in any::<prop::sample::Index>(),
+            quoted in any::<bool>(),
+        ) {
+            let n = pick.index(args.len()) + 1;
+            let command = if quoted {
+                format!("printf '[%s]' \"<@{}>\"", n)
+            } else {
+                format!("printf '[%s]' @{}", n)
+            };
+            let script = format!("{}f \"$@\"", alias_function(&command));
+            let expected = if quoted {
+                format!("[<{}>]", args[n - 1])
+            } else {
+                format!("[{}]", args[n - 1])
+            };
+            if let Some(output) = run_shell("bash", &[], &script, &args) {
+                prop_assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+            }
+        }
+
+        #[test]
+        fn prop_arguments_are_forwarded_without_placeholders(
+            args in prop::collection::vec("\\PC{0,12}", 1..6),
+        ) {
+            let script = format!("{}f \"$@\"", alias_function("printf '[%s]'"));
+            let expected: String = args.iter().map(|a| format!("[{}]", a)).collect();
+            if let Some(output) = run_shell("bash", &[], &script, &args) {
+                prop_assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_debug_traces_matched_branch() {
+        let function = alias_function("printf '[%s]'");
+        let script = format!("{}cd /tmp && AKA_DEBUG=1 f hi", function);
+        let Some(output) = run_shell("bash", &[], &script, &[]) else {
+            return;
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "[hi]");
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "aka: f: Exact /tmp -> printf '[%s]'\n"
+        );
+
+        let script = format!("{}cd /tmp && f hi", function);
+        let output = run_shell("bash", &[], &script, &[]).unwrap();
+        assert!(output.stderr.is_empty());
+    }
+
+    #[test]
+    fn test_captured_env_is_local_to_the_call() {
+        let mut def = AliasDefinition::new(
+            "printf '%s;' \"$TOOLCHAIN\"".to_string(),
+            AliasScope::Global,
+        );
+        def.env = BTreeMap::from([("TOOLCHAIN".to_string(), "nightly it's".to_string())]);
+        let mut out = Vec::new();
+        crate::codegen::write_alias(&ZshBashCodegen, &mut out, "f", vec![def]).unwrap();
+        let script = format!(
+            "TOOLCHAIN=stable\n{}f; printf '%s' \"$TOOLCHAIN\"",
+            String::from_utf8(out).unwrap()
+        );
+
+        let Some(output) = run_shell("bash", &[], &script, &[]) else {
+            return;
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "nightly it's;stable"
+        );
+    }
+
+    #[test]
+    fn test_recursive_scope_agrees_with_matches() {
+        let scopes = [
+            AliasScope::Recursive("/srv/app".to_string()),
+            AliasScope::Recursive("/".to_string()),
+            AliasScope::Exact("/srv/app".to_string()),
+        ];
+        let dirs = [
+            "/srv/app",
+            "/srv/app/src",
+            "/srv/app-old",
+            "/srv/apps/x",
+            "/srv",
+        ];
+        for scope in scopes {
+            let def = AliasDefinition::new("echo in".to_string(), scope.clone());
+            let mut out = Vec::new();
+            crate::codegen::write_alias(&ZshBashCodegen, &mut out, "f", vec![def]).unwrap();
+            let mut script = String::from_utf8(out).unwrap();
+            for dir in dirs {
+                script.push_str(&format!("PWD='{}' f 2>/dev/null || echo out\n", dir));
+            }
+
+            let Some(output) = run_shell("bash", &[], &script, &[]) else {
+                return;
+            };
+            let expected: Vec<&str> = dirs
+                .iter()
+                .map(|dir| {
+                    if scope.matches(Path::new(dir)) {
+                        "in"
+                    } else {
+                        "out"
+                    }
+                })
+                .collect();
+            assert_eq!(
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .collect::<Vec<_>>(),
+                expected,
+                "{:?}",
+                scope
+            );
+        }
+    }
+
+    #[test]
+    fn test_disable_runs_the_real_command() {
+        let def = AliasDefinition::new("printf 'aliased\\n'".to_string(), AliasScope::Global);
+        let mut out = Vec::new();
+        crate::codegen::write_alias(&ZshBashCodegen, &mut out, "echo", vec![def]).unwrap();
+        let script = format!(
+            "{}echo hi; AKA_DISABLE=1 echo hi",
+            String::from_utf8(out).unwrap()
+        );
+        let Some(output) = run_shell("bash", &[], &script, &[]) else {
+            return;
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "aliased\nhi\n");
+    }
+
+    #[test]
+    fn test_kube_guard_checks_current_context() {
+        let function = |guard: KubeGuard| {
+            let mut def = AliasDefinition::new("echo ran".to_string(), AliasScope::Global);
+            def.kube = Some(guard);
+            let mut out = Vec::new();
+            crate::codegen::write_alias(&ZshBashCodegen, &mut out, "f", vec![def]).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let run = |guard: KubeGuard, context: &str| {
+            let script = format!("kubectl() {{ echo {}; }}\n{}f", context, function(guard));
+            run_shell("bash", &[], &script, &[])
+        };
+
+        let Some(output) = run(KubeGuard::Deny("prod-*".to_string()), "prod-eu") else {
+            return;
+        };
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("refuses to run"));
+
+        let output = run(KubeGuard::Deny("prod-*".to_string()), "staging").unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "ran\n");
+
+        let output = run(KubeGuard::Only("kind-*".to_string()), "prod-eu").unwrap();
+        assert!(!output.status.success());
+        let output = run(KubeGuard::Only("kind-*".to_string()), "kind-dev").unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "ran\n");
+    }
+
+    #[test]
+    fn test_tmux_scope_checks_session_name() {
+        let defs = vec![
+            AliasDefinition::new("echo global".to_string(), AliasScope::Global),
+            AliasDefinition::new(
+                "echo work".to_string(),
+                AliasScope::Tmux("work".to_string()),
+            ),
+        ];
+        let mut out = Vec::new();
+        crate::codegen::write_alias(&ZshBashCodegen, &mut out, "f", defs).unwrap();
+        let script = format!(
+            "tmux() {{ echo \"$SESSION\"; }}\n{}f; TMUX=1 SESSION=work f; TMUX=1 SESSION=play f",
+            String::from_utf8(out).unwrap()
+        );
+
+        let Some(output) = run_shell("bash", &[], &script, &[]) else {
+            return;
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "global\nwork\nglobal\n"
+        );
+    }
+
+    #[test]
+    fn test_write_history_records_expanded_command() {
+        let function = |command: &str| {
+            let mut def = AliasDefinition::new(command.to_string(), AliasScope::Global);
+            def.write_history = true;
+            let mut out = Vec::new();
+            crate::codegen::write_alias(&ZshBashCodegen, &mut out, "f", vec![def]).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let script = format!(
+            "set -o history\n{}f 'a b'; echo \"status=$?\"; history 1",
+            function("printf '%s\\n' @1 >/dev/null; false")
+        );
+        let Some(output) = run_shell("bash", &[], &script, &[]) else {
+            return;
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.starts_with("status=1\n"), "{}", stdout);
+        assert!(
+            stdout.ends_with("printf '%s\\n' a\\ b >/dev/null; false\n"),
+            "{}",
+            stdout
+        );
+
+        let script = format!(
+            "set -o history\n{}f \"it's\"; history 1",
+            function("echo \"x\" $HOME")
+        );
+        let output = run_shell("bash", &[], &script, &[]).unwrap();
+        assert!(
+            String::from_utf8_lossy(&output.stdout).ends_with("echo \"x\" $HOME it\\'s\n"),
+            "{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+
+    #[test]
+    fn test_time_reports_duration_and_keeps_status() {
+        let mut def = AliasDefinition::new("sleep 1; false".to_string(), AliasScope::Global);
+        def.time = true;
+        let mut out = Vec::new();
+        crate::codegen::write_alias(&ZshBashCodegen, &mut out, "slow", vec![def]).unwrap();
+        let script = format!(
+            "{}slow; echo \"status=$?\"",
+            String::from_utf8(out).unwrap()
+        );
+
+        let Some(output) = run_shell("bash", &[], &script, &[]) else {
+            return;
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "status=1\n");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr == "aka: slow finished in 1s\n" || stderr == "aka: slow finished in 2s\n",
+            "{}",
+            stderr
+        );
+    }
+
+    #[test]
+    fn test_log_output_tees_and_rotates() {
+        let dir = std::env::temp_dir().join(format!("aka-log-output-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("deploy.log");
+        std::fs::write(&log, vec![b'x'; LOG_OUTPUT_MAX_BYTES as usize + 1]).unwrap();
+
+        let mut def = AliasDefinition::new(
+            "echo out; echo err >&2; false".to_string(),
+            AliasScope::Global,
+        );
+        def.log_output = Some(log.to_str().unwrap().to_string());
+        let mut out = Vec::new();
+        crate::codegen::write_alias(&ZshBashCodegen, &mut out, "f", vec![def]).unwrap();
+        let script = format!("{}f; echo \"status=$?\"", String::from_utf8(out).unwrap());
+
+        let output = run_shell("bash", &[], &script, &[]);
+        let rotated = std::fs::metadata(dir.join("deploy.log.1")).map(|m| m.len());
+        let logged = std::fs::read_to_string(&log).unwrap_or_default();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let Some(output) = output else {
+            return;
+        };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "out\nerr\nstatus=1\n"
+        );
+        assert_eq!(rotated.unwrap(), LOG_OUTPUT_MAX_BYTES + 1);
+        assert_eq!(logged, "out\nerr\n");
+    }
+
+    #[test]
+    fn test_notify_reports_exit_status() {
+        for (notifier, stub, expected) in [
+            (
+                Notifier::NotifySend,
+                r#"notify-send() { echo "$1: $2" >&3; }"#,
+                "aka: f: finished with exit status 3\n",
+            ),
+            (
+                Notifier::Osascript,
+                r#"osascript() { echo "$2" >&3; }"#,
+                concat!(
+                    "display notification \"finished with exit status 3\" ",
+                    "with title \"aka: f\"\n"
+                ),
+            ),
+        ] {
+            let codegen = ZshBashCodegen;
+            let body = format!(
+                "    (exit 3); {}\n",
+                codegen.preserve_status(&[codegen.notify("f", notifier)])
+            );
+            let mut out = Vec::new();
+            codegen.function(&mut out, "f", &body).unwrap();
+            let script = format!(
+                "exec 3>&2\n{}\n{}f; echo \"status=$?\"",
+                stub,
+                String::from_utf8(out).unwrap()
+            );
+
+            let Some(output) = run_shell("bash", &[], &script, &[]) else {
+                return;
+            };
+            assert_eq!(String::from_utf8_lossy(&output.stdout), "status=3\n");
+            assert_eq!(String::from_utf8_lossy(&output.stderr), expected);
+        }
+    }
+
+    #[test]
+    fn test_replace_placeholders_respects_quotes() {
+        assert_eq!(replace_placeholders("echo @1"), "echo \"${1}\"");
+        assert_eq!(replace_placeholders("echo \"x @12\""), "echo \"x ${12}\"");
+        assert_eq!(replace_placeholders("awk '{print @2}'"), "awk '{print $2}'");
+        assert_eq!(
+            replace_placeholders("echo \\@1 me@host"),
+            "echo \\@1 me@host"
+        );
+    }
+
+    #[test]
+    fn test_arguments_go_inside_a_trailing_group() {
+        assert_eq!(prepare_command_body("ls -la"), "ls -la \"$@\"");
+        assert_eq!(
+            prepare_command_body("make && { cd out; ls; }"),
+            "make && { cd out; ls \"$@\"; }"
+        );
+    }
+}
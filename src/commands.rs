@@ -1,6 +1,29 @@
 pub mod add;
+pub mod announce;
+pub mod complete;
+pub mod doctor;
+pub mod edit;
+pub mod effective;
+pub mod env;
+pub mod explain;
+pub mod generate;
 pub mod history;
+pub mod import;
 pub mod init;
 pub mod install;
+pub mod lint;
 pub mod list;
+pub mod migrate;
+pub mod project;
+pub mod prompt;
+pub mod query;
 pub mod remove;
+pub mod resolve;
+pub mod review;
+pub mod scope;
+pub mod script;
+pub mod ssh;
+pub mod stats;
+pub mod tip;
+pub mod var;
+pub mod version;
@@ -1,26 +1,392 @@
-use crate::store::{AliasScope, Store};
+use crate::commands::list::scope_label;
+use crate::commands::script::{scripts_dir, shell_quote, write_script};
+use crate::config::LimitsConfig;
+use crate::references::{alias_ref, expand_alias_refs, uses_alias_refs};
+use crate::store::{AliasDefinition, AliasScope, KubeGuard, ScopeSpec, Store};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
 
+/// Reject context patterns that could break out of the generated `case` statement.
+fn validate_kube_pattern(pattern: &str) -> std::result::Result<(), crate::error::AkaError> {
+    let valid = !pattern.is_empty()
+        && pattern.chars().all(|c| {
+            c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':' | '@' | '/' | '*' | '?')
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(crate::error::AkaError::ConfigError(format!(
+            "Invalid kube context pattern: {}",
+            pattern
+        )))
+    }
+}
+
+/// What to do with the definitions a new one competes with.
+#[derive(Debug, PartialEq)]
+enum Resolution {
+    KeepAll,
+    /// Indices into the competing definitions to remove.
+    Replace(Vec<usize>),
+    Cancel,
+}
+
+/// Other definitions of the alias when the new one mixes global and scoped definitions.
+///
+/// A global definition never wins where a scoped one applies, so adding one next to the
+/// other is often a mistake; definitions that only differ in path are left alone.
+fn competing_definitions<'a>(
+    defs: &'a [AliasDefinition],
+    scope: &AliasScope,
+) -> Vec<&'a AliasDefinition> {
+    let is_global = |scope: &AliasScope| matches!(scope, AliasScope::Global);
+    let others: Vec<&AliasDefinition> = defs
+        .iter()
+        .filter(|d| &d.scope != scope && !d.system)
+        .collect();
+    if others
+        .iter()
+        .any(|d| is_global(&d.scope) != is_global(scope))
+    {
+        others
+    } else {
+        Vec::new()
+    }
+}
+
+fn parse_resolution(input: &str, count: usize) -> Option<Resolution> {
+    match input.trim().to_lowercase().as_str() {
+        "" | "k" | "keep" => Some(Resolution::KeepAll),
+        "r" | "replace" => Some(Resolution::Replace((0..count).collect())),
+        "c" | "cancel" => Some(Resolution::Cancel),
+        numbers => numbers
+            .split([',', ' '])
+            .filter(|n| !n.is_empty())
+            .map(|n| match n.parse::<usize>() {
+                Ok(n) if (1..=count).contains(&n) => Some(n - 1),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(Resolution::Replace),
+    }
+}
+
+/// Show the alias's definitions next to the new one and ask which to keep.
+fn ask_resolution(
+    alias: &str,
+    new: &AliasDefinition,
+    competing: &[&AliasDefinition],
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> std::result::Result<Resolution, crate::error::AkaError> {
+    writeln!(out, "'{}' is already defined in other scopes:", alias)?;
+    for (i, def) in competing.iter().enumerate() {
+        writeln!(out, "  {}) {} {}", i + 1, def.command, scope_label(def))?;
+    }
+    writeln!(out, "  new: {} {}", new.command, scope_label(new))?;
+
+    loop {
+        write!(
+            out,
+            "[k]eep all, [r]eplace all, numbers to replace (e.g. 1,2), or [c]ancel (K/r/c): "
+        )?;
+        out.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(Resolution::Cancel);
+        }
+        if let Some(resolution) = parse_resolution(&line, competing.len()) {
+            return Ok(resolution);
+        }
+    }
+}
+
+/// Ask before replacing `old`, the alias's definition in the same scope.
+fn confirm_overwrite(
+    alias: &str,
+    old: &AliasDefinition,
+    command: &str,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> std::result::Result<bool, crate::error::AkaError> {
+    write!(
+        out,
+        "'{}' {} already runs '{}'. Replace it with '{}'? (y/N): ",
+        alias,
+        scope_label(old),
+        old.command,
+        command
+    )?;
+    out.flush()?;
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(matches!(line.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Refuse command bodies and alias counts beyond the configured limits.
+fn check_limits(
+    store: &Store,
+    alias: &str,
+    command: &str,
+    limits: &LimitsConfig,
+) -> std::result::Result<(), crate::error::AkaError> {
+    let length = command.chars().count();
+    if length > limits.max_command_length {
+        return Err(crate::error::AkaError::LimitExceeded(format!(
+            "the command for '{}' is {} characters (max {}); save it as a script instead with `aka add {} --as-script <command>`",
+            alias, length, limits.max_command_length, alias
+        )));
+    }
+    let aliases = store.list()?;
+    if !aliases.contains_key(alias) && aliases.len() >= limits.max_aliases {
+        return Err(crate::error::AkaError::LimitExceeded(format!(
+            "the store already holds {} aliases (max {}); remove unused ones (see `aka stats`) or raise limits.max_aliases in the config file",
+            aliases.len(),
+            limits.max_aliases
+        )));
+    }
+    Ok(())
+}
+
+/// Optional settings for a new definition.
+#[derive(Debug, Default)]
+pub struct AddOptions {
+    /// Restrict the alias to matching kubectl contexts.
+    pub kube: Option<KubeGuard>,
+    /// Save the command as an executable file under the data directory and run that instead.
+    pub as_script: bool,
+    /// Environment variables whose current values are stored and exported when the alias runs.
+    pub capture_env: Vec<String>,
+    /// Add the command as run to the shell history after the alias runs.
+    pub write_history: bool,
+    /// Report how long the command ran.
+    pub time: bool,
+    /// Send a desktop notification when the command finishes.
+    pub notify: bool,
+    /// File the command's output is also written to.
+    pub log_output: Option<PathBuf>,
+    /// Program that must be installed for the definition to be defined.
+    pub if_installed: Option<String>,
+    /// Commands to run instead when the program of the command is not installed.
+    pub fallbacks: Vec<String>,
+}
+
+/// Current values of the variables named in `names`.
+fn capture_env(
+    names: &[String],
+) -> std::result::Result<BTreeMap<String, String>, crate::error::AkaError> {
+    let mut env = BTreeMap::new();
+    for name in names {
+        let valid = name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(crate::error::AkaError::ConfigError(format!(
+                "Invalid environment variable name: {}",
+                name
+            )));
+        }
+        let value = std::env::var(name).map_err(|_| {
+            crate::error::AkaError::ConfigError(format!("Environment variable {} is not set", name))
+        })?;
+        env.insert(name.clone(), value);
+    }
+    Ok(env)
+}
+
+/// Absolute form of the `--log-output` path, creating its directory so `tee` can open it.
+fn resolve_log_output(path: &Path) -> std::result::Result<String, crate::error::AkaError> {
+    let path = std::env::current_dir()?.join(path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    path.to_str().map(str::to_string).ok_or_else(|| {
+        crate::error::AkaError::ConfigError(format!("{} is not valid UTF-8", path.display()))
+    })
+}
+
+/// Turn the `--scope` argument into a scope: a [`ScopeSpec`], or global when omitted.
+/// `exact:` and `recursive:` take precedence over `recursive`.
+fn resolve_scope(
+    scope: Option<String>,
+    recursive: bool,
+) -> std::result::Result<AliasScope, crate::error::AkaError> {
+    match scope {
+        Some(d) => Ok(ScopeSpec::parse(&d)?.into_scope(recursive)),
+        None => Ok(AliasScope::Global),
+    }
+}
+
+/// Command for `aka add --then`: `command`, if given, followed by references to each alias in
+/// `then`, joined with `&&` so the chain stops at the first failing step.
+pub fn then_command(
+    store: &Store,
+    alias: &str,
+    command: Option<String>,
+    then: &[String],
+) -> std::result::Result<String, crate::error::AkaError> {
+    let mut aliases = store.list()?;
+    crate::system::merge_system_aliases(&mut aliases);
+
+    let mut steps: Vec<String> = command.into_iter().collect();
+    for name in then {
+        if name == alias {
+            return Err(crate::error::AkaError::ConfigError(format!(
+                "'{}' cannot run itself with --then",
+                alias
+            )));
+        }
+        if !aliases.contains_key(name) {
+            return Err(crate::error::AkaError::AliasNotFound(name.clone()));
+        }
+        steps.push(alias_ref(name));
+    }
+    Ok(steps.join(" && "))
+}
+
+/// Print a shell function for `alias` without saving it, for `eval` in the current shell only.
+///
+/// Alias references and variables are expanded from the store as `aka init` would, but
+/// nothing is written to it.
+pub fn handle_session_add_command(
+    store: &Store,
+    alias: String,
+    command: String,
+    scope: Option<String>,
+    recursive: bool,
+    options: AddOptions,
+) -> std::result::Result<String, crate::error::AkaError> {
+    if let Some(KubeGuard::Only(pattern) | KubeGuard::Deny(pattern)) = &options.kube {
+        validate_kube_pattern(pattern)?;
+    }
+    let mut definition = AliasDefinition::new(command, resolve_scope(scope, recursive)?);
+    definition.fallbacks = options.fallbacks;
+    definition.command = definition
+        .available_command(crate::codegen::on_path)
+        .to_string();
+    if uses_alias_refs(&definition.command) {
+        let mut aliases = store.list()?;
+        crate::system::merge_system_aliases(&mut aliases);
+        let (command, warnings) = expand_alias_refs(&alias, &definition, &aliases);
+        for warning in warnings {
+            eprintln!("aka: {}", warning);
+        }
+        definition.command = command;
+    }
+    if crate::commands::var::uses_vars(&definition.command) {
+        let (command, missing) =
+            crate::commands::var::expand_vars(&definition.command, &store.vars()?);
+        for name in missing {
+            eprintln!("aka: '{}' uses undefined variable '{}'", alias, name);
+        }
+        definition.command = command;
+    }
+    definition.kube = options.kube;
+    definition.env = capture_env(&options.capture_env)?;
+    definition.write_history = options.write_history;
+    definition.time = options.time;
+    definition.notify = options.notify;
+    definition.log_output = options
+        .log_output
+        .as_deref()
+        .map(resolve_log_output)
+        .transpose()?;
+    definition.requires = options.if_installed;
+
+    let mut out = Vec::new();
+    crate::codegen::write_alias(
+        &crate::codegen::ZshBashCodegen,
+        &mut out,
+        &alias,
+        vec![definition],
+    )?;
+    Ok(String::from_utf8_lossy(&out).trim_end().to_string())
+}
+
+/// Add `alias` for `command`.
 pub fn handle_add_command(
     store: &mut Store,
     alias: String,
     command: String,
     scope: Option<String>,
     recursive: bool,
+    options: AddOptions,
 ) -> std::result::Result<String, crate::error::AkaError> {
-    let scope = if let Some(d) = scope {
-        let path = std::fs::canonicalize(d)
-            .map_err(|e| crate::error::AkaError::ConfigError(e.to_string()))?;
-        let path_str = path.to_string_lossy().to_string();
-        if recursive {
-            AliasScope::Recursive(path_str)
-        } else {
-            AliasScope::Exact(path_str)
-        }
+    let AddOptions {
+        kube,
+        as_script,
+        capture_env: env_names,
+        write_history,
+        time,
+        notify,
+        log_output,
+        if_installed,
+        fallbacks,
+    } = options;
+    if let Some(KubeGuard::Only(pattern) | KubeGuard::Deny(pattern)) = &kube {
+        validate_kube_pattern(pattern)?;
+    }
+    let env = capture_env(&env_names)?;
+    let scope = resolve_scope(scope, recursive)?;
+
+    let config = crate::config::load()?;
+    let existing = store.list()?.remove(&alias).unwrap_or_default();
+    if let Some(old) = existing
+        .iter()
+        .find(|d| d.scope == scope && d.command != command)
+        && config.confirm.overwrite.should_ask()
+        && !confirm_overwrite(
+            &alias,
+            old,
+            &command,
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout(),
+        )?
+    {
+        return Err(crate::error::AkaError::OperationCancelled);
+    }
+
+    let command = if as_script {
+        let path = write_script(&scripts_dir()?, &alias, &scope, &command)?;
+        shell_quote(&path.to_string_lossy())
     } else {
-        AliasScope::Global
+        command
     };
+    check_limits(store, &alias, &command, &config.limits)?;
+
+    let mut definition = AliasDefinition::new(command.clone(), scope);
+    definition.kube = kube;
+    definition.env = env;
+    definition.write_history = write_history;
+    definition.time = time;
+    definition.notify = notify;
+    definition.log_output = log_output.as_deref().map(resolve_log_output).transpose()?;
+    definition.requires = if_installed;
+    definition.fallbacks = fallbacks;
+
+    if config.confirm.collision.should_ask() {
+        let competing = competing_definitions(&existing, &definition.scope);
+        if !competing.is_empty() {
+            let resolution = ask_resolution(
+                &alias,
+                &definition,
+                &competing,
+                &mut std::io::stdin().lock(),
+                &mut std::io::stdout(),
+            )?;
+            match resolution {
+                Resolution::KeepAll => {}
+                Resolution::Replace(indices) => {
+                    for i in indices {
+                        store.remove_scope_from_alias(&alias, &competing[i].scope)?;
+                    }
+                }
+                Resolution::Cancel => return Err(crate::error::AkaError::OperationCancelled),
+            }
+        }
+    }
 
-    store.add(alias.clone(), command.clone(), scope)?;
+    store.add_definition(alias.clone(), definition)?;
     Ok(format!(
         "Added alias '{}' for '{}'\n(Reload shell to apply)",
         alias, command
@@ -28,12 +394,27 @@ pub fn handle_add_command(
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod tests {
     use super::*;
     use crate::Store;
     use rstest::rstest;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_capture_env_snapshots_values() {
+        // SAFETY: the variable name is unique to this test.
+        unsafe { std::env::set_var("AKA_TEST_CAPTURED", "snapshot") };
+        let env = capture_env(&["AKA_TEST_CAPTURED".to_string()]).unwrap();
+        assert_eq!(
+            env.get("AKA_TEST_CAPTURED").map(String::as_str),
+            Some("snapshot")
+        );
+
+        assert!(capture_env(&["AKA_TEST_NEVER_SET".to_string()]).is_err());
+        assert!(capture_env(&["1BAD".to_string()]).is_err());
+    }
+
     #[rstest]
     #[case("test", "echo test")]
     #[case("test_prams", "echo test @1 @2")]
@@ -41,7 +422,14 @@ mod tests {
         let dir = tempdir().unwrap();
         let path = dir.path().join("aka.redb");
         let mut store = Store::load(&path).unwrap();
-        match handle_add_command(&mut store, alias, command, None, false) {
+        match handle_add_command(
+            &mut store,
+            alias,
+            command,
+            None,
+            false,
+            AddOptions::default(),
+        ) {
             Ok(_) => assert!(true),
             Err(e) => panic!("Expected Ok, got Err: {:?}", e),
         }
@@ -56,14 +444,28 @@ mod tests {
         let mut store = Store::load(&path).unwrap();
 
         // Initial add
-        match handle_add_command(&mut store, alias.clone(), command.clone(), None, false) {
+        match handle_add_command(
+            &mut store,
+            alias.clone(),
+            command.clone(),
+            None,
+            false,
+            AddOptions::default(),
+        ) {
             Ok(_) => assert!(true),
             Err(e) => panic!("Expected Ok, got Err: {:?}", e),
         }
 
         // Overwrite with modification
         let new_command = format!("{}_modified", command);
-        match handle_add_command(&mut store, alias.clone(), new_command.clone(), None, false) {
+        match handle_add_command(
+            &mut store,
+            alias.clone(),
+            new_command.clone(),
+            None,
+            false,
+            AddOptions::default(),
+        ) {
             Ok(_) => assert!(true),
             Err(e) => panic!("Expected Ok, got Err: {:?}", e),
         }
@@ -73,4 +475,139 @@ mod tests {
         let defs = list.get(&alias).unwrap();
         assert_eq!(defs[0].command, new_command);
     }
+
+    #[test]
+    fn test_add_command_with_kube_guard() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        let guard = KubeGuard::Deny("prod-*".to_string());
+        handle_add_command(
+            &mut store,
+            "kd".to_string(),
+            "kubectl delete".to_string(),
+            None,
+            false,
+            AddOptions {
+                kube: Some(guard.clone()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(store.list().unwrap()["kd"][0].kube, Some(guard));
+
+        let result = handle_add_command(
+            &mut store,
+            "kd".to_string(),
+            "kubectl delete".to_string(),
+            None,
+            false,
+            AddOptions {
+                kube: Some(KubeGuard::Deny("prod) rm -rf ~;;".to_string())),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            result,
+            Err(crate::error::AkaError::ConfigError(msg)) if msg.starts_with("Invalid kube context")
+        ));
+    }
+
+    #[test]
+    fn test_competing_definitions() {
+        let defs = vec![
+            AliasDefinition::new(
+                "cargo build".to_string(),
+                AliasScope::Recursive("/a".to_string()),
+            ),
+            AliasDefinition::new("make".to_string(), AliasScope::Exact("/b".to_string())),
+        ];
+        assert_eq!(competing_definitions(&defs, &AliasScope::Global).len(), 2);
+        assert!(competing_definitions(&defs, &AliasScope::Exact("/c".to_string())).is_empty());
+
+        let defs = vec![AliasDefinition::new("make".to_string(), AliasScope::Global)];
+        assert_eq!(
+            competing_definitions(&defs, &AliasScope::Exact("/c".to_string())).len(),
+            1
+        );
+        assert!(competing_definitions(&defs, &AliasScope::Global).is_empty());
+    }
+
+    #[test]
+    fn test_ask_resolution() {
+        let existing = [
+            AliasDefinition::new(
+                "cargo build".to_string(),
+                AliasScope::Recursive("/a".to_string()),
+            ),
+            AliasDefinition::new("make".to_string(), AliasScope::Exact("/b".to_string())),
+        ];
+        let competing: Vec<&AliasDefinition> = existing.iter().collect();
+        let new = AliasDefinition::new("just build".to_string(), AliasScope::Global);
+
+        let mut out = Vec::new();
+        let mut input = std::io::Cursor::new("9\n2\n");
+        let resolution = ask_resolution("b", &new, &competing, &mut input, &mut out).unwrap();
+        assert_eq!(resolution, Resolution::Replace(vec![1]));
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with(
+            "'b' is already defined in other scopes:\n  \
+             1) cargo build (Recursive: /a)\n  \
+             2) make (Exact: /b)\n  \
+             new: just build (Global)\n"
+        ));
+        // The invalid answer asks again
+        assert_eq!(out.matches("(K/r/c)").count(), 2);
+
+        assert_eq!(parse_resolution("\n", 2), Some(Resolution::KeepAll));
+        assert_eq!(
+            parse_resolution("r", 2),
+            Some(Resolution::Replace(vec![0, 1]))
+        );
+        assert_eq!(parse_resolution("c", 2), Some(Resolution::Cancel));
+        assert_eq!(
+            parse_resolution("1, 2", 2),
+            Some(Resolution::Replace(vec![0, 1]))
+        );
+        assert_eq!(parse_resolution("0", 2), None);
+    }
+
+    #[test]
+    fn test_confirm_overwrite() {
+        let old = AliasDefinition::new("git status".to_string(), AliasScope::Global);
+
+        let mut out = Vec::new();
+        let mut input = std::io::Cursor::new("y\n");
+        assert!(confirm_overwrite("g", &old, "git switch", &mut input, &mut out).unwrap());
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "'g' (Global) already runs 'git status'. Replace it with 'git switch'? (y/N): "
+        );
+
+        let mut input = std::io::Cursor::new("");
+        assert!(!confirm_overwrite("g", &old, "git switch", &mut input, &mut Vec::new()).unwrap());
+    }
+
+    #[test]
+    fn test_check_limits() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add("a".to_string(), "true".to_string(), AliasScope::Global)
+            .unwrap();
+        let limits = LimitsConfig {
+            max_command_length: 10,
+            max_aliases: 1,
+        };
+
+        assert!(check_limits(&store, "a", "echo ok", &limits).is_ok());
+        assert!(matches!(
+            check_limits(&store, "a", "echo far too long", &limits),
+            Err(crate::error::AkaError::LimitExceeded(msg)) if msg.contains("--as-script")
+        ));
+        assert!(matches!(
+            check_limits(&store, "b", "true", &limits),
+            Err(crate::error::AkaError::LimitExceeded(msg)) if msg.contains("already holds 1 aliases")
+        ));
+    }
 }
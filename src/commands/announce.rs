@@ -0,0 +1,84 @@
+use crate::Store;
+use crate::store::AliasScope;
+use std::path::Path;
+
+/// Names of the aliases with a directory-scoped definition that applies in `current_dir`.
+pub(crate) fn active_scoped_aliases(
+    store: &Store,
+    current_dir: &str,
+) -> std::result::Result<Vec<String>, crate::error::AkaError> {
+    let mut aliases = store.list()?;
+    crate::system::merge_system_aliases(&mut aliases);
+
+    let mut active: Vec<String> = aliases
+        .into_iter()
+        .filter(|(_, defs)| {
+            defs.iter().any(|d| {
+                matches!(d.scope, AliasScope::Exact(_) | AliasScope::Recursive(_))
+                    && d.scope.matches(Path::new(current_dir))
+            })
+        })
+        .map(|(alias, _)| alias)
+        .collect();
+    active.sort();
+    Ok(active)
+}
+
+/// Summarize the scoped aliases active in `current_dir` for the directory-change hook.
+///
+/// Returns an empty string when no scoped alias applies, so the hook stays silent.
+pub fn handle_announce_command(
+    store: &Store,
+    current_dir: &str,
+) -> std::result::Result<String, crate::error::AkaError> {
+    let active = active_scoped_aliases(store, current_dir)?;
+    if active.is_empty() {
+        return Ok(String::new());
+    }
+
+    Ok(format!(
+        "aka: {} project alias(es) active: {}",
+        active.len(),
+        active.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_announce_lists_scoped_aliases() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aka.redb");
+        let mut store = Store::load(&path).unwrap();
+
+        store
+            .add("g".to_string(), "git".to_string(), AliasScope::Global)
+            .unwrap();
+        store
+            .add(
+                "test".to_string(),
+                "cargo test".to_string(),
+                AliasScope::Recursive("/work/app".to_string()),
+            )
+            .unwrap();
+        store
+            .add(
+                "dc".to_string(),
+                "docker compose".to_string(),
+                AliasScope::Exact("/work/app".to_string()),
+            )
+            .unwrap();
+
+        let msg = handle_announce_command(&store, "/work/app").unwrap();
+        assert_eq!(msg, "aka: 2 project alias(es) active: dc, test");
+
+        let msg = handle_announce_command(&store, "/work/app/src").unwrap();
+        assert_eq!(msg, "aka: 1 project alias(es) active: test");
+
+        let msg = handle_announce_command(&store, "/elsewhere").unwrap();
+        assert!(msg.is_empty());
+    }
+}
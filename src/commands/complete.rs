@@ -0,0 +1,133 @@
+use crate::Store;
+use crate::error::AkaError;
+use crate::store::resolve_definition;
+use clap_complete::engine::CompletionCandidate;
+
+/// Alias names with the command each one runs in `current_dir` as help text.
+///
+/// System aliases are only offered when `include_system` is set, since commands such as
+/// `remove` cannot act on them.
+pub fn alias_names(
+    store: &Store,
+    current_dir: &str,
+    include_system: bool,
+) -> std::result::Result<Vec<(String, String)>, AkaError> {
+    let mut aliases = store.list()?;
+    if include_system {
+        crate::system::merge_system_aliases(&mut aliases);
+    }
+
+    let mut names: Vec<(String, String)> = aliases
+        .into_iter()
+        .filter_map(|(alias, defs)| {
+            let def = resolve_definition(&defs, current_dir).or(defs.first())?;
+            Some((alias, def.command.clone()))
+        })
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Completion candidates for clap's dynamic completer; failures simply complete nothing.
+pub fn alias_candidates(include_system: bool) -> Vec<CompletionCandidate> {
+    let current_dir = std::env::current_dir()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    Store::new()
+        .and_then(|store| alias_names(&store, &current_dir, include_system))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(alias, command)| CompletionCandidate::new(alias).help(Some(command.into())))
+        .collect()
+}
+
+/// The names in `names` that `word` is most likely a typo of, closest first and tied names
+/// together; empty when none is close enough.
+///
+/// Short words allow a single edit so that two-letter aliases are not mistaken for commands.
+pub fn closest_names<'a>(word: &str, names: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let allowed = if word.chars().count() <= 4 { 1 } else { 2 };
+    let candidates: Vec<(usize, &str)> = names
+        .into_iter()
+        .map(|name| (edit_distance(word, name), name))
+        .filter(|(distance, _)| (1..=allowed).contains(distance))
+        .collect();
+    let Some(best) = candidates.iter().map(|(distance, _)| *distance).min() else {
+        return Vec::new();
+    };
+    candidates
+        .into_iter()
+        .filter(|(distance, _)| *distance == best)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Levenshtein distance, counting an adjacent swap as a single edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_alias_names() -> std::result::Result<(), AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        store.add("ll".to_string(), "ls -la".to_string(), AliasScope::Global)?;
+        store.add("b".to_string(), "make".to_string(), AliasScope::Global)?;
+        store.add(
+            "b".to_string(),
+            "cargo build".to_string(),
+            AliasScope::Recursive("/work".to_string()),
+        )?;
+        store.add(
+            "t".to_string(),
+            "cargo test".to_string(),
+            AliasScope::Exact("/elsewhere".to_string()),
+        )?;
+
+        let names = alias_names(&store, "/work/app", false)?;
+        assert_eq!(
+            names,
+            vec![
+                ("b".to_string(), "cargo build".to_string()),
+                ("ll".to_string(), "ls -la".to_string()),
+                ("t".to_string(), "cargo test".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_closest_names() {
+        let names = ["add", "edit", "last", "list", "remove", "review"];
+        assert_eq!(closest_names("lst", names), vec!["last", "list"]);
+        assert_eq!(closest_names("lsit", names), vec!["list"]);
+        assert_eq!(closest_names("remvoe", names), vec!["remove"]);
+        assert_eq!(closest_names("eidt", names), vec!["edit"]);
+        assert!(closest_names("edit", names).is_empty());
+        assert!(closest_names("gs", names).is_empty());
+        assert!(closest_names("deploy", names).is_empty());
+    }
+}
@@ -0,0 +1,67 @@
+use crate::Store;
+use crate::error::AkaError;
+use crate::store::salvage_definitions;
+
+/// What `aka doctor` does about corrupt records.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DoctorAction {
+    Report,
+    /// Keep the definitions that still decode; quarantine records with none.
+    Repair,
+    Quarantine,
+}
+
+/// Check the system alias file, then the store for alias records that fail to decode.
+///
+/// Such records are otherwise read as a global alias whose command is the raw value, which
+/// hides partial writes instead of surfacing them.
+pub fn handle_doctor_command(
+    store: &mut Store,
+    action: DoctorAction,
+) -> std::result::Result<String, AkaError> {
+    crate::system::check_system_file()?;
+    let corrupt = store.corrupt_records()?;
+    if corrupt.is_empty() {
+        return Ok(format!(
+            "No problems found in {} alias(es)",
+            store.list()?.len()
+        ));
+    }
+
+    let mut lines = Vec::new();
+    for (alias, raw) in &corrupt {
+        match (action, salvage_definitions(raw)) {
+            (DoctorAction::Report, salvaged) => {
+                let state = match &salvaged {
+                    Some(defs) => format!("repairable: {} definition(s) decode", defs.len()),
+                    None => "not repairable".to_string(),
+                };
+                lines.push(format!("Corrupt record '{}' ({}):", alias, state));
+                lines.push(format!("    {}", raw));
+            }
+            (DoctorAction::Repair, Some(defs)) => {
+                store.replace_definitions(alias, &defs)?;
+                lines.push(format!(
+                    "Repaired '{}' (kept {} definition(s))",
+                    alias,
+                    defs.len()
+                ));
+            }
+            (DoctorAction::Repair | DoctorAction::Quarantine, _) => {
+                store.quarantine(alias)?;
+                lines.push(format!("Quarantined '{}'", alias));
+            }
+        }
+    }
+
+    if action == DoctorAction::Report {
+        lines.push(format!(
+            "{} corrupt record(s) are read as global aliases running their raw value.\n\
+             Run `aka doctor --repair` to keep what decodes, or `aka doctor --quarantine` to set them aside.",
+            corrupt.len()
+        ));
+    } else {
+        lines.push("(Reload shell to apply)".to_string());
+    }
+    Ok(lines.join("\n"))
+}
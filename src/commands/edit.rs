@@ -0,0 +1,633 @@
+use crate::Store;
+use crate::commands::project::is_alias_name;
+use crate::commands::script::script_file;
+use crate::error::AkaError;
+use crate::store::{
+    AliasDefinition, AliasScope, EXACT_SCOPE_PREFIX, RECURSIVE_SCOPE_PREFIX, TMUX_SCOPE_PREFIX,
+    normalize_scope_path, resolve_definition, sort_by_precedence,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Top of the file `aka edit --all` opens.
+const EDIT_ALL_HEADER: &str = "\
+# Every alias in the store; edit, add or delete definitions, then save to apply them together.
+# Scopes are global, exact:PATH, recursive:PATH or tmux:NAME.
+
+";
+
+/// Edit the definition of `alias` that applies in `current_dir`.
+///
+/// Script-backed aliases open their script file, so the change takes effect on the next run;
+/// other aliases open their command, which is stored back when it changed.
+///
+/// The store is released while the editor is open so other shells can keep changing it. If
+/// `alias` changed meanwhile, the edit is refused rather than overwriting that change; it is
+/// kept in the draft, whose path is in the error.
+pub fn handle_edit_command(
+    store: &mut Store,
+    alias: &str,
+    current_dir: &str,
+    scripts: &Path,
+    edit: impl Fn(&Path) -> std::result::Result<(), AkaError>,
+) -> std::result::Result<String, AkaError> {
+    let generation = store.generation()?;
+    let before = store
+        .list()?
+        .remove(alias)
+        .ok_or_else(|| AkaError::AliasNotFound(alias.to_string()))?;
+    let mut defs = before.clone();
+    sort_by_precedence(&mut defs);
+    let def = resolve_definition(&defs, current_dir)
+        .ok_or_else(|| {
+            AkaError::ConfigError(format!(
+                "No definition of '{}' applies in {}",
+                alias, current_dir
+            ))
+        })?
+        .clone();
+
+    store.release();
+    if let Some(path) = script_file(&def.command, scripts) {
+        edit(&path)?;
+        return Ok(format!("Edited script {}", path.display()));
+    }
+
+    let draft = create_draft(
+        &format!("aka-edit-{}", alias),
+        "",
+        &format!("{}\n", def.command),
+    )?;
+    let edited = edit(&draft).and_then(|_| Ok(std::fs::read_to_string(&draft)?));
+    let command = match edited {
+        Ok(edited) => edited.trim_end_matches('\n').to_string(),
+        Err(e) => {
+            let _ = std::fs::remove_file(&draft);
+            return Err(e);
+        }
+    };
+    if command.trim().is_empty() {
+        let _ = std::fs::remove_file(&draft);
+        return Err(AkaError::OperationCancelled);
+    }
+    if command == def.command {
+        let _ = std::fs::remove_file(&draft);
+        return Ok(format!("No changes to '{}'", alias));
+    }
+    if store.generation()? != generation && store.list()?.remove(alias).as_ref() != Some(&before) {
+        return Err(AkaError::ConfigError(format!(
+            "'{}' was changed in another shell while you were editing (your edit is kept in {})",
+            alias,
+            draft.display()
+        )));
+    }
+    let _ = std::fs::remove_file(&draft);
+    store.add_definition(
+        alias.to_string(),
+        AliasDefinition {
+            command: command.clone(),
+            ..def
+        },
+    )?;
+    Ok(format!(
+        "Updated alias '{}' to '{}'\n(Reload shell to apply)",
+        alias, command
+    ))
+}
+
+/// Write `content` to a new file in the temp dir named after `stem`, for the editor to open.
+///
+/// The file is created exclusively and only readable by the user, so a file or symlink someone
+/// left at a guessable name is never written through; a taken name moves on to the next one.
+fn create_draft(
+    stem: &str,
+    extension: &str,
+    content: &str,
+) -> std::result::Result<PathBuf, AkaError> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    for attempt in 0..100 {
+        let draft = std::env::temp_dir().join(format!(
+            "{}-{}-{}{}",
+            stem,
+            std::process::id(),
+            attempt,
+            extension
+        ));
+        match options.open(&draft) {
+            Ok(mut file) => {
+                file.write_all(content.as_bytes())?;
+                return Ok(draft);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(AkaError::ConfigError(format!(
+        "Could not create a draft for {} in {}",
+        stem,
+        std::env::temp_dir().display()
+    )))
+}
+
+/// `scope` written the way `--scope` takes it.
+fn scope_spec(scope: &AliasScope) -> String {
+    match scope {
+        AliasScope::Global => "global".to_string(),
+        AliasScope::Exact(path) => format!("{}{}", EXACT_SCOPE_PREFIX, path),
+        AliasScope::Recursive(path) => format!("{}{}", RECURSIVE_SCOPE_PREFIX, path),
+        AliasScope::Tmux(session) => format!("{}{}", TMUX_SCOPE_PREFIX, session),
+    }
+}
+
+/// Read a scope written by [`scope_spec`].
+///
+/// Paths must be absolute; they are normalized when they exist, and kept as written otherwise
+/// so scopes of directories that are gone survive the round trip.
+fn parse_scope(spec: &str) -> std::result::Result<AliasScope, AkaError> {
+    if spec.eq_ignore_ascii_case("global") {
+        return Ok(AliasScope::Global);
+    }
+    if let Some(session) = spec.strip_prefix(TMUX_SCOPE_PREFIX) {
+        return Ok(AliasScope::Tmux(session.to_string()));
+    }
+    let (path, exact) = match (
+        spec.strip_prefix(EXACT_SCOPE_PREFIX),
+        spec.strip_prefix(RECURSIVE_SCOPE_PREFIX),
+    ) {
+        (Some(path), _) => (path, true),
+        (_, Some(path)) => (path, false),
+        _ => {
+            return Err(AkaError::ConfigError(format!(
+                "Invalid scope '{}': use global, exact:PATH, recursive:PATH or tmux:NAME",
+                spec
+            )));
+        }
+    };
+    if !Path::new(path).is_absolute() {
+        return Err(AkaError::InvalidScopePath(format!(
+            "{} is not an absolute path",
+            path
+        )));
+    }
+    let path = normalize_scope_path(Path::new(path)).unwrap_or_else(|_| path.to_string());
+    Ok(match exact {
+        true => AliasScope::Exact(path),
+        false => AliasScope::Recursive(path),
+    })
+}
+
+/// The file `aka edit --all` opens: each alias as an array of tables, one per definition.
+fn render_aliases(
+    aliases: &BTreeMap<String, Vec<AliasDefinition>>,
+) -> std::result::Result<String, AkaError> {
+    let mut document = BTreeMap::new();
+    for (alias, defs) in aliases {
+        let tables = defs
+            .iter()
+            .map(|def| {
+                let mut table =
+                    toml::Table::try_from(def).map_err(|e| AkaError::ConfigError(e.to_string()))?;
+                table.insert("scope".to_string(), scope_spec(&def.scope).into());
+                Ok(table)
+            })
+            .collect::<std::result::Result<Vec<_>, AkaError>>()?;
+        document.insert(alias, tables);
+    }
+    let body = toml::to_string(&document).map_err(|e| AkaError::ConfigError(e.to_string()))?;
+    Ok(format!("{}{}", EDIT_ALL_HEADER, body))
+}
+
+/// Read the aliases back from an edited [`render_aliases`] file. A definition without a
+/// scope is global.
+fn parse_aliases(
+    text: &str,
+) -> std::result::Result<BTreeMap<String, Vec<AliasDefinition>>, AkaError> {
+    let document: BTreeMap<String, Vec<toml::Table>> =
+        toml::from_str(text).map_err(|e| AkaError::ConfigError(e.to_string()))?;
+    let mut aliases = BTreeMap::new();
+    for (alias, tables) in document {
+        let mut defs: Vec<AliasDefinition> = Vec::new();
+        for mut table in tables {
+            let scope = match table.remove("scope") {
+                Some(toml::Value::String(spec)) => parse_scope(&spec)?,
+                Some(other) => {
+                    return Err(AkaError::ConfigError(format!(
+                        "'{}': scope must be a string, not {}",
+                        alias, other
+                    )));
+                }
+                None => AliasScope::Global,
+            };
+            table.insert(
+                "scope".to_string(),
+                toml::Value::try_from(&scope).map_err(|e| AkaError::ConfigError(e.to_string()))?,
+            );
+            let def: AliasDefinition = toml::Value::Table(table)
+                .try_into()
+                .map_err(|e| AkaError::ConfigError(format!("'{}': {}", alias, e)))?;
+            if defs.iter().any(|d| d.scope == def.scope) {
+                return Err(AkaError::ConfigError(format!(
+                    "'{}' has more than one definition for {}",
+                    alias,
+                    scope_spec(&def.scope)
+                )));
+            }
+            defs.push(def);
+        }
+        if !defs.is_empty() {
+            aliases.insert(alias, defs);
+        }
+    }
+    Ok(aliases)
+}
+
+/// Names whose definitions differ between `from` and `to`, including added and removed ones.
+fn changed_aliases<'a>(
+    from: &'a BTreeMap<String, Vec<AliasDefinition>>,
+    to: &'a BTreeMap<String, Vec<AliasDefinition>>,
+) -> BTreeSet<&'a String> {
+    from.keys()
+        .chain(to.keys())
+        .filter(|name| from.get(*name) != to.get(*name))
+        .collect()
+}
+
+/// Ask whether to apply the edits on top of aliases another shell changed meanwhile.
+fn confirm_merge(
+    changed: &BTreeSet<&String>,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> std::result::Result<bool, AkaError> {
+    let names: Vec<&str> = changed.iter().map(|name| name.as_str()).collect();
+    write!(
+        out,
+        "Another shell changed {} while you were editing. Apply your edits on top? (y/N): ",
+        names.join(", ")
+    )?;
+    out.flush()?;
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(matches!(line.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Open every alias in the store in the editor as one TOML file and apply the result: new
+/// aliases are added, changed ones updated and deleted ones removed, all in one transaction.
+///
+/// Nothing is changed when the file does not parse; the edits are kept in the draft, whose
+/// path is in the error. The store is released while the editor is open; if another shell
+/// changed it meanwhile, edits to the aliases it touched are refused, and otherwise only the
+/// aliases edited here are applied on top, after asking through `input` and `out`.
+pub fn handle_edit_all_command(
+    store: &mut Store,
+    edit: impl Fn(&Path) -> std::result::Result<(), AkaError>,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> std::result::Result<String, AkaError> {
+    let generation = store.generation()?;
+    let stored: BTreeMap<String, Vec<AliasDefinition>> = store.list()?.into_iter().collect();
+    let original = render_aliases(&stored)?;
+    store.release();
+
+    let draft = create_draft("aka-edit-all", ".toml", &original)?;
+    if let Err(e) = edit(&draft) {
+        let _ = std::fs::remove_file(&draft);
+        return Err(e);
+    }
+    let edited = std::fs::read_to_string(&draft)?;
+    if edited == original {
+        let _ = std::fs::remove_file(&draft);
+        return Ok("No changes".to_string());
+    }
+
+    let mut aliases = parse_aliases(&edited).map_err(|e| {
+        AkaError::ConfigError(format!(
+            "{} (your edits are kept in {})",
+            e,
+            draft.display()
+        ))
+    })?;
+    if let Some(name) = aliases
+        .keys()
+        .find(|name| !stored.contains_key(*name) && !is_alias_name(name))
+    {
+        return Err(AkaError::ConfigError(format!(
+            "'{}' is not a valid alias name (your edits are kept in {})",
+            name,
+            draft.display()
+        )));
+    }
+
+    if store.generation()? != generation {
+        let current: BTreeMap<String, Vec<AliasDefinition>> = store.list()?.into_iter().collect();
+        let theirs = changed_aliases(&stored, &current);
+        let conflicts: Vec<&str> = changed_aliases(&stored, &aliases)
+            .into_iter()
+            .filter(|name| theirs.contains(name) && aliases.get(*name) != current.get(*name))
+            .map(|name| name.as_str())
+            .collect();
+        if !conflicts.is_empty() {
+            return Err(AkaError::ConfigError(format!(
+                "{} changed in another shell while you were editing (your edits are kept in {})",
+                conflicts.join(", "),
+                draft.display()
+            )));
+        }
+        if !confirm_merge(&theirs, input, out)? {
+            return Err(AkaError::OperationCancelled);
+        }
+        let mut merged = current;
+        for name in changed_aliases(&stored, &aliases) {
+            match aliases.get(name) {
+                Some(defs) => merged.insert(name.clone(), defs.clone()),
+                None => merged.remove(name),
+            };
+        }
+        aliases = merged;
+    }
+    let _ = std::fs::remove_file(&draft);
+
+    let report = store.replace_aliases(&aliases)?;
+    let mut lines = Vec::new();
+    for (label, names) in [
+        ("Added", &report.added),
+        ("Updated", &report.updated),
+        ("Removed", &report.removed),
+    ] {
+        if !names.is_empty() {
+            lines.push(format!("{}: {}", label, names.join(", ")));
+        }
+    }
+    if lines.is_empty() {
+        return Ok("No changes".to_string());
+    }
+    lines.push("(Reload shell to apply)".to_string());
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::script::{shell_quote, write_script};
+    use crate::store::AliasScope;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_edit_command_and_script() {
+        let dir = tempdir().unwrap();
+        let scripts = dir.path().join("scripts");
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add(
+                "g".to_string(),
+                "git statsu".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+
+        let fix_typo = |path: &Path| {
+            let content = std::fs::read_to_string(path).unwrap();
+            std::fs::write(path, content.replace("statsu", "status")).unwrap();
+            Ok(())
+        };
+        let msg = handle_edit_command(&mut store, "g", "/", &scripts, fix_typo).unwrap();
+        assert!(msg.starts_with("Updated alias 'g' to 'git status'"));
+        assert_eq!(store.list().unwrap()["g"][0].command, "git status");
+
+        let msg = handle_edit_command(&mut store, "g", "/", &scripts, fix_typo).unwrap();
+        assert_eq!(msg, "No changes to 'g'");
+
+        let path = write_script(&scripts, "s", &AliasScope::Global, "git statsu").unwrap();
+        store
+            .add(
+                "s".to_string(),
+                shell_quote(&path.to_string_lossy()),
+                AliasScope::Global,
+            )
+            .unwrap();
+        let msg = handle_edit_command(&mut store, "s", "/", &scripts, fix_typo).unwrap();
+        assert!(msg.starts_with("Edited script"));
+        assert!(
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .contains("git status")
+        );
+
+        assert!(matches!(
+            handle_edit_command(&mut store, "missing", "/", &scripts, fix_typo),
+            Err(AkaError::AliasNotFound(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_does_not_write_through_a_planted_draft() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add("planted".to_string(), "git".to_string(), AliasScope::Global)
+            .unwrap();
+        let target = dir.path().join("target");
+        std::fs::write(&target, "untouched").unwrap();
+        let planted =
+            std::env::temp_dir().join(format!("aka-edit-planted-{}-0", std::process::id()));
+        let _ = std::fs::remove_file(&planted);
+        std::os::unix::fs::symlink(&target, &planted).unwrap();
+
+        let edit = |path: &Path| {
+            assert_ne!(path, planted);
+            std::fs::write(path, "tig\n").unwrap();
+            Ok(())
+        };
+        let msg = handle_edit_command(&mut store, "planted", "/", dir.path(), edit);
+        let _ = std::fs::remove_file(&planted);
+        assert!(msg.unwrap().starts_with("Updated alias 'planted' to 'tig'"));
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "untouched");
+    }
+
+    #[test]
+    fn test_edit_all_round_trip() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        let mut timed = AliasDefinition::new("cargo test".to_string(), AliasScope::Global);
+        timed.time = true;
+        store.add_definition("t".to_string(), timed).unwrap();
+        store
+            .add(
+                "t".to_string(),
+                "npm test".to_string(),
+                AliasScope::Recursive("/gone/web".to_string()),
+            )
+            .unwrap();
+        store
+            .add("g".to_string(), "git".to_string(), AliasScope::Global)
+            .unwrap();
+        store
+            .add("old".to_string(), "true".to_string(), AliasScope::Global)
+            .unwrap();
+
+        let unchanged = |_: &Path| Ok(());
+        assert_eq!(
+            handle_edit_all_command(&mut store, unchanged, &mut &b""[..], &mut Vec::new()).unwrap(),
+            "No changes"
+        );
+
+        let reorganize = |path: &Path| {
+            let content = std::fs::read_to_string(path).unwrap();
+            assert!(
+                content
+                    .contains("[[t]]\ncommand = \"cargo test\"\nscope = \"global\"\ntime = true\n")
+            );
+            assert!(content.contains("scope = \"recursive:/gone/web\"\n"));
+            let content = content
+                .replace("command = \"git\"", "command = \"git status\"")
+                .replace("[[old]]\ncommand = \"true\"\nscope = \"global\"\n", "")
+                .replace("[[g]]", "[[new]]\ncommand = \"ls\"\n\n[[g]]");
+            std::fs::write(path, content).unwrap();
+            Ok(())
+        };
+        let msg = handle_edit_all_command(&mut store, reorganize, &mut &b""[..], &mut Vec::new())
+            .unwrap();
+        assert_eq!(
+            msg,
+            "Added: new\nUpdated: g\nRemoved: old\n(Reload shell to apply)"
+        );
+        let aliases = store.list().unwrap();
+        assert_eq!(aliases["g"][0].command, "git status");
+        assert_eq!(aliases["new"][0].scope, AliasScope::Global);
+        assert!(!aliases.contains_key("old"));
+        assert_eq!(aliases["t"].len(), 2);
+        assert!(aliases["t"][0].time);
+
+        let generation = store.generation().unwrap();
+        let duplicate = |path: &Path| {
+            let content = std::fs::read_to_string(path).unwrap();
+            std::fs::write(path, format!("{}\n[[g]]\ncommand = \"tig\"\n", content)).unwrap();
+            Ok(())
+        };
+        assert!(matches!(
+            handle_edit_all_command(&mut store, duplicate, &mut &b""[..], &mut Vec::new()),
+            Err(AkaError::ConfigError(msg)) if msg.contains("more than one definition for global")
+        ));
+        assert_eq!(store.generation().unwrap(), generation);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_all_draft_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        let check = |path: &Path| {
+            assert_eq!(path.extension().unwrap(), "toml");
+            let mode = std::fs::symlink_metadata(path)
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+            Ok(())
+        };
+        assert_eq!(
+            handle_edit_all_command(&mut store, check, &mut &b""[..], &mut Vec::new()).unwrap(),
+            "No changes"
+        );
+    }
+
+    #[test]
+    fn test_edit_refuses_changes_made_meanwhile() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aka.redb");
+        let scripts = dir.path().join("scripts");
+        let mut store = Store::load(&path).unwrap();
+        store
+            .add("g".to_string(), "git".to_string(), AliasScope::Global)
+            .unwrap();
+        store
+            .add("l".to_string(), "ls".to_string(), AliasScope::Global)
+            .unwrap();
+
+        // Another shell changes the store while the editor is open
+        let edit_meanwhile = |alias: &'static str, command: &'static str| {
+            let path = path.clone();
+            move |draft: &Path| {
+                let mut other = Store::load(&path).unwrap();
+                other
+                    .add(alias.to_string(), command.to_string(), AliasScope::Global)
+                    .unwrap();
+                let content = std::fs::read_to_string(draft).unwrap();
+                std::fs::write(draft, content.replace("git", "git status")).unwrap();
+                Ok(())
+            }
+        };
+
+        let result =
+            handle_edit_command(&mut store, "g", "/", &scripts, edit_meanwhile("g", "tig"));
+        assert!(matches!(
+            result,
+            Err(AkaError::ConfigError(msg)) if msg.contains("kept in")
+        ));
+        assert_eq!(store.list().unwrap()["g"][0].command, "tig");
+
+        store
+            .add("g".to_string(), "git".to_string(), AliasScope::Global)
+            .unwrap();
+        let msg = handle_edit_command(
+            &mut store,
+            "g",
+            "/",
+            &scripts,
+            edit_meanwhile("l", "ls -la"),
+        )
+        .unwrap();
+        assert!(msg.starts_with("Updated alias 'g'"));
+
+        let generation = store.generation().unwrap();
+        let result = handle_edit_all_command(
+            &mut store,
+            edit_meanwhile("g", "tig"),
+            &mut &b"y\n"[..],
+            &mut Vec::new(),
+        );
+        assert!(matches!(
+            result,
+            Err(AkaError::ConfigError(msg)) if msg.starts_with("g changed in another shell")
+        ));
+        assert_eq!(store.generation().unwrap(), generation + 1);
+
+        store
+            .add("g".to_string(), "git".to_string(), AliasScope::Global)
+            .unwrap();
+
+        let mut out = Vec::new();
+        let result = handle_edit_all_command(
+            &mut store,
+            edit_meanwhile("new", "true"),
+            &mut &b"n\n"[..],
+            &mut out,
+        );
+        assert!(matches!(result, Err(AkaError::OperationCancelled)));
+        assert!(
+            String::from_utf8(out)
+                .unwrap()
+                .starts_with("Another shell changed new")
+        );
+
+        let msg = handle_edit_all_command(
+            &mut store,
+            edit_meanwhile("new", "false"),
+            &mut &b"y\n"[..],
+            &mut Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(msg, "Updated: g\n(Reload shell to apply)");
+        let aliases = store.list().unwrap();
+        assert_eq!(aliases["g"][0].command, "git status");
+        assert_eq!(aliases["new"][0].command, "false");
+        assert_eq!(aliases["l"][0].command, "ls -la");
+    }
+}
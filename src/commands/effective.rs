@@ -0,0 +1,147 @@
+use crate::Store;
+use crate::codegen::{PowerShellCodegen, Shell, ShellCodegen, ZshBashCodegen, elvish, fish, nu};
+use crate::commands::script::shell_quote;
+use crate::commands::var;
+use crate::error::AkaError;
+use crate::references;
+use crate::store::resolve_definition;
+
+/// One line defining `alias` as `command` in `shell`.
+///
+/// Commands that simply take their arguments at the end become plain aliases. Ones using
+/// placeholders become functions in zsh and bash, and are left as a comment for fish. nushell
+/// and elvish aliases hand the command and its arguments to bash, and PowerShell gets
+/// functions.
+fn definition_line(shell: Shell, alias: &str, command: &str) -> String {
+    let body = ZshBashCodegen.command(command);
+    let plain = body == format!("{} \"$@\"", command);
+    match shell {
+        Shell::Zsh | Shell::Bash if plain => {
+            format!("alias {}={}", alias, shell_quote(command))
+        }
+        Shell::Zsh | Shell::Bash => format!("{}() {{ {}; }}", alias, body),
+        Shell::Fish if plain => format!("alias {} {}", alias, fish::quote(command)),
+        Shell::Fish => format!(
+            "# {}: uses arguments in place, not expressible as a fish alias",
+            alias
+        ),
+        Shell::Nu => format!("alias {} = ^bash -c {} {}", alias, nu::quote(&body), alias),
+        Shell::Elvish => format!(
+            "fn {} {{|@args| e:bash -c {} {} $@args }}",
+            alias,
+            elvish::quote(&body),
+            alias
+        ),
+        Shell::PowerShell => format!(
+            "function {} {{ {} }}",
+            alias,
+            PowerShellCodegen.command(command)
+        ),
+    }
+}
+
+/// Print every alias active in `current_dir` as plain definitions for `shell`, for shells
+/// without aka such as containers or remote hosts.
+///
+/// Each alias is resolved as its function would pick it, with references to other aliases
+/// and variables expanded. Attributes such as kube guards or captured environment are not
+/// carried over.
+pub fn handle_effective_command(
+    store: &Store,
+    shell: Shell,
+    current_dir: &str,
+) -> std::result::Result<String, AkaError> {
+    let mut aliases = store.list()?;
+    crate::system::merge_system_aliases(&mut aliases);
+    let vars = store.vars()?;
+
+    let mut names: Vec<&String> = aliases.keys().collect();
+    names.sort();
+
+    let mut lines = Vec::new();
+    for alias in names {
+        let Some(def) = resolve_definition(&aliases[alias], current_dir) else {
+            continue;
+        };
+        let (mut command, warnings) = references::expand_alias_refs(alias, def, &aliases);
+        for warning in warnings {
+            eprintln!("aka: {}", warning);
+        }
+        if var::uses_vars(&command) {
+            let (expanded, missing) = var::expand_vars(&command, &vars);
+            for name in missing {
+                eprintln!("aka: '{}' uses undefined variable '{}'", alias, name);
+            }
+            command = expanded;
+        }
+        lines.push(definition_line(shell, alias, &command));
+    }
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_effective_aliases() -> std::result::Result<(), AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        store.add(
+            "t".to_string(),
+            "cargo test".to_string(),
+            AliasScope::Global,
+        )?;
+        store.add(
+            "t".to_string(),
+            "npm test".to_string(),
+            AliasScope::Recursive("/web".to_string()),
+        )?;
+        store.add(
+            "say".to_string(),
+            "echo 'it''s' @1".to_string(),
+            AliasScope::Global,
+        )?;
+        store.add(
+            "only".to_string(),
+            "ls".to_string(),
+            AliasScope::Exact("/other".to_string()),
+        )?;
+
+        assert_eq!(
+            handle_effective_command(&store, Shell::Zsh, "/web/app")?,
+            "say() { echo 'it''s' \"${1}\"; }\nalias t='npm test'"
+        );
+        assert_eq!(
+            handle_effective_command(&store, Shell::Nu, "/home")?,
+            concat!(
+                "alias say = ^bash -c r#'echo 'it''s' \"${1}\"'# say\n",
+                "alias t = ^bash -c 'cargo test \"$@\"' t"
+            )
+        );
+        assert_eq!(
+            handle_effective_command(&store, Shell::PowerShell, "/home")?,
+            concat!(
+                "function say { echo 'it''s' $args[0] }\n",
+                "function t { cargo test @args }"
+            )
+        );
+        assert_eq!(
+            handle_effective_command(&store, Shell::Elvish, "/home")?,
+            concat!(
+                "fn say {|@args| e:bash -c 'echo ''it''''s'' \"${1}\"' say $@args }\n",
+                "fn t {|@args| e:bash -c 'cargo test \"$@\"' t $@args }"
+            )
+        );
+        assert_eq!(
+            handle_effective_command(&store, Shell::Fish, "/home")?,
+            concat!(
+                "# say: uses arguments in place, not expressible as a fish alias\n",
+                "alias t 'cargo test'"
+            )
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use crate::Store;
+use crate::codegen::Shell;
+use crate::error::AkaError;
+use crate::store::{BackendKind, data_dir, store_path_or};
+use std::path::Path;
+
+/// `path`, noting when it does not exist.
+fn describe_path(path: &Path) -> String {
+    if path.exists() {
+        path.display().to_string()
+    } else {
+        format!("{} (missing)", path.display())
+    }
+}
+
+/// Name of the user's login shell, from `SHELL`.
+fn detect_shell() -> Option<String> {
+    let shell = std::env::var("SHELL").ok()?;
+    Path::new(&shell)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+/// Everything `aka env` reports, in display order.
+fn collect_entries() -> std::result::Result<Vec<(&'static str, String)>, AkaError> {
+    let mut entries = vec![("version", env!("CARGO_PKG_VERSION").to_string())];
+
+    let dir = data_dir()?;
+    let path = store_path_or(&dir, BackendKind::preferred()?);
+    entries.push(("data_dir", describe_path(&dir)));
+    entries.push(("store", describe_path(&path)));
+    entries.push(("backend", BackendKind::from_path(&path).name().to_string()));
+    let generation = if path.exists() {
+        Store::load(&path)?.generation()?.to_string()
+    } else {
+        "none".to_string()
+    };
+    entries.push(("generation", generation));
+
+    let config = match crate::config::config_path() {
+        Some(path) => describe_path(&path),
+        None => "none".to_string(),
+    };
+    entries.push(("config", config));
+    entries.push((
+        "system_file",
+        describe_path(&crate::system::system_file_path()),
+    ));
+    let usage_log = match std::env::var_os("AKA_USAGE_LOG") {
+        Some(path) if !path.is_empty() => describe_path(Path::new(&path)),
+        _ => "off".to_string(),
+    };
+    entries.push(("usage_log", usage_log));
+    let log = crate::config::load()
+        .map(|config| config.log)
+        .unwrap_or_default();
+    let log_file = match crate::logging::log_level(None, &log) {
+        Ok(level) if level != tracing::level_filters::LevelFilter::OFF => {
+            format!(
+                "{} ({})",
+                describe_path(&crate::logging::log_path(&log)?),
+                level
+            )
+        }
+        _ => "off".to_string(),
+    };
+    entries.push(("log", log_file));
+
+    let shell = detect_shell();
+    entries.push((
+        "shell",
+        shell.clone().unwrap_or_else(|| "unknown".to_string()),
+    ));
+    // The rc file `aka install` writes to, and whether it loads the hook
+    let profile = match (
+        shell.as_deref().and_then(Shell::from_name),
+        dirs::home_dir(),
+    ) {
+        (Some(shell), Some(home)) => crate::commands::install::rc_file(shell, &home),
+        _ => None,
+    };
+    let installed = profile.as_ref().is_some_and(|path| {
+        std::fs::read_to_string(path).is_ok_and(|content| content.contains("aka init"))
+    });
+    entries.push((
+        "profile",
+        profile.map_or_else(|| "unknown".to_string(), |path| describe_path(&path)),
+    ));
+    entries.push((
+        "hook",
+        match (std::env::var_os("AKA_MANAGED_ALIASES").is_some(), installed) {
+            (true, _) => "loaded",
+            (false, true) => "installed, not loaded in this shell",
+            (false, false) => "not installed",
+        }
+        .to_string(),
+    ));
+    Ok(entries)
+}
+
+fn render(entries: &[(&str, String)], json: bool) -> String {
+    if json {
+        let object: serde_json::Map<String, serde_json::Value> = entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone().into()))
+            .collect();
+        return serde_json::Value::Object(object).to_string();
+    }
+    let width = entries.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    entries
+        .iter()
+        .map(|(key, value)| format!("{:width$}  {}", key, value, width = width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Report the paths, store state, shell and hook status aka is running with.
+pub fn handle_env_command(json: bool) -> std::result::Result<String, AkaError> {
+    Ok(render(&collect_entries()?, json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let entries = vec![
+            ("version", "0.2.0".to_string()),
+            ("data_dir", "/data/aka".to_string()),
+        ];
+        assert_eq!(
+            render(&entries, false),
+            "version   0.2.0\ndata_dir  /data/aka"
+        );
+        let json: serde_json::Value = serde_json::from_str(&render(&entries, true)).unwrap();
+        assert_eq!(json["data_dir"], "/data/aka");
+    }
+}
@@ -0,0 +1,133 @@
+use crate::Store;
+use crate::error::AkaError;
+use crate::store::{AliasScope, sort_by_precedence};
+use std::path::Path;
+
+/// Walk the if/elif chain the generated function for `alias` evaluates in `current_dir`.
+///
+/// Every condition is listed in evaluation order with its outcome, followed by the command
+/// that ends up running.
+pub fn handle_explain_command(
+    store: &Store,
+    alias: &str,
+    current_dir: &str,
+) -> std::result::Result<String, AkaError> {
+    let mut defs = crate::commands::resolve::prepared_definitions(store, alias)?;
+    sort_by_precedence(&mut defs);
+
+    let mut rows: Vec<(String, String)> = Vec::new();
+    let mut chosen: Option<String> = None;
+    let mut has_global = false;
+
+    for def in &defs {
+        let keyword = if rows.is_empty() { "if" } else { "elif" };
+        let condition = match &def.scope {
+            AliasScope::Exact(path) => format!("{} directory is {}", keyword, path),
+            AliasScope::Recursive(path) => format!("{} directory is under {}", keyword, path),
+            AliasScope::Tmux(session) => format!("{} tmux session is {}", keyword, session),
+            AliasScope::Global if rows.is_empty() => "always".to_string(),
+            AliasScope::Global => "else".to_string(),
+        };
+        has_global |= def.scope == AliasScope::Global;
+
+        let outcome = if chosen.is_some() {
+            format!("skipped: {}", def.command)
+        } else if def.scope.matches(Path::new(current_dir)) {
+            chosen = Some(def.command.clone());
+            format!("match: {}", def.command)
+        } else {
+            "no match".to_string()
+        };
+        rows.push((condition, outcome));
+    }
+
+    if !has_global {
+        let passthrough = format!("command {}", alias);
+        let outcome = if chosen.is_some() {
+            format!("skipped: {}", passthrough)
+        } else {
+            chosen = Some(passthrough.clone());
+            format!("match: {} (no definition applies)", passthrough)
+        };
+        rows.push(("else".to_string(), outcome));
+    }
+
+    let width = rows.iter().map(|(c, _)| c.len()).max().unwrap_or(0);
+    let mut output = format!("{} in {}\n", alias, current_dir);
+    for (condition, outcome) in &rows {
+        output.push_str(&format!(
+            "  {:width$}  -> {}\n",
+            condition,
+            outcome,
+            width = width
+        ));
+    }
+    output.push_str(&format!("=> {}", chosen.unwrap_or_default()));
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn store_with_build(dir: &std::path::Path) -> Store {
+        let mut store = Store::load(&dir.join("aka.redb")).unwrap();
+        store
+            .add("build".to_string(), "make".to_string(), AliasScope::Global)
+            .unwrap();
+        store
+            .add(
+                "build".to_string(),
+                "cargo build".to_string(),
+                AliasScope::Recursive("/work/rust".to_string()),
+            )
+            .unwrap();
+        store
+            .add(
+                "build".to_string(),
+                "cargo build --release".to_string(),
+                AliasScope::Exact("/work/rust/app".to_string()),
+            )
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn test_explain_walks_the_chain() {
+        let dir = tempdir().unwrap();
+        let store = store_with_build(dir.path());
+
+        let output = handle_explain_command(&store, "build", "/work/rust/lib").unwrap();
+        assert_eq!(
+            output,
+            "build in /work/rust/lib\n\
+             \x20 if directory is /work/rust/app      -> no match\n\
+             \x20 elif directory is under /work/rust  -> match: cargo build\n\
+             \x20 else                                -> skipped: make\n\
+             => cargo build"
+        );
+    }
+
+    #[test]
+    fn test_explain_falls_through_to_the_real_command() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add(
+                "ls".to_string(),
+                "ls -la".to_string(),
+                AliasScope::Exact("/tmp".to_string()),
+            )
+            .unwrap();
+
+        let output = handle_explain_command(&store, "ls", "/home").unwrap();
+        assert!(output.contains("-> match: command ls (no definition applies)"));
+        assert!(output.ends_with("=> command ls"));
+
+        assert!(matches!(
+            handle_explain_command(&store, "missing", "/"),
+            Err(AkaError::AliasNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,655 @@
+use crate::Store;
+use crate::commands::project::{format_sync_report, is_alias_name};
+use crate::error::AkaError;
+use crate::store::{AliasDefinition, AliasScope, find_workspace_root, normalize_scope_path};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Origin tag for aliases owned by `aka generate compose`.
+const COMPOSE_ORIGIN: &str = "compose";
+
+/// Origin tag for aliases owned by `aka generate ssh`.
+const SSH_ORIGIN: &str = "ssh";
+
+/// File names Docker Compose looks for, in its own lookup order.
+const COMPOSE_FILES: &[&str] = &[
+    "compose.yaml",
+    "compose.yml",
+    "docker-compose.yaml",
+    "docker-compose.yml",
+];
+
+#[derive(Debug, Default, Deserialize)]
+struct ComposeFile {
+    #[serde(default)]
+    services: BTreeMap<String, serde_yaml_ng::Value>,
+}
+
+/// Shortcuts for a compose project: `up`/`down` plus `logs-<service>` and `sh-<service>`.
+fn compose_aliases(services: impl IntoIterator<Item = String>) -> BTreeMap<String, String> {
+    let mut aliases = BTreeMap::from([
+        ("up".to_string(), "docker compose up -d".to_string()),
+        ("down".to_string(), "docker compose down".to_string()),
+    ]);
+    for service in services {
+        aliases.insert(
+            format!("logs-{}", service),
+            format!("docker compose logs -f {}", service),
+        );
+        aliases.insert(
+            format!("sh-{}", service),
+            format!("docker compose exec {} sh", service),
+        );
+    }
+    aliases
+}
+
+/// Create or refresh shortcuts for the nearest compose file above `start`.
+///
+/// The aliases are scoped recursively to the directory holding the compose file, and ones
+/// for services that were removed since the last run are dropped.
+pub fn handle_generate_compose_command(
+    store: &mut Store,
+    start: &Path,
+) -> std::result::Result<String, AkaError> {
+    let markers: Vec<String> = COMPOSE_FILES.iter().map(|f| f.to_string()).collect();
+    let Some(dir) = find_workspace_root(start, &markers) else {
+        return Err(AkaError::ConfigError(format!(
+            "No compose file found above {} (looked for {})",
+            start.display(),
+            COMPOSE_FILES.join(", ")
+        )));
+    };
+    let file = COMPOSE_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join(COMPOSE_FILES[0]));
+
+    let content = std::fs::read_to_string(&file)?;
+    let compose: ComposeFile = serde_yaml_ng::from_str(&content)
+        .map_err(|e| AkaError::ConfigError(format!("Invalid {}: {}", file.display(), e)))?;
+
+    let (aliases, invalid): (BTreeMap<_, _>, BTreeMap<_, _>) =
+        compose_aliases(compose.services.into_keys())
+            .into_iter()
+            .partition(|(name, _)| is_alias_name(name));
+    let invalid: Vec<String> = invalid.into_keys().collect();
+
+    let scope = AliasScope::Recursive(normalize_scope_path(&dir)?);
+    let report = store.sync_generated(COMPOSE_ORIGIN, &scope, &aliases)?;
+    Ok(format!(
+        "Generated from {}\n{}",
+        file.display(),
+        format_sync_report(&report, &invalid, &dir)
+    ))
+}
+
+/// Concrete host names declared by `Host` lines; wildcard and negated patterns are skipped.
+fn ssh_hosts(content: &str) -> Vec<String> {
+    let mut hosts = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let (keyword, value) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or((line, ""));
+        if !keyword.eq_ignore_ascii_case("host") {
+            continue;
+        }
+        for host in value
+            .trim_start_matches([' ', '\t', '='])
+            .split_whitespace()
+        {
+            if !host.contains(['*', '?', '!']) && !hosts.iter().any(|h| h == host) {
+                hosts.push(host.to_string());
+            }
+        }
+    }
+    hosts
+}
+
+/// Default location of the user's SSH client config.
+pub fn ssh_config_path() -> std::result::Result<std::path::PathBuf, AkaError> {
+    dirs::home_dir()
+        .map(|home| home.join(".ssh").join("config"))
+        .ok_or_else(|| AkaError::ConfigError("Could not determine home directory".to_string()))
+}
+
+/// Create or refresh global `<prefix><host>` aliases for the hosts in an SSH config.
+///
+/// All of them are tagged as one group, so hosts removed from the config are dropped on the
+/// next run and `prune` removes the whole group.
+pub fn handle_generate_ssh_command(
+    store: &mut Store,
+    config: &Path,
+    prefix: &str,
+    prune: bool,
+) -> std::result::Result<String, AkaError> {
+    let hosts = if prune {
+        Vec::new()
+    } else {
+        match std::fs::read_to_string(config) {
+            Ok(content) => ssh_hosts(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(AkaError::ConfigError(format!(
+                    "No SSH config at {}",
+                    config.display()
+                )));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    let (aliases, invalid): (BTreeMap<_, _>, BTreeMap<_, _>) = hosts
+        .into_iter()
+        .map(|host| (format!("{}{}", prefix, host), format!("ssh {}", host)))
+        .partition(|(name, _)| is_alias_name(name));
+    let invalid: Vec<String> = invalid.into_keys().collect();
+
+    let report = store.sync_generated(SSH_ORIGIN, &AliasScope::Global, &aliases)?;
+    let source = if prune {
+        "Pruned SSH host aliases".to_string()
+    } else {
+        format!("Generated from {}", config.display())
+    };
+    Ok(format!(
+        "{}\n{}",
+        source,
+        format_sync_report(&report, &invalid, config)
+    ))
+}
+
+/// Prefix of the files `aka generate raycast` owns in its output directory.
+const RAYCAST_PREFIX: &str = "aka-";
+
+/// Raycast accepts at most this many arguments per script command.
+const RAYCAST_MAX_ARGS: usize = 3;
+
+/// Default output directory for Raycast script commands.
+pub fn raycast_dir() -> std::result::Result<std::path::PathBuf, AkaError> {
+    Ok(crate::store::data_dir()?.join("raycast"))
+}
+
+/// A Raycast script command running `def` as `alias`, with one text argument per `@N`.
+///
+/// Scoped definitions change into their directory first so the embedded function picks them.
+fn raycast_script(alias: &str, def: &AliasDefinition, args: usize) -> std::io::Result<String> {
+    let (title, dir) = match &def.scope {
+        AliasScope::Global | AliasScope::Tmux(_) => (alias.to_string(), None),
+        AliasScope::Exact(path) | AliasScope::Recursive(path) => {
+            let name = Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone());
+            (format!("{} ({})", alias, name), Some(path))
+        }
+    };
+
+    let mut script = String::from(
+        "#!/bin/bash
+
+",
+    );
+    script.push_str(
+        "# @raycast.schemaVersion 1
+",
+    );
+    script.push_str(&format!(
+        "# @raycast.title {}
+",
+        title
+    ));
+    script.push_str(
+        "# @raycast.mode fullOutput
+",
+    );
+    script.push_str(
+        "# @raycast.packageName aka
+",
+    );
+    script.push_str(&format!(
+        "# @raycast.description {}
+",
+        def.command.replace('\n', " ")
+    ));
+    for i in 1..=args {
+        script.push_str(&format!(
+            "# @raycast.argument{} {{ \"type\": \"text\", \"placeholder\": \"@{}\" }}\n",
+            i, i
+        ));
+    }
+    script.push('\n');
+    if let Some(dir) = dir {
+        script.push_str(&format!(
+            "cd {} || exit 1\n",
+            crate::commands::script::shell_quote(dir)
+        ));
+    }
+
+    let mut function = Vec::new();
+    crate::codegen::write_alias(
+        &crate::codegen::ZshBashCodegen,
+        &mut function,
+        alias,
+        vec![def.clone()],
+    )?;
+    script.push_str(&String::from_utf8_lossy(&function));
+    script.push_str(&format!("{} \"$@\"\n", alias));
+    Ok(script)
+}
+
+/// Write a Raycast script command for every alias definition into `dir`.
+///
+/// Files from earlier runs whose definitions are gone are removed; other files in `dir` are
+/// left alone. Alias references and variables are expanded as `aka init` would, so re-run
+/// after changing them.
+pub fn handle_generate_raycast_command(
+    store: &Store,
+    dir: &Path,
+) -> std::result::Result<String, AkaError> {
+    let mut aliases = store.list()?;
+    crate::system::merge_system_aliases(&mut aliases);
+    let vars = if aliases
+        .values()
+        .flatten()
+        .any(|def| crate::commands::var::uses_vars(&def.command))
+    {
+        store.vars()?
+    } else {
+        BTreeMap::new()
+    };
+
+    std::fs::create_dir_all(dir)?;
+    let mut written = std::collections::HashSet::new();
+    let mut skipped = Vec::new();
+    for (alias, defs) in aliases.iter().collect::<BTreeMap<_, _>>() {
+        // Launchers run outside tmux, where session-scoped definitions never apply
+        for def in defs
+            .iter()
+            .filter(|d| !matches!(d.scope, AliasScope::Tmux(_)))
+        {
+            let args = crate::commands::list::arg_count(&def.command);
+            if args > RAYCAST_MAX_ARGS {
+                skipped.push(alias.clone());
+                continue;
+            }
+            let mut def = def.clone();
+            def.command = crate::references::expand_alias_refs(alias, &def, &aliases).0;
+            def.command = crate::commands::var::expand_vars(&def.command, &vars).0;
+
+            let name = format!(
+                "{}{}.sh",
+                RAYCAST_PREFIX,
+                crate::commands::script::script_name(alias, &def.scope)
+            );
+            let path = dir.join(&name);
+            std::fs::write(&path, raycast_script(alias, &def, args)?)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+            }
+            written.insert(name);
+        }
+    }
+
+    let mut removed = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if name.starts_with(RAYCAST_PREFIX) && name.ends_with(".sh") && !written.contains(&name) {
+            std::fs::remove_file(dir.join(&name))?;
+            removed += 1;
+        }
+    }
+
+    let mut lines = vec![format!(
+        "Wrote {} script command(s) to {}",
+        written.len(),
+        dir.display()
+    )];
+    if removed > 0 {
+        lines.push(format!("Removed {} stale script command(s)", removed));
+    }
+    skipped.dedup();
+    if !skipped.is_empty() {
+        lines.push(format!(
+            "Skipped (more than {} arguments): {}",
+            RAYCAST_MAX_ARGS,
+            skipped.join(", ")
+        ));
+    }
+    lines.push("Add the directory in Raycast under Extensions > Script Commands".to_string());
+    Ok(lines.join("\n"))
+}
+
+/// Where `aka generate dockerfile-snippet` puts the exported aliases inside the image.
+const IMAGE_ALIASES_PATH: &str = "/etc/aka/aliases.sh";
+
+/// Dockerfile lines that copy `file`, relative to the build context, into the image and
+/// source it from the system-wide bash and zsh startup files that exist there.
+fn dockerfile_snippet(file: &str) -> String {
+    format!(
+        concat!(
+            "# aka aliases, exported to {file} by `aka generate dockerfile-snippet`\n",
+            "COPY {file} {dest}\n",
+            "RUN for rc in /etc/bash.bashrc /etc/zsh/zshrc; do \\\n",
+            "        if [ -f \"$rc\" ]; then echo '. {dest}' >> \"$rc\"; fi; \\\n",
+            "    done"
+        ),
+        file = file,
+        dest = IMAGE_ALIASES_PATH
+    )
+}
+
+/// Export the aliases to `file` as the shell functions `aka init --dump` writes, which run
+/// without aka installed, and print Dockerfile lines that bake them into an image.
+pub fn handle_generate_dockerfile_command(
+    store: &Store,
+    file: &Path,
+) -> std::result::Result<String, AkaError> {
+    let name = file
+        .to_str()
+        .ok_or_else(|| AkaError::ConfigError(format!("{} is not valid UTF-8", file.display())))?;
+    let mut out = std::io::BufWriter::new(std::fs::File::create(file)?);
+    crate::commands::init::handle_init_command(
+        Some(store),
+        crate::codegen::Shell::Bash,
+        true,
+        false,
+        None,
+        false,
+        &mut out,
+    )?;
+    Ok(dockerfile_snippet(name))
+}
+
+/// `install.sh` for the devcontainer feature written by `aka generate devcontainer`.
+///
+/// It prefers the binary bundled next to it and builds from the repository when that does
+/// not run in the container, then puts the exported store where aka looks for it and adds
+/// the shell hook for the container's user.
+const DEVCONTAINER_INSTALL: &str = r#"#!/bin/sh
+# Generated by `aka generate devcontainer`
+set -e
+cd "$(dirname "$0")"
+
+if ./aka --version >/dev/null 2>&1; then
+    install -m 755 aka /usr/local/bin/aka
+elif command -v cargo >/dev/null 2>&1; then
+    cargo install --locked --root /usr/local --git REPOSITORY
+else
+    echo "aka: the bundled binary does not run here and cargo is not available" >&2
+    exit 1
+fi
+
+user="${_REMOTE_USER:-root}"
+home="${_REMOTE_USER_HOME:-$(getent passwd "$user" | cut -d: -f6)}"
+data="$home/.local/share/aka"
+mkdir -p "$data"
+cp aka.json "$data/aka.json"
+for rc in "$home/.bashrc" "$home/.zshrc"; do
+    if ! grep -qs 'aka init' "$rc"; then
+        echo 'eval "$(aka init)"' >> "$rc"
+    fi
+done
+chown -R "$user" "$home/.local" "$home/.bashrc" "$home/.zshrc" 2>/dev/null || true
+"#;
+
+/// Write a devcontainer feature into `dir` that installs aka with the current aliases:
+/// `devcontainer-feature.json`, `install.sh`, the running `aka` binary and the store
+/// exported as `aka.json`.
+pub fn handle_generate_devcontainer_command(
+    store: &Store,
+    dir: &Path,
+) -> std::result::Result<String, AkaError> {
+    std::fs::create_dir_all(dir)?;
+    let manifest = serde_json::json!({
+        "id": "aka",
+        "version": env!("CARGO_PKG_VERSION"),
+        "name": "aka aliases",
+        "description": "Installs aka with the aliases exported by `aka generate devcontainer`",
+    });
+    std::fs::write(
+        dir.join("devcontainer-feature.json"),
+        serde_json::to_string_pretty(&manifest)
+            .map_err(|e| AkaError::ConfigError(e.to_string()))?
+            + "\n",
+    )?;
+
+    let install = dir.join("install.sh");
+    std::fs::write(
+        &install,
+        DEVCONTAINER_INSTALL.replace("REPOSITORY", env!("CARGO_PKG_REPOSITORY")),
+    )?;
+    std::fs::copy(std::env::current_exe()?, dir.join("aka"))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&install, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Start from an empty export so aliases removed since the last run are not kept
+    let export = dir.join(crate::store::BackendKind::Json.file_name());
+    if export.exists() {
+        std::fs::remove_file(&export)?;
+    }
+    let count = store.copy_into(&mut Store::load(&export)?)?;
+
+    Ok(format!(
+        concat!(
+            "Wrote a devcontainer feature with {} alias(es) to {}\n",
+            "Add it to devcontainer.json: \"features\": {{ \"./{}\": {{}} }}"
+        ),
+        count,
+        dir.display(),
+        dir.file_name().unwrap_or_default().to_string_lossy()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_generate_dockerfile_snippet() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add("ll".to_string(), "ls -la".to_string(), AliasScope::Global)
+            .unwrap();
+        let file = dir.path().join("aka-aliases.sh");
+
+        let snippet = handle_generate_dockerfile_command(&store, &file).unwrap();
+        assert!(std::fs::read_to_string(&file).unwrap().contains("ll() {\n"));
+        assert!(snippet.contains(&format!("COPY {} /etc/aka/aliases.sh\n", file.display())));
+
+        // The RUN line must succeed whichever startup files the image has
+        let root = dir.path().join("root");
+        std::fs::create_dir_all(root.join("etc")).unwrap();
+        std::fs::write(root.join("etc/bash.bashrc"), "").unwrap();
+        let run = snippet
+            .split_once("RUN ")
+            .unwrap()
+            .1
+            .replace("/etc/", &format!("{}/etc/", root.display()));
+        let Ok(status) = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&run)
+            .status()
+        else {
+            return;
+        };
+        assert!(status.success());
+        assert_eq!(
+            std::fs::read_to_string(root.join("etc/bash.bashrc")).unwrap(),
+            format!(". {}/etc/aka/aliases.sh\n", root.display())
+        );
+    }
+
+    #[test]
+    fn test_generate_devcontainer() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add("ll".to_string(), "ls -la".to_string(), AliasScope::Global)
+            .unwrap();
+        let feature = dir.path().join(".devcontainer").join("aka");
+
+        let msg = handle_generate_devcontainer_command(&store, &feature).unwrap();
+        assert!(msg.contains("with 1 alias(es)"));
+        assert!(msg.ends_with("\"./aka\": {} }"));
+
+        store.remove("ll").unwrap();
+        handle_generate_devcontainer_command(&store, &feature).unwrap();
+        let export = Store::load(&feature.join("aka.json")).unwrap();
+        assert!(export.list().unwrap().is_empty());
+
+        let install = std::fs::read_to_string(feature.join("install.sh")).unwrap();
+        assert!(install.contains("--git https://github.com/gohan5858/aka\n"));
+        let manifest: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(feature.join("devcontainer-feature.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(manifest["id"], "aka");
+        assert!(feature.join("aka").is_file());
+    }
+
+    #[test]
+    fn test_generate_compose() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join("app");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::write(
+            project.join("docker-compose.yml"),
+            "services:\n  web:\n    image: nginx\n  db:\n    image: postgres\n",
+        )
+        .unwrap();
+
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        let msg = handle_generate_compose_command(&mut store, &project.join("src")).unwrap();
+        assert!(msg.contains("Added: down, logs-db, logs-web, sh-db, sh-web, up"));
+
+        let aliases = store.list().unwrap();
+        assert_eq!(aliases["sh-web"][0].command, "docker compose exec web sh");
+        assert_eq!(aliases["up"][0].origin.as_deref(), Some("compose"));
+        assert_eq!(
+            aliases["logs-db"][0].scope,
+            AliasScope::Recursive(project.to_string_lossy().to_string())
+        );
+
+        std::fs::write(
+            project.join("docker-compose.yml"),
+            "services:\n  web:\n    image: nginx\n",
+        )
+        .unwrap();
+        let msg = handle_generate_compose_command(&mut store, &project).unwrap();
+        assert!(msg.contains("Removed: logs-db, sh-db"));
+    }
+
+    #[test]
+    fn test_ssh_hosts() {
+        let config = "\
+Host *
+    ServerAliveInterval 30
+Host web1 web2
+    User deploy
+host=db
+Host *.internal !bastion
+Match host web1
+    User root
+";
+        assert_eq!(ssh_hosts(config), vec!["web1", "web2", "db"]);
+    }
+
+    #[test]
+    fn test_generate_ssh() {
+        let dir = tempdir().unwrap();
+        let config = dir.path().join("config");
+        std::fs::write(&config, "Host web1\nHost db\n").unwrap();
+
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        let msg = handle_generate_ssh_command(&mut store, &config, "ssh-", false).unwrap();
+        assert!(msg.contains("Added: ssh-db, ssh-web1"));
+        assert_eq!(store.list().unwrap()["ssh-web1"][0].command, "ssh web1");
+
+        std::fs::write(&config, "Host web1\n").unwrap();
+        let msg = handle_generate_ssh_command(&mut store, &config, "ssh-", false).unwrap();
+        assert!(msg.contains("Removed: ssh-db"));
+
+        let msg = handle_generate_ssh_command(&mut store, &config, "ssh-", true).unwrap();
+        assert!(msg.contains("Removed: ssh-web1"));
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_generate_compose_without_file() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        assert!(matches!(
+            handle_generate_compose_command(&mut store, dir.path()),
+            Err(AkaError::ConfigError(msg)) if msg.starts_with("No compose file found")
+        ));
+    }
+
+    #[test]
+    fn test_generate_raycast() {
+        let dir = tempdir().unwrap();
+        let out = dir.path().join("raycast");
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add(
+                "greet".to_string(),
+                "echo hello @1".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+        store
+            .add(
+                "build".to_string(),
+                "make".to_string(),
+                AliasScope::Recursive(dir.path().to_string_lossy().to_string()),
+            )
+            .unwrap();
+        store
+            .add(
+                "many".to_string(),
+                "echo @1 @2 @3 @4".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+        std::fs::create_dir_all(&out).unwrap();
+        std::fs::write(out.join("aka-gone.sh"), "").unwrap();
+        std::fs::write(out.join("mine.sh"), "").unwrap();
+
+        let msg = handle_generate_raycast_command(&store, &out).unwrap();
+        assert!(msg.starts_with("Wrote 2 script command(s)"));
+        assert!(msg.contains("Removed 1 stale script command(s)"));
+        assert!(msg.contains("Skipped (more than 3 arguments): many"));
+        assert!(out.join("mine.sh").exists());
+
+        let greet = std::fs::read_to_string(out.join("aka-greet.sh")).unwrap();
+        assert!(greet.contains("# @raycast.title greet\n"));
+        assert!(
+            greet.contains("# @raycast.argument1 { \"type\": \"text\", \"placeholder\": \"@1\" }")
+        );
+        assert!(!greet.contains("argument2"));
+        if let Ok(output) = std::process::Command::new("bash")
+            .arg(out.join("aka-greet.sh"))
+            .arg("raycast")
+            .output()
+        {
+            assert_eq!(String::from_utf8_lossy(&output.stdout), "hello raycast\n");
+        }
+
+        let build = std::fs::read_dir(&out)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .find(|name| name.starts_with("aka-build-"))
+            .unwrap();
+        let build = std::fs::read_to_string(out.join(build)).unwrap();
+        let quoted = crate::commands::script::shell_quote(&dir.path().to_string_lossy());
+        assert!(build.contains(&format!("cd {} || exit 1", quoted)));
+    }
+}
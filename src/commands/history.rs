@@ -1,4 +1,4 @@
-use crate::commands::add::handle_add_command;
+use crate::commands::add::{AddOptions, handle_add_command};
 use crate::error::AkaError;
 use crate::Store;
 use std::collections::HashSet;
@@ -15,6 +15,7 @@ pub fn handle_history_command(
     scope: Option<String>,
     recursive: bool,
     limit: usize,
+    query: Option<String>,
 ) -> std::result::Result<String, AkaError> {
     let history_path = resolve_history_path()?;
     let entries = read_history_entries(&history_path, limit)?;
@@ -22,7 +23,7 @@ pub fn handle_history_command(
         return Ok("No history entries found".to_string());
     }
 
-    let selected = match select_with_fzf(&entries)? {
+    let selected = match select_with_fzf(&entries, query.as_deref())? {
         Some(value) => value,
         None => return Err(AkaError::OperationCancelled),
     };
@@ -32,21 +33,68 @@ pub fn handle_history_command(
         None => prompt_alias_name(&selected)?,
     };
 
-    handle_add_command(store, alias_name, selected, scope, recursive)
+    handle_add_command(
+        store,
+        alias_name,
+        selected,
+        scope,
+        recursive,
+        AddOptions::default(),
+    )
+}
+
+/// 直前に実行したコマンドをエイリアスとして登録する。
+pub fn handle_last_command(
+    store: &mut Store,
+    alias: Option<String>,
+    scope: Option<String>,
+    recursive: bool,
+) -> std::result::Result<String, AkaError> {
+    let history_path = resolve_history_path()?;
+    let entries = read_history_entries(&history_path, DEFAULT_HISTORY_LIMIT)?;
+    let last = match find_last_command(&entries) {
+        Some(value) => value,
+        None => return Ok("No history entries found".to_string()),
+    };
+
+    let alias_name = match alias {
+        Some(value) => {
+            println!("Last command: {}", last);
+            value
+        }
+        None => prompt_alias_name(&last)?,
+    };
+
+    handle_add_command(
+        store,
+        alias_name,
+        last,
+        scope,
+        recursive,
+        AddOptions::default(),
+    )
+}
+
+/// 新しい順の履歴から aka 自身の呼び出しを除いた最初のコマンドを返す。
+fn find_last_command(entries: &[String]) -> Option<String> {
+    entries
+        .iter()
+        .find(|entry| *entry != "aka" && !entry.starts_with("aka "))
+        .cloned()
 }
 
 /// 履歴ファイルのパスを解決する。
 fn resolve_history_path() -> std::result::Result<PathBuf, AkaError> {
-    if let Ok(path) = std::env::var("AKA_HISTORY_FILE") {
-        if !path.trim().is_empty() {
-            return Ok(PathBuf::from(path));
-        }
+    if let Ok(path) = std::env::var("AKA_HISTORY_FILE")
+        && !path.trim().is_empty()
+    {
+        return Ok(PathBuf::from(path));
     }
 
-    if let Ok(path) = std::env::var("HISTFILE") {
-        if !path.trim().is_empty() {
-            return Ok(PathBuf::from(path));
-        }
+    if let Ok(path) = std::env::var("HISTFILE")
+        && !path.trim().is_empty()
+    {
+        return Ok(PathBuf::from(path));
     }
 
     let home_dir = dirs::home_dir()
@@ -100,23 +148,26 @@ fn read_history_entries(path: &Path, limit: usize) -> std::result::Result<Vec<St
 
 /// 1行の履歴からコマンド部分を抽出する。
 fn parse_history_line(line: &str) -> Option<String> {
-    if let Some(rest) = line.strip_prefix(": ") {
-        if let Some((_, command)) = rest.split_once(';') {
-            return Some(command.to_string());
-        }
+    if let Some(rest) = line.strip_prefix(": ")
+        && let Some((_, command)) = rest.split_once(';')
+    {
+        return Some(command.to_string());
     }
 
-    if let Some(rest) = line.strip_prefix('#') {
-        if rest.chars().all(|c| c.is_ascii_digit()) {
-            return None;
-        }
+    if let Some(rest) = line.strip_prefix('#')
+        && rest.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
     }
 
     Some(line.to_string())
 }
 
-/// fzf を使って候補から選択する。
-fn select_with_fzf(entries: &[String]) -> std::result::Result<Option<String>, AkaError> {
+/// fzf を使って候補から選択する。`query` を指定すると初期クエリとして渡す。
+fn select_with_fzf(
+    entries: &[String],
+    query: Option<&str>,
+) -> std::result::Result<Option<String>, AkaError> {
     if entries.is_empty() {
         return Ok(None);
     }
@@ -127,7 +178,11 @@ fn select_with_fzf(entries: &[String]) -> std::result::Result<Option<String>, Ak
         .arg("--exit-0")
         .arg("--reverse")
         .arg("--height=40%")
-        .arg("--prompt=aka> ")
+        .arg("--prompt=aka> ");
+    if let Some(query) = query {
+        command.arg(format!("--query={}", query));
+    }
+    command
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
@@ -192,6 +247,21 @@ mod tests {
         assert_eq!(parsed, None);
     }
 
+    #[test]
+    fn test_find_last_command_skips_aka() {
+        let entries = vec![
+            "aka last".to_string(),
+            "aka".to_string(),
+            "cargo build --release".to_string(),
+            "ls".to_string(),
+        ];
+        assert_eq!(
+            find_last_command(&entries),
+            Some("cargo build --release".to_string())
+        );
+        assert_eq!(find_last_command(&entries[..2]), None);
+    }
+
     #[test]
     fn test_read_history_entries_with_invalid_utf8() {
         let dir = tempdir().unwrap();
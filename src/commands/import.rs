@@ -0,0 +1,221 @@
+use crate::Store;
+use crate::commands::project::{format_sync_report, is_alias_name};
+use crate::commands::script::shell_quote;
+use crate::error::AkaError;
+use crate::store::{AliasScope, normalize_scope_path};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Task runner whose targets `aka import` turns into aliases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    Just,
+    Make,
+}
+
+impl ImportSource {
+    /// Origin tag for the aliases this source owns.
+    fn origin(self) -> &'static str {
+        match self {
+            ImportSource::Just => "just",
+            ImportSource::Make => "make",
+        }
+    }
+
+    /// File names the tool looks for, in its own lookup order.
+    fn file_names(self) -> &'static [&'static str] {
+        match self {
+            ImportSource::Just => &["justfile", "Justfile", ".justfile"],
+            ImportSource::Make => &["GNUmakefile", "makefile", "Makefile"],
+        }
+    }
+}
+
+/// Public recipe names in a justfile. Recipes starting with `_` or marked `[private]` are skipped.
+fn just_recipes(content: &str) -> Vec<String> {
+    let mut recipes = Vec::new();
+    let mut private = false;
+    for line in content.lines() {
+        if line.trim().is_empty() || line.starts_with([' ', '\t', '#']) {
+            continue;
+        }
+        if line.starts_with('[') {
+            private |= line.contains("private");
+            continue;
+        }
+        let is_private = std::mem::take(&mut private);
+        let Some((head, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // Assignments (`x := y`), settings and `alias b := build` are not recipes
+        if rest.starts_with('=')
+            || ["set", "alias", "export", "import", "mod"]
+                .iter()
+                .any(|keyword| head.split_whitespace().next() == Some(*keyword))
+        {
+            continue;
+        }
+        let Some(name) = head.split_whitespace().next() else {
+            continue;
+        };
+        let name = name.trim_start_matches('@');
+        if !is_private && !name.starts_with('_') {
+            recipes.push(name.to_string());
+        }
+    }
+    recipes
+}
+
+/// Explicit targets in a Makefile, leaving out special, pattern and file targets.
+fn make_targets(content: &str) -> Vec<String> {
+    let mut targets = Vec::new();
+    for line in content.lines() {
+        if line.starts_with([' ', '\t', '#']) {
+            continue;
+        }
+        let Some((head, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // `X = a:b`, `X := y` and `X ::= y` are variable assignments
+        if head.contains('=') || rest.starts_with('=') || rest.starts_with(":=") {
+            continue;
+        }
+        targets.extend(
+            head.split_whitespace()
+                .filter(|t| !t.contains(['.', '%', '$', '/']))
+                .map(str::to_string),
+        );
+    }
+    targets
+}
+
+/// Create or refresh recursive-scoped aliases for the just recipes or make targets in `dir`.
+///
+/// Targets removed from the file since the last import are removed as well.
+pub fn handle_import_command(
+    store: &mut Store,
+    dir: &Path,
+    source: ImportSource,
+) -> std::result::Result<String, AkaError> {
+    let Some(file) = source
+        .file_names()
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+    else {
+        return Err(AkaError::ConfigError(format!(
+            "No {} in {}",
+            source.file_names().join(" or "),
+            dir.display()
+        )));
+    };
+    let content = std::fs::read_to_string(&file)?;
+
+    let (names, prefix) = match source {
+        ImportSource::Just => (just_recipes(&content), "just".to_string()),
+        // make only looks in the current directory, so point it at the project from subdirectories
+        ImportSource::Make => (
+            make_targets(&content),
+            format!("make -C {}", shell_quote(&dir.to_string_lossy())),
+        ),
+    };
+
+    let mut aliases = BTreeMap::new();
+    let mut invalid = Vec::new();
+    for name in names {
+        if is_alias_name(&name) {
+            let command = format!("{} {}", prefix, name);
+            aliases.insert(name, command);
+        } else {
+            invalid.push(name);
+        }
+    }
+
+    let scope = AliasScope::Recursive(normalize_scope_path(dir)?);
+    let report = store.sync_generated(source.origin(), &scope, &aliases)?;
+    Ok(format!(
+        "Imported {}\n{}",
+        file.display(),
+        format_sync_report(&report, &invalid, dir)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_just_recipes() {
+        let justfile = "\
+set shell := [\"bash\", \"-c\"]
+version := \"1.0\"
+alias b := build
+
+# Build everything
+build target=\"debug\": fmt
+    cargo build --{{target}}
+
+@fmt:
+    cargo fmt
+
+[private]
+helper:
+    echo hidden
+
+_secret:
+    echo hidden
+";
+        assert_eq!(just_recipes(justfile), vec!["build", "fmt"]);
+    }
+
+    #[test]
+    fn test_make_targets() {
+        let makefile = "\
+CC := gcc
+FLAGS = -O2 -DX=a:b
+.PHONY: all clean
+
+all test: main.o
+\t$(CC) -o app main.o
+
+main.o: main.c
+\t$(CC) -c main.c
+
+%.o: %.c
+\t$(CC) -c $<
+
+build/app: all
+clean:
+\trm -f *.o
+";
+        assert_eq!(make_targets(makefile), vec!["all", "test", "clean"]);
+    }
+
+    #[test]
+    fn test_import_make() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join("my app");
+        std::fs::create_dir(&project).unwrap();
+        std::fs::write(project.join("Makefile"), "build:\n\tcc\ncheck:\n\ttrue\n").unwrap();
+
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        let msg = handle_import_command(&mut store, &project, ImportSource::Make).unwrap();
+        assert!(msg.contains("Added: build, check"));
+        let aliases = store.list().unwrap();
+        assert_eq!(
+            aliases["build"][0].command,
+            format!("make -C '{}' build", project.display())
+        );
+        assert_eq!(aliases["build"][0].origin.as_deref(), Some("make"));
+
+        std::fs::write(project.join("Makefile"), "build:\n\tcc\n").unwrap();
+        let msg = handle_import_command(&mut store, &project, ImportSource::Make).unwrap();
+        assert!(msg.contains("Removed: check"));
+
+        assert!(matches!(
+            handle_import_command(&mut store, &project, ImportSource::Just),
+            Err(AkaError::ConfigError(msg)) if msg.starts_with("No justfile or Justfile")
+        ));
+    }
+}
@@ -1,274 +1,1016 @@
-use crate::store::{AliasScope, Store};
+use crate::codegen::{self, Shell};
+use crate::commands::script::shell_quote;
+use crate::commands::var;
+use crate::config::ShellConfig;
+use crate::references;
+use crate::store::{AliasDefinition, Store, resolve_definition};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const ZSH_HOOK_SCRIPT: &str = r#"
+# Add this to your ~/.zshrc: eval "$(aka init --shell zsh)"
+# Set AKA_DISABLE=1 to run the real commands and skip reloading in this session
+autoload -Uz add-zsh-hook
+
+_aka_precmd() {
+    [ -n "$AKA_DISABLE" ] && return
+    # 1. Capture last command
+    export AKA_LAST_CMD="$(fc -ln -1 | sed 's/^[[:space:]]*//')"
+
+    # 2. Check if we need to reload aliases (if last command was 'aka')
+    if [[ "$AKA_LAST_CMD" == aka* ]]; then
+         eval "$(command aka init --dump --shell zsh)"
+    fi
+}
+add-zsh-hook precmd _aka_precmd
 
-pub fn handle_init_command(
-    store: Option<&Store>,
-    dump: bool,
-) -> std::result::Result<String, crate::error::AkaError> {
-    if dump {
-        let mut output = String::new();
-        let mut managed_aliases = Vec::new();
-
-        output.push_str("if [ -n \"$ZSH_VERSION\" ]; then\n");
-        output.push_str("    if [[ -o aliases ]]; then\n");
-        output.push_str("        _aka_aliases_was_on=1\n");
-        output.push_str("    else\n");
-        output.push_str("        _aka_aliases_was_on=0\n");
-        output.push_str("    fi\n");
-        output.push_str("    unsetopt aliases\n");
-        output.push_str("elif [ -n \"$BASH_VERSION\" ]; then\n");
-        output.push_str("    _aka_aliases_was_on=$(shopt -q expand_aliases && echo 1 || echo 0)\n");
-        output.push_str("    shopt -u expand_aliases\n");
-        output.push_str("fi\n");
-
-        // Cleanup previous aliases
-        output.push_str("if [ -n \"$AKA_MANAGED_ALIASES\" ]; then\n");
-        output.push_str("    for al in $AKA_MANAGED_ALIASES; do unalias $al 2>/dev/null; unset -f $al 2>/dev/null; done\n");
-        output.push_str("fi\n");
-
-        if let Some(store) = store {
-            for (alias, definitions) in store.list()? {
-                managed_aliases.push(alias.clone());
-
-                output.push_str(&format!(
-                    "unalias {} 2>/dev/null; unset -f {} 2>/dev/null\n",
-                    alias, alias
-                ));
-                output.push_str(&format!("{}() {{\n", alias));
-                output.push_str("    local current_dir=\"$PWD\"\n");
-
-                // Sort definitions: Exact > Recursive (longest first) > Global
-                let mut defs = definitions.clone();
-                defs.sort_by(|a, b| {
-                    match (&a.scope, &b.scope) {
-                        (AliasScope::Exact(p1), AliasScope::Exact(p2)) => p2.len().cmp(&p1.len()), // Longest path first
-                        (AliasScope::Exact(_), _) => std::cmp::Ordering::Less,
-                        (_, AliasScope::Exact(_)) => std::cmp::Ordering::Greater,
-
-                        (AliasScope::Recursive(p1), AliasScope::Recursive(p2)) => {
-                            p2.len().cmp(&p1.len())
-                        }
-                        (AliasScope::Recursive(_), _) => std::cmp::Ordering::Less,
-                        (_, AliasScope::Recursive(_)) => std::cmp::Ordering::Greater,
-
-                        (AliasScope::Global, AliasScope::Global) => std::cmp::Ordering::Equal,
-                    }
-                });
-
-                let mut if_started = false;
-                let mut has_global = false;
-
-                for def in defs {
-                    let cmd_body = prepare_command_body(&def.command);
-
-                    match &def.scope {
-                        AliasScope::Exact(path) => {
-                            let op = if if_started { "elif" } else { "if" };
-                            output.push_str(&format!(
-                                "    {} [[ \"$current_dir\" == \"{}\" ]]; then\n",
-                                op, path
-                            ));
-                            output.push_str(&format!("        {}\n", cmd_body));
-                            if_started = true;
-                        }
-                        AliasScope::Recursive(path) => {
-                            let op = if if_started { "elif" } else { "if" };
-                            output.push_str(&format!(
-                                "    {} [[ \"$current_dir\" == \"{}\"* ]]; then\n",
-                                op, path
-                            ));
-                            output.push_str(&format!("        {}\n", cmd_body));
-                            if_started = true;
-                        }
-                        AliasScope::Global => {
-                            if if_started {
-                                output.push_str("    else\n");
-                            }
-                            output.push_str(&format!("        {}\n", cmd_body));
-                            has_global = true;
-                        }
-                    }
-                }
+# Announce scoped aliases on directory change (set AKA_ANNOUNCE=0 to disable)
+_aka_chpwd() {
+    if [ -z "$AKA_DISABLE" ] && [ "${AKA_ANNOUNCE:-1}" != "0" ]; then
+        command aka announce 2>/dev/null
+    fi
+}
+add-zsh-hook chpwd _aka_chpwd
+
+# Expand the alias under the cursor into its full command (bind with AKA_EXPAND_KEY)
+_aka_expand_widget() {
+    local expanded
+    expanded="$(command aka resolve --line -- "$BUFFER" 2>/dev/null)" || return 0
+    BUFFER="$expanded"
+    CURSOR=${#BUFFER}
+}
+zle -N _aka_expand_widget
+if [ -n "$AKA_EXPAND_KEY" ]; then
+    bindkey "$AKA_EXPAND_KEY" _aka_expand_widget
+fi
 
-                if !has_global {
-                    if if_started {
-                        output.push_str("    else\n");
-                    }
-                    output.push_str(&format!("        command {} \"$@\"\n", alias));
-                }
+# Open the history picker seeded with the current buffer (bind with AKA_ADD_KEY)
+_aka_add_widget() {
+    zle -I
+    if command aka add --query "$BUFFER" </dev/tty; then
+        eval "$(command aka init --dump --shell zsh)"
+    fi
+    zle reset-prompt
+}
+zle -N _aka_add_widget
+if [ -n "$AKA_ADD_KEY" ]; then
+    bindkey "$AKA_ADD_KEY" _aka_add_widget
+fi
 
-                if if_started {
-                    output.push_str("    fi\n");
-                }
+# Completion for subcommands and stored alias names (needs compinit)
+if (( $+functions[compdef] )); then
+    source <(AKA_COMPLETE=zsh command aka)
+fi
+
+if [ -z "$AKA_DISABLE" ]; then
+    eval "$(command aka init --dump --shell zsh)"
+fi
+"#;
+
+const BASH_HOOK_SCRIPT: &str = r#"
+# Add this to your ~/.bashrc: eval "$(aka init --shell bash)"
+# Set AKA_DISABLE=1 to run the real commands and skip reloading in this session
+# Capture the last command, reload and announce from PROMPT_COMMAND
+_aka_prompt_command() {
+    [ -n "$AKA_DISABLE" ] && return
+    # Capture last command
+    export AKA_LAST_CMD="$(history 1 | sed 's/^[[:space:]]*[0-9]*[[:space:]]*//')"
+
+    if [[ "$AKA_LAST_CMD" == aka* ]]; then
+         eval "$(command aka init --dump --shell bash)"
+    fi
+
+    # Announce scoped aliases on directory change (set AKA_ANNOUNCE=0 to disable)
+    if [[ "$PWD" != "$_AKA_LAST_PWD" ]]; then
+        _AKA_LAST_PWD="$PWD"
+        if [ "${AKA_ANNOUNCE:-1}" != "0" ]; then
+            command aka announce 2>/dev/null
+        fi
+    fi
+}
+_AKA_LAST_PWD="$PWD"
 
-                output.push_str("}\n");
+# Open the history picker seeded with the current line (bind with AKA_ADD_KEY)
+_aka_add_widget() {
+    if command aka add --query "$READLINE_LINE" </dev/tty; then
+        eval "$(command aka init --dump --shell bash)"
+    fi
+}
+if [ -n "$AKA_ADD_KEY" ]; then
+    bind -x "\"$AKA_ADD_KEY\": _aka_add_widget"
+fi
+
+# Completion for subcommands and stored alias names
+source <(AKA_COMPLETE=bash command aka)
+
+PROMPT_COMMAND="_aka_prompt_command;$PROMPT_COMMAND"
+
+if [ -z "$AKA_DISABLE" ]; then
+    eval "$(command aka init --dump --shell bash)"
+fi
+"#;
+
+const FISH_HOOK_SCRIPT: &str = r#"
+# Add this to your ~/.config/fish/config.fish: aka init --shell fish | source
+# Set AKA_DISABLE=1 to run the real commands and skip reloading in this session
+if status --is-interactive
+    # Capture the last command and reload aliases after running aka
+    function _aka_postexec --on-event fish_postexec
+        test -n "$AKA_DISABLE"; and return
+        set -gx AKA_LAST_CMD $argv[1]
+        if string match -q 'aka*' -- $argv[1]
+            command aka init --dump --shell fish | source
+        end
+    end
+
+    # Announce scoped aliases on directory change (set AKA_ANNOUNCE=0 to disable)
+    function _aka_chpwd --on-variable PWD
+        if test -z "$AKA_DISABLE"; and test "$AKA_ANNOUNCE" != 0
+            command aka announce 2>/dev/null
+        end
+    end
+
+    # Completion for subcommands and stored alias names
+    AKA_COMPLETE=fish command aka | source
+
+    if test -z "$AKA_DISABLE"
+        command aka init --dump --shell fish | source
+    end
+end
+"#;
+
+/// nushell cannot evaluate generated code directly, so the hook saves the dump to a file and
+/// sources it from a string hook, which nushell parses when it runs. The first prompt loads
+/// the aliases, and later ones reload them after an aka command.
+const NU_HOOK_SCRIPT: &str = r#"
+# Save this where config.nu loads it: aka init --shell nu | save -f ($nu.default-config-dir | path join aka.nu)
+# Set AKA_DISABLE=1 to run the real commands and skip reloading in this session
+$env.config.hooks.pre_execution = ($env.config.hooks.pre_execution? | default [] | append {||
+    $env.AKA_LAST_CMD = (commandline)
+})
+
+# Load the aliases on the first prompt and reload them after running aka
+$env.config.hooks.pre_prompt = ($env.config.hooks.pre_prompt? | default [] | append {
+    condition: {|| ($env.AKA_DISABLE? | is-empty) and ($env.AKA_LAST_CMD? | default 'aka' | str starts-with 'aka') }
+    code: @RELOAD@
+})
+
+# Announce scoped aliases on directory change (set AKA_ANNOUNCE=0 to disable)
+$env.config.hooks.env_change.PWD = ($env.config.hooks.env_change.PWD? | default [] | append {|before, after|
+    if ($env.AKA_DISABLE? | is-empty) and ($env.AKA_ANNOUNCE? | default '1') != '0' {
+        let message = (^aka announce | complete | get stdout | str trim)
+        if ($message | is-not-empty) { print $message }
+    }
+})
+"#;
+
+const POWERSHELL_HOOK_SCRIPT: &str = r#"
+# Add this to your $PROFILE: Invoke-Expression (& aka init --shell powershell | Out-String)
+# Set AKA_DISABLE=1 to run the real commands and skip reloading in this session
+$global:_aka_prompt = $function:prompt
+$global:_aka_last_pwd = $PWD.Path
+function global:prompt {
+    if (-not $env:AKA_DISABLE) {
+        # Reload aliases after running aka
+        $last = Get-History -Count 1
+        if ($last -and $last.Id -ne $global:_aka_last_id) {
+            $global:_aka_last_id = $last.Id
+            $env:AKA_LAST_CMD = $last.CommandLine
+            if ($last.CommandLine -like 'aka*') {
+                Invoke-Expression (& aka init --dump --shell powershell | Out-String)
             }
         }
 
-        output.push_str(&format!(
-            "export AKA_MANAGED_ALIASES=\"{}\"\n",
-            managed_aliases.join(" ")
-        ));
-
-        output.push_str("if [ -n \"$ZSH_VERSION\" ]; then\n");
-        output.push_str("    if [ \"${_aka_aliases_was_on:-0}\" = \"1\" ]; then\n");
-        output.push_str("        setopt aliases\n");
-        output.push_str("    fi\n");
-        output.push_str("elif [ -n \"$BASH_VERSION\" ]; then\n");
-        output.push_str("    if [ \"${_aka_aliases_was_on:-0}\" = \"1\" ]; then\n");
-        output.push_str("        shopt -s expand_aliases\n");
-        output.push_str("    fi\n");
-        output.push_str("fi\n");
-        output.push_str("unset _aka_aliases_was_on\n");
-
-        if output.ends_with('\n') {
-            output.pop();
+        # Announce scoped aliases on directory change (set AKA_ANNOUNCE=0 to disable)
+        if ($PWD.Path -ne $global:_aka_last_pwd) {
+            $global:_aka_last_pwd = $PWD.Path
+            if ($env:AKA_ANNOUNCE -ne '0') { & aka announce 2>$null }
         }
-        return Ok(output);
     }
+    & $global:_aka_prompt
+}
 
-    Ok(r#"
-# Add this to your ~/.zshrc (Bash support is best-effort)
-if [ -n "$ZSH_VERSION" ]; then
-    autoload -Uz add-zsh-hook
+# Completion for subcommands and stored alias names
+$env:AKA_COMPLETE = 'powershell'
+& aka | Out-String | Invoke-Expression
+Remove-Item Env:AKA_COMPLETE
 
-    _aka_precmd() {
-        # 1. Capture last command
-        export AKA_LAST_CMD="$(fc -ln -1 | sed 's/^[[:space:]]*//')"
+if (-not $env:AKA_DISABLE) {
+    Invoke-Expression (& aka init --dump --shell powershell | Out-String)
+}
+"#;
+
+const ELVISH_HOOK_SCRIPT: &str = r#"
+# Add this to your ~/.config/elvish/rc.elv: eval (aka init --shell elvish | slurp)
+# Set AKA_DISABLE=1 to run the real commands and skip reloading in this session
+use str
+
+# Capture the last command and reload aliases after running aka
+set edit:after-command = [$@edit:after-command {|m| set E:AKA_LAST_CMD = $m[src][code] }]
+set edit:before-readline = [$@edit:before-readline {
+    if (and (eq $E:AKA_DISABLE '') (str:has-prefix $E:AKA_LAST_CMD aka)) {
+        set E:AKA_LAST_CMD = ''
+        eval (e:aka init --dump --shell elvish | slurp)
+    }
+}]
 
-        # 2. Check if we need to reload aliases (if last command was 'aka')
-        if [[ "$AKA_LAST_CMD" == aka* ]]; then
-             eval "$(command aka init --dump)"
-        fi
+# Announce scoped aliases on directory change (set AKA_ANNOUNCE=0 to disable)
+set after-chdir = [$@after-chdir {|dir|
+    if (and (eq $E:AKA_DISABLE '') (not-eq $E:AKA_ANNOUNCE '0')) {
+        try { e:aka announce 2>/dev/null } catch { }
     }
-    add-zsh-hook precmd _aka_precmd
+}]
 
-elif [ -n "$BASH_VERSION" ]; then
-    # Bash fallback using PROMPT_COMMAND
-    _aka_prompt_command() {
-        # Capture last command
-        export AKA_LAST_CMD="$(history 1 | sed 's/^[[:space:]]*[0-9]*[[:space:]]*//')"
+# Completion for subcommands and stored alias names
+eval (e:env AKA_COMPLETE=elvish aka | slurp)
 
-        if [[ "$AKA_LAST_CMD" == aka* ]]; then
-             eval "$(command aka init --dump)"
-        fi
-    }
-    PROMPT_COMMAND="_aka_prompt_command;$PROMPT_COMMAND"
-fi
+if (eq $E:AKA_DISABLE '') {
+    eval (e:aka init --dump --shell elvish | slurp)
+}
+"#;
 
-eval "$(command aka init --dump)"
-"#
-    .to_string())
+const ZSH_ACTIVATE_HOOK: &str = r#"
+# Redefine the aliases for each directory entered, with only the definitions that apply there
+_aka_activate() {
+    [ -n "$AKA_DISABLE" ] && return
+    eval "$(command aka init --dump --shell zsh --activate-on-cd)"
+}
+add-zsh-hook chpwd _aka_activate
+"#;
+
+const BASH_ACTIVATE_HOOK: &str = r#"
+# Redefine the aliases for each directory entered, with only the definitions that apply there
+_aka_activate() {
+    [ -n "$AKA_DISABLE" ] && return
+    if [[ "$PWD" != "$_AKA_ACTIVE_PWD" ]]; then
+        _AKA_ACTIVE_PWD="$PWD"
+        eval "$(command aka init --dump --shell bash --activate-on-cd)"
+    fi
+}
+_AKA_ACTIVE_PWD="$PWD"
+PROMPT_COMMAND="_aka_activate;$PROMPT_COMMAND"
+"#;
+
+const FISH_ACTIVATE_HOOK: &str = r#"
+# Redefine the aliases for each directory entered, with only the definitions that apply there
+function _aka_activate --on-variable PWD
+    test -n "$AKA_DISABLE"; and return
+    command aka init --dump --shell fish --activate-on-cd | source
+end
+"#;
+
+/// `hook` changed to load only the definitions for the current directory and to reload them
+/// on every directory change.
+fn activate_on_cd_hook(
+    shell: Shell,
+    hook: &str,
+) -> std::result::Result<String, crate::error::AkaError> {
+    let activate = match shell {
+        Shell::Zsh => ZSH_ACTIVATE_HOOK,
+        Shell::Bash => BASH_ACTIVATE_HOOK,
+        Shell::Fish => FISH_ACTIVATE_HOOK,
+        _ => {
+            return Err(crate::error::AkaError::ConfigError(format!(
+                "--activate-on-cd is not supported for {}",
+                shell.name()
+            )));
+        }
+    };
+    let dump = format!("init --dump --shell {}", shell.name());
+    Ok(format!(
+        "{}{}",
+        hook.replace(&dump, &format!("{} --activate-on-cd", dump)),
+        activate
+    ))
 }
 
-fn prepare_command_body(command: &str) -> String {
-    let command = replace_placeholders(command);
-    if has_positional_args(&command) {
-        command
-    } else {
-        // Append "$@" if no args usage
-        format!("{} \"$@\"", command)
-    }
+const ZSH_CACHED_HOOK: &str = r#"
+# Aliases are loaded from @DUMP@, which aka rewrites only when the store has changed;
+# each prompt re-sources it when its first line, the generation stamp, differs
+_aka_source_dump() {
+    [ -n "$AKA_DISABLE" ] && return
+    local stamp
+    IFS= read -r stamp < @DUMP@ 2>/dev/null || return
+    if [[ "$stamp" != "$_AKA_LOADED_STAMP" ]]; then
+        _AKA_LOADED_STAMP="$stamp"
+        source @DUMP@
+    fi
+}
+_aka_load() {
+    command aka init --dump --shell zsh --cached && _aka_source_dump
+}
+autoload -Uz add-zsh-hook
+add-zsh-hook precmd _aka_source_dump
+"#;
+
+const BASH_CACHED_HOOK: &str = r#"
+# Aliases are loaded from @DUMP@, which aka rewrites only when the store has changed;
+# each prompt re-sources it when its first line, the generation stamp, differs
+_aka_source_dump() {
+    [ -n "$AKA_DISABLE" ] && return
+    local stamp
+    IFS= read -r stamp < @DUMP@ 2>/dev/null || return
+    if [[ "$stamp" != "$_AKA_LOADED_STAMP" ]]; then
+        _AKA_LOADED_STAMP="$stamp"
+        source @DUMP@
+    fi
+}
+_aka_load() {
+    command aka init --dump --shell bash --cached && _aka_source_dump
 }
+PROMPT_COMMAND="_aka_source_dump;$PROMPT_COMMAND"
+"#;
 
-fn replace_placeholders(command: &str) -> String {
-    let mut output = String::with_capacity(command.len());
-    let mut chars = command.chars().peekable();
+/// File the cached dump for `shell` is kept in.
+pub fn cached_dump_path(shell: Shell) -> std::result::Result<PathBuf, crate::error::AkaError> {
+    Ok(crate::store::data_dir()?.join(format!("dump.{}", shell.name())))
+}
 
-    while let Some(c) = chars.next() {
-        if c == '@' {
-            if let Some(&next) = chars.peek() {
-                if next.is_ascii_digit() {
-                    output.push('$');
-                    continue;
-                }
-            }
+/// `hook` changed to load the aliases from the cached dump at `dump_path` instead of
+/// evaluating a fresh dump.
+fn cached_hook(
+    shell: Shell,
+    hook: &str,
+    dump_path: &Path,
+) -> std::result::Result<String, crate::error::AkaError> {
+    let cached = match shell {
+        Shell::Zsh => ZSH_CACHED_HOOK,
+        Shell::Bash => BASH_CACHED_HOOK,
+        _ => {
+            return Err(crate::error::AkaError::ConfigError(format!(
+                "--cached is not supported for {}",
+                shell.name()
+            )));
         }
-        output.push(c);
+    };
+    let eval = format!(
+        "eval \"$(command aka init --dump --shell {})\"",
+        shell.name()
+    );
+    Ok(format!(
+        "{}{}",
+        cached.replace("@DUMP@", &shell_quote(&dump_path.to_string_lossy())),
+        hook.replace(&eval, "_aka_load")
+    ))
+}
+
+/// First line of a cached dump, which changes whenever the dump would: with the store, the
+/// version, the `[shell]` config or the system alias file.
+fn dump_stamp(store: &Store) -> std::result::Result<String, crate::error::AkaError> {
+    let shell_config = crate::config::load()
+        .map(|config| config.shell)
+        .unwrap_or_default();
+    dump_stamp_with(store, &shell_config, &crate::system::system_file_path())
+}
+
+fn dump_stamp_with(
+    store: &Store,
+    shell_config: &ShellConfig,
+    system_file: &Path,
+) -> std::result::Result<String, crate::error::AkaError> {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    shell_config.hash(&mut hasher);
+    std::fs::metadata(system_file)
+        .ok()
+        .map(|metadata| (metadata.len(), metadata.modified().ok()))
+        .hash(&mut hasher);
+    Ok(format!(
+        "# aka generation {} ({}, {:016x})",
+        store.generation()?,
+        env!("CARGO_PKG_VERSION"),
+        hasher.finish()
+    ))
+}
+
+/// Rewrite the cached dump for `shell` at `path` unless it is already up to date with the
+/// store, so reloading after an aka command that changed nothing skips generating it.
+/// `force` rewrites it regardless, for changes the stamp cannot see, such as a program an
+/// alias requires being installed.
+///
+/// Returns whether the file was written.
+pub fn write_cached_dump(
+    store: &Store,
+    shell: Shell,
+    path: &Path,
+    force: bool,
+) -> std::result::Result<bool, crate::error::AkaError> {
+    let stamp = dump_stamp(store)?;
+    let current = std::fs::read_to_string(path).unwrap_or_default();
+    if !force && current.lines().next() == Some(stamp.as_str()) {
+        return Ok(false);
+    }
+
+    let mut dump = format!("{}\n", stamp).into_bytes();
+    handle_init_command(Some(store), shell, true, false, None, false, &mut dump)?;
+    // Written aside and renamed so a shell sourcing it never sees half a dump
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, dump)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(true)
+}
+
+/// The line an rc file loads the dump for `shell` kept at `path` with.
+fn source_line(shell: Shell, path: &Path) -> String {
+    let path = path.to_string_lossy();
+    match shell {
+        Shell::Zsh | Shell::Bash => format!("source {}", shell_quote(&path)),
+        Shell::Fish => format!("source {}", codegen::fish::quote(&path)),
+        Shell::Nu => format!("source {}", codegen::nu::quote(&path)),
+        Shell::PowerShell => format!(". {}", codegen::powershell::quote(&path)),
+        Shell::Elvish => format!("eval (slurp < {})", codegen::elvish::quote(&path)),
     }
-    output
 }
 
-fn has_positional_args(command: &str) -> bool {
-    let mut chars = command.chars().peekable();
-    let mut in_single_quote = false;
-    let mut in_double_quote = false;
-    let mut escaped = false;
+/// Write the dump for `shell` to its file in the data directory, and the line sourcing it for
+/// rc files that load a static file rather than evaluate `aka init`. The file is rewritten
+/// after each aka command that changes the store; see [`refresh_sourced_dumps`].
+pub fn write_source_line(
+    store: &Store,
+    shell: Shell,
+    out: &mut dyn Write,
+) -> std::result::Result<(), crate::error::AkaError> {
+    let path = cached_dump_path(shell)?;
+    write_cached_dump(store, shell, &path, false)?;
+    writeln!(out, "{}", source_line(shell, &path))?;
+    Ok(())
+}
 
-    while let Some(c) = chars.next() {
-        if escaped {
-            escaped = false;
+/// Bring the dumps kept in the data directory up to date with the store, for shells sourcing
+/// them directly; only shells whose dump already exists are written for. `force` rewrites
+/// them even when they look current (`aka init --refresh`).
+///
+/// Returns how many dumps were written.
+pub fn refresh_sourced_dumps(force: bool) -> std::result::Result<usize, crate::error::AkaError> {
+    let mut store = None;
+    let mut written = 0;
+    for shell in Shell::ALL {
+        let path = cached_dump_path(shell)?;
+        if !path.exists() {
             continue;
         }
+        let store = match &mut store {
+            Some(store) => store,
+            None => store.insert(Store::new()?),
+        };
+        if write_cached_dump(store, shell, &path, force)? {
+            written += 1;
+        }
+    }
+    Ok(written)
+}
 
-        if c == '\\' {
-            escaped = true;
-            continue;
+/// Hook script for nushell, saving the dump to `dump_path`.
+fn nu_hook_script(dump_path: &Path) -> String {
+    let path = codegen::nu::quote(&dump_path.to_string_lossy());
+    let dir = codegen::nu::quote(&dump_path.parent().unwrap_or(dump_path).to_string_lossy());
+    let reload = format!(
+        "$env.AKA_LAST_CMD = ''; mkdir {}; ^aka init --dump --shell nu | save -f {}; source {}",
+        dir, path, path
+    );
+    NU_HOOK_SCRIPT.replace("@RELOAD@", &codegen::nu::quote(&reload))
+}
+
+/// Name of the process that ran aka, which is the shell evaluating `aka init`.
+#[cfg(unix)]
+fn parent_process_name() -> Option<String> {
+    let ppid = std::os::unix::process::parent_id();
+    if let Ok(name) = std::fs::read_to_string(format!("/proc/{}/comm", ppid)) {
+        return Some(name);
+    }
+    let output = std::process::Command::new("ps")
+        .args(["-o", "comm=", "-p", &ppid.to_string()])
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(not(unix))]
+fn parent_process_name() -> Option<String> {
+    None
+}
+
+/// The shell `aka init` runs under when `--shell` is not given: the parent process, then
+/// `$SHELL`, then PowerShell on Windows and zsh elsewhere.
+pub fn detect_shell() -> Shell {
+    parent_process_name()
+        .and_then(|name| Shell::from_name(&name))
+        .or_else(|| {
+            std::env::var("SHELL")
+                .ok()
+                .and_then(|shell| Shell::from_name(&shell))
+        })
+        .unwrap_or(if cfg!(windows) {
+            Shell::PowerShell
+        } else {
+            Shell::Zsh
+        })
+}
+
+/// Write the shell integration for `shell` into `out`.
+///
+/// Without `dump` this is the hook script for the user's rc file; with `dump` it is the
+/// generated alias functions, streamed so large stores never build the whole script in memory.
+/// `timing` makes the dump report on stderr how long it took to generate and to evaluate.
+///
+/// With `activate_in`, the current directory, scopes are resolved here instead of in the shell:
+/// the dump only defines the definitions that apply there, and the hook redefines them on
+/// every directory change. With `cached`, the hook loads the dump kept by
+/// [`write_cached_dump`].
+pub fn handle_init_command(
+    store: Option<&Store>,
+    shell: Shell,
+    dump: bool,
+    timing: bool,
+    activate_in: Option<&str>,
+    cached: bool,
+    out: &mut dyn Write,
+) -> std::result::Result<(), crate::error::AkaError> {
+    if !dump {
+        let hook = hook_script(shell)?;
+        let hook = match activate_in {
+            Some(_) => activate_on_cd_hook(shell, &hook)?,
+            None => hook,
+        };
+        let hook = match cached {
+            true => cached_hook(shell, &hook, &cached_dump_path(shell)?)?,
+            false => hook,
+        };
+        writeln!(out, "{}", hook)?;
+        return Ok(());
+    }
+    write_shell_dump(store, shell.codegen(), timing, activate_in, false, out)
+}
+
+/// The hook script for the user's rc file.
+fn hook_script(shell: Shell) -> std::result::Result<String, crate::error::AkaError> {
+    Ok(match shell {
+        Shell::Zsh => ZSH_HOOK_SCRIPT.to_string(),
+        Shell::Bash => BASH_HOOK_SCRIPT.to_string(),
+        Shell::Fish => FISH_HOOK_SCRIPT.to_string(),
+        Shell::PowerShell => POWERSHELL_HOOK_SCRIPT.to_string(),
+        Shell::Elvish => ELVISH_HOOK_SCRIPT.to_string(),
+        Shell::Nu => nu_hook_script(&crate::store::data_dir()?.join("init.nu")),
+    })
+}
+
+/// Refuse `--dispatch` for shells whose dump has no dispatcher.
+fn check_dispatch(shell: Shell) -> std::result::Result<(), crate::error::AkaError> {
+    match shell {
+        Shell::Zsh | Shell::Bash => Ok(()),
+        _ => Err(crate::error::AkaError::ConfigError(format!(
+            "--dispatch is not supported for {}",
+            shell.name()
+        ))),
+    }
+}
+
+/// Write the hook script loading the dump of [`write_dispatch_dump`] instead of the full one.
+pub fn write_dispatch_hook(
+    shell: Shell,
+    out: &mut dyn Write,
+) -> std::result::Result<(), crate::error::AkaError> {
+    check_dispatch(shell)?;
+    writeln!(out, "{}", hook_with_dump_flag(shell, "--dispatch")?)?;
+    Ok(())
+}
+
+/// The hook script for `shell`, with `flag` added to the dump it evaluates.
+fn hook_with_dump_flag(
+    shell: Shell,
+    flag: &str,
+) -> std::result::Result<String, crate::error::AkaError> {
+    let dump = format!("init --dump --shell {}", shell.name());
+    Ok(hook_script(shell)?.replace(&dump, &format!("{} {}", dump, flag)))
+}
+
+/// Refuse `--autoload` for shells other than zsh.
+fn check_autoload(shell: Shell) -> std::result::Result<(), crate::error::AkaError> {
+    match shell {
+        Shell::Zsh => Ok(()),
+        _ => Err(crate::error::AkaError::ConfigError(format!(
+            "--autoload is only supported for zsh, not {}",
+            shell.name()
+        ))),
+    }
+}
+
+/// Directory holding the function files of [`write_autoload_dump`], added to `fpath`.
+pub fn autoload_dir() -> std::result::Result<PathBuf, crate::error::AkaError> {
+    Ok(crate::store::data_dir()?.join("zsh-functions"))
+}
+
+/// Write the hook script loading the dump of [`write_autoload_dump`] instead of the full one.
+pub fn write_autoload_hook(
+    shell: Shell,
+    out: &mut dyn Write,
+) -> std::result::Result<(), crate::error::AkaError> {
+    check_autoload(shell)?;
+    writeln!(out, "{}", hook_with_dump_flag(shell, "--autoload")?)?;
+    Ok(())
+}
+
+/// Write the function of each alias to its own file in `dir` and the dump marking them
+/// `autoload`, so zsh reads a function only when it is first called.
+pub fn write_autoload_dump(
+    store: &Store,
+    shell: Shell,
+    dir: &Path,
+    out: &mut dyn Write,
+) -> std::result::Result<(), crate::error::AkaError> {
+    check_autoload(shell)?;
+    let shell_config = crate::config::load()
+        .map(|config| config.shell)
+        .unwrap_or_default();
+    let aliases = prepare_aliases(Some(store), &shell_config)?;
+    codegen::zsh::write_autoload_dump(out, dir, aliases)?;
+    Ok(())
+}
+
+/// Write the dump of stubs handing over to one dispatcher, which looks up the definition to
+/// run with `aka resolve --body` on each call; for stores with hundreds of aliases, where
+/// sourcing a function per alias gets slow.
+pub fn write_dispatch_dump(
+    store: &Store,
+    shell: Shell,
+    out: &mut dyn Write,
+) -> std::result::Result<(), crate::error::AkaError> {
+    check_dispatch(shell)?;
+    let shell_config = crate::config::load()
+        .map(|config| config.shell)
+        .unwrap_or_default();
+    let aliases = prepare_aliases(Some(store), &shell_config)?;
+    codegen::write_dispatch_dump(shell.codegen(), out, shell.name(), aliases)?;
+    Ok(())
+}
+
+/// Write the alias functions in `store` with `codegen`, as `aka init --dump` does; see
+/// [`handle_init_command`] for `timing` and `activate_in`. With `annotate`, each alias is
+/// preceded by comments listing its definitions in the order they are tried.
+pub fn write_shell_dump(
+    store: Option<&Store>,
+    codegen: &dyn codegen::ShellCodegen,
+    timing: bool,
+    activate_in: Option<&str>,
+    annotate: bool,
+    out: &mut dyn Write,
+) -> std::result::Result<(), crate::error::AkaError> {
+    let started = std::time::Instant::now();
+    let shell_config = crate::config::load()
+        .map(|config| config.shell)
+        .unwrap_or_default();
+    let aliases = prepare_aliases(store, &shell_config)?;
+
+    if timing {
+        codegen.timing_start(out)?;
+    }
+    let count = match activate_in {
+        Some(dir) => {
+            let active: Vec<_> = aliases
+                .into_iter()
+                .filter_map(|(alias, defs)| {
+                    let def = resolve_definition(&defs, dir)?.clone();
+                    Some((alias, def))
+                })
+                .collect();
+            let count = active.len();
+            codegen::write_resolved_dump(codegen, out, dir, active)?;
+            count
+        }
+        None => {
+            let count = aliases.len();
+            let options = codegen::DumpOptions {
+                plain_aliases: shell_config.plain_aliases,
+                annotate,
+            };
+            codegen::write_dump_with_options(codegen, out, aliases, options)?;
+            count
         }
+    };
+    let elapsed_us = started.elapsed().as_micros() as u64;
+    if timing {
+        codegen.timing_report(out, count, elapsed_us)?;
+    }
+    tracing::info!(aliases = count, elapsed_us, "generated shell functions");
+    Ok(())
+}
 
-        if in_single_quote {
-            if c == '\'' {
-                in_single_quote = false;
-            }
-            continue;
+/// Write the dump of [`write_shell_dump`] with `codegen` minified, so evaluating it costs less
+/// where the shell is slow, e.g. over SSH. Only the zsh, bash and sh dumps can be minified.
+pub fn write_minified_dump(
+    store: &Store,
+    codegen: &dyn codegen::ShellCodegen,
+    timing: bool,
+    out: &mut dyn Write,
+) -> std::result::Result<(), crate::error::AkaError> {
+    let mut dump = Vec::new();
+    write_shell_dump(Some(store), codegen, timing, None, false, &mut dump)?;
+    out.write_all(codegen::zsh_bash::minify(&String::from_utf8_lossy(&dump)).as_bytes())?;
+    Ok(())
+}
+
+/// Refuse `--minify` for shells whose dump is not sh.
+pub fn check_minify(shell: Shell) -> std::result::Result<(), crate::error::AkaError> {
+    match shell {
+        Shell::Zsh | Shell::Bash => Ok(()),
+        _ => Err(crate::error::AkaError::ConfigError(format!(
+            "--minify is not supported for {}",
+            shell.name()
+        ))),
+    }
+}
+
+/// The aliases in `store`, with the system aliases, the way the dump defines them: definitions
+/// for programs missing here are left out, fallback chains are settled, and references to
+/// other aliases and variables are expanded.
+pub fn prepare_aliases(
+    store: Option<&Store>,
+    shell_config: &ShellConfig,
+) -> std::result::Result<Vec<(String, Vec<AliasDefinition>)>, crate::error::AkaError> {
+    let mut aliases = Vec::new();
+    if let Some(store) = store {
+        let mut map = store.list()?;
+        crate::system::merge_system_aliases(&mut map);
+        aliases.extend(map);
+    }
+    // Definitions for programs missing on this machine are left out, as if they were not
+    // stored, and fallback chains settle on the first command installed here
+    for (_, defs) in &mut aliases {
+        defs.retain(|def| def.requires.as_deref().is_none_or(codegen::on_path));
+        for def in defs.iter_mut().filter(|def| !def.fallbacks.is_empty()) {
+            def.command = def.available_command(codegen::on_path).to_string();
         }
+    }
+    aliases.retain(|(_, defs)| !defs.is_empty());
+    if shell_config.write_history {
+        for def in aliases.iter_mut().flat_map(|(_, defs)| defs) {
+            def.write_history = true;
+        }
+    }
 
-        if in_double_quote {
-            if c == '"' {
-                in_double_quote = false;
-                continue; // Consumed quote
-            }
-            // Fallthrough to check for $ inside double quotes
-        } else {
-            // Not in any quote
-            if c == '\'' {
-                in_single_quote = true;
-                continue;
-            }
-            if c == '"' {
-                in_double_quote = true;
-                continue;
+    // Expand references to other aliases first, since the commands they bring in may use
+    // variables
+    if aliases
+        .iter()
+        .any(|(_, defs)| defs.iter().any(|d| references::uses_alias_refs(&d.command)))
+    {
+        let snapshot: HashMap<String, Vec<_>> = aliases.iter().cloned().collect();
+        for (alias, defs) in &mut aliases {
+            for def in defs {
+                let (command, warnings) = references::expand_alias_refs(alias, def, &snapshot);
+                for warning in warnings {
+                    eprintln!("aka: {}", warning);
+                }
+                def.command = command;
             }
         }
+    }
 
-        // Check for $ (valid in unquoted or double-quoted)
-        if c == '$' {
-            if let Some(&next) = chars.peek() {
-                // Check for $1, $2, ... $9, $0
-                if next.is_ascii_digit() {
-                    return true;
-                }
-                // Check for $@, $*, $#
-                if matches!(next, '@' | '*' | '#') {
-                    return true;
-                }
-                // Check for ${...}
-                if next == '{' {
-                    let mut lookahead = chars.clone();
-                    lookahead.next();
-
-                    let mut content_type = None;
-
-                    for inner in lookahead {
-                        if inner == '}' {
-                            if content_type == Some(true) {
-                                return true;
-                            }
-                            break;
-                        }
-                        if inner.is_ascii_digit() || matches!(inner, '@' | '*' | '#') {
-                            if content_type == Some(false) {
-                                // Mixed digits and letters? e.g. ${1foo}. Not positional.
-                                break;
-                            }
-                            content_type = Some(true);
-                        } else {
-                            // Any other char implies named variable
-                            content_type = Some(false);
-                        }
-                    }
+    // Only open the store for variables when some command uses them
+    let uses_vars = aliases
+        .iter()
+        .any(|(_, defs)| defs.iter().any(|d| var::uses_vars(&d.command)));
+    if let Some(store) = store
+        && uses_vars
+    {
+        let vars = store.vars()?;
+        for (alias, defs) in &mut aliases {
+            for def in defs {
+                let (command, missing) = var::expand_vars(&def.command, &vars);
+                for name in missing {
+                    eprintln!("aka: '{}' uses undefined variable '{}'", alias, name);
                 }
+                def.command = command;
             }
         }
     }
-    false
+    Ok(aliases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{AliasDefinition, AliasScope};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_dump_writes_into_any_writer() -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        store.add(
+            "hello".to_string(),
+            "echo world".to_string(),
+            AliasScope::Global,
+        )?;
+
+        let mut out = Vec::new();
+        handle_init_command(
+            Some(&store),
+            Shell::Bash,
+            true,
+            false,
+            None,
+            false,
+            &mut out,
+        )?;
+        let script = String::from_utf8(out).unwrap();
+
+        assert!(script.contains("hello() {\n"));
+        assert!(script.contains("; echo world \"$@\"\n"));
+        assert!(script.ends_with("unset _aka_aliases_was_on\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dump_leaves_out_missing_programs() -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        let mut cat = AliasDefinition::new("cat".to_string(), AliasScope::Global);
+        cat.requires = Some("sh".to_string());
+        store.add_definition("show".to_string(), cat)?;
+        let mut bat = AliasDefinition::new("bat".to_string(), AliasScope::Recursive("/w".into()));
+        bat.requires = Some("aka-missing-program".to_string());
+        store.add_definition("show".to_string(), bat.clone())?;
+        store.add_definition("b".to_string(), bat)?;
+
+        let mut out = Vec::new();
+        handle_init_command(
+            Some(&store),
+            Shell::Bash,
+            true,
+            false,
+            None,
+            false,
+            &mut out,
+        )?;
+        let script = String::from_utf8(out).unwrap();
+
+        assert!(script.contains("; cat \"$@\"\n"));
+        assert!(!script.contains("bat"));
+        assert!(script.contains("export AKA_MANAGED_ALIASES=\"show\"\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dump_timing_reports_on_stderr() -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        store.add("ll".to_string(), "ls -la".to_string(), AliasScope::Global)?;
+
+        let mut out = Vec::new();
+        handle_init_command(Some(&store), Shell::Bash, true, true, None, false, &mut out)?;
+        let script = String::from_utf8(out).unwrap();
+
+        let Ok(output) = std::process::Command::new("bash")
+            .arg("-c")
+            .arg(format!("{}type ll >/dev/null && echo defined", script))
+            .output()
+        else {
+            return Ok(());
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "defined\n");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.starts_with("aka: 1 aliases, generated in "),
+            "{}",
+            stderr
+        );
+        assert!(stderr.contains(", eval took "));
+        Ok(())
+    }
+
+    #[test]
+    fn test_activate_on_cd_defines_only_active_definitions()
+    -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        store.add(
+            "t".to_string(),
+            "echo cargo".to_string(),
+            AliasScope::Global,
+        )?;
+        store.add(
+            "t".to_string(),
+            "echo npm".to_string(),
+            AliasScope::Recursive("/web".to_string()),
+        )?;
+        store.add(
+            "only".to_string(),
+            "echo only".to_string(),
+            AliasScope::Exact("/other".to_string()),
+        )?;
+
+        let mut out = Vec::new();
+        handle_init_command(
+            Some(&store),
+            Shell::Bash,
+            true,
+            false,
+            Some("/web/app"),
+            false,
+            &mut out,
+        )?;
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("\n# Resolved for /web/app\n"));
+        assert!(!script.contains("current_dir\" =="));
+        assert!(script.contains("export AKA_MANAGED_ALIASES=\"t\"\n"));
+
+        let Ok(output) = std::process::Command::new("bash")
+            .arg("-c")
+            .arg(format!("{}t", script))
+            .output()
+        else {
+            return Ok(());
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "npm\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_activate_on_cd_hook() -> std::result::Result<(), crate::error::AkaError> {
+        let hook = activate_on_cd_hook(Shell::Zsh, ZSH_HOOK_SCRIPT)?;
+        assert!(!hook.contains("init --dump --shell zsh)"));
+        assert!(hook.ends_with("add-zsh-hook chpwd _aka_activate\n"));
+        assert!(activate_on_cd_hook(Shell::Nu, NU_HOOK_SCRIPT).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cached_hook_sources_dump() -> std::result::Result<(), crate::error::AkaError> {
+        let hook = cached_hook(
+            Shell::Bash,
+            BASH_HOOK_SCRIPT,
+            Path::new("/my data/dump.bash"),
+        )?;
+        assert!(!hook.contains("eval \"$(command aka init --dump --shell bash)\""));
+        assert!(hook.contains("        source '/my data/dump.bash'\n"));
+        assert!(hook.contains("command aka init --dump --shell bash --cached"));
+        assert!(cached_hook(Shell::Fish, FISH_HOOK_SCRIPT, Path::new("/data/dump.fish")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cached_dump_is_rewritten_only_on_change()
+    -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        let path = dir.path().join("dump.bash");
+
+        assert!(write_cached_dump(&store, Shell::Bash, &path, false)?);
+        assert!(!write_cached_dump(&store, Shell::Bash, &path, false)?);
+        assert!(write_cached_dump(&store, Shell::Bash, &path, true)?);
+        store.add("g".to_string(), "git".to_string(), AliasScope::Global)?;
+        assert!(write_cached_dump(&store, Shell::Bash, &path, false)?);
+
+        let dump = std::fs::read_to_string(&path)?;
+        assert!(dump.starts_with(&format!("{}\n", dump_stamp(&store)?)));
+        assert!(dump.contains("export AKA_MANAGED_ALIASES=\"g\"\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dump_stamp_follows_shell_config_and_system_file()
+    -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let store = Store::load(&dir.path().join("aka.redb"))?;
+        let system_file = dir.path().join("aliases.toml");
+        let plain = ShellConfig::default();
+        let stamp = dump_stamp_with(&store, &plain, &system_file)?;
+        assert_eq!(stamp, dump_stamp_with(&store, &plain, &system_file)?);
+
+        let history = ShellConfig {
+            write_history: true,
+            ..ShellConfig::default()
+        };
+        assert_ne!(stamp, dump_stamp_with(&store, &history, &system_file)?);
+
+        std::fs::write(&system_file, "[aliases]\nll = \"ls -la\"\n")?;
+        assert_ne!(stamp, dump_stamp_with(&store, &plain, &system_file)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shell_from_name() {
+        assert_eq!(Shell::from_name("-zsh"), Some(Shell::Zsh));
+        assert_eq!(Shell::from_name("/usr/local/bin/fish\n"), Some(Shell::Fish));
+        assert_eq!(Shell::from_name("nu"), Some(Shell::Nu));
+        assert_eq!(Shell::from_name("/usr/bin/elvish"), Some(Shell::Elvish));
+        assert_eq!(
+            Shell::from_name(r"C:\Program Files\PowerShell\7\pwsh.exe"),
+            Some(Shell::PowerShell)
+        );
+        assert_eq!(Shell::from_name("sh"), None);
+    }
+
+    #[test]
+    fn test_nu_hook_sources_saved_dump() {
+        let script = nu_hook_script(Path::new("/data/it's/init.nu"));
+        assert!(script.contains(concat!(
+            "    code: r##'$env.AKA_LAST_CMD = ''; mkdir r#'/data/it's'#; ",
+            "^aka init --dump --shell nu | save -f r#'/data/it's/init.nu'#; ",
+            "source r#'/data/it's/init.nu'#'##\n"
+        )));
+    }
 }
@@ -1,11 +1,82 @@
+use crate::codegen::Shell;
 use crate::error::AkaError;
 use std::fs::OpenOptions;
 use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 
-pub fn handle_install_command() -> Result<String, AkaError> {
-    let home_dir = dirs::home_dir()
-        .ok_or_else(|| AkaError::ConfigError("Could not find home directory".to_string()))?;
-    let zshrc_path = home_dir.join(".zshrc");
+/// The rc file `aka install` writes to for `shell` in `home`: for bash, `.bash_profile` when
+/// there is no `.bashrc` but it exists, as on macOS where login shells read only that.
+pub fn rc_file(shell: Shell, home: &Path) -> Option<PathBuf> {
+    match shell {
+        Shell::Zsh => Some(home.join(".zshrc")),
+        Shell::Bash => {
+            let bashrc = home.join(".bashrc");
+            let profile = home.join(".bash_profile");
+            Some(match !bashrc.exists() && profile.exists() {
+                true => profile,
+                false => bashrc,
+            })
+        }
+        Shell::Fish => Some(home.join(".config").join("fish").join("config.fish")),
+        Shell::Nu | Shell::PowerShell | Shell::Elvish => None,
+    }
+}
+
+/// The line in the rc file that loads aka into `shell`.
+fn init_line(shell: Shell) -> Option<&'static str> {
+    match shell {
+        Shell::Zsh => Some(r#"eval "$(aka init)""#),
+        Shell::Bash => Some(r#"eval "$(aka init --shell bash)""#),
+        Shell::Fish => Some("aka init --shell fish | source"),
+        Shell::Nu | Shell::PowerShell | Shell::Elvish => None,
+    }
+}
+
+/// What `aka install` appends to an rc file for `shell`.
+fn snippet(init_str: &str) -> String {
+    format!("\n\n# aka alias manager\n{}\n", init_str)
+}
+
+fn unsupported(shell: Shell) -> AkaError {
+    AkaError::ConfigError(format!(
+        "aka install does not support {}; add the output of `aka init --shell {}` to its profile",
+        shell.name(),
+        shell.name()
+    ))
+}
+
+/// File listing the rc files `aka install` has written to, one per line, for `aka uninstall`.
+pub fn installs_path() -> Result<PathBuf, AkaError> {
+    Ok(crate::store::data_dir()?.join("installed"))
+}
+
+/// Install into `path`, or the usual rc file for `shell` when it is not given, and list it in
+/// `record` (see [`installs_path`]).
+pub fn handle_install_command(
+    shell: Shell,
+    path: Option<PathBuf>,
+    record: &Path,
+) -> Result<String, AkaError> {
+    let rc_path = match path {
+        Some(path) => std::path::absolute(path)?,
+        None => {
+            let home_dir = dirs::home_dir().ok_or_else(|| {
+                AkaError::ConfigError("Could not find home directory".to_string())
+            })?;
+            rc_file(shell, &home_dir).ok_or_else(|| unsupported(shell))?
+        }
+    };
+    let message = install_into(shell, &rc_path)?;
+    record_install(record, &rc_path)?;
+    Ok(message)
+}
+
+/// Add the line loading aka into `shell` to `rc_path`, unless it is there already.
+fn install_into(shell: Shell, rc_path: &Path) -> Result<String, AkaError> {
+    let init_str = init_line(shell).ok_or_else(|| unsupported(shell))?;
+    if let Some(parent) = rc_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
 
     // Ensure file exists (create if not) or read it
     // We open with read/write/create to ensure existence and check content
@@ -15,22 +86,89 @@ pub fn handle_install_command() -> Result<String, AkaError> {
             .read(true)
             .write(true)
             .create(true)
-            .open(&zshrc_path)?;
+            .truncate(false)
+            .open(rc_path)?;
         file.read_to_string(&mut content)?;
     }
 
-    let init_str = r#"eval "$(aka init)""#;
-
     if content.contains(init_str) {
-        return Ok("Already installed in .zshrc".to_string());
+        let name = rc_path.file_name().unwrap_or_default().to_string_lossy();
+        return Ok(format!("Already installed in {}", name));
+    }
+
+    let mut file = OpenOptions::new().append(true).open(rc_path)?;
+    file.write_all(snippet(init_str).as_bytes())?;
+
+    Ok(format!("Installed to {}", rc_path.to_string_lossy()))
+}
+
+/// Note `rc_path` in the list at `record`, unless it is listed already.
+fn record_install(record: &Path, rc_path: &Path) -> Result<(), AkaError> {
+    let installed = read_installs(record);
+    if installed.iter().any(|path| path == rc_path) {
+        return Ok(());
+    }
+    if let Some(parent) = record.parent() {
+        std::fs::create_dir_all(parent)?;
     }
+    let mut file = OpenOptions::new().create(true).append(true).open(record)?;
+    writeln!(file, "{}", rc_path.display())?;
+    Ok(())
+}
 
-    let append_content = format!("\n\n# aka alias manager\n{}\n", init_str);
+fn read_installs(record: &Path) -> Vec<PathBuf> {
+    std::fs::read_to_string(record)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
 
-    let mut file = OpenOptions::new().append(true).open(&zshrc_path)?;
-    file.write_all(append_content.as_bytes())?;
+/// Take what `aka install` added out of the rc files listed in `record` and the usual one for
+/// `shell`.
+pub fn handle_uninstall_command(shell: Shell, record: &Path) -> Result<String, AkaError> {
+    let mut paths = read_installs(record);
+    if let Some(path) = dirs::home_dir().and_then(|home| rc_file(shell, &home))
+        && !paths.contains(&path)
+    {
+        paths.push(path);
+    }
+    let removed = uninstall_from(&paths)?;
+    if record.exists() {
+        std::fs::remove_file(record)?;
+    }
+    if removed.is_empty() {
+        return Ok("Not installed in any rc file".to_string());
+    }
+    Ok(removed
+        .iter()
+        .map(|path| format!("Removed from {}", path.display()))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
 
-    Ok(format!("Installed to {}", zshrc_path.to_string_lossy()))
+/// Remove the lines `aka install` appended from each of `paths`, returning the files changed.
+///
+/// Lines loading aka that the user wrote without the `# aka alias manager` comment are kept.
+fn uninstall_from(paths: &[PathBuf]) -> Result<Vec<PathBuf>, AkaError> {
+    let mut removed = Vec::new();
+    for path in paths {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        let stripped = [Shell::Zsh, Shell::Bash, Shell::Fish]
+            .into_iter()
+            .filter_map(init_line)
+            .fold(content.clone(), |content, init_str| {
+                content.replace(&snippet(init_str), "")
+            });
+        if stripped != content {
+            std::fs::write(path, stripped)?;
+            removed.push(path.clone());
+        }
+    }
+    Ok(removed)
 }
 
 #[cfg(test)]
@@ -42,13 +180,14 @@ mod tests {
     fn test_install_command() {
         let dir = tempdir().unwrap();
         let home_path = dir.path().to_path_buf();
+        let record = home_path.join("installed");
 
         unsafe {
             std::env::set_var("HOME", &home_path);
         }
 
         // 1. Install to empty
-        let res = handle_install_command();
+        let res = handle_install_command(Shell::Zsh, None, &record);
         assert!(res.is_ok());
         let msg = res.unwrap();
         assert!(msg.contains("Installed to"));
@@ -57,9 +196,10 @@ mod tests {
         assert!(zshrc.exists());
         let content = std::fs::read_to_string(&zshrc).unwrap();
         assert!(content.contains("eval \"$(aka init)\""));
+        assert_eq!(read_installs(&record), std::slice::from_ref(&zshrc));
 
         // 2. Install again (idempotency)
-        let res = handle_install_command();
+        let res = handle_install_command(Shell::Zsh, None, &record);
         assert!(res.is_ok());
         let msg = res.unwrap();
         assert_eq!(msg, "Already installed in .zshrc");
@@ -69,4 +209,50 @@ mod tests {
         let matches = content_again.matches("eval \"$(aka init)\"").count();
         assert_eq!(matches, 1);
     }
+
+    #[test]
+    fn test_install_for_bash_and_fish() {
+        let home = tempdir().unwrap();
+        std::fs::write(home.path().join(".bash_profile"), "export A=1\n").unwrap();
+        let bash_rc = rc_file(Shell::Bash, home.path()).unwrap();
+        install_into(Shell::Bash, &bash_rc).unwrap();
+        let profile = std::fs::read_to_string(home.path().join(".bash_profile")).unwrap();
+        assert!(profile.ends_with("# aka alias manager\neval \"$(aka init --shell bash)\"\n"));
+        assert!(!home.path().join(".bashrc").exists());
+
+        let config = rc_file(Shell::Fish, home.path()).unwrap();
+        let msg = install_into(Shell::Fish, &config).unwrap();
+        assert_eq!(config, home.path().join(".config/fish/config.fish"));
+        assert_eq!(msg, format!("Installed to {}", config.display()));
+        assert!(
+            std::fs::read_to_string(&config)
+                .unwrap()
+                .contains("aka init --shell fish | source")
+        );
+        assert_eq!(
+            install_into(Shell::Fish, &config).unwrap(),
+            "Already installed in config.fish"
+        );
+        assert!(rc_file(Shell::Nu, home.path()).is_none());
+        assert!(install_into(Shell::Nu, &home.path().join("config.nu")).is_err());
+    }
+
+    #[test]
+    fn test_install_to_a_custom_file_and_uninstall() {
+        let dir = tempdir().unwrap();
+        let local = dir.path().join("dotfiles").join(".zshrc.local");
+        let record = dir.path().join("installed");
+        install_into(Shell::Zsh, &local).unwrap();
+        record_install(&record, &local).unwrap();
+        record_install(&record, &local).unwrap();
+        assert_eq!(read_installs(&record), std::slice::from_ref(&local));
+
+        let mut content = std::fs::read_to_string(&local).unwrap();
+        content.push_str("export KEEP=1\n");
+        std::fs::write(&local, &content).unwrap();
+        let removed = uninstall_from(&read_installs(&record)).unwrap();
+        assert_eq!(removed, std::slice::from_ref(&local));
+        assert_eq!(std::fs::read_to_string(&local).unwrap(), "export KEEP=1\n");
+        assert!(uninstall_from(&[local]).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,366 @@
+use crate::Store;
+use crate::commands::list::scope_label;
+use crate::config::{DeprecatedPattern, LintConfig};
+use crate::references::{program_name, segments};
+use crate::store::AliasDefinition;
+use std::collections::HashSet;
+
+pub use crate::codegen::on_path;
+
+/// Words that never need a binary on PATH.
+const SHELL_BUILTINS: &[&str] = &[
+    ".", ":", "[", "[[", "alias", "builtin", "case", "cd", "command", "echo", "eval", "exec",
+    "exit", "export", "false", "for", "function", "if", "local", "popd", "printf", "pushd", "read",
+    "return", "set", "shift", "source", "test", "time", "true", "type", "unset", "until", "wait",
+    "while",
+];
+
+const MAX_FIX_ROUNDS: usize = 8;
+
+/// One problem found in a stored definition, with the corrected command when it can be fixed.
+struct Finding {
+    message: String,
+    fixed: Option<String>,
+}
+
+/// Check the system alias file and every stored definition, and optionally rewrite the ones
+/// that can be fixed.
+///
+/// `on_path` decides whether a command name resolves to an executable; it is injected so the
+/// check does not depend on the machine running the tests.
+pub fn handle_lint_command(
+    store: &mut Store,
+    config: &LintConfig,
+    fix: bool,
+    on_path: impl Fn(&str) -> bool,
+) -> std::result::Result<String, crate::error::AkaError> {
+    crate::system::check_system_file()?;
+    let aliases = store.list()?;
+    let known: HashSet<&str> = aliases.keys().map(String::as_str).collect();
+
+    let mut names: Vec<&String> = aliases.keys().collect();
+    names.sort();
+
+    let mut lines = Vec::new();
+    let mut fixes = Vec::new();
+    let (mut total, mut fixable) = (0, 0);
+
+    for alias in names {
+        for def in &aliases[alias] {
+            let mut command = def.command.clone();
+            for finding in lint_command(&command, config, &known, &on_path) {
+                total += 1;
+                let mut line = format!("{} {}: {}", alias, scope_label(def), finding.message);
+                if finding.fixed.is_some() {
+                    fixable += 1;
+                    line.push_str(if fix { " (fixed)" } else { " (fixable)" });
+                }
+                lines.push(line);
+            }
+
+            if !fix {
+                continue;
+            }
+            // Apply the fixes one after another so they compose; the cap guards against a
+            // replacement that reintroduces its own pattern
+            for _ in 0..MAX_FIX_ROUNDS {
+                let Some(fixed) = lint_command(&command, config, &known, &on_path)
+                    .into_iter()
+                    .find_map(|f| f.fixed)
+                else {
+                    break;
+                };
+                command = fixed;
+            }
+            if command != def.command {
+                fixes.push((alias.clone(), command, def.clone()));
+            }
+        }
+    }
+
+    if total == 0 {
+        return Ok("No issues found".to_string());
+    }
+
+    if fix {
+        for (alias, command, def) in fixes {
+            store.add_definition(alias, AliasDefinition { command, ..def })?;
+        }
+        lines.push(format!("{} issue(s) found, {} fixed", total, fixable));
+    } else {
+        lines.push(format!(
+            "{} issue(s) found, {} fixable with --fix",
+            total, fixable
+        ));
+    }
+    Ok(lines.join("\n"))
+}
+
+fn lint_command(
+    command: &str,
+    config: &LintConfig,
+    known_aliases: &HashSet<&str>,
+    on_path: &impl Fn(&str) -> bool,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if command != command.trim_end() {
+        findings.push(Finding {
+            message: "trailing whitespace".to_string(),
+            fixed: Some(command.trim_end().to_string()),
+        });
+    }
+
+    for segment in segments(command) {
+        let Some(program) = program_name(&segment) else {
+            continue;
+        };
+        if SHELL_BUILTINS.contains(&program)
+            || known_aliases.contains(program)
+            || program.starts_with('$')
+            || program.contains(['"', '\'', '(', '{'])
+        {
+            continue;
+        }
+        let found = if program.contains('/') {
+            std::path::Path::new(program).exists()
+        } else {
+            on_path(program)
+        };
+        if !found {
+            findings.push(Finding {
+                message: format!("'{}' not found on PATH", program),
+                fixed: None,
+            });
+        }
+    }
+
+    let unquoted = unquoted_parameters(command);
+    if !unquoted.is_empty() {
+        findings.push(Finding {
+            message: format!(
+                "unquoted {} breaks on arguments with spaces",
+                unquoted.join(", ")
+            ),
+            fixed: Some(quote_parameters(command)),
+        });
+    }
+
+    for DeprecatedPattern {
+        pattern,
+        replacement,
+    } in &config.deprecated
+    {
+        if find_word(command, pattern).is_none() {
+            continue;
+        }
+        let message = match replacement {
+            Some(replacement) => format!("'{}' is deprecated, use '{}'", pattern, replacement),
+            None => format!("'{}' is deprecated", pattern),
+        };
+        findings.push(Finding {
+            message,
+            fixed: replacement
+                .as_ref()
+                .map(|replacement| replace_words(command, pattern, replacement)),
+        });
+    }
+
+    findings
+}
+
+/// Positional parameters (`$1`, `${2}`, `$@`, `$*`) that appear outside double quotes.
+fn unquoted_parameters(command: &str) -> Vec<String> {
+    parameter_spans(command)
+        .into_iter()
+        .map(|(start, end)| command[start..end].to_string())
+        .collect()
+}
+
+fn quote_parameters(command: &str) -> String {
+    let mut output = String::with_capacity(command.len() + 4);
+    let mut last = 0;
+    for (start, end) in parameter_spans(command) {
+        output.push_str(&command[last..start]);
+        output.push('"');
+        output.push_str(&command[start..end]);
+        output.push('"');
+        last = end;
+    }
+    output.push_str(&command[last..]);
+    output
+}
+
+/// Byte ranges of unquoted positional parameters.
+fn parameter_spans(command: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut chars = command.char_indices().peekable();
+    let mut in_single_quote = false;
+    let mut in_double_quote = false;
+    let mut escaped = false;
+
+    while let Some((i, c)) = chars.next() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if !in_single_quote => escaped = true,
+            '\'' if !in_double_quote => in_single_quote = !in_single_quote,
+            '"' if !in_single_quote => in_double_quote = !in_double_quote,
+            '$' if !in_single_quote && !in_double_quote => {
+                let end = match chars.peek() {
+                    Some(&(j, next)) if next.is_ascii_digit() || matches!(next, '@' | '*') => j + 1,
+                    Some(&(j, '{')) => {
+                        let rest = &command[j + 1..];
+                        let Some(close) = rest.find('}') else {
+                            continue;
+                        };
+                        let inner = &rest[..close];
+                        if inner.is_empty()
+                            || !inner
+                                .chars()
+                                .all(|c| c.is_ascii_digit() || c == '@' || c == '*')
+                        {
+                            continue;
+                        }
+                        j + 1 + close + 1
+                    }
+                    _ => continue,
+                };
+                while chars.peek().is_some_and(|&(j, _)| j < end) {
+                    chars.next();
+                }
+                spans.push((i, end));
+            }
+            _ => {}
+        }
+    }
+    spans
+}
+
+fn is_boundary(c: Option<char>) -> bool {
+    c.is_none_or(|c| c.is_whitespace() || matches!(c, ';' | '|' | '&' | '(' | ')'))
+}
+
+/// Byte offset of `pattern` in `command` where it stands as whole words.
+fn find_word(command: &str, pattern: &str) -> Option<usize> {
+    command.match_indices(pattern).map(|(i, _)| i).find(|&i| {
+        is_boundary(command[..i].chars().next_back())
+            && is_boundary(command[i + pattern.len()..].chars().next())
+    })
+}
+
+fn replace_words(command: &str, pattern: &str, replacement: &str) -> String {
+    let mut output = command.to_string();
+    while let Some(i) = find_word(&output, pattern) {
+        output.replace_range(i..i + pattern.len(), replacement);
+        if replacement.contains(pattern) {
+            break;
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+    use tempfile::tempdir;
+
+    fn fake_path(program: &str) -> bool {
+        matches!(program, "git" | "grep" | "ls" | "docker")
+    }
+
+    #[test]
+    fn test_lint_reports_issues() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add(
+                "g".to_string(),
+                "git status".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+        store
+            .add(
+                "gg".to_string(),
+                "g && gti log ".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+        store
+            .add(
+                "find".to_string(),
+                "egrep -rn $1 \"$2\" | ls".to_string(),
+                AliasScope::Exact("/work".to_string()),
+            )
+            .unwrap();
+
+        let report =
+            handle_lint_command(&mut store, &LintConfig::default(), false, fake_path).unwrap();
+        assert_eq!(
+            report,
+            "find (Exact: /work): 'egrep' not found on PATH\n\
+             find (Exact: /work): unquoted $1 breaks on arguments with spaces (fixable)\n\
+             find (Exact: /work): 'egrep' is deprecated, use 'grep -E' (fixable)\n\
+             gg (Global): trailing whitespace (fixable)\n\
+             gg (Global): 'gti' not found on PATH\n\
+             5 issue(s) found, 3 fixable with --fix"
+        );
+    }
+
+    #[test]
+    fn test_lint_fix_rewrites_definitions() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add(
+                "find".to_string(),
+                "egrep -rn $1 ${2} ".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+
+        handle_lint_command(&mut store, &LintConfig::default(), true, fake_path).unwrap();
+        let aliases = store.list().unwrap();
+        assert_eq!(aliases["find"][0].command, "grep -E -rn \"$1\" \"${2}\"");
+
+        let report =
+            handle_lint_command(&mut store, &LintConfig::default(), false, fake_path).unwrap();
+        assert_eq!(report, "No issues found");
+    }
+
+    #[test]
+    fn test_lint_fix_keeps_definition_attributes() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        let mut def = AliasDefinition::new("egrep -rn $1".to_string(), AliasScope::Global);
+        def.time = true;
+        def.kube = Some(crate::store::KubeGuard::Deny("prod-*".to_string()));
+        store.add_definition("find".to_string(), def).unwrap();
+
+        handle_lint_command(&mut store, &LintConfig::default(), true, fake_path).unwrap();
+        let fixed = &store.list().unwrap()["find"][0];
+        assert_eq!(fixed.command, "grep -E -rn \"$1\"");
+        assert!(fixed.time);
+        assert_eq!(
+            fixed.kube,
+            Some(crate::store::KubeGuard::Deny("prod-*".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_quote_parameters() {
+        assert_eq!(
+            quote_parameters("echo $1 '$2' \"$3\" $@"),
+            "echo \"$1\" '$2' \"$3\" \"$@\""
+        );
+        assert_eq!(
+            quote_parameters("echo ${10} ${HOME}"),
+            "echo \"${10}\" ${HOME}"
+        );
+        assert!(unquoted_parameters("printf '%s' \"$@\"").is_empty());
+    }
+}
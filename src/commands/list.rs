@@ -1,11 +1,15 @@
 use std::env;
+use std::ops::Range;
+use std::path::Path;
 
 use crate::Result;
 use crate::Store;
-use crate::store::AliasScope;
+use crate::references;
+use crate::store::{AliasDefinition, AliasScope, KubeGuard, ScopeSpec, sort_by_precedence};
 use owo_colors::{OwoColorize, Stream};
+use unicode_width::UnicodeWidthChar;
 
-/// ANSIエスケープコード付き文字列の表示幅を計算
+/// ANSIエスケープコード付き文字列の表示幅を計算（全角文字は2桁として数える）
 fn visual_width(s: &str) -> usize {
     let mut width = 0;
     let mut chars = s.chars();
@@ -19,7 +23,7 @@ fn visual_width(s: &str) -> usize {
                 }
             }
         } else {
-            width += 1;
+            width += ch.width().unwrap_or(0);
         }
     }
     width
@@ -35,8 +39,187 @@ fn pad_to_width(s: &str, target_width: usize) -> String {
     }
 }
 
-pub fn handle_list_command(store: &Store, all: bool) -> Result<String> {
-    let aliases = store.list()?;
+/// `@N` と `@{var:名前}` のプレースホルダーの位置（`\@` でエスケープされたものは除く）
+fn placeholder_spans(command: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut chars = command.char_indices().peekable();
+    let mut escaped = false;
+
+    while let Some((start, c)) = chars.next() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '@' if command[start + 1..].starts_with("{var:") => {
+                if let Some(len) = command[start..].find('}') {
+                    spans.push(start..start + len + 1);
+                    while chars.next_if(|&(i, _)| i <= start + len).is_some() {}
+                }
+            }
+            '@' => {
+                let mut end = start + 1;
+                while let Some(&(i, d)) = chars.peek()
+                    && d.is_ascii_digit()
+                {
+                    end = i + 1;
+                    chars.next();
+                }
+                if end > start + 1 {
+                    spans.push(start..end);
+                }
+            }
+            _ => {}
+        }
+    }
+    spans
+}
+
+/// コマンドが受け取る引数の数（最大の `@N`）
+pub(crate) fn arg_count(command: &str) -> usize {
+    placeholder_spans(command)
+        .into_iter()
+        .filter_map(|span| command[span.start + 1..span.end].parse().ok())
+        .max()
+        .unwrap_or(0)
+}
+
+/// スコープ欄に引数の数を追記
+fn push_arg_count(scope_str: &mut String, command: &str) {
+    match arg_count(command) {
+        0 => {}
+        1 => scope_str.push_str(" (1 arg)"),
+        n => scope_str.push_str(&format!(" ({} args)", n)),
+    }
+}
+
+/// プレースホルダーを強調表示したコマンド
+fn highlight_placeholders(command: &str) -> String {
+    let mut output = String::new();
+    let mut last = 0;
+    for span in placeholder_spans(command) {
+        let plain = &command[last..span.start];
+        let placeholder = &command[span.clone()];
+        output.push_str(&format!(
+            "{}{}",
+            plain.if_supports_color(Stream::Stdout, |text| text.white()),
+            placeholder.if_supports_color(Stream::Stdout, |text| text.yellow())
+        ));
+        last = span.end;
+    }
+    let rest = &command[last..];
+    output.push_str(&format!(
+        "{}",
+        rest.if_supports_color(Stream::Stdout, |text| text.white())
+    ));
+    output
+}
+
+/// `--template` の構成要素
+#[derive(Debug, PartialEq)]
+enum TemplatePart {
+    Text(String),
+    Alias,
+    Command,
+    Scope,
+}
+
+/// `{alias}`・`{command}`・`{scope}` を含むテンプレートを解析する。
+/// `\t`・`\n`・`\\` はエスケープとして、`{{`・`}}` は波括弧そのものとして扱う
+fn parse_template(template: &str) -> Result<Vec<TemplatePart>> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('t') => text.push('\t'),
+                Some('n') => text.push('\n'),
+                Some(other) => {
+                    if other != '\\' {
+                        text.push('\\');
+                    }
+                    text.push(other);
+                }
+                None => text.push('\\'),
+            },
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(c);
+                }
+                let part = match name.as_str() {
+                    "alias" if closed => TemplatePart::Alias,
+                    "command" if closed => TemplatePart::Command,
+                    "scope" if closed => TemplatePart::Scope,
+                    _ => {
+                        return Err(crate::error::AkaError::ConfigError(format!(
+                            "Unknown field '{{{}}}' in list template (use {{alias}}, {{command}} or {{scope}})",
+                            name
+                        ))
+                        .into());
+                    }
+                };
+                if !text.is_empty() {
+                    parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(part);
+            }
+            _ => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+    Ok(parts)
+}
+
+/// (エイリアス, コマンド, スコープ) の行をテンプレートに当てはめて出力（色付け・桁揃えなし）
+fn render_template(parts: &[TemplatePart], items: Vec<(String, String, String)>) -> String {
+    items
+        .iter()
+        .map(|(alias, command, scope)| {
+            parts
+                .iter()
+                .map(|part| match part {
+                    TemplatePart::Text(text) => text.as_str(),
+                    TemplatePart::Alias => alias.as_str(),
+                    TemplatePart::Command => command.as_str(),
+                    TemplatePart::Scope => scope.as_str(),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// エイリアスを一覧表示する。`template` を指定すると各行をそのテンプレートで出力する
+///
+/// `scope` を指定するとそのスコープの定義だけを、現在のディレクトリに関係なく表示する
+pub fn handle_list_command(
+    store: &Store,
+    all: bool,
+    long: bool,
+    template: Option<&str>,
+    scope: Option<&ScopeSpec>,
+) -> Result<String> {
+    let template = template.map(parse_template).transpose()?;
+    let mut aliases = store.list()?;
+    crate::system::merge_system_aliases(&mut aliases);
     if aliases.is_empty() {
         return Ok("No aliases found".to_string());
     }
@@ -45,78 +228,300 @@ pub fn handle_list_command(store: &Store, all: bool) -> Result<String> {
         .unwrap_or_default()
         .to_string_lossy()
         .to_string();
-    let use_colors = env::var("NO_COLOR").is_err();
 
-    // 第1パス: データ収集と最大幅計算
-    let mut items = Vec::new();
-    let mut max_alias_width = 0;
-    let mut max_cmd_width = 0;
+    // 表ではスコープのパスを短く表示（--long とテンプレートでは完全なパス）
+    let home = dirs::home_dir().map(|home| home.to_string_lossy().to_string());
+    let shorten = !long && template.is_none();
 
-    for (alias, defs) in aliases {
+    // --long では他のエイリアスとの呼び出し関係も表示
+    let callers = if long {
+        references::callers(&aliases)
+    } else {
+        Default::default()
+    };
+
+    // データ収集
+    let mut items = Vec::new();
+    for (alias, defs) in &aliases {
         for def in defs {
             // スコープフィルタリング
-            if !all {
-                let is_relevant = match &def.scope {
-                    AliasScope::Global => true,
-                    AliasScope::Recursive(p) => current_dir.starts_with(p),
-                    AliasScope::Exact(p) => current_dir == *p,
-                };
-                if !is_relevant {
-                    continue;
-                }
+            match scope {
+                Some(spec) if !spec.matches(&def.scope) => continue,
+                None if !all && !def.scope.matches(Path::new(&current_dir)) => continue,
+                _ => {}
             }
 
-            let scope_str = match def.scope {
-                AliasScope::Global => "(Global)".to_string(),
-                AliasScope::Recursive(p) => format!("(Recursive: {})", p),
-                AliasScope::Exact(p) => format!("(Exact: {})", p),
+            let mut scope_str = if shorten {
+                short_scope_label(def, home.as_deref())
+            } else {
+                scope_label(def)
             };
+            push_arg_count(&mut scope_str, &def.command);
+            if long {
+                match &def.kube {
+                    Some(KubeGuard::Only(p)) => scope_str.push_str(&format!(" (kube: {})", p)),
+                    Some(KubeGuard::Deny(p)) => scope_str.push_str(&format!(" (kube: not {})", p)),
+                    None => {}
+                }
+                if !def.env.is_empty() {
+                    let names: Vec<&str> = def.env.keys().map(String::as_str).collect();
+                    scope_str.push_str(&format!(" (env: {})", names.join(", ")));
+                }
+                if def.time {
+                    scope_str.push_str(" (timed)");
+                }
+                if def.notify {
+                    scope_str.push_str(" (notify)");
+                }
+                if let Some(path) = &def.log_output {
+                    scope_str.push_str(&format!(" (log: {})", path));
+                }
+                if let Some(program) = &def.requires {
+                    scope_str.push_str(&format!(" (if installed: {})", program));
+                }
+                if !def.fallbacks.is_empty() {
+                    scope_str.push_str(&format!(" (or: {})", def.fallbacks.join(" | ")));
+                }
+                if def.write_history {
+                    scope_str.push_str(" (history)");
+                }
+                if let Some(origin) = &def.origin {
+                    scope_str.push_str(&format!(" (generated: {})", origin));
+                }
+                let calls = references::called_aliases(&def.command, &aliases);
+                if !calls.is_empty() {
+                    scope_str.push_str(&format!(" (calls: {})", calls.join(", ")));
+                }
+                if let Some(by) = callers.get(alias) {
+                    let by: Vec<&str> = by.iter().map(String::as_str).collect();
+                    scope_str.push_str(&format!(" (called by: {})", by.join(", ")));
+                }
+            }
+            items.push((alias.clone(), def.command.clone(), scope_str));
+        }
+    }
+
+    if items.is_empty() {
+        return Ok("No aliases found".to_string());
+    }
 
-            // 幅計算（色なしベース）
-            max_alias_width = max_alias_width.max(alias.len());
-            max_cmd_width = max_cmd_width.max(def.command.len());
+    Ok(match template {
+        Some(parts) => render_template(&parts, items),
+        None => render_rows(items),
+    })
+}
+
+/// 現在のディレクトリで有効な定義のみを表示し、複数ある場合は採用される定義を示す
+pub fn handle_here_command(store: &Store) -> Result<String> {
+    let mut aliases = store.list()?;
+    crate::system::merge_system_aliases(&mut aliases);
+
+    let current_dir = env::current_dir()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
 
+    let mut names: Vec<&String> = aliases.keys().collect();
+    names.sort();
+
+    let mut items = Vec::new();
+    for alias in names {
+        let mut defs: Vec<AliasDefinition> = aliases[alias]
+            .iter()
+            .filter(|d| d.scope.matches(Path::new(&current_dir)))
+            .cloned()
+            .collect();
+        sort_by_precedence(&mut defs);
+
+        let contested = defs.len() > 1;
+        for (i, def) in defs.iter().enumerate() {
+            let mut scope_str = scope_label(def);
+            push_arg_count(&mut scope_str, &def.command);
+            if contested {
+                scope_str.push_str(if i == 0 { " (active)" } else { " (shadowed)" });
+            }
             items.push((alias.clone(), def.command.clone(), scope_str));
         }
     }
 
     if items.is_empty() {
-        return Ok("No aliases found".to_string());
+        return Ok("No aliases active in this directory".to_string());
+    }
+
+    Ok(render_rows(items))
+}
+
+/// 一覧で表示するスコープのパスの末尾の要素数
+const SCOPE_PATH_COMPONENTS: usize = 3;
+
+/// スコープ表示用のラベルを生成
+pub(crate) fn scope_label(def: &AliasDefinition) -> String {
+    scope_label_with(def, &|path| path.to_string())
+}
+
+/// 一覧の表用のスコープラベル。ホームディレクトリを `~` にし、深いパスは末尾の
+/// `SCOPE_PATH_COMPONENTS` 個だけを表示する
+fn short_scope_label(def: &AliasDefinition, home: Option<&str>) -> String {
+    scope_label_with(def, &|path| shorten_path(path, home))
+}
+
+fn shorten_path(path: &str, home: Option<&str>) -> String {
+    let (root, rest) = match home {
+        Some(home) if path == home => return "~".to_string(),
+        Some(home) if path.starts_with(&format!("{}/", home.trim_end_matches('/'))) => {
+            ("~/", &path[home.trim_end_matches('/').len() + 1..])
+        }
+        _ => match path.strip_prefix('/') {
+            Some(rest) => ("/", rest),
+            None => ("", path),
+        },
+    };
+    let components: Vec<&str> = rest.split('/').filter(|c| !c.is_empty()).collect();
+    if components.len() > SCOPE_PATH_COMPONENTS {
+        let tail = &components[components.len() - SCOPE_PATH_COMPONENTS..];
+        format!("{}…/{}", root, tail.join("/"))
+    } else {
+        format!("{}{}", root, rest)
     }
+}
+
+fn scope_label_with(def: &AliasDefinition, path: &dyn Fn(&str) -> String) -> String {
+    let mut scope_str = match &def.scope {
+        AliasScope::Global => "(Global)".to_string(),
+        AliasScope::Recursive(p) => format!("(Recursive: {})", path(p)),
+        AliasScope::Exact(p) => format!("(Exact: {})", path(p)),
+        AliasScope::Tmux(s) => format!("(Tmux: {})", s),
+    };
+    if def.system {
+        scope_str.push_str(" (System)");
+    }
+    scope_str
+}
+
+/// (エイリアス, コマンド, スコープ) の行を桁揃えして出力
+///
+/// 色を付けるかどうかは `--color`、`NO_COLOR`、端末かどうかの順に cli で決まる
+fn render_rows(items: Vec<(String, String, String)>) -> String {
+    // 幅計算（色なしベース）
+    let max_alias_width = items
+        .iter()
+        .map(|(a, _, _)| visual_width(a))
+        .max()
+        .unwrap_or(0);
+    let max_cmd_width = items
+        .iter()
+        .map(|(_, c, _)| visual_width(c))
+        .max()
+        .unwrap_or(0);
 
-    // 第2パス: フォーマット出力
     let mut output = String::new();
     for (alias, command, scope_str) in items {
-        if use_colors {
-            let colored_alias = alias.if_supports_color(Stream::Stdout, |text| text.cyan());
-            let colored_command = command.if_supports_color(Stream::Stdout, |text| text.white());
-            let colored_scope =
-                scope_str.if_supports_color(Stream::Stdout, |text| text.bright_black());
-
-            let alias_str = format!("{}", colored_alias);
-            let cmd_str = format!("'{}'", colored_command);
-            let scope_final = format!("{}", colored_scope);
-
-            let padded_alias = pad_to_width(&alias_str, max_alias_width);
-            let padded_cmd = pad_to_width(&cmd_str, max_cmd_width + 2); // +2 for quotes
-
-            output.push_str(&format!(
-                "{} = {} {}\n",
-                padded_alias, padded_cmd, scope_final
-            ));
-        } else {
-            let padded_alias = pad_to_width(&alias, max_alias_width);
-            let padded_cmd = pad_to_width(&format!("'{}'", command), max_cmd_width + 2);
+        let colored_alias = alias.if_supports_color(Stream::Stdout, |text| text.cyan());
+        let colored_command = highlight_placeholders(&command);
+        let colored_scope = scope_str.if_supports_color(Stream::Stdout, |text| text.bright_black());
 
-            output.push_str(&format!(
-                "{} = {} {}\n",
-                padded_alias, padded_cmd, scope_str
-            ));
-        }
+        let alias_str = format!("{}", colored_alias);
+        let cmd_str = format!("'{}'", colored_command);
+        let scope_final = format!("{}", colored_scope);
+
+        let padded_alias = pad_to_width(&alias_str, max_alias_width);
+        let padded_cmd = pad_to_width(&cmd_str, max_cmd_width + 2); // +2 for quotes
+
+        output.push_str(&format!(
+            "{} = {} {}\n",
+            padded_alias, padded_cmd, scope_final
+        ));
     }
 
     if output.ends_with('\n') {
         output.pop();
     }
-    Ok(output)
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholder_spans() {
+        let command = "docker push @{var:REGISTRY}/app:@1 \\@2 @10 a@b";
+        let spans: Vec<&str> = placeholder_spans(command)
+            .into_iter()
+            .map(|span| &command[span])
+            .collect();
+        assert_eq!(spans, ["@{var:REGISTRY}", "@1", "@10"]);
+        assert_eq!(arg_count(command), 10);
+
+        assert_eq!(arg_count("awk '{print @2}' @1"), 2);
+        assert_eq!(arg_count("echo @{var:X} @{var:"), 0);
+        assert!(placeholder_spans("ls -la").is_empty());
+
+        let mut scope_str = "(Global)".to_string();
+        push_arg_count(&mut scope_str, "git commit -m @1");
+        assert_eq!(scope_str, "(Global) (1 arg)");
+    }
+
+    #[test]
+    fn test_visual_width_counts_wide_characters() {
+        assert_eq!(visual_width("ls -la"), 6);
+        assert_eq!(visual_width("echo こんにちは"), 15);
+        assert_eq!(visual_width("\x1b[36mビルド\x1b[0m"), 6);
+
+        // 全角を含む行と含まない行で列の位置が揃う
+        let japanese = pad_to_width("'echo 完了'", 14);
+        let ascii = pad_to_width("'ls -la'", 14);
+        assert_eq!(visual_width(&japanese), visual_width(&ascii));
+        assert_eq!(japanese, "'echo 完了'   ");
+    }
+
+    #[test]
+    fn test_list_template() {
+        let parts = parse_template("{alias}\\t{command} {{{scope}}}\\\\").unwrap();
+        let items = vec![
+            (
+                "ll".to_string(),
+                "ls -la".to_string(),
+                "(Global)".to_string(),
+            ),
+            (
+                "t".to_string(),
+                "cargo test @1".to_string(),
+                "(Recursive: /work) (1 arg)".to_string(),
+            ),
+        ];
+        assert_eq!(
+            render_template(&parts, items),
+            "ll\tls -la {(Global)}\\\nt\tcargo test @1 {(Recursive: /work) (1 arg)}\\"
+        );
+
+        let err = parse_template("{alias} {name}").unwrap_err();
+        assert!(
+            err.to_string().contains("Unknown field '{name}'"),
+            "{}",
+            err
+        );
+        assert!(parse_template("{alias").is_err());
+    }
+
+    #[test]
+    fn test_shorten_path() {
+        let home = Some("/home/me");
+        assert_eq!(shorten_path("/home/me", home), "~");
+        assert_eq!(shorten_path("/home/me/work/app", home), "~/work/app");
+        assert_eq!(
+            shorten_path("/home/me/src/github.com/org/app", home),
+            "~/…/github.com/org/app"
+        );
+        assert_eq!(shorten_path("/home/mesa/a", home), "/home/mesa/a");
+        assert_eq!(shorten_path("/opt/a/b/c/d", None), "/…/b/c/d");
+        assert_eq!(shorten_path("/srv/app", None), "/srv/app");
+
+        let def = AliasDefinition::new(
+            "make".to_string(),
+            AliasScope::Recursive("/home/me/a/b/c/d".to_string()),
+        );
+        assert_eq!(short_scope_label(&def, home), "(Recursive: ~/…/b/c/d)");
+        assert_eq!(scope_label(&def), "(Recursive: /home/me/a/b/c/d)");
+    }
 }
@@ -0,0 +1,116 @@
+use crate::Store;
+use crate::error::AkaError;
+use crate::store::{BackendKind, store_path};
+use std::path::{Path, PathBuf};
+
+/// Convert the store in `dir` to another backend, keeping the old file as a `.bak` backup.
+pub fn handle_migrate_command(
+    dir: &Path,
+    to: BackendKind,
+) -> std::result::Result<String, AkaError> {
+    let current_path = store_path(dir);
+    let current = BackendKind::from_path(&current_path);
+    if current == to {
+        return Ok(format!("Store already uses the {} backend", to.name()));
+    }
+
+    let target_path = dir.join(to.file_name());
+    if target_path.exists() {
+        return Err(AkaError::ConfigError(format!(
+            "Target store already exists: {}",
+            target_path.display()
+        )));
+    }
+
+    if !current_path.exists() {
+        return Err(AkaError::ConfigError(format!(
+            "No store found in {}",
+            dir.display()
+        )));
+    }
+
+    let count = {
+        let source = Store::load(&current_path)?;
+        let mut target = Store::load(&target_path)?;
+        source.copy_into(&mut target)?
+    };
+
+    let backup_path = backup_path(&current_path);
+    std::fs::rename(&current_path, &backup_path)?;
+
+    Ok(format!(
+        "Migrated {} alias(es) from {} to {} (backup: {})",
+        count,
+        current.name(),
+        to.name(),
+        backup_path.display()
+    ))
+}
+
+/// `<path>.bak`, or `<path>.bak.N` with the first free N when earlier migrations left backups.
+fn backup_path(path: &Path) -> PathBuf {
+    let base = format!("{}.bak", path.display());
+    std::iter::once(PathBuf::from(&base))
+        .chain((1..).map(|n| PathBuf::from(format!("{}.{}", base, n))))
+        .find(|candidate| !candidate.exists())
+        .expect("some backup name is free")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_migrate_round_trip() {
+        let dir = tempdir().unwrap();
+        {
+            let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+            store
+                .add("ll".to_string(), "ls -la".to_string(), AliasScope::Global)
+                .unwrap();
+            store
+                .add(
+                    "t".to_string(),
+                    "cargo test".to_string(),
+                    AliasScope::Recursive("/work".to_string()),
+                )
+                .unwrap();
+        }
+
+        let msg = handle_migrate_command(dir.path(), BackendKind::Json).unwrap();
+        assert!(msg.contains("Migrated 2 alias(es) from redb to json"));
+        assert!(dir.path().join("aka.redb.bak").exists());
+        assert!(!dir.path().join("aka.redb").exists());
+        assert_eq!(store_path(dir.path()), dir.path().join("aka.json"));
+
+        let store = Store::load(&dir.path().join("aka.json")).unwrap();
+        let aliases = store.list().unwrap();
+        assert_eq!(aliases["ll"][0].command, "ls -la");
+        assert_eq!(
+            aliases["t"][0].scope,
+            AliasScope::Recursive("/work".to_string())
+        );
+        drop(store);
+
+        let msg = handle_migrate_command(dir.path(), BackendKind::Json).unwrap();
+        assert_eq!(msg, "Store already uses the json backend");
+
+        let msg = handle_migrate_command(dir.path(), BackendKind::Redb).unwrap();
+        assert!(msg.contains("Migrated 2 alias(es) from json to redb"));
+        let store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        assert_eq!(store.list().unwrap().len(), 2);
+        drop(store);
+
+        // A second migration away from redb keeps the first backup
+        let first_backup = std::fs::read(dir.path().join("aka.redb.bak")).unwrap();
+        let msg = handle_migrate_command(dir.path(), BackendKind::Json).unwrap();
+        assert!(msg.contains("aka.redb.bak.1"));
+        assert_eq!(
+            std::fs::read(dir.path().join("aka.redb.bak")).unwrap(),
+            first_backup
+        );
+        assert!(dir.path().join("aka.redb.bak.1").exists());
+    }
+}
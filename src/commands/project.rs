@@ -0,0 +1,207 @@
+use crate::Store;
+use crate::error::AkaError;
+use crate::store::{AliasScope, SyncReport, normalize_scope_path};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Origin tag for aliases owned by `aka project sync`.
+const ORIGIN: &str = "project";
+
+const PROJECT_FILE: &str = ".aka.toml";
+const PACKAGE_FILE: &str = "package.json";
+
+/// Lockfiles that pick the package manager used to run `package.json` scripts.
+const RUNNERS: &[(&str, &str)] = &[
+    ("pnpm-lock.yaml", "pnpm run"),
+    ("yarn.lock", "yarn run"),
+    ("bun.lockb", "bun run"),
+    ("bun.lock", "bun run"),
+];
+
+#[derive(Debug, Default, Deserialize)]
+struct ProjectFile {
+    #[serde(default)]
+    aliases: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PackageFile {
+    #[serde(default)]
+    scripts: BTreeMap<String, String>,
+}
+
+/// Whether `name` can be defined as a shell function.
+pub(crate) fn is_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))
+}
+
+fn read_manifest<T>(
+    path: &Path,
+    parse: impl Fn(&str) -> std::result::Result<T, String>,
+) -> std::result::Result<Option<T>, AkaError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    parse(&content)
+        .map(Some)
+        .map_err(|e| AkaError::ConfigError(format!("Invalid {}: {}", path.display(), e)))
+}
+
+/// Collect the aliases a project declares, with `.aka.toml` taking precedence over scripts.
+///
+/// Returns the manifests that were found alongside the aliases.
+fn project_aliases(
+    dir: &Path,
+) -> std::result::Result<(Vec<&'static str>, BTreeMap<String, String>), AkaError> {
+    let mut found = Vec::new();
+    let mut aliases = BTreeMap::new();
+
+    let package: Option<PackageFile> = read_manifest(&dir.join(PACKAGE_FILE), |s| {
+        serde_json::from_str(s).map_err(|e| e.to_string())
+    })?;
+    if let Some(package) = package {
+        found.push(PACKAGE_FILE);
+        let runner = RUNNERS
+            .iter()
+            .find(|(lockfile, _)| dir.join(lockfile).exists())
+            .map_or("npm run", |(_, runner)| runner);
+        for script in package.scripts.into_keys() {
+            aliases.insert(script.clone(), format!("{} {}", runner, script));
+        }
+    }
+
+    let project: Option<ProjectFile> = read_manifest(&dir.join(PROJECT_FILE), |s| {
+        toml::from_str(s).map_err(|e| e.to_string())
+    })?;
+    if let Some(project) = project {
+        found.insert(0, PROJECT_FILE);
+        aliases.extend(project.aliases);
+    }
+
+    Ok((found, aliases))
+}
+
+/// Summarize a sync for the user; shared by the commands that generate aliases.
+pub(crate) fn format_sync_report(report: &SyncReport, invalid: &[String], dir: &Path) -> String {
+    let mut lines = Vec::new();
+    for (label, names) in [
+        ("Added", &report.added),
+        ("Updated", &report.updated),
+        ("Removed", &report.removed),
+    ] {
+        if !names.is_empty() {
+            lines.push(format!("{}: {}", label, names.join(", ")));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(format!("Aliases for {} are up to date", dir.display()));
+    } else {
+        lines.push("(Reload shell to apply)".to_string());
+    }
+    if !report.skipped.is_empty() {
+        lines.push(format!(
+            "Skipped (already defined by hand here): {}",
+            report.skipped.join(", ")
+        ));
+    }
+    if !invalid.is_empty() {
+        lines.push(format!(
+            "Skipped (not a valid alias name): {}",
+            invalid.join(", ")
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Create or refresh recursive-scoped aliases for the project in `dir`.
+///
+/// Aliases from an earlier sync that the manifests no longer declare are removed.
+pub fn handle_project_sync_command(
+    store: &mut Store,
+    dir: &Path,
+) -> std::result::Result<String, AkaError> {
+    let (found, aliases) = project_aliases(dir)?;
+    if found.is_empty() {
+        return Err(AkaError::ConfigError(format!(
+            "No {} or {} in {}",
+            PROJECT_FILE,
+            PACKAGE_FILE,
+            dir.display()
+        )));
+    }
+
+    let (aliases, invalid): (BTreeMap<_, _>, BTreeMap<_, _>) = aliases
+        .into_iter()
+        .partition(|(name, _)| is_alias_name(name));
+    let invalid: Vec<String> = invalid.into_keys().collect();
+
+    let scope = AliasScope::Recursive(normalize_scope_path(dir)?);
+    let report = store.sync_generated(ORIGIN, &scope, &aliases)?;
+    Ok(format!(
+        "Synced {} from {}\n{}",
+        dir.display(),
+        found.join(", "),
+        format_sync_report(&report, &invalid, dir)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_project_sync() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join("app");
+        std::fs::create_dir(&project).unwrap();
+        std::fs::write(
+            project.join(PACKAGE_FILE),
+            r#"{"scripts": {"build": "vite build", "test": "vitest", "two words": "x"}}"#,
+        )
+        .unwrap();
+        std::fs::write(project.join("pnpm-lock.yaml"), "").unwrap();
+        std::fs::write(
+            project.join(PROJECT_FILE),
+            "[aliases]\ntest = \"pnpm vitest --run\"\nup = \"docker compose up -d\"\n",
+        )
+        .unwrap();
+
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        let msg = handle_project_sync_command(&mut store, &project).unwrap();
+        assert!(msg.contains("from .aka.toml, package.json"));
+        assert!(msg.contains("Added: build, test, up"));
+        assert!(msg.contains("Skipped (not a valid alias name): two words"));
+
+        let aliases = store.list().unwrap();
+        assert_eq!(aliases["build"][0].command, "pnpm run build");
+        assert_eq!(aliases["test"][0].command, "pnpm vitest --run");
+        assert_eq!(
+            aliases["up"][0].scope,
+            AliasScope::Recursive(project.to_string_lossy().to_string())
+        );
+
+        std::fs::remove_file(project.join(PROJECT_FILE)).unwrap();
+        let msg = handle_project_sync_command(&mut store, &project).unwrap();
+        assert!(msg.contains("Updated: test"));
+        assert!(msg.contains("Removed: up"));
+        assert!(!store.list().unwrap().contains_key("up"));
+    }
+
+    #[test]
+    fn test_project_sync_without_manifest() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        assert!(matches!(
+            handle_project_sync_command(&mut store, dir.path()),
+            Err(AkaError::ConfigError(msg)) if msg.starts_with("No .aka.toml or package.json")
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::Store;
+use crate::commands::announce::active_scoped_aliases;
+
+/// Default prompt segment; `{count}` is replaced with the number of active scoped aliases.
+pub const DEFAULT_PROMPT_FORMAT: &str = "[aka:{count}]";
+
+/// Module for `starship.toml`. Starship hides a custom module whose command prints nothing,
+/// so it disappears outside directories with scoped aliases.
+const STARSHIP_MODULE: &str = r#"# Add to ~/.config/starship.toml
+[custom.aka]
+description = "Scoped aka aliases active in this directory"
+command = "aka prompt --format '{count}'"
+when = true
+shell = ["sh"]
+format = "[aka:$output]($style) "
+style = "bold purple"
+# To list the names instead: command = "aka prompt --format '{names}'""#;
+
+/// Configuration snippet for using `aka prompt` as a starship custom module.
+pub fn starship_module() -> String {
+    STARSHIP_MODULE.to_string()
+}
+
+/// Render a prompt segment for the scoped aliases active in `current_dir`.
+///
+/// `{count}` and `{names}` (comma-separated) in `format` are filled in.
+///
+/// Prints nothing when no scoped alias applies so the prompt stays clean. Reads go through
+/// the alias cache, so this stays cheap enough to run on every prompt.
+pub fn handle_prompt_command(
+    store: &Store,
+    current_dir: &str,
+    format: &str,
+) -> std::result::Result<String, crate::error::AkaError> {
+    let active = active_scoped_aliases(store, current_dir)?;
+    if active.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(format
+        .replace("{count}", &active.len().to_string())
+        .replace("{names}", &active.join(",")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_prompt_counts_scoped_aliases() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add("g".to_string(), "git".to_string(), AliasScope::Global)
+            .unwrap();
+        for alias in ["t", "b"] {
+            store
+                .add(
+                    alias.to_string(),
+                    "make".to_string(),
+                    AliasScope::Recursive("/work/app".to_string()),
+                )
+                .unwrap();
+        }
+
+        let segment =
+            handle_prompt_command(&store, "/work/app/src", DEFAULT_PROMPT_FORMAT).unwrap();
+        assert_eq!(segment, "[aka:2]");
+        assert_eq!(
+            handle_prompt_command(&store, "/work/app", "{count}: {names}").unwrap(),
+            "2: b,t"
+        );
+        assert_eq!(
+            handle_prompt_command(&store, "/home", DEFAULT_PROMPT_FORMAT).unwrap(),
+            ""
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use crate::Store;
+use crate::error::AkaError;
+use crate::store::resolve_definition;
+
+/// Which aliases `aka count` counts.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum CountScope {
+    /// Every alias, whatever its scope
+    All,
+    /// Aliases with a definition that applies in the current directory
+    Here,
+}
+
+/// Whether `alias` is defined, in `current_dir` when given, for `aka has` in scripts.
+///
+/// Only that alias is read from the store, falling back to the system aliases, so this stays
+/// cheap enough for prompts.
+pub fn handle_has_command(
+    store: &Store,
+    alias: &str,
+    current_dir: Option<&str>,
+) -> std::result::Result<bool, AkaError> {
+    let defs = match store.get(alias)? {
+        Some(defs) => defs,
+        None => crate::system::system_aliases()
+            .remove(alias)
+            .unwrap_or_default(),
+    };
+    Ok(match current_dir {
+        Some(dir) => resolve_definition(&defs, dir).is_some(),
+        None => !defs.is_empty(),
+    })
+}
+
+/// Number of aliases in `scope`, including system aliases.
+pub fn handle_count_command(
+    store: &Store,
+    scope: CountScope,
+    current_dir: &str,
+) -> std::result::Result<usize, AkaError> {
+    let mut aliases = store.list()?;
+    crate::system::merge_system_aliases(&mut aliases);
+    Ok(match scope {
+        CountScope::All => aliases.len(),
+        CountScope::Here => aliases
+            .values()
+            .filter(|defs| resolve_definition(defs, current_dir).is_some())
+            .count(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_has_and_count() -> std::result::Result<(), AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        store.add("g".to_string(), "git".to_string(), AliasScope::Global)?;
+        store.add(
+            "t".to_string(),
+            "cargo test".to_string(),
+            AliasScope::Recursive("/work/app".to_string()),
+        )?;
+
+        assert!(handle_has_command(&store, "t", None)?);
+        assert!(handle_has_command(&store, "t", Some("/work/app/src"))?);
+        assert!(!handle_has_command(&store, "t", Some("/home"))?);
+        assert!(!handle_has_command(&store, "missing", None)?);
+
+        assert_eq!(handle_count_command(&store, CountScope::All, "/home")?, 2);
+        assert_eq!(handle_count_command(&store, CountScope::Here, "/home")?, 1);
+        assert_eq!(
+            handle_count_command(&store, CountScope::Here, "/work/app")?,
+            2
+        );
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
-use crate::store::AliasScope;
 use crate::Store;
+use crate::config::ConfirmPolicy;
+use crate::store::{AliasScope, ScopeSpec};
 use std::io::{self, Write};
 
 /// Display a confirmation prompt and read user input.
@@ -19,41 +20,52 @@ fn confirm_removal(count: usize, scope: Option<&str>) -> std::result::Result<boo
 
 /// Find a matching scope in the list of definitions.
 ///
-/// For "global", returns AliasScope::Global.
-/// For path strings, normalizes the path and searches for an exact or recursive scope match.
+/// An explicit scope such as `global`, `tmux:NAME` or `exact:PATH` is returned as given. A
+/// bare directory picks whichever exact or recursive scope for it the definitions have.
 fn match_scope_in_definitions(
     definitions: &[crate::store::AliasDefinition],
     scope_str: &str,
 ) -> std::result::Result<AliasScope, crate::error::AkaError> {
-    if scope_str.to_lowercase() == "global" {
-        return Ok(AliasScope::Global);
+    let spec = ScopeSpec::parse(scope_str)?;
+    if let ScopeSpec::Scope(scope) = spec {
+        return Ok(scope);
     }
 
-    // Normalize the input path
-    let path = std::path::PathBuf::from(scope_str);
-    let normalized = path
-        .canonicalize()
-        .map_err(|e| crate::error::AkaError::InvalidScopePath(e.to_string()))?;
-    let normalized_str = normalized
-        .to_str()
-        .ok_or_else(|| crate::error::AkaError::InvalidScopePath("Invalid UTF-8 in path".to_string()))?;
-
-    // Search for matching scope in definitions
-    for def in definitions {
-        match &def.scope {
-            AliasScope::Exact(p) | AliasScope::Recursive(p) => {
-                if p == normalized_str {
-                    return Ok(def.scope.clone());
-                }
-            }
-            _ => {}
-        }
-    }
+    definitions
+        .iter()
+        .map(|def| &def.scope)
+        .find(|scope| spec.matches(scope))
+        .cloned()
+        .ok_or_else(|| {
+            crate::error::AkaError::InvalidScopePath(format!(
+                "No matching scope found for path: {}",
+                scope_str
+            ))
+        })
+}
 
-    Err(crate::error::AkaError::InvalidScopePath(format!(
-        "No matching scope found for path: {}",
-        scope_str
-    )))
+/// Warn when other aliases still call an alias that no longer exists.
+fn callers_warning(
+    store: &Store,
+    alias: &str,
+) -> std::result::Result<String, crate::error::AkaError> {
+    let mut aliases = store.list()?;
+    crate::system::merge_system_aliases(&mut aliases);
+    // Keep the removed name resolvable so calls to it are still detected
+    aliases.entry(alias.to_string()).or_default();
+
+    Ok(match crate::references::callers(&aliases).get(alias) {
+        Some(callers) => {
+            let callers: Vec<&str> = callers.iter().map(String::as_str).collect();
+            format!(
+                "\nWarning: {} alias(es) call '{}': {}",
+                callers.len(),
+                alias,
+                callers.join(", ")
+            )
+        }
+        None => String::new(),
+    })
 }
 
 pub fn handle_remove_command(
@@ -62,7 +74,9 @@ pub fn handle_remove_command(
     all: bool,
     scope: Option<String>,
     force: bool,
+    confirm: ConfirmPolicy,
 ) -> std::result::Result<String, crate::error::AkaError> {
+    let ask = !force && confirm.should_ask();
     match (all, alias, scope) {
         // Case 1: Remove all aliases (all scopes)
         (true, None, None) => {
@@ -71,7 +85,7 @@ pub fn handle_remove_command(
                 return Ok("No aliases to remove".to_string());
             }
 
-            if !force && !confirm_removal(count, None)? {
+            if ask && !confirm_removal(count, None)? {
                 return Err(crate::error::AkaError::OperationCancelled);
             }
 
@@ -81,58 +95,34 @@ pub fn handle_remove_command(
 
         // Case 2: Remove all aliases in a specific scope
         (true, None, Some(scope_str)) => {
-            // Parse the scope
-            let target_scope = if scope_str.to_lowercase() == "global" {
-                AliasScope::Global
-            } else {
-                let path = std::path::PathBuf::from(&scope_str);
-                let normalized = path
-                    .canonicalize()
-                    .map_err(|e| crate::error::AkaError::InvalidScopePath(e.to_string()))?;
-                let normalized_str = normalized
-                    .to_str()
-                    .ok_or_else(|| {
-                        crate::error::AkaError::InvalidScopePath("Invalid UTF-8 in path".to_string())
-                    })?
-                    .to_string();
-
-                // Need to determine if it's Exact or Recursive by checking existing definitions
-                // For now, we'll try both and use whichever matches
-                let all_aliases = store.list()?;
-                let mut found_scope: Option<AliasScope> = None;
-
-                for defs in all_aliases.values() {
-                    for def in defs {
-                        match &def.scope {
-                            AliasScope::Exact(p) | AliasScope::Recursive(p) => {
-                                if p == &normalized_str {
-                                    found_scope = Some(def.scope.clone());
-                                    break;
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                    if found_scope.is_some() {
-                        break;
-                    }
-                }
-
-                found_scope.unwrap_or(AliasScope::Exact(normalized_str))
+            // Parse the scope; a bare directory means whichever kind some alias uses
+            let target_scope = match ScopeSpec::parse(&scope_str)? {
+                ScopeSpec::Scope(scope) => scope,
+                spec => store
+                    .list()?
+                    .values()
+                    .flatten()
+                    .map(|def| &def.scope)
+                    .find(|scope| spec.matches(scope))
+                    .cloned()
+                    .unwrap_or_else(|| spec.into_scope(false)),
             };
 
-            let removed = store.remove_all_in_scope(&target_scope)?;
-            let count = removed.len();
+            // Count before removing so that declining leaves the store untouched
+            let count = store
+                .list()?
+                .values()
+                .filter(|defs| defs.iter().any(|d| d.scope == target_scope))
+                .count();
 
             if count == 0 {
                 return Ok(format!("No aliases found in scope '{}'", scope_str));
             }
 
-            if !force && !confirm_removal(count, Some(&scope_str))? {
+            if ask && !confirm_removal(count, Some(&scope_str))? {
                 return Err(crate::error::AkaError::OperationCancelled);
             }
 
-            // Re-execute since we already consumed the result for counting
             store.remove_all_in_scope(&target_scope)?;
             Ok(format!(
                 "Removed {} alias(es) from scope '{}'",
@@ -145,11 +135,20 @@ pub fn handle_remove_command(
             Some(defs) => {
                 let count = defs.len();
                 Ok(format!(
-                    "Removed alias '{}' ({} definitions)",
-                    alias_name, count
+                    "Removed alias '{}' ({} definitions){}",
+                    alias_name,
+                    count,
+                    callers_warning(store, &alias_name)?
                 ))
             }
-            None => Err(crate::error::AkaError::AliasNotFound(alias_name)),
+            None => {
+                let system_aliases = crate::system::system_aliases();
+                if system_aliases.contains_key(&alias_name) {
+                    Err(crate::error::AkaError::SystemAlias(alias_name))
+                } else {
+                    Err(crate::error::AkaError::AliasNotFound(alias_name))
+                }
+            }
         },
 
         // Case 4: Remove a specific alias from a specific scope
@@ -174,8 +173,10 @@ pub fn handle_remove_command(
 
                     if remaining == 0 {
                         Ok(format!(
-                            "Removed alias '{}' from scope '{}' (no definitions remaining)",
-                            alias_name, scope_str
+                            "Removed alias '{}' from scope '{}' (no definitions remaining){}",
+                            alias_name,
+                            scope_str,
+                            callers_warning(store, &alias_name)?
                         ))
                     } else {
                         Ok(format!(
@@ -199,6 +200,7 @@ pub fn handle_remove_command(
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod tests {
     use super::*;
     use crate::store::AliasScope;
@@ -217,7 +219,14 @@ mod tests {
             .add(alias.clone(), "echo test".to_string(), AliasScope::Global)
             .unwrap();
 
-        match handle_remove_command(&mut store, Some(alias.clone()), false, None, false) {
+        match handle_remove_command(
+            &mut store,
+            Some(alias.clone()),
+            false,
+            None,
+            false,
+            ConfirmPolicy::Always,
+        ) {
             Ok(_) => assert!(true),
             Err(e) => panic!("Expected Ok, got Err: {:?}", e),
         }
@@ -231,7 +240,14 @@ mod tests {
         let path = dir.path().join("aka.redb");
         let mut store = Store::load(&path).unwrap();
         // remove returns Ok even if not found (just explicit message)
-        match handle_remove_command(&mut store, Some(alias.clone()), false, None, false) {
+        match handle_remove_command(
+            &mut store,
+            Some(alias.clone()),
+            false,
+            None,
+            false,
+            ConfirmPolicy::Always,
+        ) {
             Ok(_) => panic!("Expected Err, got Ok"),
             Err(crate::error::AkaError::AliasNotFound(a)) => assert_eq!(a, alias),
             Err(e) => panic!("Expected AliasNotFound, got {:?}", e),
@@ -253,11 +269,85 @@ mod tests {
             .unwrap();
 
         // Remove all with force flag
-        let result = handle_remove_command(&mut store, None, true, None, true);
+        let result =
+            handle_remove_command(&mut store, None, true, None, true, ConfirmPolicy::Always);
         assert!(result.is_ok());
         assert!(result.unwrap().contains("Removed 2 alias(es)"));
 
         // Verify all removed
         assert!(store.list().unwrap().is_empty());
     }
+
+    #[test]
+    fn test_remove_all_in_scope_without_asking() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add(
+                "foo".to_string(),
+                "echo foo".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+        store
+            .add(
+                "foo".to_string(),
+                "echo here".to_string(),
+                AliasScope::Exact("/tmp".to_string()),
+            )
+            .unwrap();
+
+        let msg = handle_remove_command(
+            &mut store,
+            None,
+            true,
+            Some("global".to_string()),
+            false,
+            ConfirmPolicy::Never,
+        )
+        .unwrap();
+        assert_eq!(msg, "Removed 1 alias(es) from scope 'global'");
+        assert_eq!(store.list().unwrap()["foo"][0].command, "echo here");
+    }
+
+    #[test]
+    fn test_remove_warns_about_callers() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aka.redb");
+        let mut store = Store::load(&path).unwrap();
+        store
+            .add("g".to_string(), "git".to_string(), AliasScope::Global)
+            .unwrap();
+        store
+            .add("gs".to_string(), "g status".to_string(), AliasScope::Global)
+            .unwrap();
+        store
+            .add("gp".to_string(), "g push".to_string(), AliasScope::Global)
+            .unwrap();
+
+        let msg = handle_remove_command(
+            &mut store,
+            Some("g".to_string()),
+            false,
+            None,
+            false,
+            ConfirmPolicy::Always,
+        )
+        .unwrap();
+        assert_eq!(
+            msg,
+            "Removed alias 'g' (1 definitions)\nWarning: 2 alias(es) call 'g': gp, gs"
+        );
+
+        let msg = handle_remove_command(
+            &mut store,
+            Some("gs".to_string()),
+            false,
+            None,
+            false,
+            ConfirmPolicy::Always,
+        )
+        .unwrap();
+        assert_eq!(msg, "Removed alias 'gs' (1 definitions)");
+    }
 }
@@ -0,0 +1,199 @@
+use crate::Store;
+use crate::codegen::{self, Shell};
+use crate::error::AkaError;
+use crate::store::{AliasDefinition, resolve_definition};
+
+/// The definitions of `alias` the way the dump defines them, with references to other aliases
+/// and variables expanded.
+pub fn prepared_definitions(
+    store: &Store,
+    alias: &str,
+) -> std::result::Result<Vec<AliasDefinition>, AkaError> {
+    let shell_config = crate::config::load()
+        .map(|config| config.shell)
+        .unwrap_or_default();
+    crate::commands::init::prepare_aliases(Some(store), &shell_config)?
+        .into_iter()
+        .find_map(|(name, defs)| (name == alias).then_some(defs))
+        .ok_or_else(|| AkaError::AliasNotFound(alias.to_string()))
+}
+
+/// Print the command `alias` runs in `current_dir`, as the generated function would pick it.
+pub fn handle_resolve_command(
+    store: &Store,
+    alias: &str,
+    current_dir: &str,
+) -> std::result::Result<String, AkaError> {
+    let defs = prepared_definitions(store, alias)?;
+    resolve_definition(&defs, current_dir)
+        .map(|def| def.command.clone())
+        .ok_or_else(|| AkaError::ScopeNotFoundInAlias(alias.to_string(), current_dir.to_string()))
+}
+
+/// Print `line` with its first word, an alias, replaced by the command it runs in
+/// `current_dir`, for the zsh expand widget.
+///
+/// Aliases that place their arguments themselves are refused: the words after them would not
+/// end up where the function puts them.
+pub fn handle_resolve_line_command(
+    store: &Store,
+    line: &str,
+    current_dir: &str,
+) -> std::result::Result<String, AkaError> {
+    let (alias, rest) = line.split_at(line.find(' ').unwrap_or(line.len()));
+    let command = handle_resolve_command(store, alias, current_dir)?;
+    if codegen::zsh_bash::places_arguments(&command) {
+        return Err(AkaError::ConfigError(format!(
+            "'{}' takes arguments and cannot be expanded in place",
+            alias
+        )));
+    }
+    Ok(format!("{}{}", command, rest))
+}
+
+/// Print the statements the dispatcher of `init --dump --dispatch` evaluates to run `alias`
+/// in `current_dir`: the command with everything its definition asks for around it.
+pub fn handle_resolve_body_command(
+    store: &Store,
+    alias: &str,
+    current_dir: &str,
+    shell: Shell,
+) -> std::result::Result<String, AkaError> {
+    let defs = prepared_definitions(store, alias)?;
+    let def = resolve_definition(&defs, current_dir).ok_or_else(|| {
+        AkaError::ScopeNotFoundInAlias(alias.to_string(), current_dir.to_string())
+    })?;
+    Ok(codegen::dispatched_body(shell.codegen(), alias, def))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_command() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aka.redb");
+        let mut store = Store::load(&path).unwrap();
+
+        store
+            .add(
+                "t".to_string(),
+                "cargo test".to_string(),
+                AliasScope::Recursive("/work".to_string()),
+            )
+            .unwrap();
+
+        assert_eq!(
+            handle_resolve_command(&store, "t", "/work/app").unwrap(),
+            "cargo test"
+        );
+        assert!(matches!(
+            handle_resolve_command(&store, "t", "/home"),
+            Err(AkaError::ScopeNotFoundInAlias(_, _))
+        ));
+        assert!(matches!(
+            handle_resolve_command(&store, "missing", "/work"),
+            Err(AkaError::AliasNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_expands_variables() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store.set_var("REG", "ghcr.io").unwrap();
+        store
+            .add(
+                "img".to_string(),
+                "docker pull @{var:REG}/x".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+
+        assert_eq!(
+            handle_resolve_command(&store, "img", "/work").unwrap(),
+            "docker pull ghcr.io/x"
+        );
+    }
+
+    #[test]
+    fn test_resolve_expands_alias_references() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add("ll".to_string(), "ls -la".to_string(), AliasScope::Global)
+            .unwrap();
+        let chain = crate::commands::add::then_command(
+            &store,
+            "chain",
+            Some("echo a".to_string()),
+            &["ll".to_string()],
+        )
+        .unwrap();
+        store
+            .add("chain".to_string(), chain, AliasScope::Global)
+            .unwrap();
+
+        assert_eq!(
+            handle_resolve_command(&store, "chain", "/work").unwrap(),
+            "echo a && ls -la"
+        );
+    }
+
+    #[test]
+    fn test_resolve_line_expands_the_first_word() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store.set_var("REG", "ghcr.io").unwrap();
+        store
+            .add(
+                "img".to_string(),
+                "docker pull @{var:REG}/x".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+        store
+            .add(
+                "g".to_string(),
+                "git @1 \"@2\"".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+
+        assert_eq!(
+            handle_resolve_line_command(&store, "img --quiet | tee log", "/work").unwrap(),
+            "docker pull ghcr.io/x --quiet | tee log"
+        );
+        assert_eq!(
+            handle_resolve_line_command(&store, "img", "/work").unwrap(),
+            "docker pull ghcr.io/x"
+        );
+        assert!(handle_resolve_line_command(&store, "g commit msg", "/work").is_err());
+        assert!(matches!(
+            handle_resolve_line_command(&store, "ls -la", "/work"),
+            Err(AkaError::AliasNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_body_command() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        let mut def = crate::store::AliasDefinition::new(
+            "cargo test @1".to_string(),
+            AliasScope::Recursive("/work".to_string()),
+        );
+        def.env = std::collections::BTreeMap::from([("RUST_LOG".to_string(), "debug".to_string())]);
+        store.add_definition("t".to_string(), def).unwrap();
+
+        let body = handle_resolve_body_command(&store, "t", "/work/app", Shell::Bash).unwrap();
+        assert!(body.ends_with("local -x RUST_LOG='debug'; cargo test \"${1}\""));
+        assert!(matches!(
+            handle_resolve_body_command(&store, "t", "/home", Shell::Bash),
+            Err(AkaError::ScopeNotFoundInAlias(_, _))
+        ));
+    }
+}
@@ -0,0 +1,302 @@
+use crate::Store;
+use crate::commands::edit::handle_edit_command;
+use crate::commands::list::scope_label;
+use crate::commands::script::prune_scripts;
+use crate::error::AkaError;
+use crate::store::{AliasDefinition, AliasScope};
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Months are counted as 30 days; review only needs a rough age.
+const SECONDS_PER_MONTH: u64 = 30 * SECONDS_PER_DAY;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Keep,
+    Edit,
+    Delete,
+    Skip,
+    Quit,
+}
+
+fn parse_action(line: &str) -> Option<Action> {
+    match line.trim().to_lowercase().as_str() {
+        "" | "k" | "keep" => Some(Action::Keep),
+        "e" | "edit" => Some(Action::Edit),
+        "d" | "delete" => Some(Action::Delete),
+        "s" | "skip" => Some(Action::Skip),
+        "q" | "quit" => Some(Action::Quit),
+        _ => None,
+    }
+}
+
+/// Count aliases run since the last review as reviewed at `now`.
+pub fn record_usage(
+    store: &mut Store,
+    usage_log: &Path,
+    now: u64,
+) -> std::result::Result<(), AkaError> {
+    let (used, lines) = crate::usage::load_usage_since(usage_log, store.usage_offset()?)?;
+    store.mark_reviewed(&used, now)?;
+    store.set_usage_offset(lines)
+}
+
+/// An alias due for review, with when it was last added, kept, or used.
+struct StaleAlias {
+    alias: String,
+    reviewed: Option<u64>,
+    defs: Vec<AliasDefinition>,
+}
+
+/// Aliases neither added, kept, nor used since `cutoff`, never-reviewed ones first, then oldest first.
+fn stale_aliases(store: &Store, cutoff: u64) -> std::result::Result<Vec<StaleAlias>, AkaError> {
+    let reviewed = store.reviewed()?;
+    let mut stale: Vec<StaleAlias> = store
+        .list()?
+        .into_iter()
+        .map(|(alias, defs)| StaleAlias {
+            reviewed: reviewed.get(&alias).copied(),
+            alias,
+            defs,
+        })
+        .filter(|s| s.reviewed.is_none_or(|at| at < cutoff))
+        .collect();
+    stale.sort_by(|a, b| a.reviewed.cmp(&b.reviewed).then(a.alias.cmp(&b.alias)));
+    Ok(stale)
+}
+
+fn format_age(at: Option<u64>, now: u64) -> String {
+    let Some(at) = at else {
+        return "never reviewed".to_string();
+    };
+    match now.saturating_sub(at) {
+        s if s < SECONDS_PER_DAY => "last reviewed today".to_string(),
+        s if s < 2 * SECONDS_PER_MONTH => format!("last reviewed {} days ago", s / SECONDS_PER_DAY),
+        s => format!("last reviewed {} months ago", s / SECONDS_PER_MONTH),
+    }
+}
+
+/// Walk through aliases not reviewed or used in `months` months, asking whether to keep, edit, or delete each.
+pub fn handle_review_command(
+    store: &mut Store,
+    months: u64,
+    now: u64,
+    scripts: &Path,
+    edit: impl Fn(&Path) -> std::result::Result<(), AkaError>,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> std::result::Result<String, AkaError> {
+    let stale = stale_aliases(store, now.saturating_sub(months * SECONDS_PER_MONTH))?;
+    if stale.is_empty() {
+        return Ok(format!(
+            "No aliases left unreviewed for {} month(s)",
+            months
+        ));
+    }
+
+    let (mut kept, mut edited, mut deleted) = (0, 0, 0);
+    'aliases: for (
+        i,
+        StaleAlias {
+            alias,
+            reviewed,
+            defs,
+        },
+    ) in stale.iter().enumerate()
+    {
+        writeln!(
+            out,
+            "[{}/{}] {} ({})",
+            i + 1,
+            stale.len(),
+            alias,
+            format_age(*reviewed, now)
+        )?;
+        for def in defs {
+            writeln!(out, "    {} {}", def.command, scope_label(def))?;
+        }
+
+        let action = loop {
+            write!(
+                out,
+                "[k]eep, [e]dit, [d]elete, [s]kip, or [q]uit (K/e/d/s/q): "
+            )?;
+            out.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                break Action::Quit;
+            }
+            if let Some(action) = parse_action(&line) {
+                break action;
+            }
+        };
+
+        match action {
+            Action::Keep => kept += 1,
+            Action::Edit => {
+                for def in defs {
+                    // Global definitions apply everywhere, so an empty path selects only them
+                    let dir = match &def.scope {
+                        AliasScope::Global => "",
+                        AliasScope::Recursive(p) | AliasScope::Exact(p) => p,
+                        AliasScope::Tmux(session) => {
+                            writeln!(
+                                out,
+                                "Skipping tmux definition; re-add it with `aka add --scope tmux:{}`",
+                                session
+                            )?;
+                            continue;
+                        }
+                    };
+                    writeln!(
+                        out,
+                        "{}",
+                        handle_edit_command(store, alias, dir, scripts, &edit)?
+                    )?;
+                }
+                edited += 1;
+            }
+            Action::Delete => {
+                store.remove(alias)?;
+                writeln!(out, "Removed alias '{}'", alias)?;
+                deleted += 1;
+                continue;
+            }
+            Action::Skip => continue,
+            Action::Quit => break 'aliases,
+        }
+        store.mark_reviewed(std::slice::from_ref(alias), now)?;
+    }
+
+    if deleted > 0 {
+        prune_scripts(store, scripts)?;
+    }
+    let mut summary = format!(
+        "Kept {}, edited {}, deleted {} alias(es)",
+        kept, edited, deleted
+    );
+    if edited + deleted > 0 {
+        summary.push_str("\n(Reload shell to apply)");
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const NOW: u64 = 400 * SECONDS_PER_DAY;
+
+    #[test]
+    fn test_review_keep_edit_delete() {
+        let dir = tempdir().unwrap();
+        let scripts = dir.path().join("scripts");
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        for (alias, command) in [
+            ("a", "echo a"),
+            ("b", "echo b"),
+            ("c", "echo c"),
+            ("fresh", "ls"),
+        ] {
+            store
+                .add(alias.to_string(), command.to_string(), AliasScope::Global)
+                .unwrap();
+        }
+        let old = NOW - 7 * SECONDS_PER_MONTH;
+        store
+            .mark_reviewed(&["a".to_string(), "b".to_string(), "c".to_string()], old)
+            .unwrap();
+        store.mark_reviewed(&["fresh".to_string()], NOW).unwrap();
+
+        let mut input = "k\ne\nd\n".as_bytes();
+        let mut out = Vec::new();
+        let summary = handle_review_command(
+            &mut store,
+            6,
+            NOW,
+            &scripts,
+            |path| Ok(std::fs::write(path, "echo edited\n")?),
+            &mut input,
+            &mut out,
+        )
+        .unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("[1/3] a (last reviewed 7 months ago)"));
+        assert!(!out.contains("fresh"));
+        assert!(summary.starts_with("Kept 1, edited 1, deleted 1 alias(es)"));
+
+        let aliases = store.list().unwrap();
+        assert_eq!(aliases["b"][0].command, "echo edited");
+        assert!(!aliases.contains_key("c"));
+        assert_eq!(store.reviewed().unwrap()["a"], NOW);
+    }
+
+    #[test]
+    fn test_review_quits_on_eof_and_lists_untracked_first() {
+        let dir = tempdir().unwrap();
+        // A store written before review tracking has no timestamp for its aliases
+        let path = dir.path().join("aka.json");
+        std::fs::write(&path, r#"{"aliases": {"untracked": "echo u"}}"#).unwrap();
+        let mut store = Store::load(&path).unwrap();
+        store
+            .add(
+                "old".to_string(),
+                "echo old".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+        store.mark_reviewed(&["old".to_string()], 0).unwrap();
+
+        let mut out = Vec::new();
+        let summary = handle_review_command(
+            &mut store,
+            6,
+            NOW,
+            dir.path(),
+            |_| unreachable!(),
+            &mut "".as_bytes(),
+            &mut out,
+        )
+        .unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("[1/2] untracked (never reviewed)"));
+        assert_eq!(summary, "Kept 0, edited 0, deleted 0 alias(es)");
+    }
+
+    #[test]
+    fn test_record_usage_counts_as_review() {
+        let dir = tempdir().unwrap();
+        let log = dir.path().join("usage.log");
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add(
+                "g".to_string(),
+                "git status".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+        store.mark_reviewed(&["g".to_string()], 0).unwrap();
+
+        std::fs::write(&log, "g\n").unwrap();
+        record_usage(&mut store, &log, NOW).unwrap();
+        assert_eq!(store.reviewed().unwrap()["g"], NOW);
+        assert_eq!(store.usage_offset().unwrap(), 1);
+
+        let summary = handle_review_command(
+            &mut store,
+            6,
+            NOW,
+            dir.path(),
+            |_| unreachable!(),
+            &mut "".as_bytes(),
+            &mut Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(summary, "No aliases left unreviewed for 6 month(s)");
+    }
+}
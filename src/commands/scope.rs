@@ -0,0 +1,113 @@
+use crate::Store;
+use crate::error::AkaError;
+use crate::store::normalize_scope_path;
+use std::path::{Path, PathBuf};
+
+/// Turn a user-supplied directory into the absolute form scopes are stored in.
+///
+/// Existing directories are normalized like `add --scope`; the old location of a moved
+/// project usually no longer exists, so other paths are only made absolute.
+fn absolute_scope_path(path: &str) -> std::result::Result<String, AkaError> {
+    let path = Path::new(path);
+    if path.exists() {
+        return normalize_scope_path(path);
+    }
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    let absolute: PathBuf = absolute.components().collect();
+    absolute.into_os_string().into_string().map_err(|path| {
+        AkaError::InvalidScopePath(format!("{} is not valid UTF-8", Path::new(&path).display()))
+    })
+}
+
+/// Rewrite every scope under `old` to the same place under `new`.
+pub fn handle_scope_move_command(
+    store: &mut Store,
+    old: &str,
+    new: &str,
+) -> std::result::Result<String, AkaError> {
+    let old = absolute_scope_path(old)?;
+    let new = absolute_scope_path(new)?;
+
+    let moved = store.move_scopes(&old, &new)?;
+    if moved == 0 {
+        return Ok(format!("No scopes found under {}", old));
+    }
+    Ok(format!(
+        "Moved {} scoped definition(s) from {} to {}\n(Reload shell to apply)",
+        moved, old, new
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_scope_move_command() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add(
+                "t".to_string(),
+                "cargo test".to_string(),
+                AliasScope::Recursive("/gone/project".to_string()),
+            )
+            .unwrap();
+
+        let msg = handle_scope_move_command(&mut store, "/gone/project/", "/srv/project").unwrap();
+        assert!(msg.starts_with("Moved 1 scoped definition(s) from /gone/project to /srv/project"));
+        assert_eq!(
+            store.list().unwrap()["t"][0].scope,
+            AliasScope::Recursive("/srv/project".to_string())
+        );
+
+        let msg = handle_scope_move_command(&mut store, "/gone/project", "/srv/x").unwrap();
+        assert_eq!(msg, "No scopes found under /gone/project");
+    }
+
+    #[test]
+    fn test_scope_move_normalizes_existing_directories() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add(
+                "t".to_string(),
+                "cargo test".to_string(),
+                AliasScope::Exact("/gone/project".to_string()),
+            )
+            .unwrap();
+        let target = dir.path().join("project");
+        std::fs::create_dir(&target).unwrap();
+
+        let with_slash = format!("{}/", target.display());
+        handle_scope_move_command(&mut store, "/gone/project", &with_slash).unwrap();
+        assert_eq!(
+            store.list().unwrap()["t"][0].scope,
+            AliasScope::Exact(normalize_scope_path(&target).unwrap())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scope_move_rejects_non_utf8_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        let target = dir.path().join(std::ffi::OsStr::from_bytes(b"caf\xe9"));
+        std::fs::create_dir(&target).unwrap();
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        assert!(matches!(
+            handle_scope_move_command(&mut store, "/gone/project", link.to_str().unwrap()),
+            Err(AkaError::InvalidScopePath(_))
+        ));
+    }
+}
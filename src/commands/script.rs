@@ -0,0 +1,237 @@
+use crate::Store;
+use crate::error::AkaError;
+use crate::store::AliasScope;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Starting point for `aka add --script`.
+const TEMPLATE: &str = "#!/usr/bin/env bash\n# Arguments are passed as \"$@\"\n\n";
+
+/// Directory holding the bodies of script-backed aliases.
+pub fn scripts_dir() -> std::result::Result<PathBuf, AkaError> {
+    Ok(crate::store::data_dir()?.join("scripts"))
+}
+
+/// Quote `value` for the shell unless it only holds characters that never need it.
+pub(crate) fn shell_quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | '+'))
+    {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// Reverse [`shell_quote`] for a command that is a single quoted word.
+fn shell_unquote(value: &str) -> String {
+    match value
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))
+    {
+        Some(inner) => inner.replace(r"'\''", "'"),
+        None => value.to_string(),
+    }
+}
+
+/// The script a definition runs, when its command is a script file in `dir`.
+pub fn script_file(command: &str, dir: &Path) -> Option<PathBuf> {
+    let path = PathBuf::from(shell_unquote(command));
+    (path.parent() == Some(dir)).then_some(path)
+}
+
+/// Open `path` in `$VISUAL` or `$EDITOR` (falling back to `vi`) and wait for it to exit.
+pub fn open_in_editor(path: &Path) -> std::result::Result<(), AkaError> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or_else(|| "vi".to_string());
+    // Through the shell so that editors configured with flags (`code --wait`) work
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(path)
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AkaError::OperationCancelled)
+    }
+}
+
+/// Let the user write a new script in their editor, starting from a template.
+///
+/// Leaving the template untouched cancels.
+pub fn compose_script(
+    dir: &Path,
+    edit: impl Fn(&Path) -> std::result::Result<(), AkaError>,
+) -> std::result::Result<String, AkaError> {
+    std::fs::create_dir_all(dir)?;
+    let draft = dir.join(format!(".draft-{}", std::process::id()));
+    std::fs::write(&draft, TEMPLATE)?;
+    let edited = edit(&draft).and_then(|_| Ok(std::fs::read_to_string(&draft)?));
+    let _ = std::fs::remove_file(&draft);
+
+    let body = edited?;
+    if body.trim() == TEMPLATE.trim() || body.trim().is_empty() {
+        return Err(AkaError::OperationCancelled);
+    }
+    Ok(body)
+}
+
+/// Delete scripts in `dir` that no stored definition runs any more.
+pub fn prune_scripts(store: &Store, dir: &Path) -> std::result::Result<usize, AkaError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let used: HashSet<PathBuf> = store
+        .list()?
+        .values()
+        .flatten()
+        .filter_map(|def| script_file(&def.command, dir))
+        .collect();
+
+    let mut pruned = 0;
+    for entry in entries {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if !hidden && path.is_file() && !used.contains(&path) {
+            std::fs::remove_file(&path)?;
+            pruned += 1;
+        }
+    }
+    Ok(pruned)
+}
+
+/// File name for the script of `alias` in `scope`.
+///
+/// Scoped definitions get a suffix derived from their path so that each scope keeps its own
+/// script; FNV-1a is used because it is stable across builds.
+pub(crate) fn script_name(alias: &str, scope: &AliasScope) -> String {
+    let path = match scope {
+        AliasScope::Global => return alias.to_string(),
+        AliasScope::Exact(path) => format!("exact:{}", path),
+        AliasScope::Recursive(path) => format!("recursive:{}", path),
+        AliasScope::Tmux(session) => format!("tmux:{}", session),
+    };
+    let hash = path.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{}-{:08x}", alias, hash as u32)
+}
+
+/// Save `body` as an executable script for `alias` in `dir` and return its path.
+pub fn write_script(
+    dir: &Path,
+    alias: &str,
+    scope: &AliasScope,
+    body: &str,
+) -> std::result::Result<PathBuf, AkaError> {
+    if alias.contains(['/', '\0']) || alias.starts_with('.') {
+        return Err(AkaError::ConfigError(format!(
+            "'{}' cannot be used as a script name",
+            alias
+        )));
+    }
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(script_name(alias, scope));
+
+    let mut content = String::new();
+    if !body.starts_with("#!") {
+        content.push_str("#!/usr/bin/env bash\n");
+    }
+    content.push_str(body);
+    if !body.ends_with('\n') {
+        content.push('\n');
+    }
+    std::fs::write(&path, content)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_script() {
+        let dir = tempdir().unwrap();
+        let global =
+            write_script(dir.path(), "deploy", &AliasScope::Global, "echo \"$1\"").unwrap();
+        assert_eq!(global, dir.path().join("deploy"));
+        assert_eq!(
+            std::fs::read_to_string(&global).unwrap(),
+            "#!/usr/bin/env bash\necho \"$1\"\n"
+        );
+
+        let scoped = write_script(
+            dir.path(),
+            "deploy",
+            &AliasScope::Recursive("/work/app".to_string()),
+            "#!/bin/sh\necho scoped\n",
+        )
+        .unwrap();
+        assert_ne!(scoped, global);
+        assert_eq!(
+            std::fs::read_to_string(&scoped).unwrap(),
+            "#!/bin/sh\necho scoped\n"
+        );
+
+        assert!(write_script(dir.path(), "../x", &AliasScope::Global, "true").is_err());
+    }
+
+    #[test]
+    fn test_compose_and_prune_scripts() {
+        let dir = tempdir().unwrap();
+        let scripts = dir.path().join("scripts");
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+
+        let body = compose_script(&scripts, |path| {
+            let draft = std::fs::read_to_string(path).unwrap();
+            std::fs::write(path, format!("{}make deploy\n", draft)).unwrap();
+            Ok(())
+        })
+        .unwrap();
+        assert!(body.ends_with("\nmake deploy\n"));
+        assert!(matches!(
+            compose_script(&scripts, |_| Ok(())),
+            Err(AkaError::OperationCancelled)
+        ));
+
+        let path = write_script(&scripts, "deploy", &AliasScope::Global, &body).unwrap();
+        let command = shell_quote(&path.to_string_lossy());
+        assert_eq!(script_file(&command, &scripts), Some(path.clone()));
+        store
+            .add("deploy".to_string(), command, AliasScope::Global)
+            .unwrap();
+        write_script(&scripts, "old", &AliasScope::Global, "true").unwrap();
+
+        assert_eq!(prune_scripts(&store, &scripts).unwrap(), 1);
+        assert!(path.exists());
+        assert!(!scripts.join("old").exists());
+        assert_eq!(std::fs::read_dir(&scripts).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/usr/local/bin"), "/usr/local/bin");
+        assert_eq!(
+            shell_quote("/Library/Application Support/it's"),
+            r"'/Library/Application Support/it'\''s'"
+        );
+        assert_eq!(shell_unquote(&shell_quote("/a b/it's")), "/a b/it's");
+    }
+}
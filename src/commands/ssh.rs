@@ -0,0 +1,71 @@
+use crate::Store;
+use crate::codegen::Shell;
+use crate::commands::effective::handle_effective_command;
+use crate::commands::script::shell_quote;
+use crate::error::AkaError;
+
+/// Command for the remote login shell that starts an interactive bash reading `aliases`
+/// after the user's own `~/.bashrc`.
+///
+/// The rcfile is handed over through process substitution, so nothing is written on the
+/// remote host; the login shell only needs to understand POSIX quoting.
+fn remote_command(aliases: &str) -> String {
+    let rcfile = format!("[ -f ~/.bashrc ] && . ~/.bashrc\n{}\n", aliases);
+    let bash = format!(
+        "exec bash --rcfile <(printf '%s' {}) -i",
+        shell_quote(&rcfile)
+    );
+    format!("exec bash -c {}", shell_quote(&bash))
+}
+
+/// Open an SSH session to `host` with the aliases active in `current_dir` defined in its
+/// shell. `ssh_args` are passed to ssh before the host.
+pub fn handle_ssh_command(
+    store: &Store,
+    host: &str,
+    ssh_args: &[String],
+    current_dir: &str,
+) -> std::result::Result<String, AkaError> {
+    let aliases = handle_effective_command(store, Shell::Bash, current_dir)?;
+    std::process::Command::new("ssh")
+        .args(ssh_args)
+        .arg("-t")
+        .arg(host)
+        .arg(remote_command(&aliases))
+        .status()?;
+    Ok(String::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_command_defines_aliases() {
+        let home = tempfile::tempdir().unwrap();
+        std::fs::write(home.path().join(".bashrc"), "export FROM_BASHRC=yes\n").unwrap();
+        let aliases = "alias hi='echo \"it'\\''s\" $FROM_BASHRC'\nsay() { echo \"${1}\"; }";
+
+        let Ok(output) = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(remote_command(aliases))
+            .env("HOME", home.path())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .and_then(|mut child| {
+                use std::io::Write;
+                child
+                    .stdin
+                    .take()
+                    .unwrap()
+                    .write_all(b"hi\nsay there\nexit\n")?;
+                child.wait_with_output()
+            })
+        else {
+            return;
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "it's yes\nthere\n");
+    }
+}
@@ -0,0 +1,132 @@
+use crate::Store;
+use crate::store::{AliasDefinition, resolve_definition, sort_by_precedence};
+use std::collections::HashMap;
+
+/// Typing speed used for the time estimate: 40 words per minute of five characters.
+const CHARS_PER_MINUTE: u64 = 200;
+
+/// How many aliases the time-saved report lists.
+const TOP_ALIASES: usize = 10;
+
+/// Report alias usage from the local usage log, optionally as an estimate of typing saved.
+pub fn handle_stats_command(
+    store: &Store,
+    counts: &HashMap<String, u64>,
+    current_dir: &str,
+    time_saved: bool,
+) -> std::result::Result<String, crate::error::AkaError> {
+    if counts.is_empty() {
+        return Ok(
+            "No usage recorded yet (export AKA_USAGE_LOG in your shell to start tracking)"
+                .to_string(),
+        );
+    }
+
+    let mut aliases = store.list()?;
+    crate::system::merge_system_aliases(&mut aliases);
+
+    if !time_saved {
+        let mut rows: Vec<(&String, u64)> = aliases
+            .keys()
+            .map(|alias| (alias, counts.get(alias).copied().unwrap_or(0)))
+            .collect();
+        rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let width = rows.iter().map(|(a, _)| a.len()).max().unwrap_or(0);
+        return Ok(rows
+            .iter()
+            .map(|(alias, uses)| format!("{:width$} {} use(s)", alias, uses, width = width))
+            .collect::<Vec<_>>()
+            .join("\n"));
+    }
+
+    let mut rows: Vec<(String, u64, u64)> = aliases
+        .into_iter()
+        .filter_map(|(alias, defs)| {
+            let uses = *counts.get(&alias)?;
+            let saved = chars_saved_per_use(&alias, defs, current_dir);
+            Some((alias, uses, saved))
+        })
+        .collect();
+    rows.sort_by(|a, b| (b.1 * b.2).cmp(&(a.1 * a.2)).then(a.0.cmp(&b.0)));
+
+    let total: u64 = rows.iter().map(|(_, uses, saved)| uses * saved).sum();
+    let width = rows.iter().map(|(a, _, _)| a.len()).max().unwrap_or(0);
+
+    let mut output = String::from("Estimated typing saved:\n");
+    for (alias, uses, saved) in rows.iter().take(TOP_ALIASES) {
+        output.push_str(&format!(
+            "{:width$} {} x {} chars = {}\n",
+            alias,
+            uses,
+            saved,
+            uses * saved,
+            width = width
+        ));
+    }
+    output.push_str(&format!(
+        "Total: {} characters (~{} at 40 wpm)",
+        total,
+        format_duration(total * 60 / CHARS_PER_MINUTE)
+    ));
+    Ok(output)
+}
+
+/// Characters saved each time `alias` is typed instead of the command it runs here.
+fn chars_saved_per_use(alias: &str, mut defs: Vec<AliasDefinition>, current_dir: &str) -> u64 {
+    sort_by_precedence(&mut defs);
+    let Some(def) = resolve_definition(&defs, current_dir).or(defs.first()) else {
+        return 0;
+    };
+    def.command
+        .chars()
+        .count()
+        .saturating_sub(alias.chars().count()) as u64
+}
+
+fn format_duration(seconds: u64) -> String {
+    match seconds {
+        s if s < 60 => format!("{} s", s),
+        s if s < 3600 => format!("{} min", s / 60),
+        s => format!("{} h {} min", s / 3600, s % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_time_saved_estimate() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add(
+                "g".to_string(),
+                "git status".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+        store
+            .add("ll".to_string(), "ls -la".to_string(), AliasScope::Global)
+            .unwrap();
+
+        let counts = HashMap::from([("g".to_string(), 30), ("ll".to_string(), 10)]);
+        let report = handle_stats_command(&store, &counts, "/", true).unwrap();
+
+        // g: 30 x 9, ll: 10 x 4 -> 310 characters, 93 seconds of typing
+        assert!(report.contains("g  30 x 9 chars = 270"));
+        assert!(report.contains("ll 10 x 4 chars = 40"));
+        assert!(report.ends_with("Total: 310 characters (~1 min at 40 wpm)"));
+    }
+
+    #[test]
+    fn test_stats_without_usage() {
+        let dir = tempdir().unwrap();
+        let store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        let report = handle_stats_command(&store, &HashMap::new(), "/", false).unwrap();
+        assert!(report.starts_with("No usage recorded yet"));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::Store;
+use crate::store::{AliasScope, sort_by_precedence};
+use std::collections::HashMap;
+
+/// Pick a rarely used alias as a reminder, suitable for a prompt or MOTD.
+///
+/// The pick is drawn from the least used third of the aliases; `seed` chooses among them, so
+/// passing the current day gives an "alias of the day". Returns an empty string when there
+/// are no aliases, keeping prompts quiet.
+pub fn handle_tip_command(
+    store: &Store,
+    counts: &HashMap<String, u64>,
+    seed: u64,
+) -> std::result::Result<String, crate::error::AkaError> {
+    let mut aliases: Vec<_> = store.list()?.into_iter().collect();
+    if aliases.is_empty() {
+        return Ok(String::new());
+    }
+
+    aliases.sort_by(|a, b| {
+        let uses = |alias: &String| counts.get(alias).copied().unwrap_or(0);
+        uses(&a.0).cmp(&uses(&b.0)).then(a.0.cmp(&b.0))
+    });
+    let rare = aliases.len().div_ceil(3);
+    let (alias, mut defs) = aliases.swap_remove((seed % rare as u64) as usize);
+    sort_by_precedence(&mut defs);
+
+    let def = &defs[0];
+    let location = match &def.scope {
+        AliasScope::Global => String::new(),
+        AliasScope::Exact(path) => format!(" in {}", path),
+        AliasScope::Recursive(path) => format!(" under {}", path),
+        AliasScope::Tmux(session) => format!(" in tmux session {}", session),
+    };
+    Ok(format!(
+        "Tip: '{}' runs '{}'{}",
+        alias, def.command, location
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_tip_prefers_rarely_used_aliases() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        for (alias, command) in [("a", "echo a"), ("b", "echo b"), ("c", "echo c")] {
+            store
+                .add(alias.to_string(), command.to_string(), AliasScope::Global)
+                .unwrap();
+        }
+        store
+            .add(
+                "d".to_string(),
+                "make deploy".to_string(),
+                AliasScope::Recursive("/srv/app".to_string()),
+            )
+            .unwrap();
+
+        let counts = HashMap::from([
+            ("a".to_string(), 50),
+            ("b".to_string(), 40),
+            ("c".to_string(), 30),
+        ]);
+
+        // The rarest third of four aliases is two: d (never used) and c
+        assert_eq!(
+            handle_tip_command(&store, &counts, 0).unwrap(),
+            "Tip: 'd' runs 'make deploy' under /srv/app"
+        );
+        assert_eq!(
+            handle_tip_command(&store, &counts, 1).unwrap(),
+            "Tip: 'c' runs 'echo c'"
+        );
+    }
+
+    #[test]
+    fn test_tip_is_silent_without_aliases() {
+        let dir = tempdir().unwrap();
+        let store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        assert!(
+            handle_tip_command(&store, &HashMap::new(), 7)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use crate::Store;
+use crate::error::AkaError;
+use std::collections::BTreeMap;
+
+const VAR_PREFIX: &str = "@{var:";
+
+/// Whether `name` is usable as a variable: letters, digits and `_`, not starting with a digit.
+fn is_var_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether `command` references any template variable.
+pub fn uses_vars(command: &str) -> bool {
+    command.contains(VAR_PREFIX)
+}
+
+/// Replace `@{var:NAME}` references with their values.
+///
+/// References to undefined variables are left in place and returned so the caller can warn.
+pub fn expand_vars(command: &str, vars: &BTreeMap<String, String>) -> (String, Vec<String>) {
+    let mut output = String::with_capacity(command.len());
+    let mut missing = Vec::new();
+    let mut rest = command;
+    while let Some(start) = rest.find(VAR_PREFIX) {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + VAR_PREFIX.len()..];
+        let Some(end) = after.find('}') else {
+            output.push_str(&rest[start..]);
+            return (output, missing);
+        };
+        let name = &after[..end];
+        match vars.get(name) {
+            Some(value) => output.push_str(value),
+            None => {
+                output.push_str(&rest[start..start + VAR_PREFIX.len() + end + 1]);
+                missing.push(name.to_string());
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    (output, missing)
+}
+
+/// Aliases whose commands reference `name`.
+fn users_of(store: &Store, name: &str) -> std::result::Result<Vec<String>, AkaError> {
+    let reference = format!("{}{}}}", VAR_PREFIX, name);
+    let mut users: Vec<String> = store
+        .list()?
+        .into_iter()
+        .filter(|(_, defs)| defs.iter().any(|d| d.command.contains(&reference)))
+        .map(|(alias, _)| alias)
+        .collect();
+    users.sort();
+    Ok(users)
+}
+
+pub fn handle_var_set_command(
+    store: &mut Store,
+    name: &str,
+    value: &str,
+) -> std::result::Result<String, AkaError> {
+    if !is_var_name(name) {
+        return Err(AkaError::ConfigError(format!(
+            "Invalid variable name '{}' (use letters, digits and _)",
+            name
+        )));
+    }
+    store.set_var(name, value)?;
+
+    let users = users_of(store, name)?;
+    let mut output = format!("Set {} = '{}'", name, value);
+    if !users.is_empty() {
+        output.push_str(&format!(
+            " (used by {})\n(Reload shell to apply)",
+            users.join(", ")
+        ));
+    }
+    Ok(output)
+}
+
+pub fn handle_var_unset_command(
+    store: &mut Store,
+    name: &str,
+) -> std::result::Result<String, AkaError> {
+    if !store.remove_var(name)? {
+        return Ok(format!("Variable '{}' is not set", name));
+    }
+    let users = users_of(store, name)?;
+    let mut output = format!("Removed variable '{}'", name);
+    if !users.is_empty() {
+        output.push_str(&format!(
+            "\nWarning: still referenced by {}",
+            users.join(", ")
+        ));
+    }
+    Ok(output)
+}
+
+pub fn handle_var_list_command(store: &Store) -> std::result::Result<String, AkaError> {
+    let vars = store.vars()?;
+    if vars.is_empty() {
+        return Ok("No variables set".to_string());
+    }
+    let width = vars.keys().map(String::len).max().unwrap_or(0);
+    Ok(vars
+        .iter()
+        .map(|(name, value)| format!("{:width$} = '{}'", name, value, width = width))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_expand_vars() {
+        let vars = BTreeMap::from([("REGISTRY".to_string(), "ghcr.io/acme".to_string())]);
+        assert_eq!(
+            expand_vars("docker push @{var:REGISTRY}/app:@1", &vars),
+            ("docker push ghcr.io/acme/app:@1".to_string(), vec![])
+        );
+        assert_eq!(
+            expand_vars("echo @{var:NOPE} @{var:REGISTRY} @{var:", &vars),
+            (
+                "echo @{var:NOPE} ghcr.io/acme @{var:".to_string(),
+                vec!["NOPE".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn test_var_commands() {
+        let dir = tempdir().unwrap();
+        let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+        store
+            .add(
+                "push".to_string(),
+                "docker push @{var:REGISTRY}/app".to_string(),
+                AliasScope::Global,
+            )
+            .unwrap();
+
+        let msg = handle_var_set_command(&mut store, "REGISTRY", "ghcr.io/acme").unwrap();
+        assert_eq!(
+            msg,
+            "Set REGISTRY = 'ghcr.io/acme' (used by push)\n(Reload shell to apply)"
+        );
+        assert!(handle_var_set_command(&mut store, "1X", "v").is_err());
+        assert_eq!(
+            handle_var_list_command(&store).unwrap(),
+            "REGISTRY = 'ghcr.io/acme'"
+        );
+
+        let msg = handle_var_unset_command(&mut store, "REGISTRY").unwrap();
+        assert_eq!(
+            msg,
+            "Removed variable 'REGISTRY'\nWarning: still referenced by push"
+        );
+        assert_eq!(
+            handle_var_unset_command(&mut store, "REGISTRY").unwrap(),
+            "Variable 'REGISTRY' is not set"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use crate::codegen::Shell;
+use crate::error::AkaError;
+
+/// What `aka version` reports, for packagers and scripts checking the installed build.
+#[derive(Debug)]
+struct BuildInfo {
+    version: &'static str,
+    git_sha: &'static str,
+    build_date: &'static str,
+    shells: Vec<&'static str>,
+    store_schema_version: u32,
+}
+
+fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_sha: env!("AKA_GIT_SHA"),
+        build_date: env!("AKA_BUILD_DATE"),
+        shells: Shell::ALL.iter().map(|shell| shell.name()).collect(),
+        store_schema_version: crate::store::SCHEMA_VERSION,
+    }
+}
+
+fn render(info: &BuildInfo, json: bool) -> String {
+    if json {
+        return serde_json::json!({
+            "version": info.version,
+            "git_sha": info.git_sha,
+            "build_date": info.build_date,
+            "shells": info.shells,
+            "store_schema_version": info.store_schema_version,
+        })
+        .to_string();
+    }
+    format!(
+        "aka {} ({} {})\nshells: {}\nstore schema: {}",
+        info.version,
+        info.git_sha,
+        info.build_date,
+        info.shells.join(", "),
+        info.store_schema_version
+    )
+}
+
+/// Report the version, commit, build date, supported shells and store schema of this build.
+pub fn handle_version_command(json: bool) -> std::result::Result<String, AkaError> {
+    Ok(render(&build_info(), json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let info = BuildInfo {
+            version: "0.2.0",
+            git_sha: "abc123",
+            build_date: "2026-01-02",
+            shells: vec!["zsh", "fish"],
+            store_schema_version: 1,
+        };
+        assert_eq!(
+            render(&info, false),
+            "aka 0.2.0 (abc123 2026-01-02)\nshells: zsh, fish\nstore schema: 1"
+        );
+        let json: serde_json::Value = serde_json::from_str(&render(&info, true)).unwrap();
+        assert_eq!(json["git_sha"], "abc123");
+        assert_eq!(json["shells"], serde_json::json!(["zsh", "fish"]));
+        assert_eq!(json["store_schema_version"], 1);
+    }
+}
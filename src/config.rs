@@ -0,0 +1,342 @@
+use crate::error::AkaError;
+use crate::store::BackendKind;
+use serde::Deserialize;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+/// User settings read from `config.toml` and `config.local.toml`; every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub cli: CliConfig,
+    pub confirm: ConfirmConfig,
+    pub lint: LintConfig,
+    pub limits: LimitsConfig,
+    pub list: ListConfig,
+    pub log: LogConfig,
+    pub shell: ShellConfig,
+    pub store: StoreConfig,
+}
+
+#[derive(Debug, Default, Hash, Deserialize)]
+#[serde(default)]
+pub struct ShellConfig {
+    /// Add every alias's command, as run, to the shell history (like `add --write-history`).
+    pub write_history: bool,
+    /// Dump simple global aliases as native aliases instead of functions.
+    pub plain_aliases: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ListConfig {
+    /// Line format for `aka list`, e.g. `"{alias}\t{command}\t{scope}"` (like `list --template`).
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct StoreConfig {
+    /// Fail on records that do not decode instead of reading them as legacy global aliases.
+    pub strict: bool,
+    /// Directory holding the store, used when `aka_DATA_DIR` is unset.
+    pub data_dir: Option<PathBuf>,
+    /// Format for a newly created store, used when `AKA_BACKEND` is unset.
+    pub backend: Option<BackendKind>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// `error`, `warn`, `info`, `debug`, or `trace`; logging is off when unset.
+    pub level: Option<String>,
+    /// Log file, defaulting to `aka.log` in the state directory.
+    pub file: Option<PathBuf>,
+}
+
+/// When a prompt asks before acting: `--force` still skips it regardless.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConfirmPolicy {
+    Always,
+    Never,
+    /// Ask only when stdin is a terminal, so scripts go ahead unattended.
+    TtyOnly,
+}
+
+impl ConfirmPolicy {
+    pub fn should_ask(self) -> bool {
+        match self {
+            ConfirmPolicy::Always => true,
+            ConfirmPolicy::Never => false,
+            ConfirmPolicy::TtyOnly => std::io::stdin().is_terminal(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ConfirmConfig {
+    /// `aka remove --all`, with or without `--scope`.
+    pub remove_all: ConfirmPolicy,
+    /// `aka add` replacing the alias's definition in the same scope.
+    pub overwrite: ConfirmPolicy,
+    /// `aka add` for a name already defined in other scopes.
+    pub collision: ConfirmPolicy,
+}
+
+impl Default for ConfirmConfig {
+    fn default() -> Self {
+        ConfirmConfig {
+            remove_all: ConfirmPolicy::Always,
+            overwrite: ConfirmPolicy::Never,
+            collision: ConfirmPolicy::TtyOnly,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct CliConfig {
+    /// Treat `aka <alias> [command]` and bare `aka` as add/remove/list; off means subcommands only.
+    pub implicit: bool,
+}
+
+impl Default for CliConfig {
+    fn default() -> Self {
+        CliConfig { implicit: true }
+    }
+}
+
+/// Whether implicit add/remove/list is enabled; `AKA_IMPLICIT=0` overrides the config.
+pub fn implicit_enabled(config: &CliConfig) -> bool {
+    match std::env::var("AKA_IMPLICIT") {
+        Ok(value) if !value.trim().is_empty() => !matches!(
+            value.trim().to_lowercase().as_str(),
+            "0" | "false" | "off" | "no"
+        ),
+        _ => config.implicit,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Longest command body `aka add` accepts, in characters.
+    pub max_command_length: usize,
+    /// Most distinct alias names the store may hold.
+    pub max_aliases: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        LimitsConfig {
+            max_command_length: 1024,
+            max_aliases: 2000,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct LintConfig {
+    /// Patterns `aka lint` reports; setting this replaces the built-in list.
+    pub deprecated: Vec<DeprecatedPattern>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        let builtin = [
+            ("egrep", "grep -E"),
+            ("fgrep", "grep -F"),
+            ("docker-compose", "docker compose"),
+        ];
+        LintConfig {
+            deprecated: builtin
+                .into_iter()
+                .map(|(pattern, replacement)| DeprecatedPattern {
+                    pattern: pattern.to_string(),
+                    replacement: Some(replacement.to_string()),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A command or flag to flag in alias bodies, with an optional drop-in replacement.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeprecatedPattern {
+    pub pattern: String,
+    #[serde(default)]
+    pub replacement: Option<String>,
+}
+
+/// Resolve the config file, honoring `AKA_CONFIG` before the platform config directory.
+pub fn config_path() -> Option<PathBuf> {
+    match std::env::var("AKA_CONFIG") {
+        Ok(path) if !path.trim().is_empty() => Some(PathBuf::from(path)),
+        _ => dirs::config_dir().map(|dir| dir.join("aka").join("config.toml")),
+    }
+}
+
+/// Machine-specific overrides next to `path`: `config.toml` pairs with `config.local.toml`.
+pub fn local_config_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+        Some(ext) => path.with_file_name(format!("{}.local.{}", stem, ext.to_string_lossy())),
+        None => path.with_file_name(format!("{}.local", stem)),
+    }
+}
+
+/// Parse the TOML file at `path`, or `None` when it does not exist.
+fn read_table(path: &Path) -> std::result::Result<Option<toml::Table>, AkaError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    content.parse().map(Some).map_err(|e| {
+        AkaError::ConfigError(format!("Invalid config file {}: {}", path.display(), e))
+    })
+}
+
+/// Overlay `local` onto `base`: tables merge key by key, any other value replaces the base one.
+fn merge_tables(base: &mut toml::Table, local: toml::Table) {
+    for (key, value) in local {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(local)) => {
+                merge_tables(base, local)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Load the config at `path` with its `.local` overrides on top. Missing files mean defaults.
+pub fn load_config(path: &Path) -> std::result::Result<Config, AkaError> {
+    let local_path = local_config_path(path);
+    let mut table = read_table(path)?.unwrap_or_default();
+    if let Some(local) = read_table(&local_path)? {
+        merge_tables(&mut table, local);
+    }
+
+    toml::Value::Table(table).try_into().map_err(|e| {
+        AkaError::ConfigError(format!(
+            "Invalid config file {} (with {}): {}",
+            path.display(),
+            local_path.display(),
+            e
+        ))
+    })
+}
+
+/// Load the user's config, falling back to defaults when there is no config location.
+pub fn load() -> std::result::Result<Config, AkaError> {
+    match config_path() {
+        Some(path) => load_config(&path),
+        None => Ok(Config::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let config = load_config(&path).unwrap();
+        assert!(config.lint.deprecated.iter().any(|d| d.pattern == "egrep"));
+
+        std::fs::write(
+            &path,
+            r#"
+[lint]
+deprecated = [{ pattern = "git checkout", replacement = "git switch" }, { pattern = "--no-verify" }]
+"#,
+        )
+        .unwrap();
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.lint.deprecated.len(), 2);
+        assert_eq!(config.limits.max_command_length, 1024);
+        assert_eq!(config.lint.deprecated[1].replacement, None);
+        assert!(config.cli.implicit);
+        assert_eq!(config.confirm.remove_all, ConfirmPolicy::Always);
+
+        std::fs::write(
+            &path,
+            "[confirm]\nremove_all = \"tty-only\"\noverwrite = \"always\"\n",
+        )
+        .unwrap();
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.confirm.remove_all, ConfirmPolicy::TtyOnly);
+        assert_eq!(config.confirm.overwrite, ConfirmPolicy::Always);
+        assert_eq!(config.confirm.collision, ConfirmPolicy::TtyOnly);
+        assert_eq!(config.log.level, None);
+        assert!(!config.store.strict);
+        assert!(!config.shell.write_history);
+        assert_eq!(config.list.template, None);
+
+        std::fs::write(&path, "[cli]\nimplicit = false\n").unwrap();
+        assert!(!load_config(&path).unwrap().cli.implicit);
+
+        std::fs::write(&path, "[lint\n").unwrap();
+        assert!(matches!(
+            load_config(&path),
+            Err(AkaError::ConfigError(msg)) if msg.contains("Invalid config file")
+        ));
+
+        std::fs::write(&path, "[store]\nstrict = \"yes\"\n").unwrap();
+        assert!(matches!(
+            load_config(&path),
+            Err(AkaError::ConfigError(msg)) if msg.contains("Invalid config file")
+        ));
+    }
+
+    #[test]
+    fn test_local_config_overrides() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let local = dir.path().join("config.local.toml");
+        assert_eq!(local_config_path(&path), local);
+
+        std::fs::write(
+            &path,
+            "[store]\nstrict = true\ndata_dir = \"/shared/aka\"\n\n[confirm]\noverwrite = \"always\"\n",
+        )
+        .unwrap();
+        std::fs::write(&local, "[store]\ndata_dir = \"/machine/aka\"\n").unwrap();
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.store.data_dir, Some(PathBuf::from("/machine/aka")));
+        assert!(config.store.strict);
+        assert_eq!(config.confirm.overwrite, ConfirmPolicy::Always);
+
+        // The local file alone is enough, e.g. before the shared config is synced
+        std::fs::remove_file(&path).unwrap();
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.store.data_dir, Some(PathBuf::from("/machine/aka")));
+        assert!(!config.store.strict);
+
+        std::fs::write(&local, "[store]\nbackend = \"json\"\n").unwrap();
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.store.backend, Some(BackendKind::Json));
+
+        std::fs::write(&local, "[store]\nbackend = \"lmdb\"\n").unwrap();
+        assert!(matches!(
+            load_config(&path),
+            Err(AkaError::ConfigError(msg)) if msg.contains("unknown backend 'lmdb'")
+        ));
+
+        std::fs::write(&local, "[store]\nstrict = 1\n").unwrap();
+        assert!(matches!(
+            load_config(&path),
+            Err(AkaError::ConfigError(msg)) if msg.contains("config.local.toml")
+        ));
+    }
+}
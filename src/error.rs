@@ -23,6 +23,10 @@ pub enum AkaError {
     #[error("Storage error: {0}")]
     StorageError(#[from] redb::StorageError),
 
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    SqliteError(#[from] rusqlite::Error),
+
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
@@ -32,12 +36,101 @@ pub enum AkaError {
     #[error("No definition found for alias '{0}' in scope '{1}'")]
     ScopeNotFoundInAlias(String, String),
 
+    #[error("Alias '{0}' is provided by the system layer and is read-only")]
+    SystemAlias(String),
+
+    #[error("The store is read-only ({0}); this command would change it")]
+    ReadOnly(String),
+
     #[error("Invalid scope path: {0}")]
     InvalidScopePath(String),
 
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+
+    #[error(
+        "'{0}' is neither an alias nor a command; did you mean {commands}?",
+        commands = .1.iter().map(|c| format!("'aka {}'", c)).collect::<Vec<_>>().join(" or ")
+    )]
+    UnknownCommand(String, Vec<String>),
+
+    #[error("Corrupt record for alias '{alias}': {raw} (see `aka doctor`)")]
+    CorruptRecord { alias: String, raw: String },
+
     #[error("Operation cancelled")]
     OperationCancelled,
 
     #[error("Unknown error: {0}")]
     Other(#[from] anyhow::Error),
 }
+
+impl AkaError {
+    /// Stable identifier for the error kind, e.g. `alias_not_found`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AkaError::DatabaseError(_)
+            | AkaError::RedbError(_)
+            | AkaError::TransactionError(_)
+            | AkaError::TableError(_)
+            | AkaError::CommitError(_)
+            | AkaError::StorageError(_) => "database_error",
+            #[cfg(feature = "sqlite")]
+            AkaError::SqliteError(_) => "database_error",
+            AkaError::IoError(_) => "io_error",
+            AkaError::ConfigError(_) => "config_error",
+            AkaError::AliasNotFound(_) => "alias_not_found",
+            AkaError::ScopeNotFoundInAlias(_, _) => "scope_not_found",
+            AkaError::SystemAlias(_) => "system_alias",
+            AkaError::ReadOnly(_) => "read_only",
+            AkaError::InvalidScopePath(_) => "invalid_scope_path",
+            AkaError::LimitExceeded(_) => "limit_exceeded",
+            AkaError::UnknownCommand(_, _) => "unknown_command",
+            AkaError::CorruptRecord { .. } => "corrupt_record",
+            AkaError::OperationCancelled => "cancelled",
+            AkaError::Other(_) => "error",
+        }
+    }
+
+    /// The alias and scope an error is about, where it names them.
+    fn context(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut context = serde_json::Map::new();
+        match self {
+            AkaError::AliasNotFound(alias) | AkaError::SystemAlias(alias) => {
+                context.insert("alias".to_string(), alias.clone().into());
+            }
+            AkaError::ScopeNotFoundInAlias(alias, scope) => {
+                context.insert("alias".to_string(), alias.clone().into());
+                context.insert("scope".to_string(), scope.clone().into());
+            }
+            AkaError::UnknownCommand(alias, suggestions) => {
+                context.insert("alias".to_string(), alias.clone().into());
+                context.insert("suggestions".to_string(), suggestions.clone().into());
+            }
+            AkaError::CorruptRecord { alias, raw } => {
+                context.insert("alias".to_string(), alias.clone().into());
+                context.insert("raw".to_string(), raw.clone().into());
+            }
+            AkaError::InvalidScopePath(scope) => {
+                context.insert("scope".to_string(), scope.clone().into());
+            }
+            _ => {}
+        }
+        context
+    }
+}
+
+/// Render `error` as the one-line JSON object written to stderr under `--output json`.
+pub fn error_json(error: &anyhow::Error) -> String {
+    let (code, context) = match error.downcast_ref::<AkaError>() {
+        Some(e) => (e.code(), e.context()),
+        None => ("error", serde_json::Map::new()),
+    };
+    serde_json::json!({
+        "error": {
+            "code": code,
+            "message": error.to_string(),
+            "context": context,
+        }
+    })
+    .to_string()
+}
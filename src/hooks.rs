@@ -0,0 +1,182 @@
+//! User scripts run after a command changes the store: executables named after the event in
+//! the `hooks` directory next to the config file, e.g. `~/.config/aka/hooks/post-add`.
+//!
+//! A hook gets the change as JSON on stdin, and `AKA_HOOK` set to the event name. What it
+//! prints goes to stderr, so it never mixes with aka's own output.
+
+use crate::error::AkaError;
+use crate::store::AliasDefinition;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Changes a hook can be run after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// An alias was added or given another definition
+    PostAdd,
+    /// Aliases or definitions were removed
+    PostRemove,
+    /// Aliases were synced from a project, an import or a generator, or rewritten in bulk by
+    /// `scope move`, `lint --fix`, `doctor` or `migrate`
+    PostSync,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PostAdd => "post-add",
+            Self::PostRemove => "post-remove",
+            Self::PostSync => "post-sync",
+        }
+    }
+}
+
+/// The `hooks` directory next to the config file.
+pub fn hooks_dir() -> Option<PathBuf> {
+    Some(crate::config::config_path()?.parent()?.join("hooks"))
+}
+
+/// The hook for `event` in `dir`, when there is an executable file for it.
+pub fn find(dir: &Path, event: HookEvent) -> Option<PathBuf> {
+    let path = dir.join(event.name());
+    let metadata = std::fs::metadata(&path).ok()?;
+    #[cfg(unix)]
+    let executable = {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    };
+    #[cfg(not(unix))]
+    let executable = true;
+    (metadata.is_file() && executable).then_some(path)
+}
+
+/// What a hook is told about `event`: the definitions of each alias that changed, before and
+/// after, or `None` when nothing did.
+pub fn payload(
+    event: HookEvent,
+    before: &HashMap<String, Vec<AliasDefinition>>,
+    after: &HashMap<String, Vec<AliasDefinition>>,
+) -> Option<serde_json::Value> {
+    let none = Vec::new();
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let aliases: serde_json::Map<String, serde_json::Value> = names
+        .into_iter()
+        .filter(|alias| before.get(*alias) != after.get(*alias))
+        .map(|alias| {
+            let change = serde_json::json!({
+                "before": before.get(alias).unwrap_or(&none),
+                "after": after.get(alias).unwrap_or(&none),
+            });
+            (alias.clone(), change)
+        })
+        .collect();
+    if aliases.is_empty() {
+        return None;
+    }
+    Some(serde_json::json!({ "event": event.name(), "aliases": aliases }))
+}
+
+/// What a hook is told after a command that rewrote the store without changing any alias,
+/// such as `aka migrate`.
+pub fn unchanged_payload(event: HookEvent) -> serde_json::Value {
+    serde_json::json!({ "event": event.name(), "aliases": {} })
+}
+
+/// Like [`payload`], for a command that can both add and remove aliases, such as `aka edit`:
+/// `post-add` is told about the aliases that still have definitions, `post-remove` about the
+/// ones that are gone.
+pub fn split_payload(
+    event: HookEvent,
+    before: &HashMap<String, Vec<AliasDefinition>>,
+    after: &HashMap<String, Vec<AliasDefinition>>,
+) -> Option<serde_json::Value> {
+    let removed = event == HookEvent::PostRemove;
+    let part = |aliases: &HashMap<String, Vec<AliasDefinition>>| -> HashMap<_, _> {
+        aliases
+            .iter()
+            .filter(|(alias, _)| after.contains_key(*alias) != removed)
+            .map(|(alias, defs)| (alias.clone(), defs.clone()))
+            .collect()
+    };
+    payload(event, &part(before), &part(after))
+}
+
+/// Run `hook` for `event`, writing `payload` to its stdin, and wait for it to finish.
+pub fn run(
+    hook: &Path,
+    event: HookEvent,
+    payload: &serde_json::Value,
+) -> std::result::Result<(), AkaError> {
+    let mut child = Command::new(hook)
+        .env("AKA_HOOK", event.name())
+        .stdin(Stdio::piped())
+        .stdout(std::io::stderr())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that ignores the change may exit before reading it
+        match stdin.write_all(format!("{}\n", payload).as_bytes()) {
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.into()),
+            _ => {}
+        }
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(AkaError::ConfigError(format!(
+            "hook {} failed ({})",
+            hook.display(),
+            status
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+
+    #[test]
+    fn test_payload_lists_changed_aliases() {
+        let def = |command: &str| AliasDefinition::new(command.to_string(), AliasScope::Global);
+        let before = HashMap::from([
+            ("g".to_string(), vec![def("git")]),
+            ("old".to_string(), vec![def("ls")]),
+        ]);
+        let after = HashMap::from([
+            ("g".to_string(), vec![def("git")]),
+            ("new".to_string(), vec![def("echo")]),
+        ]);
+        let change = payload(HookEvent::PostSync, &before, &after).unwrap();
+        assert_eq!(change["event"], "post-sync");
+        let aliases = change["aliases"].as_object().unwrap();
+        assert_eq!(aliases.keys().collect::<Vec<_>>(), ["new", "old"]);
+        assert_eq!(aliases["new"]["before"], serde_json::json!([]));
+        assert_eq!(aliases["new"]["after"][0]["command"], "echo");
+        assert_eq!(aliases["old"]["after"], serde_json::json!([]));
+
+        assert!(payload(HookEvent::PostAdd, &before, &before).is_none());
+    }
+
+    #[test]
+    fn test_split_payload_tells_each_hook_its_part() {
+        let def = |command: &str| AliasDefinition::new(command.to_string(), AliasScope::Global);
+        let before = HashMap::from([
+            ("g".to_string(), vec![def("git")]),
+            ("old".to_string(), vec![def("ls")]),
+        ]);
+        let after = HashMap::from([
+            ("g".to_string(), vec![def("git status")]),
+            ("new".to_string(), vec![def("echo")]),
+        ]);
+        let added = split_payload(HookEvent::PostAdd, &before, &after).unwrap();
+        let names: Vec<&String> = added["aliases"].as_object().unwrap().keys().collect();
+        assert_eq!(names, ["g", "new"]);
+        let removed = split_payload(HookEvent::PostRemove, &before, &after).unwrap();
+        let names: Vec<&String> = removed["aliases"].as_object().unwrap().keys().collect();
+        assert_eq!(names, ["old"]);
+
+        assert!(split_payload(HookEvent::PostRemove, &after, &after).is_none());
+    }
+}
@@ -1,9 +1,23 @@
+#[cfg(feature = "cli")]
 pub mod cli;
+pub mod codegen;
+#[cfg(feature = "cli")]
 pub mod commands;
+pub mod config;
 pub mod error;
+#[cfg(feature = "cli")]
+pub mod hooks;
+#[cfg(feature = "cli")]
+pub mod logging;
+pub mod references;
 pub mod store;
+pub mod system;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod usage;
 
 pub use anyhow::Result;
+#[cfg(feature = "cli")]
 pub use cli::run_cli;
 pub use error::AkaError;
 pub use store::Store;
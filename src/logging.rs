@@ -0,0 +1,74 @@
+use crate::config::LogConfig;
+use crate::error::AkaError;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
+
+/// `aka.log` in the state directory (`~/.local/state/aka`), or the data dir where there is none.
+pub fn log_path(config: &LogConfig) -> std::result::Result<PathBuf, AkaError> {
+    match (&config.file, dirs::state_dir()) {
+        (Some(file), _) => Ok(file.clone()),
+        (None, Some(dir)) => Ok(dir.join("aka").join("aka.log")),
+        (None, None) => Ok(crate::store::data_dir()?.join("aka.log")),
+    }
+}
+
+/// The level to log at: `--log-level` wins over the config, and logging is off without either.
+pub fn log_level(
+    flag: Option<&str>,
+    config: &LogConfig,
+) -> std::result::Result<LevelFilter, AkaError> {
+    match flag.or(config.level.as_deref()) {
+        Some(level) => level.parse().map_err(|_| {
+            AkaError::ConfigError(format!(
+                "Invalid log level '{}' (expected off, error, warn, info, debug, or trace)",
+                level
+            ))
+        }),
+        None => Ok(LevelFilter::OFF),
+    }
+}
+
+/// Append log records at `level` and above to `path` for the rest of the process.
+pub fn init(level: LevelFilter, path: &Path) -> std::result::Result<(), AkaError> {
+    if level == LevelFilter::OFF {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    // Only fails when a subscriber is already installed, which then keeps logging
+    let _ = tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_max_level(level)
+        .try_init();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_level() {
+        let config = LogConfig {
+            level: Some("info".to_string()),
+            file: None,
+        };
+        assert_eq!(log_level(None, &config).unwrap(), LevelFilter::INFO);
+        assert_eq!(
+            log_level(Some("debug"), &config).unwrap(),
+            LevelFilter::DEBUG
+        );
+        assert_eq!(
+            log_level(None, &LogConfig::default()).unwrap(),
+            LevelFilter::OFF
+        );
+        assert!(log_level(Some("loud"), &config).is_err());
+    }
+}
@@ -0,0 +1,303 @@
+use crate::store::{AliasDefinition, AliasScope, resolve_definition};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Start of a reference to another alias, as written by `aka add --then`.
+pub const ALIAS_REF_PREFIX: &str = "@{alias:";
+
+/// Text referring to `alias` from another alias's command.
+pub fn alias_ref(alias: &str) -> String {
+    format!("{}{}}}", ALIAS_REF_PREFIX, alias)
+}
+
+/// Whether `command` references any other alias with `@{alias:NAME}`.
+pub fn uses_alias_refs(command: &str) -> bool {
+    command.contains(ALIAS_REF_PREFIX)
+}
+
+/// Names referenced with `@{alias:NAME}` in `command`, in order.
+pub fn referenced_aliases(command: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = command;
+    while let Some(start) = rest.find(ALIAS_REF_PREFIX) {
+        let after = &rest[start + ALIAS_REF_PREFIX.len()..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        names.push(&after[..end]);
+        rest = &after[end + 1..];
+    }
+    names
+}
+
+/// Replace the `@{alias:NAME}` references in `def` with the command NAME runs in the same
+/// scope, so composites pick up changes to the aliases they are built from.
+///
+/// A reference that cannot be expanded (an undefined alias, no definition for the scope, or
+/// a cycle) becomes a call to NAME by name, and is described in the returned warnings.
+pub fn expand_alias_refs(
+    alias: &str,
+    def: &AliasDefinition,
+    aliases: &HashMap<String, Vec<AliasDefinition>>,
+) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut stack = vec![alias.to_string()];
+    let command = expand_refs(&def.command, &def.scope, aliases, &mut stack, &mut warnings);
+    (command, warnings)
+}
+
+fn expand_refs(
+    command: &str,
+    scope: &AliasScope,
+    aliases: &HashMap<String, Vec<AliasDefinition>>,
+    stack: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) -> String {
+    let mut output = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(start) = rest.find(ALIAS_REF_PREFIX) {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + ALIAS_REF_PREFIX.len()..];
+        let Some(end) = after.find('}') else {
+            output.push_str(&rest[start..]);
+            return output;
+        };
+        let name = &after[..end];
+        let target = aliases
+            .get(name)
+            .and_then(|defs| definition_for(defs, scope));
+        let expanded = match target {
+            _ if stack.iter().any(|caller| caller == name) => {
+                warnings.push(format!("'{}' refers to '{}' in a cycle", stack[0], name));
+                None
+            }
+            None if aliases.contains_key(name) => {
+                warnings.push(format!(
+                    "'{}' refers to '{}', which has no definition for its scope",
+                    stack[0], name
+                ));
+                None
+            }
+            None => {
+                warnings.push(format!(
+                    "'{}' refers to undefined alias '{}'",
+                    stack[0], name
+                ));
+                None
+            }
+            Some(target) => {
+                stack.push(name.to_string());
+                let text = expand_refs(&target.command, scope, aliases, stack, warnings);
+                stack.pop();
+                Some(text)
+            }
+        };
+        match expanded {
+            Some(text) if segments(&text).len() > 1 => {
+                output.push_str(&format!("{{ {}; }}", text.trim()));
+            }
+            Some(text) => output.push_str(text.trim()),
+            None => output.push_str(name),
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// The definition of a referenced alias that applies where a composite with `scope` runs.
+fn definition_for<'a>(
+    defs: &'a [AliasDefinition],
+    scope: &AliasScope,
+) -> Option<&'a AliasDefinition> {
+    match scope {
+        AliasScope::Exact(path) | AliasScope::Recursive(path) => resolve_definition(defs, path),
+        AliasScope::Tmux(_) => defs
+            .iter()
+            .find(|def| def.scope == *scope)
+            .or_else(|| defs.iter().find(|def| def.scope == AliasScope::Global)),
+        AliasScope::Global => defs.iter().find(|def| def.scope == AliasScope::Global),
+    }
+}
+
+/// Split a command line at unquoted `|`, `&` and `;` into the commands it runs.
+pub(crate) fn segments(command: &str) -> Vec<String> {
+    let mut segments = vec![String::new()];
+    let mut in_single_quote = false;
+    let mut in_double_quote = false;
+    let mut escaped = false;
+
+    for c in command.chars() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && !in_single_quote {
+            escaped = true;
+        } else if c == '\'' && !in_double_quote {
+            in_single_quote = !in_single_quote;
+        } else if c == '"' && !in_single_quote {
+            in_double_quote = !in_double_quote;
+        } else if matches!(c, '|' | '&' | ';') && !in_single_quote && !in_double_quote {
+            segments.push(String::new());
+            continue;
+        }
+        segments.last_mut().unwrap().push(c);
+    }
+    segments
+}
+
+/// The program a segment runs, skipping leading `VAR=value` assignments.
+pub(crate) fn program_name(segment: &str) -> Option<&str> {
+    segment.split_whitespace().find(|word| {
+        !word.split_once('=').is_some_and(|(name, _)| {
+            !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        })
+    })
+}
+
+/// Other aliases that `command` runs, in order of first appearance.
+pub fn called_aliases<'a>(
+    command: &str,
+    aliases: &'a HashMap<String, Vec<AliasDefinition>>,
+) -> Vec<&'a str> {
+    let mut called: Vec<&str> = Vec::new();
+    for segment in segments(command) {
+        let names = referenced_aliases(&segment)
+            .into_iter()
+            .chain(program_name(&segment));
+        for name in names {
+            if let Some((alias, _)) = aliases.get_key_value(name)
+                && !called.contains(&alias.as_str())
+            {
+                called.push(alias);
+            }
+        }
+    }
+    called
+}
+
+/// Map each alias to the aliases that call it, across all of their definitions.
+pub fn callers(
+    aliases: &HashMap<String, Vec<AliasDefinition>>,
+) -> BTreeMap<String, BTreeSet<String>> {
+    let mut callers: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (caller, defs) in aliases {
+        for def in defs {
+            for callee in called_aliases(&def.command, aliases) {
+                if callee != caller {
+                    callers
+                        .entry(callee.to_string())
+                        .or_default()
+                        .insert(caller.clone());
+                }
+            }
+        }
+    }
+    callers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::AliasScope;
+
+    #[test]
+    fn test_segments_respect_quotes() {
+        assert_eq!(
+            segments("grep \"a|b\" file | wc -l"),
+            vec!["grep \"a|b\" file ", " wc -l"]
+        );
+        assert_eq!(program_name("FOO=1 BAR=2 make test"), Some("make"));
+    }
+
+    #[test]
+    fn test_callers() {
+        let def = |command: &str| {
+            vec![AliasDefinition::new(
+                command.to_string(),
+                AliasScope::Global,
+            )]
+        };
+        let aliases = HashMap::from([
+            ("g".to_string(), def("git")),
+            ("gs".to_string(), def("g status")),
+            ("sync".to_string(), def("g pull && gs | less")),
+            ("echo_g".to_string(), def("echo g")),
+        ]);
+
+        assert_eq!(
+            called_aliases("g pull && gs | less", &aliases),
+            vec!["g", "gs"]
+        );
+
+        let callers = callers(&aliases);
+        assert_eq!(callers["g"].iter().collect::<Vec<_>>(), vec!["gs", "sync"]);
+        assert_eq!(callers["gs"].iter().collect::<Vec<_>>(), vec!["sync"]);
+        assert!(!callers.contains_key("echo_g"));
+    }
+
+    #[test]
+    fn test_expand_alias_refs() {
+        let def =
+            |command: &str, scope: AliasScope| AliasDefinition::new(command.to_string(), scope);
+        let aliases = HashMap::from([
+            (
+                "build".to_string(),
+                vec![
+                    def("cargo build", AliasScope::Global),
+                    def("npm run build", AliasScope::Recursive("/web".to_string())),
+                ],
+            ),
+            (
+                "check".to_string(),
+                vec![def("cargo fmt --check; cargo clippy", AliasScope::Global)],
+            ),
+            (
+                "ci".to_string(),
+                vec![def("@{alias:build} && @{alias:check}", AliasScope::Global)],
+            ),
+            (
+                "loop".to_string(),
+                vec![def("@{alias:loop}", AliasScope::Global)],
+            ),
+            (
+                "web".to_string(),
+                vec![def("x", AliasScope::Exact("/web".to_string()))],
+            ),
+        ]);
+        let expand = |command: &str, scope: AliasScope| {
+            expand_alias_refs("new", &def(command, scope), &aliases)
+        };
+
+        assert_eq!(
+            expand("@{alias:ci} && git push", AliasScope::Global),
+            (
+                "{ cargo build && { cargo fmt --check; cargo clippy; }; } && git push".to_string(),
+                vec![]
+            )
+        );
+        assert_eq!(
+            expand("@{alias:build}", AliasScope::Exact("/web/app".to_string())).0,
+            "npm run build"
+        );
+        assert_eq!(
+            expand("@{alias:web} && @{alias:gone}", AliasScope::Global),
+            (
+                "web && gone".to_string(),
+                vec![
+                    "'new' refers to 'web', which has no definition for its scope".to_string(),
+                    "'new' refers to undefined alias 'gone'".to_string(),
+                ]
+            )
+        );
+        assert_eq!(
+            expand_alias_refs("loop", &aliases["loop"][0], &aliases),
+            (
+                "loop".to_string(),
+                vec!["'loop' refers to 'loop' in a cycle".to_string()]
+            )
+        );
+        assert_eq!(
+            referenced_aliases("@{alias:a} && b @{alias:c}"),
+            vec!["a", "c"]
+        );
+    }
+}
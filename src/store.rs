@@ -1,47 +1,766 @@
-use redb::{Database, ReadableDatabase, ReadableTable, ReadableTableMetadata, TableDefinition};
+mod backend;
+mod cache;
+
+#[cfg(feature = "sqlite")]
+use backend::SqliteBackend;
+use backend::{Backend, Change, JsonBackend, RedbBackend};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::Path};
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// Version of the layout of stored alias records; bump it when a change needs migrating.
+pub const SCHEMA_VERSION: u32 = 1;
+
+const ALIASES: &str = "aliases";
+
+/// Store-level bookkeeping such as the generation counter.
+const META: &str = "meta";
+
+const GENERATION_KEY: &str = "generation";
+
+/// Template variables referenced from commands as `@{var:NAME}`.
+const VARS: &str = "vars";
+
+/// When each alias was last added, kept by `aka review`, or seen in the usage log (unix seconds).
+const REVIEWED: &str = "reviewed";
 
-const TABLE: TableDefinition<&str, &str> = TableDefinition::new("aliases");
+/// How many usage log lines `aka review` has already credited to `REVIEWED`.
+const USAGE_OFFSET_KEY: &str = "review_usage_offset";
+
+/// Alias records set aside by `aka doctor --quarantine`, kept verbatim for manual recovery.
+const QUARANTINE: &str = "quarantine";
+
+/// Every table a store may hold; migrations copy all of them.
+const TABLES: [&str; 5] = [ALIASES, META, VARS, REVIEWED, QUARANTINE];
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AliasScope {
     Global,
     Recursive(String),
     Exact(String),
+    /// Applies inside the tmux session with this name, wherever its panes are.
+    Tmux(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AliasDefinition {
     pub command: String,
     pub scope: AliasScope,
+    /// Set for definitions merged in from the read-only system layer; never persisted.
+    #[serde(skip)]
+    pub system: bool,
+    /// Generator that owns this definition (e.g. `project`); `None` for ones added by hand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// Check against `kubectl config current-context` made before the command runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kube: Option<KubeGuard>,
+    /// Environment exported while the command runs, captured by `add --capture-env`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Add the command as run, with its arguments, to the shell history afterwards.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub write_history: bool,
+    /// Report on stderr how long the command ran.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub time: bool,
+    /// Send a desktop notification with the exit status once the command finishes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub notify: bool,
+    /// File the command's stdout and stderr are also appended to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_output: Option<String>,
+    /// Program that must be on `PATH` when the dump is generated for the definition to be
+    /// defined, so one synced store can serve machines with different tools.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requires: Option<String>,
+    /// Commands tried in order, from `add --try`, when the program `command` starts with is
+    /// not on `PATH` where the dump is generated.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<String>,
+}
+
+/// Kubernetes context condition for a definition; patterns are shell globs such as `prod-*`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum KubeGuard {
+    /// Run only while the current context matches.
+    Only(String),
+    /// Refuse to run while the current context matches.
+    Deny(String),
+}
+
+impl AliasDefinition {
+    pub fn new(command: String, scope: AliasScope) -> Self {
+        AliasDefinition {
+            command,
+            scope,
+            system: false,
+            origin: None,
+            kube: None,
+            env: BTreeMap::new(),
+            write_history: false,
+            time: false,
+            notify: false,
+            log_output: None,
+            requires: None,
+            fallbacks: Vec::new(),
+        }
+    }
+
+    /// The command to run where `on_path` tells which programs are installed: `command` or
+    /// the first of `fallbacks` whose program is, and the last one when none are.
+    pub fn available_command(&self, on_path: impl Fn(&str) -> bool) -> &str {
+        std::iter::once(&self.command)
+            .chain(&self.fallbacks)
+            .find(|command| command.split_whitespace().next().is_some_and(&on_path))
+            .or(self.fallbacks.last())
+            .unwrap_or(&self.command)
+    }
+}
+
+/// `--scope` prefix that binds a definition to a tmux session instead of a directory.
+pub const TMUX_SCOPE_PREFIX: &str = "tmux:";
+
+/// `--scope` prefix for a definition that applies in one directory only.
+pub const EXACT_SCOPE_PREFIX: &str = "exact:";
+
+/// `--scope` prefix for a definition that applies in a directory and below it.
+pub const RECURSIVE_SCOPE_PREFIX: &str = "recursive:";
+
+/// `--scope` value that resolves to the enclosing workspace root.
+pub const WORKSPACE_SCOPE: &str = "workspace";
+
+/// `--scope` value that resolves to the root of the enclosing git repository.
+pub const REPO_SCOPE: &str = "@repo";
+
+const DEFAULT_WORKSPACE_MARKERS: &[&str] = &[".git", "Cargo.toml", "package.json"];
+
+/// Marker files that identify a workspace root, overridable via `AKA_WORKSPACE_MARKERS`.
+fn workspace_markers() -> Vec<String> {
+    match std::env::var("AKA_WORKSPACE_MARKERS") {
+        Ok(value) if !value.trim().is_empty() => value
+            .split(',')
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty())
+            .collect(),
+        _ => DEFAULT_WORKSPACE_MARKERS
+            .iter()
+            .map(|m| m.to_string())
+            .collect(),
+    }
+}
+
+/// Walk up from `start` and return the nearest directory containing one of `markers`.
+pub(crate) fn find_workspace_root(start: &Path, markers: &[String]) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| markers.iter().any(|m| dir.join(m).exists()))
+        .map(Path::to_path_buf)
+}
+
+/// Name of the tmux session this process runs in, asked once from `tmux` when `TMUX` is set.
+fn current_tmux_session() -> Option<&'static str> {
+    static SESSION: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+    SESSION
+        .get_or_init(|| {
+            std::env::var_os("TMUX").filter(|v| !v.is_empty())?;
+            let output = std::process::Command::new("tmux")
+                .args(["display-message", "-p", "#S"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())?;
+            Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        })
+        .as_deref()
+}
+
+impl AliasScope {
+    /// The scope for a directory given on the command line, `Recursive` when `recursive`,
+    /// or for a tmux session given as `tmux:NAME`.
+    pub fn from_user_path(
+        path: &str,
+        recursive: bool,
+    ) -> std::result::Result<Self, crate::error::AkaError> {
+        if let Some(session) = path.strip_prefix(TMUX_SCOPE_PREFIX) {
+            if session.is_empty() || session.contains(['"', '\'', '\\', '\n']) {
+                return Err(crate::error::AkaError::InvalidScopePath(format!(
+                    "'{}' is not a usable tmux session name",
+                    session
+                )));
+            }
+            return Ok(AliasScope::Tmux(session.to_string()));
+        }
+        let path = normalize_scope_path(Path::new(path))?;
+        Ok(if recursive {
+            AliasScope::Recursive(path)
+        } else {
+            AliasScope::Exact(path)
+        })
+    }
+
+    /// Whether a definition with this scope applies in `dir`.
+    ///
+    /// Paths are compared by component, so `/tmp` covers `/tmp/x` but not `/tmpfoo`. Tmux
+    /// scopes ignore `dir` and compare against the session this process runs in.
+    pub fn matches(&self, dir: &Path) -> bool {
+        match self {
+            AliasScope::Global => true,
+            AliasScope::Recursive(p) => dir.starts_with(p),
+            AliasScope::Exact(p) => dir == Path::new(p),
+            AliasScope::Tmux(session) => current_tmux_session() == Some(session.as_str()),
+        }
+    }
+}
+
+/// A scope as given to `--scope`: `global`, `tmux:NAME`, `exact:PATH`, `recursive:PATH`, or
+/// a bare directory whose kind is left to the command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScopeSpec {
+    /// A fully specified scope.
+    Scope(AliasScope),
+    /// A normalized directory given without `exact:` or `recursive:`.
+    Path(String),
+}
+
+impl ScopeSpec {
+    /// Parse `spec`. Relative directories such as `.` and `..` are taken from the current
+    /// directory, and `workspace` and `@repo` are the recursive scope of the enclosing
+    /// workspace or git repository.
+    pub fn parse(spec: &str) -> std::result::Result<Self, crate::error::AkaError> {
+        if spec.eq_ignore_ascii_case("global") {
+            return Ok(Self::Scope(AliasScope::Global));
+        }
+        if spec == WORKSPACE_SCOPE {
+            return root_scope(&workspace_markers()).map(Self::Scope);
+        }
+        if spec == REPO_SCOPE {
+            return root_scope(&[".git".to_string()]).map(Self::Scope);
+        }
+        if spec.starts_with(TMUX_SCOPE_PREFIX) {
+            return AliasScope::from_user_path(spec, false).map(Self::Scope);
+        }
+        if let Some(path) = spec.strip_prefix(EXACT_SCOPE_PREFIX) {
+            let path = normalize_scope_path(Path::new(path))?;
+            return Ok(Self::Scope(AliasScope::Exact(path)));
+        }
+        if let Some(path) = spec.strip_prefix(RECURSIVE_SCOPE_PREFIX) {
+            let path = normalize_scope_path(Path::new(path))?;
+            return Ok(Self::Scope(AliasScope::Recursive(path)));
+        }
+        Ok(Self::Path(normalize_scope_path(Path::new(spec))?))
+    }
+
+    /// The scope to store a new definition in; a bare directory is `Recursive` when
+    /// `recursive` and `Exact` otherwise.
+    pub fn into_scope(self, recursive: bool) -> AliasScope {
+        match self {
+            Self::Scope(scope) => scope,
+            Self::Path(path) if recursive => AliasScope::Recursive(path),
+            Self::Path(path) => AliasScope::Exact(path),
+        }
+    }
+
+    /// Whether `scope` is the one named here; a bare directory names both its exact and its
+    /// recursive scope.
+    pub fn matches(&self, scope: &AliasScope) -> bool {
+        match (self, scope) {
+            (Self::Scope(spec), scope) => spec == scope,
+            (Self::Path(path), AliasScope::Exact(p) | AliasScope::Recursive(p)) => path == p,
+            (Self::Path(_), _) => false,
+        }
+    }
+}
+
+/// Recursive scope of the nearest directory above the current one holding one of `markers`.
+fn root_scope(markers: &[String]) -> std::result::Result<AliasScope, crate::error::AkaError> {
+    let cwd = std::env::current_dir()?;
+    let cwd = std::fs::canonicalize(&cwd).unwrap_or(cwd);
+    let root = find_workspace_root(&cwd, markers).ok_or_else(|| {
+        crate::error::AkaError::ConfigError(format!(
+            "No workspace root found above {} (markers: {})",
+            cwd.display(),
+            markers.join(", ")
+        ))
+    })?;
+    Ok(AliasScope::Recursive(normalize_scope_path(&root)?))
+}
+
+/// Resolve a directory to the form scopes are stored in: absolute, with symlinks resolved and
+/// no trailing slash.
+///
+/// Scopes are compared as strings, so paths that are not valid UTF-8 are rejected rather than
+/// stored lossily.
+pub fn normalize_scope_path(path: &Path) -> std::result::Result<String, crate::error::AkaError> {
+    let resolved = std::fs::canonicalize(path).map_err(|e| {
+        crate::error::AkaError::InvalidScopePath(format!("{}: {}", path.display(), e))
+    })?;
+    resolved.to_str().map(str::to_string).ok_or_else(|| {
+        crate::error::AkaError::InvalidScopePath(format!(
+            "{} is not valid UTF-8",
+            resolved.display()
+        ))
+    })
+}
+
+/// Pick the definition that wins in `current_dir`, mirroring the generated shell function.
+pub fn resolve_definition<'a>(
+    defs: &'a [AliasDefinition],
+    current_dir: &str,
+) -> Option<&'a AliasDefinition> {
+    let mut applicable: Vec<&AliasDefinition> = defs
+        .iter()
+        .filter(|d| d.scope.matches(Path::new(current_dir)))
+        .collect();
+    applicable.sort_by(|a, b| precedence(&a.scope, &b.scope));
+    applicable.into_iter().next()
+}
+
+/// The stored command `alias` runs in `current_dir`, including system aliases, as the generated
+/// function would pick it. References to other aliases and variables are left unexpanded; `aka
+/// resolve` expands them the way the dump does.
+pub fn resolve_alias(
+    store: &Store,
+    alias: &str,
+    current_dir: &str,
+) -> std::result::Result<String, crate::error::AkaError> {
+    let mut aliases = store.list()?;
+    crate::system::merge_system_aliases(&mut aliases);
+
+    let defs = aliases
+        .get(alias)
+        .ok_or_else(|| crate::error::AkaError::AliasNotFound(alias.to_string()))?;
+
+    resolve_definition(defs, current_dir)
+        .map(|def| def.command.clone())
+        .ok_or_else(|| {
+            crate::error::AkaError::ScopeNotFoundInAlias(alias.to_string(), current_dir.to_string())
+        })
+}
+
+/// Sort definitions into resolution order: Exact > Recursive > Tmux > Global, longest path first.
+pub fn sort_by_precedence(defs: &mut [AliasDefinition]) {
+    defs.sort_by(|a, b| precedence(&a.scope, &b.scope));
+}
+
+fn precedence(a: &AliasScope, b: &AliasScope) -> std::cmp::Ordering {
+    match (a, b) {
+        (AliasScope::Exact(p1), AliasScope::Exact(p2)) => p2.len().cmp(&p1.len()), // Longest path first
+        (AliasScope::Exact(_), _) => std::cmp::Ordering::Less,
+        (_, AliasScope::Exact(_)) => std::cmp::Ordering::Greater,
+
+        (AliasScope::Recursive(p1), AliasScope::Recursive(p2)) => p2.len().cmp(&p1.len()),
+        (AliasScope::Recursive(_), _) => std::cmp::Ordering::Less,
+        (_, AliasScope::Recursive(_)) => std::cmp::Ordering::Greater,
+
+        (AliasScope::Tmux(s1), AliasScope::Tmux(s2)) => s1.cmp(s2),
+        (AliasScope::Tmux(_), _) => std::cmp::Ordering::Less,
+        (_, AliasScope::Tmux(_)) => std::cmp::Ordering::Greater,
+
+        (AliasScope::Global, AliasScope::Global) => std::cmp::Ordering::Equal,
+    }
+}
+
+/// Decode a stored value, treating anything that is not a definition list as a legacy global alias.
+fn parse_definitions(value: &str) -> Vec<AliasDefinition> {
+    match serde_json::from_str::<Vec<AliasDefinition>>(value) {
+        Ok(defs) => defs,
+        Err(_) => vec![AliasDefinition::new(value.to_string(), AliasScope::Global)],
+    }
+}
+
+/// Whether `value` was meant to be a definition list but does not decode as one.
+///
+/// Plain strings are legacy commands; JSON structures and truncated writes (which always
+/// start like an encoded list) are not, even though `parse_definitions` accepts them.
+pub fn is_corrupt(value: &str) -> bool {
+    if serde_json::from_str::<Vec<AliasDefinition>>(value).is_ok() {
+        return false;
+    }
+    let trimmed = value.trim_start();
+    trimmed.starts_with("[{")
+        || serde_json::from_str::<serde_json::Value>(value)
+            .is_ok_and(|v| v.is_array() || v.is_object())
+}
+
+/// The definitions that can still be recovered from a corrupt record, if any.
+pub fn salvage_definitions(value: &str) -> Option<Vec<AliasDefinition>> {
+    let defs: Vec<AliasDefinition> = match serde_json::from_str::<serde_json::Value>(value).ok()? {
+        serde_json::Value::Array(items) => items
+            .into_iter()
+            .filter_map(|item| serde_json::from_value(item).ok())
+            .collect(),
+        object @ serde_json::Value::Object(_) => vec![serde_json::from_value(object).ok()?],
+        _ => return None,
+    };
+    (!defs.is_empty()).then_some(defs)
+}
+
+/// What [`Store::sync_generated`] or [`Store::replace_aliases`] changed, by alias name.
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncReport {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+    /// Aliases left alone because a hand-added definition already uses the name in that scope.
+    pub skipped: Vec<String>,
+}
+
+/// On-disk formats the store can be kept in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    Redb,
+    Json,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl BackendKind {
+    /// Candidates checked, in order, when looking for an existing store.
+    pub const ALL: &[BackendKind] = &[
+        BackendKind::Redb,
+        BackendKind::Json,
+        #[cfg(feature = "sqlite")]
+        BackendKind::Sqlite,
+    ];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            BackendKind::Redb => "aka.redb",
+            BackendKind::Json => "aka.json",
+            #[cfg(feature = "sqlite")]
+            BackendKind::Sqlite => "aka.sqlite",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BackendKind::Redb => "redb",
+            BackendKind::Json => "json",
+            #[cfg(feature = "sqlite")]
+            BackendKind::Sqlite => "sqlite",
+        }
+    }
+
+    /// Infer the backend from a store path's extension, defaulting to redb.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => BackendKind::Json,
+            #[cfg(feature = "sqlite")]
+            Some("sqlite") => BackendKind::Sqlite,
+            _ => BackendKind::Redb,
+        }
+    }
+
+    /// Backend for newly created stores, chosen with `AKA_BACKEND` or the config's
+    /// `[store] backend` (redb by default).
+    pub fn preferred() -> std::result::Result<Self, crate::error::AkaError> {
+        match std::env::var("AKA_BACKEND") {
+            Ok(value) if !value.trim().is_empty() => value
+                .trim()
+                .parse()
+                .map_err(crate::error::AkaError::ConfigError),
+            _ => Ok(crate::config::load()?
+                .store
+                .backend
+                .unwrap_or(BackendKind::Redb)),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for BackendKind {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl std::str::FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if !cfg!(feature = "sqlite") && s.eq_ignore_ascii_case("sqlite") {
+            return Err(
+                "sqlite support is not enabled in this build (rebuild with --features sqlite)"
+                    .to_string(),
+            );
+        }
+        BackendKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.name() == s.to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = BackendKind::ALL.iter().map(|k| k.name()).collect();
+                format!(
+                    "unknown backend '{}' (expected one of: {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Strict decoding from `AKA_STRICT` or the config's `[store] strict`.
+///
+/// An unreadable config is reported when logging is set up, so it simply means lenient here.
+fn strict_mode() -> bool {
+    match std::env::var("AKA_STRICT") {
+        Ok(value) if !value.trim().is_empty() => !matches!(
+            value.trim().to_lowercase().as_str(),
+            "0" | "false" | "off" | "no"
+        ),
+        _ => crate::config::load().is_ok_and(|config| config.store.strict),
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Directory holding the store files (`$aka_DATA_DIR/aka`, `[store] data_dir`, or the platform data dir).
+pub fn data_dir() -> std::result::Result<PathBuf, crate::error::AkaError> {
+    let data_dir = if let Ok(dir) = std::env::var("aka_DATA_DIR") {
+        PathBuf::from(dir)
+    } else if let Some(dir) = crate::config::load()?.store.data_dir {
+        dir
+    } else {
+        dirs::data_dir()
+            .ok_or_else(|| crate::error::AkaError::ConfigError("Data dir not found".to_string()))?
+    };
+    Ok(data_dir.join("aka"))
+}
+
+/// Name of the file that, placed in the data directory, makes the store read-only for every
+/// aka run, e.g. on a shared volume.
+pub const WRITE_LOCK_FILE: &str = "aka.lock";
+
+/// The write lockfile in `dir`, if one has been created there.
+pub fn write_lock(dir: &Path) -> Option<PathBuf> {
+    let path = dir.join(WRITE_LOCK_FILE);
+    path.exists().then_some(path)
+}
+
+/// Why the store in `dir` cannot be changed from here, such as a read-only mount, found
+/// without writing to it.
+pub fn unwritable(dir: &Path) -> Option<String> {
+    let path = store_path(dir);
+    let result = match path.exists() {
+        true => std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .map(drop),
+        false => std::fs::create_dir_all(dir),
+    };
+    match result {
+        Err(e) if backend::is_unwritable(&e) => {
+            Some(format!("{} cannot be written: {}", path.display(), e))
+        }
+        _ => None,
+    }
+}
+
+/// Locate the existing store in `dir`, falling back to `fallback` for a new store.
+pub fn store_path_or(dir: &Path, fallback: BackendKind) -> PathBuf {
+    BackendKind::ALL
+        .iter()
+        .map(|kind| dir.join(kind.file_name()))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join(fallback.file_name()))
+}
+
+/// Locate the existing store in `dir`, falling back to a new redb store.
+pub fn store_path(dir: &Path) -> PathBuf {
+    store_path_or(dir, BackendKind::Redb)
+}
+
+fn encode_definitions(
+    definitions: &[AliasDefinition],
+) -> std::result::Result<String, crate::error::AkaError> {
+    serde_json::to_string(definitions)
+        .map_err(|e| crate::error::AkaError::ConfigError(e.to_string()))
+}
+
+fn open_backend(path: &Path) -> std::result::Result<Box<dyn Backend>, crate::error::AkaError> {
+    tracing::debug!(path = %path.display(), "opening store");
+    Ok(match BackendKind::from_path(path) {
+        BackendKind::Redb => Box::new(RedbBackend::open(path)?),
+        BackendKind::Json => Box::new(JsonBackend::open(path)?),
+        #[cfg(feature = "sqlite")]
+        BackendKind::Sqlite => Box::new(SqliteBackend::open(path)?),
+    })
 }
 
 /// The storage for aliases
+///
+/// The backend is opened on first use, so read-only commands served from the cache never
+/// touch the database.
 pub struct Store {
-    db: Database,
+    path: PathBuf,
+    backend: OnceCell<Box<dyn Backend>>,
+    /// Entries read from the backend this run, written to the cache once the store closes.
+    pending_cache: RefCell<Option<(u64, cache::Entries)>>,
+    /// Fail on corrupt records instead of reading them as legacy global aliases.
+    strict: bool,
 }
 
 impl Store {
+    /// The store in [`data_dir`], creating the directory when it is missing. On a read-only
+    /// mount the store is opened for reading only, and changing it fails with
+    /// `AkaError::ReadOnly`.
     pub fn new() -> std::result::Result<Self, crate::error::AkaError> {
-        let data_dir = if let Ok(dir) = std::env::var("aka_DATA_DIR") {
-            std::path::PathBuf::from(dir)
-        } else {
-            dirs::data_dir().ok_or_else(|| {
-                crate::error::AkaError::ConfigError("Data dir not found".to_string())
-            })?
-        };
-        let base_path = data_dir.join("aka");
-        let path = base_path.join("aka.redb");
-        Self::load(&path)
+        let path = store_path_or(&data_dir()?, BackendKind::preferred()?);
+        if let Some(parent) = path.parent()
+            && let Err(e) = std::fs::create_dir_all(parent)
+            && !backend::is_unwritable(&e)
+        {
+            return Err(e.into());
+        }
+        Ok(Store {
+            path,
+            backend: OnceCell::new(),
+            pending_cache: RefCell::new(None),
+            strict: strict_mode(),
+        })
     }
 
     pub fn load(path: &Path) -> std::result::Result<Self, crate::error::AkaError> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        let db = Database::create(path).map_err(crate::error::AkaError::from)?;
-        Ok(Store { db })
+        let store = Store {
+            path: path.to_path_buf(),
+            backend: OnceCell::new(),
+            pending_cache: RefCell::new(None),
+            strict: false,
+        };
+        store.backend()?;
+        Ok(store)
+    }
+
+    /// Make corrupt records an error (`AkaError::CorruptRecord`) rather than legacy aliases.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    fn decode(
+        &self,
+        alias: &str,
+        value: &str,
+    ) -> std::result::Result<Vec<AliasDefinition>, crate::error::AkaError> {
+        if self.strict && is_corrupt(value) {
+            return Err(crate::error::AkaError::CorruptRecord {
+                alias: alias.to_string(),
+                raw: value.to_string(),
+            });
+        }
+        Ok(parse_definitions(value))
+    }
+
+    /// Path of the underlying store file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn backend(&self) -> std::result::Result<&dyn Backend, crate::error::AkaError> {
+        if self.backend.get().is_none() {
+            let _ = self.backend.set(open_backend(&self.path)?);
+        }
+        Ok(self.backend.get().expect("backend initialized").as_ref())
+    }
+
+    fn backend_mut(&mut self) -> std::result::Result<&mut dyn Backend, crate::error::AkaError> {
+        self.backend()?;
+        Ok(self
+            .backend
+            .get_mut()
+            .expect("backend initialized")
+            .as_mut())
+    }
+
+    /// Apply `changes` together with a generation bump, then drop the now stale read cache.
+    fn commit(
+        &mut self,
+        mut changes: Vec<Change>,
+    ) -> std::result::Result<(), crate::error::AkaError> {
+        let generation = self.generation()? + 1;
+        for (table, key, value) in &changes {
+            let action = if value.is_some() { "set" } else { "delete" };
+            tracing::debug!(table, key = %key, action, "store change");
+        }
+        tracing::info!(
+            path = %self.path.display(),
+            generation,
+            changes = changes.len(),
+            "commit"
+        );
+        changes.push((
+            META,
+            GENERATION_KEY.to_string(),
+            Some(generation.to_string()),
+        ));
+        self.backend_mut()?.write(changes)?;
+        self.pending_cache.take();
+        cache::invalidate(&self.path);
+        Ok(())
+    }
+
+    /// Counter incremented by every mutation; lets callers detect concurrent changes.
+    pub fn generation(&self) -> std::result::Result<u64, crate::error::AkaError> {
+        Ok(self
+            .backend()?
+            .get(META, GENERATION_KEY)?
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0))
+    }
+
+    /// Close the backend so other processes can write meanwhile, e.g. while an editor is
+    /// open; it is reopened on next use.
+    ///
+    /// Some backends touch the file when closing, so the cache is stamped only after the
+    /// backend is gone. It is skipped if another process changed the store meanwhile.
+    pub fn release(&mut self) {
+        let Some(backend) = self.backend.take() else {
+            return;
+        };
+        let Some((generation, entries)) = self.pending_cache.take() else {
+            return;
+        };
+        let unchanged = backend
+            .get(META, GENERATION_KEY)
+            .ok()
+            .map(|value| value.and_then(|v| v.parse().ok()).unwrap_or(0))
+            == Some(generation);
+        drop(backend);
+        if unchanged && let Some(stamp) = cache::stamp(&self.path) {
+            cache::write(&self.path, stamp, generation, &entries);
+        }
+    }
+
+    /// Copy every table into `target`, used when migrating between backends.
+    pub fn copy_into(
+        &self,
+        target: &mut Store,
+    ) -> std::result::Result<usize, crate::error::AkaError> {
+        let mut copied = 0;
+        let mut changes = Vec::new();
+        for table in TABLES {
+            let entries = self.backend()?.entries(table)?;
+            if table == ALIASES {
+                copied = entries.len();
+            }
+            changes.extend(entries.into_iter().map(|(k, v)| (table, k, Some(v))));
+        }
+        target.backend_mut()?.write(changes)?;
+        cache::invalidate(&target.path);
+        Ok(copied)
     }
 
     pub fn add(
@@ -50,88 +769,78 @@ impl Store {
         command: String,
         scope: AliasScope,
     ) -> std::result::Result<(), crate::error::AkaError> {
-        let write_txn = self.db.begin_write()?;
-        {
-            let mut table = write_txn.open_table(TABLE)?;
-
-            // Read existing definitions
-            let mut definitions = if let Some(value) = table.get(alias.as_str())? {
-                let s = value.value();
-                match serde_json::from_str::<Vec<AliasDefinition>>(s) {
-                    Ok(defs) => defs,
-                    Err(_) => {
-                        // Legacy: treat as single global alias
-                        vec![AliasDefinition {
-                            command: s.to_string(),
-                            scope: AliasScope::Global,
-                        }]
-                    }
-                }
-            } else {
-                Vec::new()
-            };
-
-            // Remove existing definition for same scope if exists (overwrite)
-            definitions.retain(|d| d.scope != scope);
+        self.add_definition(alias, AliasDefinition::new(command, scope))
+    }
 
-            // Add new definition
-            definitions.push(AliasDefinition { command, scope });
+    /// Store `definition`, replacing the alias's existing definition for the same scope.
+    pub fn add_definition(
+        &mut self,
+        alias: String,
+        definition: AliasDefinition,
+    ) -> std::result::Result<(), crate::error::AkaError> {
+        // Read existing definitions
+        let mut definitions = self
+            .backend()?
+            .get(ALIASES, &alias)?
+            .map(|value| self.decode(&alias, &value))
+            .transpose()?
+            .unwrap_or_default();
+
+        // Remove existing definition for same scope if exists (overwrite)
+        definitions.retain(|d| d.scope != definition.scope);
+
+        // Add new definition
+        definitions.push(definition);
+
+        let json = encode_definitions(&definitions)?;
+        self.commit(vec![
+            (REVIEWED, alias.clone(), Some(unix_now().to_string())),
+            (ALIASES, alias, Some(json)),
+        ])
+    }
 
-            let json = serde_json::to_string(&definitions)
-                .map_err(|e| crate::error::AkaError::ConfigError(e.to_string()))?;
-            table.insert(alias.as_str(), json.as_str())?;
-        }
-        write_txn.commit()?;
-        Ok(())
+    /// The definitions of `alias` alone, read with a point lookup instead of the whole table.
+    pub fn get(
+        &self,
+        alias: &str,
+    ) -> std::result::Result<Option<Vec<AliasDefinition>>, crate::error::AkaError> {
+        self.backend()?
+            .get(ALIASES, alias)?
+            .map(|value| self.decode(alias, &value))
+            .transpose()
     }
 
     pub fn remove(
         &mut self,
         alias: &str,
     ) -> std::result::Result<Option<Vec<AliasDefinition>>, crate::error::AkaError> {
-        let write_txn = self.db.begin_write()?;
-        let res = {
-            let mut table = write_txn.open_table(TABLE)?;
-            if let Some(value) = table.remove(alias)? {
-                let s = value.value();
-                match serde_json::from_str::<Vec<AliasDefinition>>(s) {
-                    Ok(defs) => Some(defs),
-                    Err(_) => Some(vec![AliasDefinition {
-                        command: s.to_string(),
-                        scope: AliasScope::Global,
-                    }]),
-                }
-            } else {
-                None
-            }
+        let Some(value) = self.backend()?.get(ALIASES, alias)? else {
+            return Ok(None);
         };
-        write_txn.commit()?;
-        Ok(res)
+        let definitions = self.decode(alias, &value)?;
+        self.commit(vec![
+            (ALIASES, alias.to_string(), None),
+            (REVIEWED, alias.to_string(), None),
+        ])?;
+        Ok(Some(definitions))
     }
 
     /// Remove all aliases from the store.
     ///
     /// Returns the number of aliases that were removed.
     pub fn remove_all(&mut self) -> std::result::Result<usize, crate::error::AkaError> {
-        let write_txn = self.db.begin_write()?;
-        let count = {
-            let mut table = write_txn.open_table(TABLE)?;
-            let count = table.len()?;
-
-            // Collect all keys first to avoid iterator invalidation
-            let keys: Vec<String> = table
-                .iter()?
-                .map(|item| item.map(|(k, _)| k.value().to_string()))
-                .collect::<std::result::Result<Vec<_>, _>>()?;
-
-            // Remove all entries
-            for key in keys {
-                table.remove(key.as_str())?;
-            }
-
-            count as usize
-        };
-        write_txn.commit()?;
+        let backend = self.backend()?;
+        let keys: Vec<Change> = backend
+            .entries(ALIASES)?
+            .into_iter()
+            .map(|(k, _)| (ALIASES, k, None))
+            .collect();
+        let count = keys.len();
+        let reviewed = backend
+            .entries(REVIEWED)?
+            .into_iter()
+            .map(|(k, _)| (REVIEWED, k, None));
+        self.commit(keys.into_iter().chain(reviewed).collect())?;
         Ok(count)
     }
 
@@ -144,58 +853,33 @@ impl Store {
         alias: &str,
         scope: &AliasScope,
     ) -> std::result::Result<Option<AliasDefinition>, crate::error::AkaError> {
-        let write_txn = self.db.begin_write()?;
-        let removed = {
-            let mut table = write_txn.open_table(TABLE)?;
-
-            // Read current definitions
-            let definitions = if let Some(value) = table.get(alias)? {
-                let s = value.value().to_string();
-                match serde_json::from_str::<Vec<AliasDefinition>>(&s) {
-                    Ok(defs) => Some(defs),
-                    Err(_) => Some(vec![AliasDefinition {
-                        command: s,
-                        scope: AliasScope::Global,
-                    }]),
-                }
-            } else {
-                None
-            };
-
-            if let Some(mut defs) = definitions {
-                // Find and remove the matching scope
-                let initial_len = defs.len();
-                let mut removed_def = None;
-                defs.retain(|d| {
-                    if &d.scope == scope {
-                        removed_def = Some(d.clone());
-                        false
-                    } else {
-                        true
-                    }
-                });
+        // Read current definitions
+        let Some(mut defs) = self
+            .backend()?
+            .get(ALIASES, alias)?
+            .map(|value| self.decode(alias, &value))
+            .transpose()?
+        else {
+            return Ok(None);
+        };
 
-                // If nothing was removed, return None
-                if defs.len() == initial_len {
-                    None
-                } else {
-                    // If no definitions remain, remove the key entirely
-                    if defs.is_empty() {
-                        table.remove(alias)?;
-                    } else {
-                        // Otherwise, update with remaining definitions
-                        let json = serde_json::to_string(&defs)
-                            .map_err(|e| crate::error::AkaError::ConfigError(e.to_string()))?;
-                        table.insert(alias, json.as_str())?;
-                    }
-                    removed_def
-                }
-            } else {
-                None
-            }
+        // Find and remove the matching scope
+        let Some(index) = defs.iter().position(|d| &d.scope == scope) else {
+            return Ok(None);
         };
-        write_txn.commit()?;
-        Ok(removed)
+        let removed_def = defs.remove(index);
+
+        // If no definitions remain, remove the key entirely, with its review state
+        let changes = if defs.is_empty() {
+            vec![
+                (ALIASES, alias.to_string(), None),
+                (REVIEWED, alias.to_string(), None),
+            ]
+        } else {
+            vec![(ALIASES, alias.to_string(), Some(encode_definitions(&defs)?))]
+        };
+        self.commit(changes)?;
+        Ok(Some(removed_def))
     }
 
     /// Remove all definitions with the specified scope from all aliases.
@@ -205,92 +889,352 @@ impl Store {
         &mut self,
         scope: &AliasScope,
     ) -> std::result::Result<HashMap<String, Vec<AliasDefinition>>, crate::error::AkaError> {
-        let write_txn = self.db.begin_write()?;
-        let removed = {
-            let mut table = write_txn.open_table(TABLE)?;
-            let mut removed_map: HashMap<String, Vec<AliasDefinition>> = HashMap::new();
-
-            // Read all aliases first
-            let all_aliases: Vec<(String, String)> = table
-                .iter()?
-                .map(|item| {
-                    let (k, v) = item?;
-                    Ok((k.value().to_string(), v.value().to_string()))
-                })
-                .collect::<std::result::Result<Vec<_>, redb::Error>>()?;
-
-            // Process each alias
-            for (alias, value_str) in all_aliases {
-                let mut definitions = match serde_json::from_str::<Vec<AliasDefinition>>(&value_str)
-                {
-                    Ok(defs) => defs,
-                    Err(_) => vec![AliasDefinition {
-                        command: value_str,
-                        scope: AliasScope::Global,
-                    }],
-                };
+        let mut removed_map: HashMap<String, Vec<AliasDefinition>> = HashMap::new();
+        let mut changes = Vec::new();
+
+        for (alias, value_str) in self.backend()?.entries(ALIASES)? {
+            let (removed_defs, definitions): (Vec<_>, Vec<_>) = self
+                .decode(&alias, &value_str)?
+                .into_iter()
+                .partition(|d| &d.scope == scope);
+
+            // If any were removed, update or delete the alias
+            if !removed_defs.is_empty() {
+                if definitions.is_empty() {
+                    changes.push((ALIASES, alias.clone(), None));
+                    changes.push((REVIEWED, alias.clone(), None));
+                } else {
+                    let value = Some(encode_definitions(&definitions)?);
+                    changes.push((ALIASES, alias.clone(), value));
+                }
+                removed_map.insert(alias, removed_defs);
+            }
+        }
+
+        if !changes.is_empty() {
+            self.commit(changes)?;
+        }
+        Ok(removed_map)
+    }
 
-                // Filter out definitions with matching scope
-                let mut removed_defs = Vec::new();
-                definitions.retain(|d| {
-                    if &d.scope == scope {
-                        removed_defs.push(d.clone());
+    /// Rewrite every scope at or below `old` to live under `new`, in one transaction.
+    ///
+    /// Fails without changing anything when a moved definition would land on a scope the alias
+    /// already has a definition for.
+    /// Returns the number of definitions moved.
+    pub fn move_scopes(
+        &mut self,
+        old: &str,
+        new: &str,
+    ) -> std::result::Result<usize, crate::error::AkaError> {
+        let relocate = |path: &str| -> Option<String> {
+            let rest = path.strip_prefix(old)?;
+            (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", new, rest))
+        };
+
+        let mut moved = 0;
+        let mut changes = Vec::new();
+        for (alias, value_str) in self.backend()?.entries(ALIASES)? {
+            let mut definitions = self.decode(&alias, &value_str)?;
+            let mut relocated = Vec::new();
+            definitions.retain(|def| {
+                let scope = match &def.scope {
+                    AliasScope::Exact(p) => relocate(p).map(AliasScope::Exact),
+                    AliasScope::Recursive(p) => relocate(p).map(AliasScope::Recursive),
+                    AliasScope::Global | AliasScope::Tmux(_) => None,
+                };
+                match scope {
+                    Some(scope) => {
+                        relocated.push(AliasDefinition {
+                            scope,
+                            ..def.clone()
+                        });
                         false
-                    } else {
-                        true
-                    }
-                });
-
-                // If any were removed, update or delete the alias
-                if !removed_defs.is_empty() {
-                    removed_map.insert(alias.clone(), removed_defs);
-
-                    if definitions.is_empty() {
-                        table.remove(alias.as_str())?;
-                    } else {
-                        let json = serde_json::to_string(&definitions)
-                            .map_err(|e| crate::error::AkaError::ConfigError(e.to_string()))?;
-                        table.insert(alias.as_str(), json.as_str())?;
                     }
+                    None => true,
                 }
+            });
+
+            if relocated.is_empty() {
+                continue;
+            }
+            // Refuse before anything is written rather than drop the definition in the way
+            if let Some(taken) = relocated
+                .iter()
+                .find(|r| definitions.iter().any(|d| d.scope == r.scope))
+            {
+                let scope = match &taken.scope {
+                    AliasScope::Exact(p) => format!("{}{}", EXACT_SCOPE_PREFIX, p),
+                    AliasScope::Recursive(p) => format!("{}{}", RECURSIVE_SCOPE_PREFIX, p),
+                    AliasScope::Global | AliasScope::Tmux(_) => unreachable!("only paths move"),
+                };
+                return Err(crate::error::AkaError::ConfigError(format!(
+                    "'{}' already has a definition for {}; remove it before moving scopes there",
+                    alias, scope
+                )));
+            }
+            moved += relocated.len();
+            definitions.extend(relocated);
+            changes.push((ALIASES, alias, Some(encode_definitions(&definitions)?)));
+        }
+
+        if !changes.is_empty() {
+            self.commit(changes)?;
+        }
+        Ok(moved)
+    }
+
+    /// Make the definitions `origin` owns in `scope` match `entries` exactly, in one transaction.
+    ///
+    /// Definitions the generator created earlier are updated or removed; a hand-added
+    /// definition for the same alias and scope is never overwritten.
+    pub fn sync_generated(
+        &mut self,
+        origin: &str,
+        scope: &AliasScope,
+        entries: &BTreeMap<String, String>,
+    ) -> std::result::Result<SyncReport, crate::error::AkaError> {
+        let mut report = SyncReport::default();
+        let mut stored: HashMap<String, Vec<AliasDefinition>> = self
+            .backend()?
+            .entries(ALIASES)?
+            .into_iter()
+            .map(|(alias, value)| {
+                let defs = self.decode(&alias, &value)?;
+                Ok((alias, defs))
+            })
+            .collect::<std::result::Result<_, crate::error::AkaError>>()?;
+        let mut touched = BTreeSet::new();
+
+        for (alias, defs) in stored.iter_mut() {
+            if entries.contains_key(alias) {
+                continue;
+            }
+            let before = defs.len();
+            defs.retain(|d| !(&d.scope == scope && d.origin.as_deref() == Some(origin)));
+            if defs.len() != before {
+                report.removed.push(alias.clone());
+                touched.insert(alias.clone());
+            }
+        }
+
+        for (alias, command) in entries {
+            let defs = stored.entry(alias.clone()).or_default();
+            match defs.iter_mut().find(|d| &d.scope == scope) {
+                None => {
+                    defs.push(AliasDefinition {
+                        origin: Some(origin.to_string()),
+                        ..AliasDefinition::new(command.clone(), scope.clone())
+                    });
+                    report.added.push(alias.clone());
+                }
+                Some(def) if def.origin.as_deref() != Some(origin) => {
+                    report.skipped.push(alias.clone());
+                    continue;
+                }
+                Some(def) if &def.command == command => continue,
+                Some(def) => {
+                    def.command = command.clone();
+                    report.updated.push(alias.clone());
+                }
+            }
+            touched.insert(alias.clone());
+        }
+
+        let mut changes = Vec::new();
+        for alias in touched {
+            let defs = &stored[&alias];
+            let value = if defs.is_empty() {
+                None
+            } else {
+                Some(encode_definitions(defs)?)
+            };
+            changes.push((ALIASES, alias, value));
+        }
+        if !changes.is_empty() {
+            self.commit(changes)?;
+        }
+        report.removed.sort();
+        Ok(report)
+    }
+
+    /// Make the alias table hold exactly `aliases`, in one transaction.
+    ///
+    /// Aliases whose definitions are unchanged are left alone, so `aka review` keeps their age.
+    pub fn replace_aliases(
+        &mut self,
+        aliases: &BTreeMap<String, Vec<AliasDefinition>>,
+    ) -> std::result::Result<SyncReport, crate::error::AkaError> {
+        let mut report = SyncReport::default();
+        let stored: HashMap<String, Vec<AliasDefinition>> = self
+            .backend()?
+            .entries(ALIASES)?
+            .into_iter()
+            .map(|(alias, value)| {
+                let defs = self.decode(&alias, &value)?;
+                Ok((alias, defs))
+            })
+            .collect::<std::result::Result<_, crate::error::AkaError>>()?;
+
+        let mut changes = Vec::new();
+        for alias in stored.keys() {
+            if !aliases.contains_key(alias) {
+                report.removed.push(alias.clone());
+                changes.push((ALIASES, alias.clone(), None));
+                changes.push((REVIEWED, alias.clone(), None));
             }
+        }
+        for (alias, defs) in aliases {
+            match stored.get(alias) {
+                Some(old) if old == defs => continue,
+                Some(_) => report.updated.push(alias.clone()),
+                None => report.added.push(alias.clone()),
+            }
+            changes.push((ALIASES, alias.clone(), Some(encode_definitions(defs)?)));
+            changes.push((REVIEWED, alias.clone(), Some(unix_now().to_string())));
+        }
+        if !changes.is_empty() {
+            self.commit(changes)?;
+        }
+        report.removed.sort();
+        Ok(report)
+    }
 
-            removed_map
+    /// Set the template variable `name`, replacing any previous value.
+    pub fn set_var(
+        &mut self,
+        name: &str,
+        value: &str,
+    ) -> std::result::Result<(), crate::error::AkaError> {
+        self.commit(vec![(VARS, name.to_string(), Some(value.to_string()))])
+    }
+
+    /// Remove the template variable `name`; returns whether it existed.
+    pub fn remove_var(&mut self, name: &str) -> std::result::Result<bool, crate::error::AkaError> {
+        if self.backend()?.get(VARS, name)?.is_none() {
+            return Ok(false);
+        }
+        self.commit(vec![(VARS, name.to_string(), None)])?;
+        Ok(true)
+    }
+
+    pub fn vars(&self) -> std::result::Result<BTreeMap<String, String>, crate::error::AkaError> {
+        Ok(self.backend()?.entries(VARS)?.into_iter().collect())
+    }
+
+    /// Alias records that fail to decode, with their raw stored value.
+    pub fn corrupt_records(
+        &self,
+    ) -> std::result::Result<Vec<(String, String)>, crate::error::AkaError> {
+        Ok(self
+            .backend()?
+            .entries(ALIASES)?
+            .into_iter()
+            .filter(|(_, value)| is_corrupt(value))
+            .collect())
+    }
+
+    /// Overwrite every definition of `alias` with `definitions`.
+    pub fn replace_definitions(
+        &mut self,
+        alias: &str,
+        definitions: &[AliasDefinition],
+    ) -> std::result::Result<(), crate::error::AkaError> {
+        let json = encode_definitions(definitions)?;
+        self.commit(vec![(ALIASES, alias.to_string(), Some(json))])
+    }
+
+    /// Move the raw record of `alias` out of the alias table into quarantine.
+    pub fn quarantine(&mut self, alias: &str) -> std::result::Result<bool, crate::error::AkaError> {
+        let Some(value) = self.backend()?.get(ALIASES, alias)? else {
+            return Ok(false);
         };
-        write_txn.commit()?;
-        Ok(removed)
+        self.commit(vec![
+            (QUARANTINE, alias.to_string(), Some(value)),
+            (ALIASES, alias.to_string(), None),
+        ])?;
+        Ok(true)
+    }
+
+    /// Unix time each alias was last added or reviewed; aliases from before tracking are absent.
+    pub fn reviewed(&self) -> std::result::Result<BTreeMap<String, u64>, crate::error::AkaError> {
+        Ok(self
+            .backend()?
+            .entries(REVIEWED)?
+            .into_iter()
+            .filter_map(|(alias, at)| Some((alias, at.parse().ok()?)))
+            .collect())
+    }
+
+    /// Record `aliases` as reviewed at unix time `at`.
+    pub fn mark_reviewed(
+        &mut self,
+        aliases: &[String],
+        at: u64,
+    ) -> std::result::Result<(), crate::error::AkaError> {
+        if aliases.is_empty() {
+            return Ok(());
+        }
+        self.commit(
+            aliases
+                .iter()
+                .map(|alias| (REVIEWED, alias.clone(), Some(at.to_string())))
+                .collect(),
+        )
+    }
+
+    /// Usage log lines already credited by `aka review`.
+    pub fn usage_offset(&self) -> std::result::Result<usize, crate::error::AkaError> {
+        Ok(self
+            .backend()?
+            .get(META, USAGE_OFFSET_KEY)?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0))
+    }
+
+    pub fn set_usage_offset(
+        &mut self,
+        offset: usize,
+    ) -> std::result::Result<(), crate::error::AkaError> {
+        self.commit(vec![(
+            META,
+            USAGE_OFFSET_KEY.to_string(),
+            Some(offset.to_string()),
+        )])
     }
 
     pub fn list(
         &self,
     ) -> std::result::Result<HashMap<String, Vec<AliasDefinition>>, crate::error::AkaError> {
-        let read_txn = self.db.begin_read()?;
-        let mut map = HashMap::new();
-        match read_txn.open_table(TABLE) {
-            Ok(table) => {
-                for item in table.iter()? {
-                    let (k, v) = item?;
-                    let s = v.value();
-                    let defs = match serde_json::from_str::<Vec<AliasDefinition>>(s) {
-                        Ok(d) => d,
-                        Err(_) => vec![AliasDefinition {
-                            command: s.to_string(),
-                            scope: AliasScope::Global,
-                        }],
-                    };
-                    map.insert(k.value().to_string(), defs);
-                }
+        let entries = match cache::read(&self.path) {
+            Some(entries) => {
+                tracing::debug!(aliases = entries.len(), "read aliases from cache");
+                entries
             }
-            Err(redb::TableError::TableDoesNotExist(_)) => {
-                // Table doesn't exist yet, return empty map
+            None => {
+                let entries = self.backend()?.entries(ALIASES)?;
+                *self.pending_cache.borrow_mut() = Some((self.generation()?, entries.clone()));
+                entries
             }
-            Err(e) => return Err(e.into()),
-        }
-        Ok(map)
+        };
+        entries
+            .into_iter()
+            .map(|(k, v)| {
+                let defs = self.decode(&k, &v)?;
+                Ok((k, defs))
+            })
+            .collect()
+    }
+}
+
+impl Drop for Store {
+    fn drop(&mut self) {
+        self.release();
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::unnecessary_get_then_check)]
 mod tests {
     #[allow(unused_imports)]
     use super::*;
@@ -327,6 +1271,10 @@ mod tests {
         let defs = aliases.get("foo").unwrap();
         assert_eq!(defs.len(), 2);
 
+        // Test point lookup
+        assert_eq!(store.get("foo")?.as_ref(), Some(defs));
+        assert_eq!(store.get("missing")?, None);
+
         // Test remove
         let removed = store.remove("foo")?;
         assert!(removed.is_some());
@@ -368,6 +1316,39 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_removing_aliases_forgets_their_review_state()
+    -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        let tmp = AliasScope::Exact("/tmp".to_string());
+        store.add(
+            "foo".to_string(),
+            "echo foo".to_string(),
+            AliasScope::Global,
+        )?;
+        store.add("foo".to_string(), "echo tmp".to_string(), tmp.clone())?;
+        store.add("bar".to_string(), "echo bar".to_string(), tmp.clone())?;
+        store.add(
+            "baz".to_string(),
+            "echo baz".to_string(),
+            AliasScope::Global,
+        )?;
+
+        // The last definition of an alias takes its review state with it
+        store.remove_all_in_scope(&tmp)?;
+        let reviewed = store.reviewed()?;
+        assert!(reviewed.contains_key("foo"));
+        assert!(!reviewed.contains_key("bar"));
+
+        store.remove_scope_from_alias("foo", &AliasScope::Global)?;
+        assert!(!store.reviewed()?.contains_key("foo"));
+
+        store.remove_all()?;
+        assert!(store.reviewed()?.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn test_remove_scope_from_alias_partial() -> std::result::Result<(), crate::error::AkaError> {
         let dir = tempdir()?;
@@ -441,6 +1422,412 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_json_backend_ops() -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("aka.json");
+        let mut store = Store::load(&path)?;
+
+        store.add(
+            "foo".to_string(),
+            "echo foo".to_string(),
+            AliasScope::Global,
+        )?;
+        store.add(
+            "foo".to_string(),
+            "echo bar".to_string(),
+            AliasScope::Exact("/tmp".to_string()),
+        )?;
+        store.remove_scope_from_alias("foo", &AliasScope::Global)?;
+
+        // The file is plain JSON and survives a reopen
+        let content = std::fs::read_to_string(&path)?;
+        assert!(content.contains("echo bar"));
+        let store = Store::load(&path)?;
+        let aliases = store.list()?;
+        assert_eq!(aliases["foo"].len(), 1);
+        assert_eq!(aliases["foo"][0].command, "echo bar");
+
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_backend_ops() -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("aka.sqlite");
+        let mut store = Store::load(&path)?;
+
+        store.add(
+            "foo".to_string(),
+            "echo foo".to_string(),
+            AliasScope::Global,
+        )?;
+        store.add(
+            "bar".to_string(),
+            "echo bar".to_string(),
+            AliasScope::Global,
+        )?;
+        assert_eq!(store.remove("bar")?.unwrap()[0].command, "echo bar");
+
+        let store = Store::load(&path)?;
+        let aliases = store.list()?;
+        assert_eq!(aliases.len(), 1);
+        assert_eq!(aliases["foo"][0].command, "echo foo");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generation_and_read_cache() -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let path = dir.path().join("aka.redb");
+        let mut store = Store::load(&path)?;
+        assert_eq!(store.generation()?, 0);
+
+        store.add(
+            "foo".to_string(),
+            "echo foo".to_string(),
+            AliasScope::Global,
+        )?;
+        store.add(
+            "bar".to_string(),
+            "echo bar".to_string(),
+            AliasScope::Global,
+        )?;
+        assert_eq!(store.generation()?, 2);
+        store.list()?;
+        drop(store);
+
+        // The cache is written once the store closes and stays fresh across opens
+        let cache_path = cache::cache_path(&path);
+        assert_eq!(cache::read(&path).map(|entries| entries.len()), Some(2));
+        let store = Store::load(&path)?;
+        assert_eq!(store.list()?.len(), 2);
+
+        // Any mutation drops the cache
+        let mut store = store;
+        store.remove("bar")?;
+        assert!(!cache_path.exists());
+        assert_eq!(store.generation()?, 3);
+        assert_eq!(store.list()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_scopes() -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        store.add(
+            "b".to_string(),
+            "make".to_string(),
+            AliasScope::Recursive("/old/app".to_string()),
+        )?;
+        store.add(
+            "b".to_string(),
+            "make test".to_string(),
+            AliasScope::Exact("/old/app/tests".to_string()),
+        )?;
+        store.add(
+            "b".to_string(),
+            "make other".to_string(),
+            AliasScope::Exact("/old/application".to_string()),
+        )?;
+        store.add("g".to_string(), "git".to_string(), AliasScope::Global)?;
+
+        assert_eq!(store.move_scopes("/old/app", "/new/app")?, 2);
+
+        let mut scopes: Vec<AliasScope> =
+            store.list()?["b"].iter().map(|d| d.scope.clone()).collect();
+        scopes.sort_by_key(|s| format!("{:?}", s));
+        assert_eq!(
+            scopes,
+            vec![
+                AliasScope::Exact("/new/app/tests".to_string()),
+                AliasScope::Exact("/old/application".to_string()),
+                AliasScope::Recursive("/new/app".to_string()),
+            ]
+        );
+        assert_eq!(store.move_scopes("/nowhere", "/else")?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_scopes_refuses_to_overwrite() -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        store.add(
+            "b".to_string(),
+            "make".to_string(),
+            AliasScope::Recursive("/old".to_string()),
+        )?;
+        store.add(
+            "t".to_string(),
+            "move me".to_string(),
+            AliasScope::Exact("/old/a".to_string()),
+        )?;
+        store.add(
+            "t".to_string(),
+            "keep me".to_string(),
+            AliasScope::Exact("/new/a".to_string()),
+        )?;
+        let before = store.list()?;
+
+        match store.move_scopes("/old", "/new") {
+            Err(crate::error::AkaError::ConfigError(msg)) => {
+                assert!(
+                    msg.contains("'t'") && msg.contains("exact:/new/a"),
+                    "{}",
+                    msg
+                )
+            }
+            other => panic!("Expected ConfigError, got {:?}", other),
+        }
+        assert_eq!(store.list()?, before);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sync_generated() -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        let scope = AliasScope::Recursive("/app".to_string());
+        store.add("test".to_string(), "my-test".to_string(), scope.clone())?;
+
+        let entries = BTreeMap::from([
+            ("build".to_string(), "npm run build".to_string()),
+            ("lint".to_string(), "npm run lint".to_string()),
+            ("test".to_string(), "npm run test".to_string()),
+        ]);
+        let report = store.sync_generated("project", &scope, &entries)?;
+        assert_eq!(report.added, vec!["build", "lint"]);
+        assert_eq!(report.skipped, vec!["test"]);
+        assert_eq!(store.list()?["test"][0].command, "my-test");
+        assert_eq!(store.list()?["build"][0].origin.as_deref(), Some("project"));
+
+        let entries = BTreeMap::from([("build".to_string(), "npm run compile".to_string())]);
+        let report = store.sync_generated("project", &scope, &entries)?;
+        assert_eq!(report.updated, vec!["build"]);
+        assert_eq!(report.removed, vec!["lint"]);
+        let aliases = store.list()?;
+        assert!(!aliases.contains_key("lint"));
+        assert_eq!(aliases["build"][0].command, "npm run compile");
+        assert_eq!(aliases["test"][0].command, "my-test");
+
+        let report = store.sync_generated("project", &scope, &entries)?;
+        assert_eq!(report, SyncReport::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupt_records() -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let mut store = Store::load(&dir.path().join("aka.redb"))?;
+        store.add("ok".to_string(), "ls".to_string(), AliasScope::Global)?;
+        store.backend_mut()?.write(vec![
+            (
+                ALIASES,
+                "legacy".to_string(),
+                Some("[ -f x ] && ls".to_string()),
+            ),
+            (
+                ALIASES,
+                "partial".to_string(),
+                Some(r#"[{"command":"ls","scope":"Glo"#.to_string()),
+            ),
+            (
+                ALIASES,
+                "mixed".to_string(),
+                Some(r#"[{"command":"ls","scope":"Global"},{"scope":7}]"#.to_string()),
+            ),
+        ])?;
+
+        let corrupt: Vec<String> = store
+            .corrupt_records()?
+            .into_iter()
+            .map(|(a, _)| a)
+            .collect();
+        assert_eq!(corrupt, vec!["mixed", "partial"]);
+
+        assert_eq!(
+            salvage_definitions(r#"[{"command":"ls","scope":"Glo"#),
+            None
+        );
+        let salvaged = salvage_definitions(r#"{"command":"ls","scope":"Global"}"#).unwrap();
+        assert_eq!(salvaged[0].command, "ls");
+
+        store.set_strict(true);
+        assert!(matches!(
+            store.list(),
+            Err(crate::error::AkaError::CorruptRecord { alias, .. }) if alias == "mixed"
+        ));
+        assert!(store.remove("partial").is_err());
+        assert_eq!(store.remove("ok")?.map(|defs| defs.len()), Some(1));
+        store.set_strict(false);
+
+        assert!(store.quarantine("partial")?);
+        assert!(!store.list()?.contains_key("partial"));
+        assert_eq!(store.backend()?.entries(QUARANTINE)?.len(), 1);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scope_from_user_path() -> std::result::Result<(), crate::error::AkaError> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempdir()?;
+        let real = dir.path().canonicalize()?.join("real");
+        std::fs::create_dir(&real)?;
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(&real, &link)?;
+        let real_str = real.to_str().unwrap().to_string();
+
+        // Symlinks and trailing slashes normalize to the same stored path
+        assert_eq!(
+            AliasScope::from_user_path(&format!("{}/", link.display()), false)?,
+            AliasScope::Exact(real_str.clone())
+        );
+        assert_eq!(
+            AliasScope::from_user_path(&format!("{}/./", real.display()), true)?,
+            AliasScope::Recursive(real_str)
+        );
+
+        assert!(matches!(
+            AliasScope::from_user_path(&dir.path().join("missing").to_string_lossy(), false),
+            Err(crate::error::AkaError::InvalidScopePath(_))
+        ));
+
+        assert_eq!(
+            AliasScope::from_user_path("tmux:work", true)?,
+            AliasScope::Tmux("work".to_string())
+        );
+        assert!(AliasScope::from_user_path("tmux:", false).is_err());
+
+        let invalid = dir.path().join(std::ffi::OsStr::from_bytes(b"bad\xff"));
+        std::fs::create_dir(&invalid)?;
+        assert!(matches!(
+            normalize_scope_path(&invalid),
+            Err(crate::error::AkaError::InvalidScopePath(msg)) if msg.contains("not valid UTF-8")
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_workspace_root() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("repo");
+        let nested = root.join("crates").join("core").join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir(root.join(".git")).unwrap();
+
+        let markers = vec![".git".to_string()];
+        assert_eq!(find_workspace_root(&nested, &markers), Some(root.clone()));
+
+        // The nearest marker wins
+        std::fs::write(root.join("crates").join("core").join("Cargo.toml"), "").unwrap();
+        let markers = vec![".git".to_string(), "Cargo.toml".to_string()];
+        assert_eq!(
+            find_workspace_root(&nested, &markers),
+            Some(root.join("crates").join("core"))
+        );
+
+        let markers = vec!["no-such-marker".to_string()];
+        assert_eq!(find_workspace_root(&nested, &markers), None);
+    }
+
+    #[test]
+    fn test_available_command() {
+        let mut def = AliasDefinition::new("bat -p".to_string(), AliasScope::Global);
+        assert_eq!(def.available_command(|_| false), "bat -p");
+
+        def.fallbacks = vec!["less".to_string(), "cat".to_string()];
+        assert_eq!(def.available_command(|_| true), "bat -p");
+        assert_eq!(def.available_command(|program| program == "cat"), "cat");
+        assert_eq!(def.available_command(|program| program != "bat"), "less");
+        assert_eq!(def.available_command(|_| false), "cat");
+    }
+
+    #[test]
+    fn test_scope_spec_parse() -> std::result::Result<(), crate::error::AkaError> {
+        let dir = tempdir()?;
+        let path = dir.path().canonicalize()?.to_str().unwrap().to_string();
+        let exact = AliasScope::Exact(path.clone());
+        let recursive = AliasScope::Recursive(path.clone());
+
+        assert_eq!(
+            ScopeSpec::parse("Global")?,
+            ScopeSpec::Scope(AliasScope::Global)
+        );
+        assert_eq!(
+            ScopeSpec::parse("tmux:work")?,
+            ScopeSpec::Scope(AliasScope::Tmux("work".to_string()))
+        );
+        assert_eq!(
+            ScopeSpec::parse(&format!("exact:{}", path))?,
+            ScopeSpec::Scope(exact.clone())
+        );
+        let spec = ScopeSpec::parse(&format!("recursive:{}/", path))?;
+        assert!(spec.matches(&recursive) && !spec.matches(&exact));
+        assert_eq!(spec.into_scope(false), recursive);
+
+        // A bare directory names either kind, and becomes exact unless asked otherwise
+        let spec = ScopeSpec::parse(&path)?;
+        assert!(spec.matches(&recursive) && spec.matches(&exact));
+        assert!(!spec.matches(&AliasScope::Global));
+        assert_eq!(spec.clone().into_scope(false), exact);
+        assert_eq!(spec.into_scope(true), recursive);
+
+        assert!(ScopeSpec::parse("exact:/does/not/exist").is_err());
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case(AliasScope::Recursive("/tmp".to_string()), "/tmp", true)]
+    #[case(AliasScope::Recursive("/tmp".to_string()), "/tmp/x/y", true)]
+    #[case(AliasScope::Recursive("/tmp".to_string()), "/tmpfoo", false)]
+    #[case(AliasScope::Recursive("/tmp".to_string()), "/tmp-other/x", false)]
+    #[case(AliasScope::Recursive("/".to_string()), "/srv", true)]
+    #[case(AliasScope::Exact("/tmp".to_string()), "/tmp/", true)]
+    #[case(AliasScope::Exact("/tmp".to_string()), "/tmp/x", false)]
+    #[case(AliasScope::Exact("/tmp".to_string()), "/tmpfoo", false)]
+    #[case(AliasScope::Global, "/anywhere", true)]
+    fn test_scope_matches(#[case] scope: AliasScope, #[case] dir: &str, #[case] expected: bool) {
+        assert_eq!(scope.matches(Path::new(dir)), expected);
+    }
+
+    #[test]
+    fn test_resolve_definition() {
+        let defs = vec![
+            AliasDefinition::new("global".to_string(), AliasScope::Global),
+            AliasDefinition::new("rec".to_string(), AliasScope::Recursive("/a".to_string())),
+            AliasDefinition::new(
+                "deep".to_string(),
+                AliasScope::Recursive("/a/b".to_string()),
+            ),
+            AliasDefinition::new("exact".to_string(), AliasScope::Exact("/a".to_string())),
+        ];
+
+        assert_eq!(resolve_definition(&defs, "/a").unwrap().command, "exact");
+        assert_eq!(resolve_definition(&defs, "/a/c").unwrap().command, "rec");
+        assert_eq!(resolve_definition(&defs, "/a/b/c").unwrap().command, "deep");
+        assert_eq!(resolve_definition(&defs, "/z").unwrap().command, "global");
+        assert!(resolve_definition(&defs[1..], "/z").is_none());
+        // A sibling that merely shares the prefix is not inside the scope
+        assert_eq!(resolve_definition(&defs, "/ab").unwrap().command, "global");
+
+        // Directory scopes win over a tmux session, which wins over global
+        let mut defs = vec![
+            AliasDefinition::new("global".to_string(), AliasScope::Global),
+            AliasDefinition::new("tmux".to_string(), AliasScope::Tmux("work".to_string())),
+            AliasDefinition::new("rec".to_string(), AliasScope::Recursive("/a".to_string())),
+        ];
+        sort_by_precedence(&mut defs);
+        let order: Vec<&str> = defs.iter().map(|d| d.command.as_str()).collect();
+        assert_eq!(order, ["rec", "tmux", "global"]);
+    }
+
     #[test]
     fn test_remove_all_in_scope_global() -> std::result::Result<(), crate::error::AkaError> {
         let dir = tempdir()?;
@@ -0,0 +1,359 @@
+use crate::error::AkaError;
+use redb::{
+    Database, ReadOnlyDatabase, ReadTransaction, ReadableDatabase, ReadableTable, TableDefinition,
+};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A pending change to one key of a table: `Some(value)` upserts, `None` deletes.
+pub(crate) type Change = (&'static str, String, Option<String>);
+
+/// Raw key/value persistence underneath `Store`.
+///
+/// Values are the JSON-encoded definition lists; decoding stays in `Store` so every
+/// backend shares the same legacy handling.
+pub(crate) trait Backend {
+    fn get(&self, table: &str, key: &str) -> std::result::Result<Option<String>, AkaError>;
+
+    fn entries(&self, table: &str) -> std::result::Result<Vec<(String, String)>, AkaError>;
+
+    /// Apply all changes atomically.
+    fn write(&mut self, changes: Vec<Change>) -> std::result::Result<(), AkaError>;
+}
+
+/// Whether `e` means the file cannot be written here, e.g. on a read-only mount.
+pub(crate) fn is_unwritable(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::ReadOnlyFilesystem | std::io::ErrorKind::PermissionDenied
+    )
+}
+
+/// `e` from writing the store at `path`, as [`AkaError::ReadOnly`] when the file cannot be
+/// written there at all.
+fn write_error(path: &Path, e: std::io::Error) -> AkaError {
+    match is_unwritable(&e) {
+        true => AkaError::ReadOnly(format!("{} cannot be written: {}", path.display(), e)),
+        false => e.into(),
+    }
+}
+
+enum RedbDatabase {
+    Writable(Database),
+    ReadOnly(ReadOnlyDatabase),
+    /// No store exists and none can be created, so there is nothing to read.
+    Absent,
+}
+
+pub(crate) struct RedbBackend {
+    db: RedbDatabase,
+    path: PathBuf,
+}
+
+impl RedbBackend {
+    /// Open the store at `path`, only for reading when the file cannot be written, such as on
+    /// a read-only mount, so commands that only read it still work; a store that does not
+    /// exist there reads as empty.
+    pub(crate) fn open(path: &Path) -> std::result::Result<Self, AkaError> {
+        let db = match Database::create(path) {
+            Ok(db) => RedbDatabase::Writable(db),
+            // A missing directory is one `Store::new` could not create
+            Err(redb::DatabaseError::Storage(redb::StorageError::Io(e)))
+                if is_unwritable(&e)
+                    || (e.kind() == std::io::ErrorKind::NotFound
+                        && path.parent().is_some_and(|dir| !dir.exists())) =>
+            {
+                tracing::info!(path = %path.display(), error = %e, "opening store read-only");
+                match path.exists() {
+                    true => RedbDatabase::ReadOnly(ReadOnlyDatabase::open(path)?),
+                    false => RedbDatabase::Absent,
+                }
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(RedbBackend {
+            db,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// A read transaction, or `None` when there is no store to read.
+    fn begin_read(&self) -> std::result::Result<Option<ReadTransaction>, redb::TransactionError> {
+        match &self.db {
+            RedbDatabase::Writable(db) => db.begin_read().map(Some),
+            RedbDatabase::ReadOnly(db) => db.begin_read().map(Some),
+            RedbDatabase::Absent => Ok(None),
+        }
+    }
+}
+
+impl Backend for RedbBackend {
+    fn get(&self, table: &str, key: &str) -> std::result::Result<Option<String>, AkaError> {
+        let Some(read_txn) = self.begin_read()? else {
+            return Ok(None);
+        };
+        match read_txn.open_table(TableDefinition::<&str, &str>::new(table)) {
+            Ok(t) => Ok(t.get(key)?.map(|v| v.value().to_string())),
+            Err(redb::TableError::TableDoesNotExist(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn entries(&self, table: &str) -> std::result::Result<Vec<(String, String)>, AkaError> {
+        let Some(read_txn) = self.begin_read()? else {
+            return Ok(Vec::new());
+        };
+        let mut entries = Vec::new();
+        match read_txn.open_table(TableDefinition::<&str, &str>::new(table)) {
+            Ok(t) => {
+                for item in t.iter()? {
+                    let (k, v) = item?;
+                    entries.push((k.value().to_string(), v.value().to_string()));
+                }
+            }
+            Err(redb::TableError::TableDoesNotExist(_)) => {
+                // Table doesn't exist yet, nothing stored
+            }
+            Err(e) => return Err(e.into()),
+        }
+        Ok(entries)
+    }
+
+    fn write(&mut self, changes: Vec<Change>) -> std::result::Result<(), AkaError> {
+        let RedbDatabase::Writable(db) = &self.db else {
+            return Err(AkaError::ReadOnly(format!(
+                "{} cannot be written here",
+                self.path.display()
+            )));
+        };
+        let write_txn = db.begin_write()?;
+        for (table, key, value) in changes {
+            let mut t = write_txn.open_table(TableDefinition::<&str, &str>::new(table))?;
+            match value {
+                Some(value) => {
+                    t.insert(key.as_str(), value.as_str())?;
+                }
+                None => {
+                    t.remove(key.as_str())?;
+                }
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+}
+
+/// A single human-readable JSON document, rewritten atomically on every change.
+pub(crate) struct JsonBackend {
+    path: PathBuf,
+    tables: BTreeMap<String, BTreeMap<String, serde_json::Value>>,
+}
+
+impl JsonBackend {
+    pub(crate) fn open(path: &Path) -> std::result::Result<Self, AkaError> {
+        let tables = match std::fs::read_to_string(path) {
+            Ok(content) if content.trim().is_empty() => BTreeMap::new(),
+            Ok(content) => serde_json::from_str(&content).map_err(|e| {
+                AkaError::ConfigError(format!("Invalid store file {}: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(JsonBackend {
+            path: path.to_path_buf(),
+            tables,
+        })
+    }
+
+    /// Definition lists are kept as structured JSON so the file stays editable; anything
+    /// else (legacy plain commands) is stored verbatim as a string.
+    fn encode(value: String) -> serde_json::Value {
+        match serde_json::from_str::<serde_json::Value>(&value) {
+            Ok(parsed) if parsed.is_array() || parsed.is_object() => parsed,
+            _ => serde_json::Value::String(value),
+        }
+    }
+
+    fn decode(value: &serde_json::Value) -> String {
+        match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl Backend for JsonBackend {
+    fn get(&self, table: &str, key: &str) -> std::result::Result<Option<String>, AkaError> {
+        Ok(self
+            .tables
+            .get(table)
+            .and_then(|t| t.get(key))
+            .map(Self::decode))
+    }
+
+    fn entries(&self, table: &str) -> std::result::Result<Vec<(String, String)>, AkaError> {
+        Ok(self
+            .tables
+            .get(table)
+            .map(|t| {
+                t.iter()
+                    .map(|(k, v)| (k.clone(), Self::decode(v)))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn write(&mut self, changes: Vec<Change>) -> std::result::Result<(), AkaError> {
+        let mut tables = self.tables.clone();
+        for (table, key, value) in changes {
+            let t = tables.entry(table.to_string()).or_default();
+            match value {
+                Some(value) => {
+                    t.insert(key, Self::encode(value));
+                }
+                None => {
+                    t.remove(&key);
+                }
+            }
+        }
+
+        let json = serde_json::to_string_pretty(&tables)
+            .map_err(|e| AkaError::ConfigError(e.to_string()))?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json).map_err(|e| write_error(&self.path, e))?;
+        std::fs::rename(&tmp_path, &self.path).map_err(|e| write_error(&self.path, e))?;
+
+        self.tables = tables;
+        Ok(())
+    }
+}
+
+/// One SQLite table per store table, so aliases can be queried with standard tools.
+#[cfg(feature = "sqlite")]
+pub(crate) struct SqliteBackend {
+    conn: rusqlite::Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteBackend {
+    pub(crate) fn open(path: &Path) -> std::result::Result<Self, AkaError> {
+        let conn = rusqlite::Connection::open(path)?;
+        Ok(SqliteBackend { conn })
+    }
+
+    fn ensure_table(&self, table: &str) -> std::result::Result<(), AkaError> {
+        self.conn.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS \"{}\" (name TEXT PRIMARY KEY, value TEXT NOT NULL)",
+                table
+            ),
+            [],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl Backend for SqliteBackend {
+    fn get(&self, table: &str, key: &str) -> std::result::Result<Option<String>, AkaError> {
+        use rusqlite::OptionalExtension;
+
+        self.ensure_table(table)?;
+        Ok(self
+            .conn
+            .query_row(
+                &format!("SELECT value FROM \"{}\" WHERE name = ?1", table),
+                [key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn entries(&self, table: &str) -> std::result::Result<Vec<(String, String)>, AkaError> {
+        self.ensure_table(table)?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT name, value FROM \"{}\" ORDER BY name",
+            table
+        ))?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    fn write(&mut self, changes: Vec<Change>) -> std::result::Result<(), AkaError> {
+        for (table, _, _) in &changes {
+            self.ensure_table(table)?;
+        }
+        let tx = self.conn.transaction()?;
+        for (table, key, value) in changes {
+            match value {
+                Some(value) => {
+                    tx.execute(
+                        &format!(
+                            "INSERT OR REPLACE INTO \"{}\" (name, value) VALUES (?1, ?2)",
+                            table
+                        ),
+                        [key.as_str(), value.as_str()],
+                    )?;
+                }
+                None => {
+                    tx.execute(
+                        &format!("DELETE FROM \"{}\" WHERE name = ?1", table),
+                        [key.as_str()],
+                    )?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_redb_reads_and_refuses_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("aka.redb");
+        let mut writable = RedbBackend::open(&path).unwrap();
+        writable
+            .write(vec![("aliases", "g".to_string(), Some("git".to_string()))])
+            .unwrap();
+        drop(writable);
+
+        let mut backends = [
+            RedbBackend {
+                db: RedbDatabase::ReadOnly(ReadOnlyDatabase::open(&path).unwrap()),
+                path: path.clone(),
+            },
+            RedbBackend {
+                db: RedbDatabase::Absent,
+                path: dir.path().join("missing").join("aka.redb"),
+            },
+        ];
+        assert_eq!(
+            backends[0].get("aliases", "g").unwrap().as_deref(),
+            Some("git")
+        );
+        assert!(backends[1].entries("aliases").unwrap().is_empty());
+        for backend in &mut backends {
+            assert!(matches!(
+                backend.write(vec![("aliases", "t".to_string(), None)]),
+                Err(AkaError::ReadOnly(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_unwritable_errors() {
+        let path = Path::new("/ro/aka.json");
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(matches!(write_error(path, denied), AkaError::ReadOnly(_)));
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert!(matches!(write_error(path, missing), AkaError::IoError(_)));
+        assert!(is_unwritable(&std::io::Error::from(
+            std::io::ErrorKind::ReadOnlyFilesystem
+        )));
+    }
+}
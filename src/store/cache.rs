@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Raw `(alias, encoded definitions)` pairs as stored in the aliases table.
+pub(crate) type Entries = Vec<(String, String)>;
+
+/// Size and modification time of the store file, used to tell whether the cache is fresh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Stamp {
+    len: u64,
+    modified_ns: u128,
+}
+
+/// Serialized alias table kept next to the store so read-only commands can skip opening it.
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFile {
+    stamp: Stamp,
+    generation: u64,
+    aliases: Entries,
+}
+
+pub(crate) fn cache_path(store_path: &Path) -> PathBuf {
+    store_path.with_file_name("aka.cache.json")
+}
+
+pub(crate) fn stamp(store_path: &Path) -> Option<Stamp> {
+    let metadata = std::fs::metadata(store_path).ok()?;
+    let modified_ns = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+    Some(Stamp {
+        len: metadata.len(),
+        modified_ns,
+    })
+}
+
+/// Return the cached raw alias entries if they still match the store file on disk.
+pub(crate) fn read(store_path: &Path) -> Option<Entries> {
+    let current = stamp(store_path)?;
+    let content = std::fs::read_to_string(cache_path(store_path)).ok()?;
+    let cache: CacheFile = serde_json::from_str(&content).ok()?;
+    (cache.stamp == current).then_some(cache.aliases)
+}
+
+/// Best effort: a cache that cannot be written only costs the next read a store open.
+pub(crate) fn write(
+    store_path: &Path,
+    stamp: Stamp,
+    generation: u64,
+    aliases: &[(String, String)],
+) {
+    let cache = CacheFile {
+        stamp,
+        generation,
+        aliases: aliases.to_vec(),
+    };
+    let Ok(json) = serde_json::to_string(&cache) else {
+        return;
+    };
+    let path = cache_path(store_path);
+    let tmp_path = path.with_extension("json.tmp");
+    if std::fs::write(&tmp_path, json).is_ok() {
+        let _ = std::fs::rename(&tmp_path, &path);
+    }
+}
+
+pub(crate) fn invalidate(store_path: &Path) {
+    let _ = std::fs::remove_file(cache_path(store_path));
+}
@@ -0,0 +1,175 @@
+use crate::error::AkaError;
+use crate::store::{AliasDefinition, AliasScope};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Location of the administrator-provisioned alias file.
+const DEFAULT_SYSTEM_FILE: &str = "/etc/aka/aliases.toml";
+
+#[derive(Debug, Deserialize)]
+struct SystemFile {
+    #[serde(default)]
+    aliases: HashMap<String, SystemEntry>,
+}
+
+/// An entry in the system file: either a bare command (global) or a table with a scope.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SystemEntry {
+    Command(String),
+    Definition {
+        command: String,
+        scope: Option<String>,
+        #[serde(default)]
+        recursive: bool,
+    },
+}
+
+impl SystemEntry {
+    fn into_definition(self) -> AliasDefinition {
+        let (command, scope) = match self {
+            SystemEntry::Command(command) => (command, AliasScope::Global),
+            SystemEntry::Definition {
+                command,
+                scope: None,
+                ..
+            } => (command, AliasScope::Global),
+            SystemEntry::Definition {
+                command,
+                scope: Some(path),
+                recursive,
+            } => {
+                let scope = if recursive {
+                    AliasScope::Recursive(path)
+                } else {
+                    AliasScope::Exact(path)
+                };
+                (command, scope)
+            }
+        };
+        let mut def = AliasDefinition::new(command, scope);
+        def.system = true;
+        def
+    }
+}
+
+/// Resolve the system alias file, honoring `AKA_SYSTEM_FILE` for non-standard installs.
+pub fn system_file_path() -> PathBuf {
+    match std::env::var("AKA_SYSTEM_FILE") {
+        Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+        _ => PathBuf::from(DEFAULT_SYSTEM_FILE),
+    }
+}
+
+/// Load the read-only system aliases. A missing file simply means there are none.
+pub fn load_system_aliases(
+    path: &Path,
+) -> std::result::Result<HashMap<String, Vec<AliasDefinition>>, AkaError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let file: SystemFile = toml::from_str(&content).map_err(|e| {
+        AkaError::ConfigError(format!(
+            "Invalid system alias file {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+
+    Ok(file
+        .aliases
+        .into_iter()
+        .map(|(alias, entry)| (alias, vec![entry.into_definition()]))
+        .collect())
+}
+
+/// The system aliases, read once per process.
+///
+/// A broken system file is the administrator's problem, not the user's: it is reported once
+/// on stderr and treated as empty so every other command keeps working. `doctor` and `lint`
+/// report it as an error through [`check_system_file`].
+pub fn system_aliases() -> HashMap<String, Vec<AliasDefinition>> {
+    static ALIASES: OnceLock<HashMap<String, Vec<AliasDefinition>>> = OnceLock::new();
+    ALIASES
+        .get_or_init(|| {
+            load_system_aliases(&system_file_path()).unwrap_or_else(|e| {
+                eprintln!("aka: ignoring system aliases: {}", e);
+                HashMap::new()
+            })
+        })
+        .clone()
+}
+
+/// Fail when the system alias file exists but cannot be read or parsed.
+pub fn check_system_file() -> std::result::Result<(), AkaError> {
+    load_system_aliases(&system_file_path()).map(|_| ())
+}
+
+/// Merge the system layer into the user's aliases.
+///
+/// User definitions always win: a system definition is only added when the user has no
+/// definition for the same alias and scope.
+pub fn merge_system_aliases(aliases: &mut HashMap<String, Vec<AliasDefinition>>) {
+    for (alias, system_defs) in system_aliases() {
+        let defs = aliases.entry(alias).or_default();
+        for system_def in system_defs {
+            if !defs.iter().any(|d| d.scope == system_def.scope) {
+                defs.push(system_def);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_system_aliases() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.toml");
+        std::fs::write(
+            &path,
+            r#"
+[aliases]
+ll = "ls -la"
+deploy = { command = "make deploy", scope = "/srv/app", recursive = true }
+"#,
+        )
+        .unwrap();
+
+        let aliases = load_system_aliases(&path).unwrap();
+        assert_eq!(aliases["ll"][0].command, "ls -la");
+        assert_eq!(aliases["ll"][0].scope, AliasScope::Global);
+        assert!(aliases["ll"][0].system);
+        assert_eq!(
+            aliases["deploy"][0].scope,
+            AliasScope::Recursive("/srv/app".to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_system_aliases_missing_file() {
+        let dir = tempdir().unwrap();
+        let aliases = load_system_aliases(&dir.path().join("missing.toml")).unwrap();
+        assert!(aliases.is_empty());
+    }
+
+    #[test]
+    fn test_load_system_aliases_invalid_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("aliases.toml");
+        std::fs::write(&path, "[aliases\n").unwrap();
+
+        match load_system_aliases(&path) {
+            Err(AkaError::ConfigError(msg)) => assert!(msg.contains("Invalid system alias file")),
+            other => panic!("Expected ConfigError, got {:?}", other),
+        }
+    }
+}
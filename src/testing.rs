@@ -0,0 +1,224 @@
+//! Helpers for testing wrappers and plugins built on aka against the real store logic,
+//! enabled with the `testing` feature.
+//!
+//! ```
+//! use aka::store::AliasScope;
+//! use aka::testing::{TempStore, assert_no_definition_applies, assert_resolves};
+//!
+//! let temp = TempStore::new()?
+//!     .with_alias("g", "git")?
+//!     .with_scoped_alias("t", "cargo test", AliasScope::Recursive("/work".to_string()))?;
+//! assert_resolves(temp.store(), "t", "/work/app", "cargo test");
+//! assert_no_definition_applies(temp.store(), "t", "/home");
+//! # Ok::<(), aka::AkaError>(())
+//! ```
+
+use crate::error::AkaError;
+use crate::store::{AliasDefinition, AliasScope, BackendKind, Store, resolve_definition};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// A store in a temporary data directory, removed when this is dropped.
+pub struct TempStore {
+    store: Store,
+    dir: TempDir,
+}
+
+impl TempStore {
+    /// An empty redb store, laid out the way `aka` finds it under `aka_DATA_DIR`.
+    pub fn new() -> std::result::Result<Self, AkaError> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("aka").join(BackendKind::Redb.file_name());
+        let store = Store::load(&path)?;
+        Ok(TempStore { store, dir })
+    }
+
+    /// Add a global definition of `alias` running `command`.
+    pub fn with_alias(self, alias: &str, command: &str) -> std::result::Result<Self, AkaError> {
+        self.with_scoped_alias(alias, command, AliasScope::Global)
+    }
+
+    /// Add a definition of `alias` running `command` in `scope`.
+    pub fn with_scoped_alias(
+        self,
+        alias: &str,
+        command: &str,
+        scope: AliasScope,
+    ) -> std::result::Result<Self, AkaError> {
+        self.with_definition(alias, AliasDefinition::new(command.to_string(), scope))
+    }
+
+    /// Add `def`, with whatever options it sets, to `alias`.
+    pub fn with_definition(
+        mut self,
+        alias: &str,
+        def: AliasDefinition,
+    ) -> std::result::Result<Self, AkaError> {
+        self.store.add_definition(alias.to_string(), def)?;
+        Ok(self)
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    pub fn store_mut(&mut self) -> &mut Store {
+        &mut self.store
+    }
+
+    /// The value of `aka_DATA_DIR` pointing `aka` at this store.
+    pub fn data_dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// `program`, e.g. an installed `aka`, set up to use this store.
+    ///
+    /// The store is released first so the command can open it; it is reopened on next use. The
+    /// config and system alias files are looked up in the temporary directory too.
+    pub fn command(&mut self, program: impl Into<PathBuf>) -> Command {
+        self.store.release();
+        let mut command = Command::new(program.into());
+        command
+            .env("aka_DATA_DIR", self.dir.path())
+            .env("AKA_CONFIG", self.dir.path().join("config.toml"))
+            .env("AKA_SYSTEM_FILE", self.dir.path().join("aliases.toml"));
+        command
+    }
+}
+
+/// The command `alias` runs in `dir` from this store alone, leaving out the host's system aliases.
+fn resolve_in_store(
+    store: &Store,
+    alias: &str,
+    dir: &str,
+) -> std::result::Result<String, AkaError> {
+    let defs = store
+        .get(alias)?
+        .ok_or_else(|| AkaError::AliasNotFound(alias.to_string()))?;
+    resolve_definition(&defs, dir)
+        .map(|def| def.command.clone())
+        .ok_or_else(|| AkaError::ScopeNotFoundInAlias(alias.to_string(), dir.to_string()))
+}
+
+/// Assert that `alias` runs `expected` in `dir`, as the generated function would pick it.
+#[track_caller]
+pub fn assert_resolves(store: &Store, alias: &str, dir: &str, expected: &str) {
+    match resolve_in_store(store, alias, dir) {
+        Ok(command) => assert_eq!(
+            command, expected,
+            "'{}' in {} runs '{}', not '{}'",
+            alias, dir, command, expected
+        ),
+        Err(e) => panic!("'{}' in {} does not resolve: {}", alias, dir, e),
+    }
+}
+
+/// Assert that no definition of `alias` applies in `dir`, so the real command runs.
+#[track_caller]
+pub fn assert_no_definition_applies(store: &Store, alias: &str, dir: &str) {
+    match resolve_in_store(store, alias, dir) {
+        Err(AkaError::ScopeNotFoundInAlias(_, _)) => {}
+        Ok(command) => panic!("'{}' in {} runs '{}'", alias, dir, command),
+        Err(e) => panic!("'{}' in {}: {}", alias, dir, e),
+    }
+}
+
+/// Run `script` in `shell`, started in `dir`, after loading the functions `aka init --dump`
+/// writes for `store`; `None` when the shell is not installed.
+#[cfg(feature = "cli")]
+pub fn run_with_aliases(
+    store: &Store,
+    shell: crate::codegen::Shell,
+    dir: &Path,
+    script: &str,
+) -> std::result::Result<Option<std::process::Output>, AkaError> {
+    use crate::codegen::Shell;
+
+    let mut dump = Vec::new();
+    crate::commands::init::write_shell_dump(
+        Some(store),
+        shell.codegen(),
+        false,
+        None,
+        false,
+        &mut dump,
+    )?;
+    let (program, flags): (&str, &[&str]) = match shell {
+        Shell::Zsh => ("zsh", &["-f", "-c"]),
+        Shell::Bash => ("bash", &["-c"]),
+        Shell::Fish => ("fish", &["--no-config", "-c"]),
+        Shell::Nu => ("nu", &["-n", "-c"]),
+        Shell::PowerShell => ("pwsh", &["-NoProfile", "-Command"]),
+        Shell::Elvish => ("elvish", &["-norc", "-c"]),
+    };
+    let script = format!("{}\n{}", String::from_utf8_lossy(&dump), script);
+    Ok(Command::new(program)
+        .args(flags)
+        .arg(script)
+        .current_dir(dir)
+        .output()
+        .ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_store_and_assertions() {
+        let temp = TempStore::new()
+            .unwrap()
+            .with_alias("g", "git")
+            .unwrap()
+            .with_scoped_alias("t", "cargo test", AliasScope::Exact("/work".to_string()))
+            .unwrap();
+        assert_resolves(temp.store(), "g", "/anywhere", "git");
+        assert_resolves(temp.store(), "t", "/work", "cargo test");
+        assert_no_definition_applies(temp.store(), "t", "/work/app");
+        assert!(temp.data_dir().join("aka").join("aka.redb").exists());
+    }
+
+    #[test]
+    #[should_panic(expected = "'g' in /work runs 'git', not 'tig'")]
+    fn test_assert_resolves_reports_the_command() {
+        let temp = TempStore::new().unwrap().with_alias("g", "git").unwrap();
+        assert_resolves(temp.store(), "g", "/work", "tig");
+    }
+
+    #[test]
+    fn test_command_stays_inside_the_temp_dir() {
+        let mut temp = TempStore::new().unwrap();
+        let command = temp.command("aka");
+        let dir = temp.data_dir().to_path_buf();
+        for (key, value) in command.get_envs() {
+            let value = Path::new(value.unwrap());
+            assert!(
+                value.starts_with(&dir),
+                "{:?} points outside: {:?}",
+                key,
+                value
+            );
+        }
+        assert_eq!(command.get_envs().count(), 3);
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_run_with_aliases() {
+        let temp = TempStore::new()
+            .unwrap()
+            .with_alias("greet", "echo hello @1")
+            .unwrap();
+        let Some(output) = run_with_aliases(
+            temp.store(),
+            crate::codegen::Shell::Bash,
+            temp.data_dir(),
+            "greet world",
+        )
+        .unwrap() else {
+            return;
+        };
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello world\n");
+    }
+}
@@ -0,0 +1,89 @@
+use crate::error::AkaError;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Usage log the generated functions append to, one alias name per line.
+///
+/// Tracking only happens while `AKA_USAGE_LOG` is set in the shell; nothing leaves the machine.
+pub fn usage_log_path() -> std::result::Result<PathBuf, AkaError> {
+    match std::env::var("AKA_USAGE_LOG") {
+        Ok(path) if !path.trim().is_empty() => Ok(PathBuf::from(path)),
+        _ => Ok(crate::store::data_dir()?.join("usage.log")),
+    }
+}
+
+/// Count invocations per alias. A missing log simply means nothing was recorded yet.
+pub fn load_usage_counts(path: &Path) -> std::result::Result<HashMap<String, u64>, AkaError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut counts = HashMap::new();
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        *counts.entry(line.to_string()).or_insert(0) += 1;
+    }
+    Ok(counts)
+}
+
+/// Aliases logged after the first `offset` lines, plus the log's current line count.
+///
+/// A log shorter than `offset` was rotated or cleared, so all of it counts as new.
+pub fn load_usage_since(
+    path: &Path,
+    offset: usize,
+) -> std::result::Result<(Vec<String>, usize), AkaError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+        Err(e) => return Err(e.into()),
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let start = if lines.len() < offset { 0 } else { offset };
+    let mut names: Vec<String> = lines[start..]
+        .iter()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    names.dedup();
+    Ok((names, lines.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_usage_counts() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("usage.log");
+        std::fs::write(&path, "g\nll\ng\n\ng\n").unwrap();
+
+        let counts = load_usage_counts(&path).unwrap();
+        assert_eq!(counts["g"], 3);
+        assert_eq!(counts["ll"], 1);
+
+        let missing = load_usage_counts(&dir.path().join("missing.log")).unwrap();
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_load_usage_since() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("usage.log");
+        std::fs::write(&path, "g\nll\ng\nk\n").unwrap();
+
+        assert_eq!(
+            load_usage_since(&path, 2).unwrap(),
+            (vec!["g".to_string(), "k".to_string()], 4)
+        );
+        assert_eq!(load_usage_since(&path, 4).unwrap(), (vec![], 4));
+        // A log shorter than the offset has been rotated and is read from the start.
+        assert_eq!(load_usage_since(&path, 9).unwrap().0.len(), 3);
+    }
+}
@@ -0,0 +1,34 @@
+_aka_aliases_was_on=$(shopt -q expand_aliases && echo 1 || echo 0)
+shopt -u expand_aliases
+if [ -n "$AKA_MANAGED_ALIASES" ]; then
+    for al in $AKA_MANAGED_ALIASES; do unalias $al 2>/dev/null; unset -f $al 2>/dev/null; done
+fi
+
+# Global
+unalias ll 2>/dev/null; unset -f ll 2>/dev/null
+ll() {
+    if [ -n "$AKA_DISABLE" ]; then command ll "$@"; return; fi
+    local current_dir="$PWD"
+    if [ -n "$AKA_USAGE_LOG" ]; then printf '%s\n' ll >> "$AKA_USAGE_LOG"; fi
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'll: Global -> ls -la' >&2; fi; ls -la "$@"
+}
+if complete -p ls &>/dev/null || { _completion_loader ls; complete -p ls; } &>/dev/null; then eval "$(complete -p ls) ll"; fi
+
+# Scoped: Recursive /work
+unalias greet 2>/dev/null; unset -f greet 2>/dev/null
+greet() {
+    if [ -n "$AKA_DISABLE" ]; then command greet "$@"; return; fi
+    local current_dir="$PWD"
+    if [ -n "$AKA_USAGE_LOG" ]; then printf '%s\n' greet >> "$AKA_USAGE_LOG"; fi
+    if [[ "$current_dir" == "/work" || "$current_dir" == "/work/"* ]]; then
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'greet: Recursive /work -> echo hello @1' >&2; fi; echo hello "${1}"
+    else
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'greet: Global -> echo hi' >&2; fi; echo hi "$@"
+    fi
+}
+if complete -p echo &>/dev/null || { _completion_loader echo; complete -p echo; } &>/dev/null; then eval "$(complete -p echo) greet"; fi
+export AKA_MANAGED_ALIASES="ll greet"
+if [ "${_aka_aliases_was_on:-0}" = "1" ]; then
+    shopt -s expand_aliases
+fi
+unset _aka_aliases_was_on
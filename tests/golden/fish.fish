@@ -0,0 +1,46 @@
+for al in (string split -n ' ' -- "$AKA_MANAGED_ALIASES")
+    functions -e $al
+end
+function _aka_set_status
+    return $argv[1]
+end
+
+# Global
+functions -e ll
+function ll
+    if test -n "$AKA_DISABLE"; command ll $argv; return; end
+    set -l current_dir $PWD
+    if test -n "$AKA_USAGE_LOG"; printf '%s\n' ll >> $AKA_USAGE_LOG; end
+        if test -n "$AKA_DEBUG"; printf 'aka: %s\n' 'll: Global -> ls -la' >&2; end; ls -la $argv
+end
+complete -c ll -w ls
+
+# Scoped: Exact /work/rust/app, Recursive /work/rust
+functions -e build
+function build
+    if test -n "$AKA_DISABLE"; command build $argv; return; end
+    set -l current_dir $PWD
+    if test -n "$AKA_USAGE_LOG"; printf '%s\n' build >> $AKA_USAGE_LOG; end
+    if test "$current_dir" = '/work/rust/app'
+        if test -n "$AKA_DEBUG"; printf 'aka: %s\n' 'build: Exact /work/rust/app -> cargo build -p app' >&2; end; cargo build -p app $argv
+    else if test "$current_dir" = '/work/rust'; or contains -- '/work/rust/' (string sub -l 11 -- $current_dir)
+        if test -n "$AKA_DEBUG"; printf 'aka: %s\n' 'build: Recursive /work/rust -> cargo build' >&2; end; cargo build $argv
+    else
+        if test -n "$AKA_DEBUG"; printf 'aka: %s\n' 'build: Global -> make' >&2; end; make $argv
+    end
+end
+
+# Scoped: Tmux work
+functions -e gcm
+function gcm
+    if test -n "$AKA_DISABLE"; command gcm $argv; return; end
+    set -l current_dir $PWD
+    if test -n "$AKA_USAGE_LOG"; printf '%s\n' gcm >> $AKA_USAGE_LOG; end
+    if test -n "$TMUX"; and contains -- 'work' (tmux display-message -p '#S' 2>/dev/null)
+        if test -n "$AKA_DEBUG"; printf 'aka: %s\n' 'gcm: Tmux work -> git commit -m "@1" && awk \'{print @2}\'' >&2; end; git commit -m "$argv[1]" && awk '{print $2}'
+    else
+        if test -n "$AKA_DEBUG"; printf 'aka: %s\n' 'gcm: no definition applies, running the command itself' >&2; end; command gcm $argv
+    end
+end
+complete -c gcm -w git
+set -gx AKA_MANAGED_ALIASES 'll build gcm'
@@ -0,0 +1,25 @@
+if [ -n "$AKA_MANAGED_ALIASES" ]; then
+    for al in $AKA_MANAGED_ALIASES; do unalias $al 2>/dev/null; unset -f $al 2>/dev/null; done
+fi
+
+# Global
+# cat-pretty: not a valid function name in sh, skipped
+unalias ll 2>/dev/null; unset -f ll 2>/dev/null
+ll() {
+    if [ -n "$AKA_DISABLE" ]; then command ll "$@"; return; fi
+    if [ -n "$AKA_USAGE_LOG" ]; then printf '%s\n' ll >> "$AKA_USAGE_LOG"; fi
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'll: Global -> ls -la' >&2; fi; ls -la "$@"
+}
+
+# Scoped: Recursive /work
+unalias greet 2>/dev/null; unset -f greet 2>/dev/null
+greet() {
+    if [ -n "$AKA_DISABLE" ]; then command greet "$@"; return; fi
+    if [ -n "$AKA_USAGE_LOG" ]; then printf '%s\n' greet >> "$AKA_USAGE_LOG"; fi
+    if [ "$PWD" = "/work" ] || case "$PWD" in "/work/"*) true;; *) false;; esac; then
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'greet: Recursive /work -> echo hello @1' >&2; fi; echo hello "${1}"
+    else
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'greet: Global -> echo hi' >&2; fi; echo hi "$@"
+    fi
+}
+export AKA_MANAGED_ALIASES="cat-pretty ll greet"
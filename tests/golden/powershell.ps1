@@ -0,0 +1,41 @@
+if ($env:AKA_MANAGED_ALIASES) {
+    foreach ($al in $env:AKA_MANAGED_ALIASES -split ' ') { Remove-Item -LiteralPath "Function:$al" -ErrorAction SilentlyContinue }
+}
+
+# Global
+Remove-Item -LiteralPath 'Alias:ll' -Force -ErrorAction SilentlyContinue
+function global:ll {
+    if ($env:AKA_DISABLE) { & (Get-Command -Name 'll' -CommandType Application -TotalCount 1 -ErrorAction Stop) @args; return }
+    $current_dir = $PWD.Path
+    if ($env:AKA_USAGE_LOG) { Add-Content -LiteralPath $env:AKA_USAGE_LOG -Value 'll' }
+    if ($env:AKA_DEBUG) { [Console]::Error.WriteLine('aka: ll: Global -> ls -la') }; ls -la @args
+}
+
+# Scoped: Exact /work/rust/app, Recursive /work/rust
+Remove-Item -LiteralPath 'Alias:build' -Force -ErrorAction SilentlyContinue
+function global:build {
+    if ($env:AKA_DISABLE) { & (Get-Command -Name 'build' -CommandType Application -TotalCount 1 -ErrorAction Stop) @args; return }
+    $current_dir = $PWD.Path
+    if ($env:AKA_USAGE_LOG) { Add-Content -LiteralPath $env:AKA_USAGE_LOG -Value 'build' }
+    if ($current_dir -ceq '/work/rust/app') {
+        if ($env:AKA_DEBUG) { [Console]::Error.WriteLine('aka: build: Exact /work/rust/app -> cargo build -p app') }; cargo build -p app @args
+    } elseif ($current_dir -ceq '/work/rust' -or $current_dir.StartsWith('/work/rust' + [IO.Path]::DirectorySeparatorChar, [StringComparison]::Ordinal)) {
+        if ($env:AKA_DEBUG) { [Console]::Error.WriteLine('aka: build: Recursive /work/rust -> cargo build') }; cargo build @args
+    } else {
+        if ($env:AKA_DEBUG) { [Console]::Error.WriteLine('aka: build: Global -> make') }; make @args
+    }
+}
+
+# Scoped: Tmux work
+Remove-Item -LiteralPath 'Alias:gcm' -Force -ErrorAction SilentlyContinue
+function global:gcm {
+    if ($env:AKA_DISABLE) { & (Get-Command -Name 'gcm' -CommandType Application -TotalCount 1 -ErrorAction Stop) @args; return }
+    $current_dir = $PWD.Path
+    if ($env:AKA_USAGE_LOG) { Add-Content -LiteralPath $env:AKA_USAGE_LOG -Value 'gcm' }
+    if ($env:TMUX -and ((tmux display-message -p '#S' 2>$null) -ceq 'work')) {
+        if ($env:AKA_DEBUG) { [Console]::Error.WriteLine('aka: gcm: Tmux work -> git commit -m "@1" && awk ''{print @2}''') }; git commit -m "$($args[0])" && awk '{print $2}'
+    } else {
+        if ($env:AKA_DEBUG) { [Console]::Error.WriteLine('aka: gcm: no definition applies, running the command itself') }; & (Get-Command -Name 'gcm' -CommandType Application -TotalCount 1 -ErrorAction Stop) @args
+    }
+}
+$env:AKA_MANAGED_ALIASES = 'll build gcm'
@@ -0,0 +1,64 @@
+if [ -n "$ZSH_VERSION" ]; then
+    if [[ -o aliases ]]; then
+        _aka_aliases_was_on=1
+    else
+        _aka_aliases_was_on=0
+    fi
+    unsetopt aliases
+elif [ -n "$BASH_VERSION" ]; then
+    _aka_aliases_was_on=$(shopt -q expand_aliases && echo 1 || echo 0)
+    shopt -u expand_aliases
+fi
+if [ -n "$AKA_MANAGED_ALIASES" ]; then
+    for al in $AKA_MANAGED_ALIASES; do unalias $al 2>/dev/null; unset -f $al 2>/dev/null; done
+fi
+
+# Global
+unalias ll 2>/dev/null; unset -f ll 2>/dev/null
+ll() {
+    if [ -n "$AKA_DISABLE" ]; then command ll "$@"; return; fi
+    local current_dir="$PWD"
+    if [ -n "$AKA_USAGE_LOG" ]; then printf '%s\n' ll >> "$AKA_USAGE_LOG"; fi
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'll: Global -> ls -la' >&2; fi; ls -la "$@"
+}
+if [ -n "$ZSH_VERSION" ]; then (( $+functions[compdef] )) && compdef ll=ls; elif [ -n "$BASH_VERSION" ]; then if complete -p ls &>/dev/null || { _completion_loader ls; complete -p ls; } &>/dev/null; then eval "$(complete -p ls) ll"; fi; fi
+
+# Scoped: Exact /tmp
+unalias greet 2>/dev/null; unset -f greet 2>/dev/null
+greet() {
+    if [ -n "$AKA_DISABLE" ]; then command greet "$@"; return; fi
+    local current_dir="$PWD"
+    if [ -n "$AKA_USAGE_LOG" ]; then printf '%s\n' greet >> "$AKA_USAGE_LOG"; fi
+    if [[ "$current_dir" == "/tmp" ]]; then
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'greet: Exact /tmp -> echo hello @1' >&2; fi; echo hello "${1}"
+    else
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'greet: no definition applies, running the command itself' >&2; fi; command greet "$@"
+    fi
+}
+if [ -n "$ZSH_VERSION" ]; then (( $+functions[compdef] )) && compdef greet=echo; elif [ -n "$BASH_VERSION" ]; then if complete -p echo &>/dev/null || { _completion_loader echo; complete -p echo; } &>/dev/null; then eval "$(complete -p echo) greet"; fi; fi
+
+# Scoped: Exact /work/rust/app, Recursive /work/rust
+unalias build 2>/dev/null; unset -f build 2>/dev/null
+build() {
+    if [ -n "$AKA_DISABLE" ]; then command build "$@"; return; fi
+    local current_dir="$PWD"
+    if [ -n "$AKA_USAGE_LOG" ]; then printf '%s\n' build >> "$AKA_USAGE_LOG"; fi
+    if [[ "$current_dir" == "/work/rust/app" ]]; then
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'build: Exact /work/rust/app -> cargo build --release' >&2; fi; cargo build --release "$@"
+    elif [[ "$current_dir" == "/work/rust" || "$current_dir" == "/work/rust/"* ]]; then
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'build: Recursive /work/rust -> cargo build' >&2; fi; cargo build "$@"
+    else
+        if [ -n "$AKA_DEBUG" ]; then printf 'aka: %s\n' 'build: Global -> make' >&2; fi; make "$@"
+    fi
+}
+export AKA_MANAGED_ALIASES="ll greet build"
+if [ -n "$ZSH_VERSION" ]; then
+    if [ "${_aka_aliases_was_on:-0}" = "1" ]; then
+        setopt aliases
+    fi
+elif [ -n "$BASH_VERSION" ]; then
+    if [ "${_aka_aliases_was_on:-0}" = "1" ]; then
+        shopt -s expand_aliases
+    fi
+fi
+unset _aka_aliases_was_on
@@ -2,8 +2,18 @@ use assert_cmd::Command;
 use predicates::prelude::*;
 use tempfile::TempDir;
 
+/// An empty directory the config file and the system alias file are looked up in, so the
+/// developer's own config, hooks and `/etc/aka/aliases.toml` stay out of the tests.
+fn isolated_dir() -> &'static std::path::Path {
+    static DIR: std::sync::OnceLock<TempDir> = std::sync::OnceLock::new();
+    DIR.get_or_init(setup).path()
+}
+
 fn cmd() -> Command {
-    Command::new(env!("CARGO_BIN_EXE_aka"))
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_aka"));
+    cmd.env("AKA_CONFIG", isolated_dir().join("config.toml"))
+        .env("AKA_SYSTEM_FILE", isolated_dir().join("aliases.toml"));
+    cmd
 }
 
 fn setup() -> TempDir {
@@ -82,3 +92,73 @@ fn test_history_fzf_not_found() {
         .failure()
         .stderr(predicate::str::contains("fzf not found"));
 }
+
+#[test]
+fn test_history_picker_receives_query() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().to_str().unwrap();
+    let history_path = temp_dir.path().join(".zsh_history");
+
+    std::fs::write(&history_path, ": 1700000000:0;ls -la\n").expect("failed to write history");
+
+    // Fake fzf that selects whatever was passed as --query
+    let fzf_path = temp_dir.path().join("fzf");
+    write_executable(
+        &fzf_path,
+        "#!/bin/sh\ncat >/dev/null\nfor a in \"$@\"; do case \"$a\" in --query=*) echo \"${a#--query=}\";; esac; done\n",
+    );
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", data_dir)
+        .env("AKA_HISTORY_FILE", &history_path)
+        .env("AKA_FZF_BIN", &fzf_path)
+        .args(["add", "--query", "docker compose up -d"])
+        .write_stdin("dcu\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Added alias 'dcu' for 'docker compose up -d'",
+        ));
+}
+
+#[test]
+fn test_last_aliases_most_recent_command() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().to_str().unwrap();
+    let history_path = temp_dir.path().join(".zsh_history");
+
+    let history = [
+        ": 1700000000:0;ls -la",
+        ": 1700000001:0;cargo build --release",
+        ": 1700000002:0;aka last",
+    ]
+    .join("\n");
+    std::fs::write(&history_path, history).expect("failed to write history");
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", data_dir)
+        .env("AKA_HISTORY_FILE", &history_path)
+        .args(["last", "cbr"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("Last command: cargo build --release").and(
+                predicate::str::contains("Added alias 'cbr' for 'cargo build --release'"),
+            ),
+        );
+
+    // Without a name the alias is prompted for
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", data_dir)
+        .env("AKA_HISTORY_FILE", &history_path)
+        .arg("last")
+        .write_stdin("b\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Added alias 'b' for 'cargo build --release'",
+        ));
+}
@@ -1,9 +1,26 @@
+// The older tests below predate the clippy gate and are kept as written.
+#![allow(
+    unused_variables,
+    clippy::needless_borrows_for_generic_args,
+    clippy::unnecessary_to_owned
+)]
+
 use assert_cmd::Command;
 use predicates::prelude::*;
 use tempfile::TempDir;
 
+/// An empty directory the config file and the system alias file are looked up in, so the
+/// developer's own config, hooks and `/etc/aka/aliases.toml` stay out of the tests.
+fn isolated_dir() -> &'static std::path::Path {
+    static DIR: std::sync::OnceLock<TempDir> = std::sync::OnceLock::new();
+    DIR.get_or_init(setup).path()
+}
+
 fn cmd() -> Command {
-    Command::new(env!("CARGO_BIN_EXE_aka"))
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_aka"));
+    cmd.env("AKA_CONFIG", isolated_dir().join("config.toml"))
+        .env("AKA_SYSTEM_FILE", isolated_dir().join("aliases.toml"));
+    cmd
 }
 
 fn setup() -> TempDir {
@@ -181,12 +198,13 @@ fn test_init_command() {
     cmd()
         .env("NO_COLOR", "1")
         .envs(env_vars.clone())
-        .args(&["init", "--dump"])
+        .args(&["init", "--dump", "--shell", "zsh"])
         .assert()
         .success()
         .stdout(
             predicate::str::contains("hello")
-                .and(predicate::str::contains("[[ -o aliases ]]")),
+                .and(predicate::str::contains("[[ -o aliases ]]"))
+                .and(predicate::str::contains("BASH_VERSION").not()),
         );
 }
 
@@ -204,7 +222,7 @@ fn test_positional_args_substitution() {
         .assert()
         .success();
 
-    // Init --dump should replace @1 with $1 and NOT append "$@"
+    // Init --dump should replace @1 with a quoted "${1}" and NOT append "$@"
     cmd()
         .env("NO_COLOR", "1")
         .envs(env_vars.clone())
@@ -212,7 +230,8 @@ fn test_positional_args_substitution() {
         .assert()
         .success()
         .stdout(
-            predicate::str::contains("grep foo $1").and(predicate::str::contains("\"$@\"").not()),
+            predicate::str::contains("grep foo \"${1}\"\n")
+                .and(predicate::str::contains("grep foo \"${1}\" \"$@\"").not()),
         );
 }
 
@@ -417,7 +436,7 @@ fn test_scoped_aliases() {
     let stdout = String::from_utf8(output.stdout).unwrap();
     assert!(stdout.contains("foo() {"));
     assert!(stdout.contains(&format!(
-        "if [[ \"$current_dir\" == \"{}\"* ]]; then",
+        "if [[ \"$current_dir\" == \"{0}\" || \"$current_dir\" == \"{0}/\"* ]]; then",
         tmp_str
     )));
     assert!(stdout.contains("echo scoped \"$@\""));
@@ -446,7 +465,7 @@ fn test_scoped_alias_implicit_dir() {
     cmd()
         .env("NO_COLOR", "1")
         .envs(env_vars.clone())
-        .arg("list")
+        .args(["list", "--long"])
         .assert()
         .success()
         .stdout(predicate::str::contains(format!(
@@ -702,3 +721,1175 @@ fn test_remove_scope_not_found() {
         .failure()
         .stderr(predicate::str::contains("Invalid scope path"));
 }
+
+#[test]
+fn test_system_aliases_layer() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().to_str().unwrap();
+    let system_file = temp_dir.path().join("system.toml");
+    std::fs::write(
+        &system_file,
+        "[aliases]\nll = \"ls -la\"\ngs = \"git status\"\n",
+    )
+    .unwrap();
+    let env_vars = vec![
+        ("aka_DATA_DIR", data_dir),
+        ("AKA_SYSTEM_FILE", system_file.to_str().unwrap()),
+    ];
+
+    // User definition shadows the system one for the same scope
+    cmd()
+        .env("NO_COLOR", "1")
+        .envs(env_vars.clone())
+        .args(["add", "gs", "git status -sb"])
+        .assert()
+        .success();
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .envs(env_vars.clone())
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("ll = 'ls -la'")
+                .and(predicate::str::contains("(Global) (System)"))
+                .and(predicate::str::contains("gs = 'git status -sb'"))
+                .and(predicate::str::contains("'git status'").not()),
+        );
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .envs(env_vars.clone())
+        .args(["init", "--dump"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ll() {").and(predicate::str::contains("ls -la \"$@\"")));
+
+    // System aliases cannot be removed
+    cmd()
+        .env("NO_COLOR", "1")
+        .envs(env_vars.clone())
+        .args(["remove", "ll"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("read-only"));
+}
+
+#[test]
+fn test_broken_system_file_is_skipped_with_a_warning() {
+    let temp_dir = setup();
+    let system_file = temp_dir.path().join("system.toml");
+    std::fs::write(&system_file, "[aliases\n").unwrap();
+    let env_vars = vec![
+        ("aka_DATA_DIR", temp_dir.path().to_str().unwrap()),
+        ("AKA_SYSTEM_FILE", system_file.to_str().unwrap()),
+    ];
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .envs(env_vars.clone())
+        .args(["add", "ll", "ls -la"])
+        .assert()
+        .success();
+
+    // Everyday commands keep working on the user's own aliases
+    let output = cmd()
+        .env("NO_COLOR", "1")
+        .envs(env_vars.clone())
+        .arg("list")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("ll = 'ls -la'"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        stderr.matches("ignoring system aliases").count(),
+        1,
+        "{}",
+        stderr
+    );
+
+    cmd()
+        .envs(env_vars.clone())
+        .args(["init", "--dump"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ll() {"));
+
+    // doctor and lint surface the problem
+    for args in [["doctor"].as_slice(), ["lint"].as_slice()] {
+        cmd()
+            .envs(env_vars.clone())
+            .args(args)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid system alias file"));
+    }
+}
+
+#[test]
+fn test_workspace_scope() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().join("data");
+    let repo = temp_dir.path().join("repo");
+    let nested = repo.join("src").join("deep");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(repo.join("Cargo.toml"), "").unwrap();
+    let repo_str = std::fs::canonicalize(&repo).unwrap();
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", &data_dir)
+        .current_dir(&nested)
+        .args(["add", "t", "cargo test", "--scope", "workspace"])
+        .assert()
+        .success();
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", &data_dir)
+        .args(["list", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "t = 'cargo test' (Recursive: {})",
+            repo_str.to_string_lossy()
+        )));
+}
+
+#[test]
+fn test_announce_scoped_aliases() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().join("data");
+    let project = temp_dir.path().join("project");
+    std::fs::create_dir_all(&project).unwrap();
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", &data_dir)
+        .args(["add", "g", "git"])
+        .assert()
+        .success();
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", &data_dir)
+        .current_dir(&project)
+        .args(["add", "lint", "cargo clippy", "--scope", "."])
+        .assert()
+        .success();
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", &data_dir)
+        .current_dir(&project)
+        .arg("announce")
+        .assert()
+        .success()
+        .stdout("aka: 1 project alias(es) active: lint\n");
+
+    // Outside any scope the hook stays silent
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", &data_dir)
+        .current_dir(temp_dir.path())
+        .arg("announce")
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn test_here_marks_winning_definition() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().join("data");
+    let project = temp_dir.path().join("project");
+    std::fs::create_dir_all(&project).unwrap();
+
+    cmd()
+        .env("aka_DATA_DIR", &data_dir)
+        .args(["add", "t", "make test"])
+        .assert()
+        .success();
+
+    cmd()
+        .env("aka_DATA_DIR", &data_dir)
+        .current_dir(&project)
+        .args(["add", "t", "cargo test", "--scope", "."])
+        .assert()
+        .success();
+
+    cmd()
+        .env("aka_DATA_DIR", &data_dir)
+        .current_dir(temp_dir.path())
+        .args(["add", "other", "echo other", "--scope", "."])
+        .assert()
+        .success();
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", &data_dir)
+        .current_dir(&project)
+        .arg("here")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r"t = 'cargo test' \(Exact: .*\) \(active\)")
+                .unwrap()
+                .and(predicate::str::contains(
+                    "t = 'make test'  (Global) (shadowed)",
+                ))
+                .and(predicate::str::contains("other").not()),
+        );
+}
+
+#[test]
+fn test_migrate_to_json() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().to_str().unwrap();
+    let env_vars = vec![("aka_DATA_DIR", data_dir)];
+
+    cmd()
+        .envs(env_vars.clone())
+        .args(["add", "ll", "ls -la"])
+        .assert()
+        .success();
+
+    cmd()
+        .envs(env_vars.clone())
+        .args(["migrate", "--to", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Migrated 1 alias(es) from redb to json",
+        ));
+
+    assert!(temp_dir.path().join("aka").join("aka.json").exists());
+    assert!(temp_dir.path().join("aka").join("aka.redb.bak").exists());
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .envs(env_vars.clone())
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ll = 'ls -la'"));
+}
+
+#[test]
+fn test_completion_offers_alias_names() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().to_str().unwrap();
+
+    cmd()
+        .env("aka_DATA_DIR", data_dir)
+        .args(["add", "deploy", "make deploy"])
+        .assert()
+        .success();
+
+    // What the generated bash completion function runs for `aka rm <TAB>`
+    cmd()
+        .env("aka_DATA_DIR", data_dir)
+        .env("AKA_COMPLETE", "bash")
+        .env("_CLAP_COMPLETE_INDEX", "2")
+        .env("_CLAP_IFS", "\n")
+        .args(["--", "aka", "rm", ""])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deploy\n"));
+}
+
+#[test]
+fn test_stats_reads_usage_log() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().to_str().unwrap();
+    let log = temp_dir.path().join("usage.log");
+    std::fs::write(&log, "gs\ngs\n").unwrap();
+
+    cmd()
+        .env("aka_DATA_DIR", data_dir)
+        .args(["add", "gs", "git status"])
+        .assert()
+        .success();
+
+    cmd()
+        .env("aka_DATA_DIR", data_dir)
+        .env("AKA_USAGE_LOG", &log)
+        .args(["stats", "--time-saved"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("gs 2 x 8 chars = 16"));
+}
+
+#[test]
+fn test_list_long_shows_alias_references() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().to_str().unwrap();
+
+    for (alias, command) in [("g", "git"), ("gs", "g status")] {
+        cmd()
+            .env("aka_DATA_DIR", data_dir)
+            .args(["add", alias, command])
+            .assert()
+            .success();
+    }
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", data_dir)
+        .args(["list", "--long"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("g  = 'git'      (Global) (called by: gs)").and(
+                predicate::str::contains("gs = 'g status' (Global) (calls: g)"),
+            ),
+        );
+}
+
+#[test]
+fn test_env_reports_data_dir() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().to_str().unwrap();
+
+    let output = cmd()
+        .env("aka_DATA_DIR", data_dir)
+        .env_remove("AKA_MANAGED_ALIASES")
+        .args(["env", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let env: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        env["data_dir"],
+        format!("{} (missing)", temp_dir.path().join("aka").display())
+    );
+    assert_eq!(env["generation"], "none");
+    assert_ne!(env["hook"], "loaded");
+}
+
+#[test]
+fn test_add_as_script() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().to_str().unwrap();
+    let script = temp_dir.path().join("aka").join("scripts").join("hi");
+
+    cmd()
+        .env("aka_DATA_DIR", data_dir)
+        .args(["add", "hi", "echo \"hi $1\"", "--as-script"])
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(&script).unwrap(),
+        "#!/usr/bin/env bash\necho \"hi $1\"\n"
+    );
+
+    cmd()
+        .env("aka_DATA_DIR", data_dir)
+        .env("NO_COLOR", "1")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(script.to_str().unwrap()));
+}
+
+#[test]
+fn test_var_expanded_in_dump() {
+    let temp_dir = setup();
+    let data_dir = temp_dir.path().to_str().unwrap();
+
+    cmd()
+        .env("aka_DATA_DIR", data_dir)
+        .args(["add", "push", "docker push @{var:REGISTRY}/app"])
+        .assert()
+        .success();
+    cmd()
+        .env("aka_DATA_DIR", data_dir)
+        .args(["var", "set", "REGISTRY", "ghcr.io/acme"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(used by push)"));
+
+    cmd()
+        .env("aka_DATA_DIR", data_dir)
+        .args(["init", "--dump"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "docker push ghcr.io/acme/app \"$@\"",
+        ));
+}
+
+#[test]
+fn test_json_error_output() {
+    let temp_dir = setup();
+
+    let output = cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["remove", "ghost", "--output", "json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["error"]["code"], "alias_not_found");
+    assert_eq!(error["error"]["message"], "Alias not found: ghost");
+    assert_eq!(error["error"]["context"]["alias"], "ghost");
+}
+
+#[test]
+fn test_session_alias_is_not_saved() {
+    let temp_dir = setup();
+
+    let output = cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["add", "--session", "hi", "echo hello @1"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let function = String::from_utf8(output.stdout).unwrap();
+    assert!(function.contains("hi() {"));
+
+    if let Ok(output) = std::process::Command::new("bash")
+        .arg("-c")
+        .arg(format!("{}\nhi world", function))
+        .output()
+    {
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello world\n");
+    }
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", temp_dir.path())
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No aliases found"));
+}
+
+#[test]
+fn test_then_composes_aliases_by_reference() {
+    let temp_dir = setup();
+    let aka = |args: &[&str]| {
+        cmd()
+            .env("aka_DATA_DIR", temp_dir.path())
+            .args(args)
+            .assert()
+            .success();
+    };
+
+    aka(&["add", "build", "echo building"]);
+    aka(&["add", "ci", "--then", "build", "--then", "build"]);
+    aka(&["add", "build", "echo rebuilt"]);
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["init", "--dump"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "; echo rebuilt && echo rebuilt \"$@\"\n",
+        ));
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["add", "deploy", "--then", "missing"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_mistyped_command_suggests_correction() {
+    let temp_dir = setup();
+
+    cmd()
+        .env("NO_COLOR", "1")
+        .env("aka_DATA_DIR", temp_dir.path())
+        .arg("lst")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "did you mean 'aka last' or 'aka list'?",
+        ));
+}
+
+#[test]
+fn test_implicit_mode_can_be_disabled() {
+    let temp_dir = setup();
+    let config = temp_dir.path().join("config.toml");
+    std::fs::write(&config, "[cli]\nimplicit = false\n").unwrap();
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env("AKA_CONFIG", &config)
+        .args(["hello", "echo hello"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unrecognized subcommand 'hello'"));
+
+    // The environment overrides the config
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env("AKA_CONFIG", &config)
+        .env("AKA_IMPLICIT", "1")
+        .args(["hello", "echo hello"])
+        .assert()
+        .success();
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env("AKA_IMPLICIT", "0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("a subcommand is required"));
+}
+
+#[test]
+fn test_backend_can_be_chosen_in_config() {
+    let temp_dir = setup();
+    let config = temp_dir.path().join("config.toml");
+    std::fs::write(&config, "[store]\nbackend = \"json\"\n").unwrap();
+
+    let data_dir = temp_dir.path().join("from-config");
+    cmd()
+        .env("aka_DATA_DIR", &data_dir)
+        .env("AKA_CONFIG", &config)
+        .args(["add", "hello", "echo hello"])
+        .assert()
+        .success();
+    assert!(data_dir.join("aka").join("aka.json").exists());
+    assert!(!data_dir.join("aka").join("aka.redb").exists());
+
+    // The environment overrides the config
+    let data_dir = temp_dir.path().join("from-env");
+    cmd()
+        .env("aka_DATA_DIR", &data_dir)
+        .env("AKA_CONFIG", &config)
+        .env("AKA_BACKEND", "redb")
+        .args(["add", "hello", "echo hello"])
+        .assert()
+        .success();
+    assert!(data_dir.join("aka").join("aka.redb").exists());
+    assert!(!data_dir.join("aka").join("aka.json").exists());
+}
+
+#[test]
+fn test_log_level_writes_log_file() {
+    let temp_dir = setup();
+    let log = temp_dir.path().join("state").join("aka.log");
+    let config = temp_dir.path().join("config.toml");
+    std::fs::write(&config, format!("[log]\nfile = {:?}\n", log)).unwrap();
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env("AKA_CONFIG", &config)
+        .args(["--log-level", "debug", "add", "hello", "echo hello"])
+        .assert()
+        .success();
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env("AKA_CONFIG", &config)
+        .args(["--log-level", "debug", "remove", "ghost"])
+        .assert()
+        .failure();
+
+    let content = std::fs::read_to_string(&log).unwrap();
+    assert!(content.contains("store change"));
+    assert!(content.contains("key=hello action=\"set\""));
+    assert!(content.contains("command failed error=Alias not found: ghost"));
+}
+
+#[test]
+fn test_doctor_repairs_corrupt_records() {
+    let temp_dir = setup();
+    let store_dir = temp_dir.path().join("aka");
+    std::fs::create_dir_all(&store_dir).unwrap();
+    std::fs::write(
+        store_dir.join("aka.json"),
+        r#"{"aliases": {
+            "ll": "ls -la",
+            "mixed": [{"command": "make", "scope": "Global"}, {"scope": 7}],
+            "partial": "[{\"command\":\"ls\",\"scope\":\"Glo"
+        }}"#,
+    )
+    .unwrap();
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Corrupt record 'mixed' (repairable: 1 definition(s) decode)",
+        ))
+        .stdout(predicate::str::contains(
+            "Corrupt record 'partial' (not repairable)",
+        ))
+        .stdout(predicate::str::contains("2 corrupt record(s)"));
+
+    // Strict mode refuses to read the corrupt records as aliases
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env("AKA_STRICT", "1")
+        .arg("list")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Corrupt record for alias 'mixed'"));
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["doctor", "--repair"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Repaired 'mixed' (kept 1 definition(s))",
+        ))
+        .stdout(predicate::str::contains("Quarantined 'partial'"));
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No problems found in 2 alias(es)"));
+}
+
+#[test]
+fn test_readonly_refuses_changes() {
+    let temp_dir = setup();
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["add", "ll", "ls -la"])
+        .assert()
+        .success();
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["--readonly", "rm", "ll"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "The store is read-only (--readonly)",
+        ));
+
+    // A lockfile in the data directory has the same effect, while reads keep working
+    std::fs::write(temp_dir.path().join("aka").join("aka.lock"), "").unwrap();
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["add", "gs", "git status"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("aka.lock exists"));
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["list", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ll"))
+        .stdout(predicate::str::contains("gs").not());
+}
+
+#[test]
+fn test_explicit_scope_kinds() {
+    let temp_dir = setup();
+    let project = temp_dir.path().join("project");
+    std::fs::create_dir(&project).unwrap();
+    let project = project.canonicalize().unwrap();
+    let exact = format!("exact:{}", project.display());
+    let recursive = format!("recursive:{}", project.display());
+
+    for (scope, command) in [(&exact, "make"), (&recursive, "make all")] {
+        cmd()
+            .env("aka_DATA_DIR", temp_dir.path())
+            .args(["add", "b", command, "--scope", scope])
+            .assert()
+            .success();
+    }
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["list", "--template", "{command}", "--scope", &recursive])
+        .assert()
+        .success()
+        .stdout("make all\n");
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["rm", "b", "--scope", &exact])
+        .assert()
+        .success();
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["list", "--template", "{command}"])
+        .args(["--scope", project.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("make all\n");
+}
+
+#[test]
+fn test_scope_shorthands() {
+    let temp_dir = setup();
+    let repo = temp_dir.path().join("repo");
+    let nested = repo.join("src").join("bin");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::create_dir(repo.join(".git")).unwrap();
+    let repo = repo.canonicalize().unwrap();
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["add", "g", "git", "--global", "--scope", "."])
+        .assert()
+        .failure();
+
+    for (alias, scope) in [
+        ("g", "--global"),
+        ("r", "--scope=@repo"),
+        ("u", "--scope=.."),
+    ] {
+        cmd()
+            .env("aka_DATA_DIR", temp_dir.path())
+            .current_dir(&nested)
+            .args(["add", alias, "true", scope])
+            .assert()
+            .success();
+    }
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["list", "--all", "--long", "--template", "{alias} {scope}"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("g (Global)"))
+        .stdout(predicate::str::contains(format!(
+            "r (Recursive: {})",
+            repo.display()
+        )))
+        .stdout(predicate::str::contains(format!(
+            "u (Exact: {})",
+            repo.join("src").display()
+        )));
+}
+
+#[test]
+fn test_try_fallbacks() {
+    let temp_dir = setup();
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["add", "show", "cat", "--try", "bat"])
+        .assert()
+        .failure();
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["add", "show", "--try", "aka-missing-tool -p"])
+        .args(["--try", "echo fallback"])
+        .assert()
+        .success();
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["list", "--all", "--long"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("aka-missing-tool -p"))
+        .stdout(predicate::str::contains("(or: echo fallback)"));
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["init", "--dump", "--shell", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("echo fallback \"$@\""))
+        .stdout(predicate::str::contains("aka-missing-tool").not());
+}
+
+#[test]
+fn test_dispatch_dump_resolves_on_each_call() {
+    let temp_dir = setup();
+    let project = temp_dir.path().join("project");
+    std::fs::create_dir(&project).unwrap();
+    let aka = |args: &[&str]| {
+        cmd()
+            .env("aka_DATA_DIR", temp_dir.path())
+            .args(args)
+            .assert()
+            .success();
+    };
+    aka(&["add", "greet", "echo hello @1"]);
+    aka(&[
+        "add",
+        "greet",
+        "echo project @1; return 3",
+        "--scope",
+        project.to_str().unwrap(),
+    ]);
+
+    let output = cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["init", "--dump", "--shell", "bash", "--dispatch"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let dump = String::from_utf8(output.stdout).unwrap();
+    assert!(dump.contains("greet() { _aka_dispatch greet \"$@\"; }"));
+
+    // Definitions changed after sourcing apply without reloading
+    let bin_dir = std::path::Path::new(env!("CARGO_BIN_EXE_aka"))
+        .parent()
+        .unwrap();
+    let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap());
+    let script = format!(
+        "{}\ngreet world\ncd {}\ngreet there; echo \"$?\"\naka add greet 'echo hi @1' >/dev/null\ncd /\ngreet again",
+        dump,
+        project.display()
+    );
+    if let Ok(output) = std::process::Command::new("bash")
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env("AKA_CONFIG", isolated_dir().join("config.toml"))
+        .env("AKA_SYSTEM_FILE", isolated_dir().join("aliases.toml"))
+        .env("PATH", path)
+        .arg("-c")
+        .arg(script)
+        .output()
+    {
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "hello world\nproject there\n3\nhi again\n"
+        );
+    }
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["init", "--dump", "--shell", "fish", "--dispatch"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_color_flag_takes_precedence_over_environment() {
+    let temp_dir = setup();
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["add", "g", "git"])
+        .assert()
+        .success();
+
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env("NO_COLOR", "1")
+        .args(["list", "--color", "always"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[36mg\x1b[39m"));
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env("FORCE_COLOR", "1")
+        .env("NO_COLOR", "1")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[").not());
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env("FORCE_COLOR", "1")
+        .env_remove("NO_COLOR")
+        .args(["list", "--color", "never"])
+        .assert()
+        .success()
+        .stdout("g = 'git' (Global)\n");
+}
+
+#[test]
+fn test_print_path_keeps_the_sourced_dump_current() {
+    let temp_dir = setup();
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["add", "greet", "echo hello @1"])
+        .assert()
+        .success();
+    let output = cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["init", "--print-path", "--shell", "bash"])
+        .output()
+        .unwrap();
+    let line = String::from_utf8(output.stdout).unwrap();
+    let path = temp_dir.path().join("aka").join("dump.bash");
+    assert_eq!(line, format!("source {}\n", path.display()));
+
+    // Rewritten by commands that change the store, not by ones that only read it
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["add", "bye", "echo bye"])
+        .assert()
+        .success();
+    let dump = std::fs::read_to_string(&path).unwrap();
+    assert!(dump.contains("bye()"));
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .arg("list")
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), dump);
+
+    // A config change the store does not see still makes the dump stale
+    let config = temp_dir.path().join("config.toml");
+    std::fs::write(&config, "[shell]\nwrite_history = true\n").unwrap();
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env("AKA_CONFIG", &config)
+        .args(["init", "--print-path", "--shell", "bash"])
+        .assert()
+        .success();
+    assert_ne!(std::fs::read_to_string(&path).unwrap(), dump);
+    std::fs::remove_file(&config).unwrap();
+
+    // --refresh rewrites it even when the stamp looks current
+    let stamp = dump.lines().next().unwrap();
+    std::fs::write(&path, format!("{}\n", stamp)).unwrap();
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .args(["init", "--refresh"])
+        .assert()
+        .success()
+        .stdout("Refreshed 1 dump(s)\n");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), dump);
+
+    if let Ok(output) = std::process::Command::new("bash")
+        .arg("-c")
+        .arg(format!("{}greet world; bye", line))
+        .output()
+    {
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "hello world\nbye\n"
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_hooks_receive_the_change() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = setup();
+    let hooks = temp_dir.path().join("config").join("hooks");
+    std::fs::create_dir_all(&hooks).unwrap();
+    let log = temp_dir.path().join("hook.log");
+    let script = format!(
+        "#!/bin/sh\n{{ printf '%s ' \"$AKA_HOOK\"; cat; }} >> '{}'\n",
+        log.display()
+    );
+    for (name, script) in [
+        ("post-add", script.as_str()),
+        ("post-remove", "#!/bin/sh\nexit 3\n"),
+    ] {
+        let path = hooks.join(name);
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let aka = || {
+        let mut cmd = cmd();
+        cmd.env("aka_DATA_DIR", temp_dir.path()).env(
+            "AKA_CONFIG",
+            temp_dir.path().join("config").join("config.toml"),
+        );
+        cmd
+    };
+
+    aka().args(["add", "g", "git"]).assert().success();
+    let logged = std::fs::read_to_string(&log).unwrap();
+    let (event, payload) = logged.split_once(' ').unwrap();
+    assert_eq!(event, "post-add");
+    let payload: serde_json::Value = serde_json::from_str(payload).unwrap();
+    assert_eq!(payload["aliases"]["g"]["before"], serde_json::json!([]));
+    assert_eq!(payload["aliases"]["g"]["after"][0]["command"], "git");
+
+    // Re-adding the same definition changes nothing, so the hook is not run
+    aka().args(["add", "g", "git"]).assert().success();
+    assert_eq!(std::fs::read_to_string(&log).unwrap(), logged);
+
+    // A failing hook is reported, but the change stands
+    aka()
+        .args(["remove", "g"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("post-remove failed"));
+    aka()
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No aliases found"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_bulk_rewrites_run_the_sync_hook() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = setup();
+    let hooks = temp_dir.path().join("config").join("hooks");
+    std::fs::create_dir_all(&hooks).unwrap();
+    let log = temp_dir.path().join("hook.log");
+    let hook = hooks.join("post-sync");
+    std::fs::write(
+        &hook,
+        format!("#!/bin/sh\n{{ cat; echo; }} >> '{}'\n", log.display()),
+    )
+    .unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let aka = || {
+        let mut cmd = cmd();
+        cmd.env("aka_DATA_DIR", temp_dir.path()).env(
+            "AKA_CONFIG",
+            temp_dir.path().join("config").join("config.toml"),
+        );
+        cmd
+    };
+    let runs = || {
+        std::fs::read_to_string(&log)
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let old = temp_dir.path().join("old");
+    let new = temp_dir.path().join("new");
+    std::fs::create_dir(&old).unwrap();
+    aka()
+        .args(["add", "t", "cargo test", "--scope"])
+        .arg(format!("recursive:{}", old.display()))
+        .assert()
+        .success();
+    std::fs::rename(&old, &new).unwrap();
+    aka()
+        .arg("scope")
+        .arg("move")
+        .arg(&old)
+        .arg(&new)
+        .assert()
+        .success();
+    let logged = runs();
+    assert_eq!(logged.len(), 1);
+    assert_eq!(
+        logged[0]["aliases"]["t"]["after"][0]["scope"]["Recursive"],
+        std::fs::canonicalize(&new).unwrap().to_str().unwrap()
+    );
+
+    // A migration changes no alias, but the store file is rewritten all the same
+    aka().args(["migrate", "--to", "json"]).assert().success();
+    let logged = runs();
+    assert_eq!(logged.len(), 2);
+    assert_eq!(
+        logged[1],
+        serde_json::json!({ "event": "post-sync", "aliases": {} })
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_hooks_skipped_when_the_store_cannot_be_read_first() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = setup();
+    let store_dir = temp_dir.path().join("aka");
+    std::fs::create_dir_all(&store_dir).unwrap();
+    std::fs::write(
+        store_dir.join("aka.json"),
+        r#"{"aliases": {"partial": "[{\"command\":\"ls\",\"scope\":\"Glo"}}"#,
+    )
+    .unwrap();
+    let hooks = temp_dir.path().join("config").join("hooks");
+    std::fs::create_dir_all(&hooks).unwrap();
+    let hook = hooks.join("post-sync");
+    std::fs::write(&hook, "#!/bin/sh\necho ran >&2\n").unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    // Strict mode cannot list the store before doctor quarantines the corrupt record
+    cmd()
+        .env("aka_DATA_DIR", temp_dir.path())
+        .env(
+            "AKA_CONFIG",
+            temp_dir.path().join("config").join("config.toml"),
+        )
+        .env("AKA_STRICT", "1")
+        .args(["doctor", "--quarantine"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "aka: hooks skipped: Corrupt record",
+        ))
+        .stderr(predicate::str::contains("ran").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_edit_runs_add_and_remove_hooks() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = setup();
+    let hooks = temp_dir.path().join("config").join("hooks");
+    std::fs::create_dir_all(&hooks).unwrap();
+    let log = temp_dir.path().join("hook.log");
+    let script = format!(
+        "#!/bin/sh\necho hook output\n{{ printf '%s ' \"$AKA_HOOK\"; cat; }} >> '{}'\n",
+        log.display()
+    );
+    for name in ["post-add", "post-remove"] {
+        let path = hooks.join(name);
+        std::fs::write(&path, &script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let editor = temp_dir.path().join("editor");
+    std::fs::write(
+        &editor,
+        "#!/bin/sh\nsed -i -e 's/^\\[\\[g\\]\\]$/[[gs]]/' -e 's/\"git\"/\"git status\"/' \"$1\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let aka = || {
+        let mut cmd = cmd();
+        cmd.env("aka_DATA_DIR", temp_dir.path())
+            .env(
+                "AKA_CONFIG",
+                temp_dir.path().join("config").join("config.toml"),
+            )
+            .env("VISUAL", &editor);
+        cmd
+    };
+
+    aka().args(["add", "g", "git"]).assert().success();
+    std::fs::remove_file(&log).unwrap();
+
+    // Renaming g to gs in the editor adds one alias and removes the other; what the hooks
+    // print stays out of aka's own output
+    aka()
+        .args(["--output", "json", "edit", "--all"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hook output").not())
+        .stderr(predicate::str::contains("hook output"));
+    let logged = std::fs::read_to_string(&log).unwrap();
+    let runs: Vec<(&str, serde_json::Value)> = logged
+        .lines()
+        .map(|line| {
+            let (event, payload) = line.split_once(' ').unwrap();
+            (event, serde_json::from_str(payload).unwrap())
+        })
+        .collect();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].0, "post-add");
+    assert_eq!(
+        runs[0].1["aliases"]["gs"]["after"][0]["command"],
+        "git status"
+    );
+    assert!(runs[0].1["aliases"].get("g").is_none());
+    assert_eq!(runs[1].0, "post-remove");
+    assert_eq!(runs[1].1["aliases"]["g"]["before"][0]["command"], "git");
+    assert!(runs[1].1["aliases"].get("gs").is_none());
+}
@@ -0,0 +1,85 @@
+use aka::Store;
+use aka::codegen::Shell;
+use aka::commands::init::handle_init_command;
+use aka::store::AliasScope;
+use tempfile::tempdir;
+
+/// Dump a store built from `aliases` exactly as `aka init --dump` would.
+fn dump(aliases: &[(&str, &str, AliasScope)]) -> String {
+    // Keep the host's system alias file out of the snapshots
+    unsafe { std::env::set_var("AKA_SYSTEM_FILE", "/nonexistent/aka/aliases.toml") };
+
+    let dir = tempdir().unwrap();
+    let mut store = Store::load(&dir.path().join("aka.redb")).unwrap();
+    for (alias, command, scope) in aliases {
+        store
+            .add(alias.to_string(), command.to_string(), scope.clone())
+            .unwrap();
+    }
+
+    let mut out = Vec::new();
+    handle_init_command(Some(&store), Shell::Zsh, true, false, None, false, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn snapshot_empty_store() {
+    insta::assert_snapshot!(dump(&[]));
+}
+
+#[test]
+fn snapshot_scoped_aliases() {
+    insta::assert_snapshot!(dump(&[
+        ("ll", "ls -la", AliasScope::Global),
+        ("build", "make", AliasScope::Global),
+        (
+            "build",
+            "cargo build",
+            AliasScope::Recursive("/work/rust".to_string())
+        ),
+        (
+            "build",
+            "cargo build --release",
+            AliasScope::Exact("/work/rust/app".to_string())
+        ),
+        (
+            "serve",
+            "python -m http.server",
+            AliasScope::Exact("/srv/www".to_string())
+        ),
+    ]));
+}
+
+#[test]
+fn snapshot_placeholders() {
+    insta::assert_snapshot!(dump(&[
+        ("gc", "git commit -m @1", AliasScope::Global),
+        ("swap", "mv @2 @1", AliasScope::Global),
+        ("each", "for f in $@; do echo $f; done", AliasScope::Global),
+        ("tool", "${HOME}/bin/tool", AliasScope::Global),
+        ("first", "echo ${1}", AliasScope::Global),
+    ]));
+}
+
+#[test]
+fn snapshot_quotes() {
+    insta::assert_snapshot!(dump(&[
+        ("greet", "echo 'hello world'", AliasScope::Global),
+        ("search", "grep -rn \"TODO: fix\" .", AliasScope::Global),
+        ("literal", "echo '$1 stays literal'", AliasScope::Global),
+        ("quoted", "printf '%s\\n' \"$1\"", AliasScope::Global),
+    ]));
+}
+
+#[test]
+fn snapshot_unicode() {
+    insta::assert_snapshot!(dump(&[
+        ("hi", "echo こんにちは", AliasScope::Global),
+        (
+            "proj",
+            "cd ~/プロジェクト && ls",
+            AliasScope::Recursive("/home/ユーザー/作業".to_string())
+        ),
+        ("wide", "echo 🚀 launch", AliasScope::Global),
+    ]));
+}